@@ -0,0 +1,483 @@
+// 授权管理：从 Approval / ApprovalForAll 日志（及 Permit2 的 Approval / Permit）还原账户授权过的 (token, spender)，
+// 有索引器时走 ankr_getLogs，否则按区块分段 eth_getLogs；再经 Multicall3 读取当前值，只保留仍然有效的授权并标注名称。
+// 撤销时总是逐笔生成待签名交易；账户已委托（EIP-7702）时另附一个 EIP-5792 bundle，钱包能执行 wallet_sendCalls 时可改用它
+use crate::actions::tx::{UnsignedTx, buffered_gas, prepare_tx};
+use crate::core::db::AppDB;
+use crate::core::state::AppState;
+use crate::data::erc7730::known_names;
+use crate::data::tokenlist::token_meta_map;
+use crate::eips::eip5792::{AtomicStatus, Call, SendCallsParams};
+use crate::eips::erc721::set_approval_for_all_calldata;
+use crate::error::AppError;
+use crate::evm::address::known::PERMIT2;
+use crate::evm::gas::{FeeTier, estimate_fees_for_chain};
+use crate::evm::permit2::IAllowanceTransfer;
+use crate::evm::selector::{IERC20, IERC721};
+use crate::rpc::ankr::advance::get_logs_by_ankr;
+use crate::rpc::ankr::models::AnkrBlockchain;
+use crate::rpc::method::{block_number, estimate_gas_from, get_code, get_logs};
+use crate::rpc::multicall3::{self, MULTICALL3_ADDR, Multicall3};
+use crate::utils::num::str_to_u64;
+use crate::utils::time::now_s;
+use alloy_primitives::{Address, B256, Bytes, U160, U256};
+use alloy_sol_types::{SolCall, SolEvent, sol};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use tauri::State;
+
+/// 没有索引器、也没指定起始区块时回看的时长
+pub const DEFAULT_LOOKBACK_SECS: u64 = 180 * 24 * 3600;
+/// eth_getLogs 单次查询的区块跨度；节点拒绝时减半重试
+pub const LOG_CHUNK_BLOCKS: u64 = 50_000;
+pub const MIN_LOG_CHUNK_BLOCKS: u64 = 500;
+/// ankr_getLogs 最多翻的页数，超出时只用已取到的部分并标记为不完整
+pub const MAX_INDEXER_PAGES: usize = 20;
+/// 每次 aggregate3 的子调用数
+pub const MULTICALL_BATCH: usize = 200;
+/// 估算失败时撤销交易的 gas 上限
+pub const FALLBACK_GAS_REVOKE: u64 = 80_000;
+
+sol! {
+    interface ITokenApprovals {
+        event Approval(address indexed owner, address indexed spender, uint256 value);
+        event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalKind {
+    Erc20,
+    /// ERC-721 / ERC-1155 的 setApprovalForAll
+    Operator,
+    Permit2,
+}
+
+/// 日志里出现过的一组授权；Operator 的 token 为 NFT 合约
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ApprovalKey {
+    pub kind: ApprovalKind,
+    pub token: Address,
+    pub spender: Address,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenApproval {
+    pub kind: ApprovalKind,
+    pub token: Address,
+    pub token_symbol: Option<String>,
+    pub spender: Address,
+    /// 合约注册表 / 地址簿中的名称
+    pub spender_label: Option<String>,
+    /// ERC-20 与 Permit2 的当前额度；Operator 为 None
+    pub amount: Option<U256>,
+    pub unlimited: bool,
+    /// Permit2 额度的过期时间
+    pub expiration: Option<u64>,
+    /// 最近一次授权日志所在区块
+    pub last_block: u64,
+}
+
+/// 扫描结果及其覆盖范围；complete 为 false 时界面需提示可能有遗漏
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalScan {
+    pub approvals: Vec<TokenApproval>,
+    /// 实际扫描的区块范围（含两端）
+    pub from_block: u64,
+    pub to_block: u64,
+    /// 从创世块扫到 head 且没有被翻页上限截断
+    pub complete: bool,
+    /// 索引器失败、改走 RPC 时的错误
+    pub indexer_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RevokePlan {
+    pub atomic: AtomicStatus,
+    pub calls: Vec<Call>,
+    /// 账户已委托时交给 wallet_sendCalls 的 bundle；委托合约未必实现批量执行，仅作可选项
+    pub bundle: Option<SendCallsParams>,
+    /// 逐笔签名发送，nonce 依次递增；始终提供，bundle 无法执行时用它
+    pub txs: Vec<UnsignedTx>,
+}
+
+// ========== 日志 ==========
+
+/// topic0 为四种授权事件之一、topic1 为 owner
+fn approval_topics(owner: Address) -> Value {
+    let events = [
+        ITokenApprovals::Approval::SIGNATURE_HASH,
+        ITokenApprovals::ApprovalForAll::SIGNATURE_HASH,
+        IAllowanceTransfer::Approval::SIGNATURE_HASH,
+        IAllowanceTransfer::Permit::SIGNATURE_HASH,
+    ];
+    json!([events, owner.into_word()])
+}
+
+fn topic_address(topic: &Value) -> Option<Address> {
+    topic.as_str()?.parse::<B256>().ok().map(Address::from_word)
+}
+
+/// eth_getLogs 给十六进制，部分索引器给数字
+fn log_block(log: &Value) -> u64 {
+    match &log["blockNumber"] {
+        Value::String(s) => str_to_u64(s).unwrap_or_default(),
+        v => v.as_u64().unwrap_or_default(),
+    }
+}
+
+/// ERC-721 的单个 tokenId 授权（4 个 topic）随转移自动清除，不计入
+fn approval_key(log: &Value, permit2: Address) -> Option<ApprovalKey> {
+    let emitter = log["address"].as_str()?.parse::<Address>().ok()?;
+    let topics = log["topics"].as_array()?;
+    let topic0 = topics.first()?.as_str()?.parse::<B256>().ok()?;
+    let (kind, token, spender) = match (topic0, topics.len()) {
+        (t, 3) if t == ITokenApprovals::Approval::SIGNATURE_HASH => (ApprovalKind::Erc20, emitter, topic_address(&topics[2])?),
+        (t, 3) if t == ITokenApprovals::ApprovalForAll::SIGNATURE_HASH => {
+            (ApprovalKind::Operator, emitter, topic_address(&topics[2])?)
+        }
+        (t, 4)
+            if emitter == permit2
+                && (t == IAllowanceTransfer::Approval::SIGNATURE_HASH || t == IAllowanceTransfer::Permit::SIGNATURE_HASH) =>
+        {
+            (ApprovalKind::Permit2, topic_address(&topics[2])?, topic_address(&topics[3])?)
+        }
+        _ => return None,
+    };
+    Some(ApprovalKey { kind, token, spender })
+}
+
+/// 去重，记下每组授权最近出现的区块
+pub fn collect_candidates(logs: &[Value], permit2: Address) -> BTreeMap<ApprovalKey, u64> {
+    let mut candidates = BTreeMap::new();
+    for log in logs {
+        if let Some(key) = approval_key(log, permit2) {
+            let block = candidates.entry(key).or_insert(0);
+            *block = (*block).max(log_block(log));
+        }
+    }
+    candidates
+}
+
+/// 返回 (日志, 是否翻完)；达到 MAX_INDEXER_PAGES 仍有下一页时为 false
+async fn logs_from_indexer(
+    client: &Client,
+    ankr_url: &str,
+    chain: AnkrBlockchain,
+    topics: &Value,
+) -> anyhow::Result<(Vec<Value>, bool)> {
+    let mut logs = Vec::new();
+    let mut page_token = None;
+    for _ in 0..MAX_INDEXER_PAGES {
+        let (page, next) = get_logs_by_ankr(client, ankr_url, chain.as_str(), topics.clone(), page_token).await?;
+        logs.extend(page);
+        match next {
+            Some(token) => page_token = Some(token),
+            None => return Ok((logs, true)),
+        }
+    }
+    Ok((logs, false))
+}
+
+/// 按区块分段查询；节点报范围过大或超时时缩小跨度重试
+async fn logs_from_rpc(client: &Client, url: &str, topics: &Value, from: u64, to: u64) -> Result<Vec<Value>, AppError> {
+    let mut logs = Vec::new();
+    let (mut start, mut chunk) = (from, LOG_CHUNK_BLOCKS);
+    while start <= to {
+        let end = (start + chunk - 1).min(to);
+        let filter = json!({ "fromBlock": format!("0x{start:x}"), "toBlock": format!("0x{end:x}"), "topics": topics });
+        match get_logs(client.clone(), url, filter).await {
+            Ok(page) => {
+                logs.extend(page);
+                start = end + 1;
+            }
+            Err(_) if chunk > MIN_LOG_CHUNK_BLOCKS => chunk /= 2,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(logs)
+}
+
+// ========== 确认 ==========
+
+fn confirm_call(owner: Address, key: &ApprovalKey, permit2: Address) -> multicall3::Call {
+    match key.kind {
+        ApprovalKind::Erc20 => multicall3::Call::new(key.token, &IERC20::allowanceCall { owner, spender: key.spender }),
+        ApprovalKind::Operator => {
+            multicall3::Call::new(key.token, &IERC721::isApprovedForAllCall { owner, operator: key.spender })
+        }
+        ApprovalKind::Permit2 => multicall3::Call::new(
+            permit2,
+            &IAllowanceTransfer::allowanceCall { user: owner, token: key.token, spender: key.spender },
+        ),
+    }
+}
+
+/// 在 head 区块读取当前值；读取失败、额度为 0、已撤销或 Permit2 额度过期的都丢掉
+pub async fn confirm_approvals(
+    multicall: &Multicall3,
+    owner: Address,
+    candidates: &BTreeMap<ApprovalKey, u64>,
+    permit2: Address,
+    now: u64,
+) -> Result<Vec<TokenApproval>, AppError> {
+    let entries: Vec<(&ApprovalKey, &u64)> = candidates.iter().collect();
+    let mut approvals = Vec::new();
+    for batch in entries.chunks(MULTICALL_BATCH) {
+        let calls: Vec<multicall3::Call> = batch.iter().map(|(key, _)| confirm_call(owner, key, permit2)).collect();
+        let results = multicall.aggregate3(&calls).await?;
+        for ((key, block), result) in batch.iter().zip(&results) {
+            let (amount, unlimited, expiration) = match key.kind {
+                ApprovalKind::Erc20 => match result.decode::<IERC20::allowanceCall>() {
+                    Some(amount) if !amount.is_zero() => (Some(amount), amount >= U256::MAX >> 1, None),
+                    _ => continue,
+                },
+                ApprovalKind::Operator => match result.decode::<IERC721::isApprovedForAllCall>() {
+                    Some(true) => (None, true, None),
+                    _ => continue,
+                },
+                ApprovalKind::Permit2 => match result.decode::<IAllowanceTransfer::allowanceCall>() {
+                    // Permit2 在 block.timestamp > expiration 时视为过期
+                    Some(r) if !r.amount.is_zero() && r.expiration.to::<u64>() >= now => {
+                        (Some(U256::from(r.amount)), r.amount == U160::MAX, Some(r.expiration.to::<u64>()))
+                    }
+                    _ => continue,
+                },
+            };
+            approvals.push(TokenApproval {
+                kind: key.kind,
+                token: key.token,
+                token_symbol: None,
+                spender: key.spender,
+                spender_label: None,
+                amount,
+                unlimited,
+                expiration,
+                last_block: **block,
+            });
+        }
+    }
+    Ok(approvals)
+}
+
+// ========== 撤销 ==========
+
+/// 逐个生成撤销调用；Permit2 的授权合并成一笔 lockdown 放在最后
+pub fn revoke_calls(targets: &[ApprovalKey], permit2: Address) -> Vec<Call> {
+    let mut calls = Vec::new();
+    let mut lockdown = Vec::new();
+    for key in targets {
+        let data: Bytes = match key.kind {
+            ApprovalKind::Erc20 => IERC20::approveCall { spender: key.spender, value: U256::ZERO }.abi_encode().into(),
+            ApprovalKind::Operator => set_approval_for_all_calldata(key.spender, false),
+            ApprovalKind::Permit2 => {
+                lockdown.push(IAllowanceTransfer::TokenSpenderPair { token: key.token, spender: key.spender });
+                continue;
+            }
+        };
+        calls.push(Call { to: key.token, data, value: U256::ZERO });
+    }
+    if !lockdown.is_empty() {
+        let data = IAllowanceTransfer::lockdownCall { approvals: lockdown }.abi_encode().into();
+        calls.push(Call { to: permit2, data, value: U256::ZERO });
+    }
+    calls
+}
+
+fn parse_address(value: &str) -> Result<Address, AppError> {
+    value.parse::<Address>().map_err(|_| AppError::Parse("invalid address"))
+}
+
+// ========== 命令 ==========
+
+/// 扫描 owner 当前仍有效的授权。ankr_url 提供且支持该链时查全部历史，
+/// 否则从 from_block（默认回看 DEFAULT_LOOKBACK_SECS）起逐段 eth_getLogs
+#[tauri::command]
+pub async fn approval_scan(
+    owner: String,
+    chain_id: u64,
+    rpc_url: String,
+    ankr_url: Option<String>,
+    from_block: Option<u64>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<ApprovalScan, AppError> {
+    let owner = parse_address(&owner)?;
+    let client = state.https_client.lock().await.clone();
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let permit2 = caps.permit2.unwrap_or(PERMIT2);
+    let head = block_number(client.clone(), &rpc_url).await?;
+    let topics = approval_topics(owner);
+
+    let indexed = match (ankr_url.as_deref(), AnkrBlockchain::from_chain_id(chain_id)) {
+        (Some(ankr_url), Some(chain)) => Some(logs_from_indexer(&client, ankr_url, chain, &topics).await),
+        _ => None,
+    };
+    let (logs, from, complete, indexer_error) = match indexed {
+        Some(Ok((logs, complete))) => (logs, 0, complete, None),
+        other => {
+            let lookback = DEFAULT_LOOKBACK_SECS * 1000 / caps.block_time_ms.max(1);
+            let from = from_block.unwrap_or_else(|| head.saturating_sub(lookback));
+            let logs = logs_from_rpc(&client, &rpc_url, &topics, from, head).await?;
+            (logs, from, from == 0, other.and_then(|r| r.err()).map(|e| e.to_string()))
+        }
+    };
+    let candidates = collect_candidates(&logs, permit2);
+
+    let multicall = Multicall3::new(client, &rpc_url).with_address(caps.multicall3.unwrap_or(MULTICALL3_ADDR)).at_block(head);
+    let mut approvals = confirm_approvals(&multicall, owner, &candidates, permit2, now_s()).await?;
+
+    let tokens = token_meta_map(appdb.db.as_ref()).unwrap_or_default();
+    let names: HashMap<Address, String> = known_names(&state, chain_id).await;
+    for approval in &mut approvals {
+        approval.token_symbol = tokens
+            .get(&(chain_id, approval.token))
+            .map(|(symbol, _)| symbol.clone())
+            .or_else(|| names.get(&approval.token).cloned());
+        approval.spender_label = names.get(&approval.spender).cloned();
+    }
+    // 无限额的排在前面
+    approvals.sort_by(|a, b| b.unlimited.cmp(&a.unlimited).then(b.last_block.cmp(&a.last_block)));
+    Ok(ApprovalScan { approvals, from_block: from, to_block: head, complete, indexer_error })
+}
+
+/// 批量撤销：返回依次递增 nonce 的待签名交易；账户已是 EIP-7702 委托账户时另附 wallet_sendCalls bundle
+#[tauri::command]
+pub async fn approval_revoke_prepare(
+    owner: String,
+    targets: Vec<ApprovalKey>,
+    chain_id: u64,
+    rpc_url: String,
+    fee_tier: Option<FeeTier>,
+    state: State<'_, AppState>,
+) -> Result<RevokePlan, AppError> {
+    let owner = parse_address(&owner)?;
+    let client = state.https_client.lock().await.clone();
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let calls = revoke_calls(&targets, caps.permit2.unwrap_or(PERMIT2));
+    let code = get_code(client.clone(), &rpc_url, &format!("{:#x}", owner), "latest").await.unwrap_or_default();
+    let atomic = AtomicStatus::detect(caps.eip7702, &code);
+
+    let bundle = (atomic == AtomicStatus::Supported).then(|| SendCallsParams::new(chain_id, owner, calls.clone(), true));
+
+    let fees = estimate_fees_for_chain(&client, &rpc_url, chain_id, &state).await?;
+    let fee = fees.tier(fee_tier.unwrap_or(FeeTier::Normal));
+    let mut txs = Vec::with_capacity(calls.len());
+    for (i, call) in calls.iter().enumerate() {
+        let gas = estimate_gas_from(
+            client.clone(),
+            &rpc_url,
+            &format!("{:#x}", owner),
+            &format!("{:#x}", call.to),
+            "0x0",
+            &format!("0x{}", hex::encode(&call.data)),
+        )
+        .await
+        .map(|g| buffered_gas(g.saturating_to::<u64>()))
+        .unwrap_or(FALLBACK_GAS_REVOKE);
+        let mut tx = prepare_tx(&client, &rpc_url, &caps, fee, owner, call.to, U256::ZERO, call.data.clone(), gas).await?;
+        // 每笔都读到同一个 pending nonce，按顺序递增
+        tx.nonce += i as u64;
+        tx.signing_hash = tx.compute_signing_hash();
+        txs.push(tx);
+    }
+    Ok(RevokePlan { atomic, calls, bundle, txs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::multicall3::IMulticall3;
+    use crate::utils::test_http;
+    use alloy_primitives::aliases::U48;
+    use alloy_primitives::address;
+
+    const OWNER: Address = address!("0x1111111111111111111111111111111111111111");
+    const TOKEN: Address = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+    const SPENDER: Address = address!("0x2222222222222222222222222222222222222222");
+    const NFT: Address = address!("0x3333333333333333333333333333333333333333");
+
+    fn log(emitter: Address, topics: Vec<B256>, block: u64) -> Value {
+        json!({ "address": emitter, "topics": topics, "data": "0x", "blockNumber": format!("0x{block:x}") })
+    }
+
+    #[test]
+    fn test_candidates_from_logs() {
+        let (owner, spender) = (OWNER.into_word(), SPENDER.into_word());
+        let logs = vec![
+            log(TOKEN, vec![ITokenApprovals::Approval::SIGNATURE_HASH, owner, spender], 10),
+            log(TOKEN, vec![ITokenApprovals::Approval::SIGNATURE_HASH, owner, spender], 30),
+            // ERC-721 单个 tokenId 授权
+            log(NFT, vec![ITokenApprovals::Approval::SIGNATURE_HASH, owner, spender, B256::with_last_byte(7)], 40),
+            log(NFT, vec![ITokenApprovals::ApprovalForAll::SIGNATURE_HASH, owner, spender], 20),
+            log(PERMIT2, vec![IAllowanceTransfer::Permit::SIGNATURE_HASH, owner, TOKEN.into_word(), spender], 50),
+            // 不是 Permit2 合约发出的同名事件
+            log(NFT, vec![IAllowanceTransfer::Approval::SIGNATURE_HASH, owner, TOKEN.into_word(), spender], 60),
+        ];
+        let candidates = collect_candidates(&logs, PERMIT2);
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[&ApprovalKey { kind: ApprovalKind::Erc20, token: TOKEN, spender: SPENDER }], 30);
+        assert_eq!(candidates[&ApprovalKey { kind: ApprovalKind::Operator, token: NFT, spender: SPENDER }], 20);
+        assert_eq!(candidates[&ApprovalKey { kind: ApprovalKind::Permit2, token: TOKEN, spender: SPENDER }], 50);
+    }
+
+    #[test]
+    fn test_revoke_calls() {
+        let other = address!("0x4444444444444444444444444444444444444444");
+        let targets = [
+            ApprovalKey { kind: ApprovalKind::Permit2, token: TOKEN, spender: SPENDER },
+            ApprovalKey { kind: ApprovalKind::Erc20, token: TOKEN, spender: SPENDER },
+            ApprovalKey { kind: ApprovalKind::Operator, token: NFT, spender: SPENDER },
+            ApprovalKey { kind: ApprovalKind::Permit2, token: other, spender: SPENDER },
+        ];
+        let calls = revoke_calls(&targets, PERMIT2);
+        assert_eq!(calls.len(), 3);
+        let approve = IERC20::approveCall::abi_decode(&calls[0].data).unwrap();
+        assert_eq!((calls[0].to, approve.spender, approve.value), (TOKEN, SPENDER, U256::ZERO));
+        let operator = IERC721::setApprovalForAllCall::abi_decode(&calls[1].data).unwrap();
+        assert_eq!((calls[1].to, operator.approved), (NFT, false));
+        let lockdown = IAllowanceTransfer::lockdownCall::abi_decode(&calls[2].data).unwrap();
+        assert_eq!(calls[2].to, PERMIT2);
+        assert_eq!(lockdown.approvals.iter().map(|p| p.token).collect::<Vec<_>>(), vec![TOKEN, other]);
+
+        assert_eq!(AtomicStatus::detect(true, "0xef0100aabb"), AtomicStatus::Supported);
+        assert_eq!(AtomicStatus::detect(true, "0x"), AtomicStatus::Ready);
+        assert_eq!(AtomicStatus::detect(false, "0xef0100aabb"), AtomicStatus::Unsupported);
+    }
+
+    #[tokio::test]
+    async fn test_confirm_drops_revoked_and_expired() {
+        let now = 1_000_000;
+        let ok = |data: Vec<u8>| IMulticall3::Result { success: true, returnData: data.into() };
+        let permit2_allowance = |expiration: u64| IAllowanceTransfer::allowanceReturn {
+            amount: U160::MAX,
+            expiration: U48::from(expiration),
+            nonce: U48::from(1),
+        };
+        let results = vec![
+            ok(IERC20::allowanceCall::abi_encode_returns(&U256::MAX)),
+            ok(IERC721::isApprovedForAllCall::abi_encode_returns(&false)),
+            // BTreeMap 按 token 排序，过期的那个在前
+            ok(IAllowanceTransfer::allowanceCall::abi_encode_returns(&permit2_allowance(now - 60))),
+            ok(IAllowanceTransfer::allowanceCall::abi_encode_returns(&permit2_allowance(now + 60))),
+        ];
+        let returns = IMulticall3::aggregate3Call::abi_encode_returns(&results);
+        let body = test_http::rpc_result(format!("0x{}", hex::encode(returns)));
+        let base = test_http::serve(vec![("/", body.as_str())]);
+
+        let expired_token = address!("0x5555555555555555555555555555555555555555");
+        let candidates = BTreeMap::from([
+            (ApprovalKey { kind: ApprovalKind::Erc20, token: TOKEN, spender: SPENDER }, 1),
+            (ApprovalKey { kind: ApprovalKind::Operator, token: NFT, spender: SPENDER }, 2),
+            (ApprovalKey { kind: ApprovalKind::Permit2, token: TOKEN, spender: SPENDER }, 3),
+            (ApprovalKey { kind: ApprovalKind::Permit2, token: expired_token, spender: SPENDER }, 4),
+        ]);
+        let multicall = Multicall3::new(Client::new(), &format!("{base}/"));
+        let approvals = confirm_approvals(&multicall, OWNER, &candidates, PERMIT2, now).await.unwrap();
+        assert_eq!(approvals.len(), 2);
+        assert_eq!((approvals[0].kind, approvals[0].unlimited, approvals[0].amount), (ApprovalKind::Erc20, true, Some(U256::MAX)));
+        assert_eq!((approvals[1].kind, approvals[1].expiration), (ApprovalKind::Permit2, Some(now + 60)));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod approval;
+pub mod nft;
+pub mod tx;
@@ -0,0 +1,417 @@
+// NFT 转账与授权：
+// 先用 ERC-165 判断标准，再构造 calldata、检查收款方能否接收、以发送者身份模拟并估算 gas，
+// 按链能力生成待签名交易；前端签名后由 nft_send 校验签名者并广播
+use crate::actions::tx::{UnsignedTx, buffered_gas, prepare_tx, tx_send};
+use crate::core::state::AppState;
+use crate::data::ens::NftStandard;
+use crate::eips::eip7702::DELEGATION_PREFIX;
+use crate::eips::{erc721, erc1155};
+use crate::error::AppError;
+use crate::evm::capability::ChainCapabilities;
+use crate::evm::gas::{FeeTier, TierFee, estimate_fees_for_chain};
+use crate::rpc::method::{eth_call_from, estimate_gas_from, get_code};
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::{SolError, sol};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// 估算失败时的 gas 上限
+pub const FALLBACK_GAS_721: u64 = 150_000;
+pub const FALLBACK_GAS_1155: u64 = 200_000;
+/// 每多一个批量条目追加的 gas
+pub const FALLBACK_GAS_PER_BATCH_ITEM: u64 = 40_000;
+
+sol! {
+    error Error(string message);
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NftItem {
+    pub token_id: U256,
+    /// ERC-1155 数量；ERC-721 忽略
+    pub amount: Option<U256>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NftTransferParams {
+    pub contract: Address,
+    pub from: Address,
+    pub to: Address,
+    pub items: Vec<NftItem>,
+    /// 不传时用 ERC-165 检测
+    pub standard: Option<NftStandard>,
+    /// 透传给接收回调的 data
+    pub data: Option<Bytes>,
+}
+
+/// token_id 有值且为 ERC-721 时是单个 approve，否则是 setApprovalForAll
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NftApprovalParams {
+    pub contract: Address,
+    pub owner: Address,
+    pub operator: Address,
+    pub token_id: Option<U256>,
+    pub approved: bool,
+    pub standard: Option<NftStandard>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiverStatus {
+    /// 普通外部账户，不会触发回调
+    Eoa,
+    /// 合约（或 7702 委托账户）回调返回了正确的 magic
+    Accepts,
+    /// 回调 revert 或返回值不对，safeTransferFrom 会失败
+    Rejects,
+    /// 检查时网络出错
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NftWarning {
+    SelfTransfer,
+    /// 收款方是合约但无法接收该标准的 NFT
+    ReceiverRejects,
+    ReceiverUnverified,
+    /// 收款方是 EIP-7702 委托账户，是否能接收取决于委托合约
+    DelegatedRecipient,
+    /// 授权对象是普通外部账户，通常是钓鱼
+    OperatorIsEoa,
+    SimulationReverted { reason: Option<String>, data: String },
+    SimulationFailed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub success: bool,
+    pub gas_estimate: Option<u64>,
+    pub revert_data: Option<String>,
+    pub revert_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NftPlan {
+    pub standard: NftStandard,
+    pub tx: UnsignedTx,
+    /// 授权交易不检查接收方
+    pub receiver: Option<ReceiverStatus>,
+    pub simulation: SimulationResult,
+    pub warnings: Vec<NftWarning>,
+}
+
+/// 依次检查 ERC-721、ERC-1155 接口 ID
+pub async fn detect_standard(client: &Client, url: &str, contract: &Address) -> Result<NftStandard, AppError> {
+    if erc721::supports_interface(client, url, contract, erc721::INTERFACE_ID).await? {
+        return Ok(NftStandard::Erc721);
+    }
+    if erc721::supports_interface(client, url, contract, erc1155::INTERFACE_ID).await? {
+        return Ok(NftStandard::Erc1155);
+    }
+    Err(AppError::NftStandardUnknown(format!("{:#x}", contract)))
+}
+
+/// 没有代码的是 EOA；有代码（含 7702 委托）的调用对应回调确认
+pub async fn check_receiver(
+    client: &Client,
+    url: &str,
+    standard: NftStandard,
+    contract: &Address,
+    from: &Address,
+    to: &Address,
+    items: &[(U256, U256)],
+) -> (ReceiverStatus, bool) {
+    let Ok(code) = get_code(client.clone(), url, &format!("{:#x}", to), "latest").await else {
+        return (ReceiverStatus::Unknown, false);
+    };
+    if code.is_empty() || code == "0x" {
+        return (ReceiverStatus::Eoa, false);
+    }
+    let delegated = code.to_lowercase().starts_with(DELEGATION_PREFIX);
+    let accepts = match standard {
+        NftStandard::Erc721 => {
+            let token_id = items.first().map(|(id, _)| *id).unwrap_or_default();
+            erc721::receiver_accepts(client, url, contract, from, to, token_id).await
+        }
+        NftStandard::Erc1155 => erc1155::receiver_accepts(client, url, contract, from, to, items).await,
+    };
+    let status = match accepts {
+        Some(true) => ReceiverStatus::Accepts,
+        Some(false) => ReceiverStatus::Rejects,
+        None => ReceiverStatus::Unknown,
+    };
+    (status, delegated)
+}
+
+/// 解析 Error(string)；其它自定义错误只保留原始数据
+pub fn revert_reason(data_hex: &str) -> Option<String> {
+    let raw = hex::decode(data_hex.trim_start_matches("0x")).ok()?;
+    Error::abi_decode(&raw).ok().map(|e| e.message)
+}
+
+/// 以 from 身份 eth_call 并估算 gas
+pub async fn simulate(client: &Client, url: &str, from: &Address, to: &Address, data: &Bytes) -> Result<SimulationResult, AppError> {
+    let (from, to, data) = (format!("{:#x}", from), format!("{:#x}", to), format!("0x{}", hex::encode(data)));
+    match eth_call_from(client.clone(), url, &from, &to, &data, "latest").await {
+        Ok(_) => {
+            let gas_estimate = estimate_gas_from(client.clone(), url, &from, &to, "0x0", &data)
+                .await
+                .ok()
+                .map(|gas| gas.saturating_to::<u64>());
+            Ok(SimulationResult { success: true, gas_estimate, revert_data: None, revert_reason: None })
+        }
+        Err(AppError::RpcReverted(revert)) => Ok(SimulationResult {
+            success: false,
+            gas_estimate: None,
+            revert_reason: revert_reason(&revert),
+            revert_data: Some(revert),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+fn fallback_gas(standard: NftStandard, items: usize) -> u64 {
+    match standard {
+        NftStandard::Erc721 => FALLBACK_GAS_721,
+        NftStandard::Erc1155 => FALLBACK_GAS_1155 + FALLBACK_GAS_PER_BATCH_ITEM * items.saturating_sub(1) as u64,
+    }
+}
+
+/// 构造交易并附上模拟结果；模拟失败不报错，只记入 warnings
+async fn build_plan(
+    client: &Client,
+    url: &str,
+    caps: &ChainCapabilities,
+    fee: &TierFee,
+    standard: NftStandard,
+    from: Address,
+    contract: Address,
+    data: Bytes,
+    items: usize,
+    mut warnings: Vec<NftWarning>,
+    receiver: Option<ReceiverStatus>,
+) -> Result<NftPlan, AppError> {
+    let simulation = match simulate(client, url, &from, &contract, &data).await {
+        Ok(simulation) => simulation,
+        Err(e) => {
+            warnings.push(NftWarning::SimulationFailed { error: e.to_string() });
+            SimulationResult { success: false, gas_estimate: None, revert_data: None, revert_reason: None }
+        }
+    };
+    if let Some(revert) = &simulation.revert_data {
+        warnings.push(NftWarning::SimulationReverted { reason: simulation.revert_reason.clone(), data: revert.clone() });
+    }
+    let gas_limit = simulation.gas_estimate.map(buffered_gas).unwrap_or_else(|| fallback_gas(standard, items));
+    let tx = prepare_tx(client, url, caps, fee, from, contract, U256::ZERO, data, gas_limit).await?;
+    Ok(NftPlan { standard, tx, receiver, simulation, warnings })
+}
+
+/// 校验并生成转账 calldata；返回 (calldata, 回调用的 (id, amount) 列表)
+pub fn transfer_calldata(standard: NftStandard, params: &NftTransferParams) -> Result<(Bytes, Vec<(U256, U256)>), AppError> {
+    if params.to == Address::ZERO {
+        return Err(AppError::Parse("recipient is the zero address"));
+    }
+    let data = params.data.clone().unwrap_or_default();
+    let items: Vec<(U256, U256)> = params
+        .items
+        .iter()
+        .map(|item| (item.token_id, item.amount.unwrap_or(U256::from(1))))
+        .collect();
+    let calldata = match (standard, items.as_slice()) {
+        (_, []) => return Err(AppError::Parse("no NFT to transfer")),
+        (NftStandard::Erc721, [(id, _)]) => erc721::safe_transfer_calldata(params.from, params.to, *id, &data),
+        (NftStandard::Erc721, _) => return Err(AppError::Parse("ERC-721 transfers one token per transaction")),
+        (NftStandard::Erc1155, [(id, amount)]) => {
+            erc1155::safe_transfer_calldata(params.from, params.to, *id, *amount, &data)
+        }
+        (NftStandard::Erc1155, _) => erc1155::safe_batch_transfer_calldata(
+            params.from,
+            params.to,
+            items.iter().map(|(id, _)| *id).collect(),
+            items.iter().map(|(_, amount)| *amount).collect(),
+            &data,
+        )?,
+    };
+    Ok((calldata, items))
+}
+
+pub fn approval_calldata(standard: NftStandard, params: &NftApprovalParams) -> Bytes {
+    match (standard, params.token_id) {
+        (NftStandard::Erc721, Some(token_id)) => {
+            let spender = if params.approved { params.operator } else { Address::ZERO };
+            erc721::approve_calldata(spender, token_id)
+        }
+        _ => erc721::set_approval_for_all_calldata(params.operator, params.approved),
+    }
+}
+
+async fn resolve_standard(client: &Client, url: &str, contract: &Address, given: Option<NftStandard>) -> Result<NftStandard, AppError> {
+    match given {
+        Some(standard) => Ok(standard),
+        None => detect_standard(client, url, contract).await,
+    }
+}
+
+#[tauri::command]
+pub async fn nft_detect_standard(
+    contract: String,
+    rpc_url: String,
+    state: State<'_, AppState>,
+) -> Result<NftStandard, AppError> {
+    let contract = contract.parse::<Address>().map_err(|_| AppError::Parse("invalid address"))?;
+    let client = state.https_client.lock().await.clone();
+    detect_standard(&client, &rpc_url, &contract).await
+}
+
+#[tauri::command]
+pub async fn nft_transfer_prepare(
+    params: NftTransferParams,
+    chain_id: u64,
+    rpc_url: String,
+    fee_tier: Option<FeeTier>,
+    state: State<'_, AppState>,
+) -> Result<NftPlan, AppError> {
+    let client = state.https_client.lock().await.clone();
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let fees = estimate_fees_for_chain(&client, &rpc_url, chain_id, &state).await?;
+    let fee = fees.tier(fee_tier.unwrap_or(FeeTier::Normal));
+    let standard = resolve_standard(&client, &rpc_url, &params.contract, params.standard).await?;
+    let (calldata, items) = transfer_calldata(standard, &params)?;
+
+    let mut warnings = Vec::new();
+    if params.to == params.from {
+        warnings.push(NftWarning::SelfTransfer);
+    }
+    let (receiver, delegated) =
+        check_receiver(&client, &rpc_url, standard, &params.contract, &params.from, &params.to, &items).await;
+    if delegated {
+        warnings.push(NftWarning::DelegatedRecipient);
+    }
+    match receiver {
+        ReceiverStatus::Rejects => warnings.push(NftWarning::ReceiverRejects),
+        ReceiverStatus::Unknown => warnings.push(NftWarning::ReceiverUnverified),
+        ReceiverStatus::Eoa | ReceiverStatus::Accepts => {}
+    }
+    build_plan(&client, &rpc_url, &caps, fee, standard, params.from, params.contract, calldata, items.len(), warnings, Some(receiver))
+        .await
+}
+
+#[tauri::command]
+pub async fn nft_approval_prepare(
+    params: NftApprovalParams,
+    chain_id: u64,
+    rpc_url: String,
+    fee_tier: Option<FeeTier>,
+    state: State<'_, AppState>,
+) -> Result<NftPlan, AppError> {
+    let client = state.https_client.lock().await.clone();
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let fees = estimate_fees_for_chain(&client, &rpc_url, chain_id, &state).await?;
+    let fee = fees.tier(fee_tier.unwrap_or(FeeTier::Normal));
+    let standard = resolve_standard(&client, &rpc_url, &params.contract, params.standard).await?;
+    let calldata = approval_calldata(standard, &params);
+
+    let mut warnings = Vec::new();
+    if params.approved {
+        let code = get_code(client.clone(), &rpc_url, &format!("{:#x}", params.operator), "latest").await;
+        if matches!(code.as_deref(), Ok("") | Ok("0x")) {
+            warnings.push(NftWarning::OperatorIsEoa);
+        }
+    }
+    build_plan(&client, &rpc_url, &caps, fee, standard, params.owner, params.contract, calldata, 1, warnings, None).await
+}
+
+/// signature 为对 tx.signing_hash 的 65 字节签名；返回交易哈希
+#[tauri::command]
+pub async fn nft_send(
+    tx: UnsignedTx,
+    signature: String,
+    rpc_url: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    tx_send(tx, signature, rpc_url, state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_http;
+    use alloy_primitives::address;
+
+    fn params(items: Vec<NftItem>) -> NftTransferParams {
+        NftTransferParams {
+            contract: address!("0x3333333333333333333333333333333333333333"),
+            from: address!("0x1111111111111111111111111111111111111111"),
+            to: address!("0x2222222222222222222222222222222222222222"),
+            items,
+            standard: None,
+            data: None,
+        }
+    }
+
+    fn item(id: u64, amount: Option<u64>) -> NftItem {
+        NftItem { token_id: U256::from(id), amount: amount.map(U256::from) }
+    }
+
+    #[test]
+    fn test_transfer_calldata_by_standard() {
+        let (single, _) = transfer_calldata(NftStandard::Erc721, &params(vec![item(1, None)])).unwrap();
+        assert_eq!(&single[..4], &[0x42, 0x84, 0x2e, 0x0e]);
+        assert!(transfer_calldata(NftStandard::Erc721, &params(vec![item(1, None), item(2, None)])).is_err());
+
+        let (one, items) = transfer_calldata(NftStandard::Erc1155, &params(vec![item(1, None)])).unwrap();
+        assert_eq!(&one[..4], &[0xf2, 0x42, 0x43, 0x2a]);
+        assert_eq!(items, vec![(U256::from(1), U256::from(1))]);
+        let (batch, _) = transfer_calldata(NftStandard::Erc1155, &params(vec![item(1, Some(3)), item(2, Some(4))])).unwrap();
+        assert_eq!(&batch[..4], &[0x2e, 0xb2, 0xc2, 0xd6]);
+
+        let mut zero = params(vec![item(1, None)]);
+        zero.to = Address::ZERO;
+        assert!(transfer_calldata(NftStandard::Erc721, &zero).is_err());
+        assert!(transfer_calldata(NftStandard::Erc721, &params(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_approval_revoke_single_token() {
+        let mut approval = NftApprovalParams {
+            contract: address!("0x3333333333333333333333333333333333333333"),
+            owner: address!("0x1111111111111111111111111111111111111111"),
+            operator: address!("0x2222222222222222222222222222222222222222"),
+            token_id: Some(U256::from(9)),
+            approved: false,
+            standard: None,
+        };
+        // 撤销单个 approve 即授权给零地址
+        let data = approval_calldata(NftStandard::Erc721, &approval);
+        assert_eq!(&data[..4], &[0x09, 0x5e, 0xa7, 0xb3]);
+        assert!(data[4..36].iter().all(|b| *b == 0));
+        approval.token_id = None;
+        assert_eq!(&approval_calldata(NftStandard::Erc1155, &approval)[..4], &[0xa2, 0x2c, 0xb4, 0x65]);
+    }
+
+    #[test]
+    fn test_revert_reason_and_gas_buffer() {
+        let data = format!("0x{}", hex::encode(Error { message: "not owner".into() }.abi_encode()));
+        assert_eq!(revert_reason(&data).as_deref(), Some("not owner"));
+        assert_eq!(revert_reason("0xdeadbeef"), None);
+        assert_eq!(buffered_gas(100_000), 120_000);
+        assert_eq!(fallback_gas(NftStandard::Erc1155, 3), FALLBACK_GAS_1155 + 2 * FALLBACK_GAS_PER_BATCH_ITEM);
+    }
+
+    #[tokio::test]
+    async fn test_detect_standard() {
+        let contract = address!("0x3333333333333333333333333333333333333333");
+        let yes = test_http::rpc_result(format!("0x{}1", "0".repeat(63)));
+        let base = test_http::serve(vec![("/", yes.as_str())]);
+        assert_eq!(detect_standard(&Client::new(), &base, &contract).await.unwrap(), NftStandard::Erc721);
+
+        let no = test_http::rpc_result(format!("0x{}", "0".repeat(64)));
+        let base = test_http::serve(vec![("/", no.as_str())]);
+        assert!(matches!(
+            detect_standard(&Client::new(), &base, &contract).await,
+            Err(AppError::NftStandardUnknown(_))
+        ));
+    }
+}
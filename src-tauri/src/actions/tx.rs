@@ -0,0 +1,353 @@
+// 待签名交易：按链能力选择 EIP-1559 或 legacy，费用取自手续费预言机的某一档，
+// L2 上补足 L1 数据费后计算签名哈希；签名回来后校验签名者并编码成原始交易
+use crate::core::state::AppState;
+use crate::eips::eip1559::{build_1559_elp, eip1559_signing_hash};
+use crate::eips::eip155::build_155_elp;
+use crate::error::AppError;
+use crate::evm::capability::{ChainCapabilities, L2Type};
+use crate::evm::gas::{FeeTier, TierFee, estimate_fees_for_chain};
+use crate::evm::l2fee::{
+    FeeBreakdown, L1FeeSource, arbitrum_gas_components, linea_estimate_gas, max_sendable, op_l1_fee, with_l1_buffer,
+};
+use crate::rpc::method::{estimate_gas_from, get_balance, get_nonce, send_raw_transaction};
+use crate::utils::num::str_to_u256;
+use alloy_consensus::{SignableTransaction, TxEip1559, TxLegacy};
+use alloy_eips::eip2930::AccessList;
+use alloy_primitives::{Address, B256, Bytes, Signature, TxKind, U256};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// 估算值上浮比例（百分比）
+pub const GAS_LIMIT_BUFFER_PCT: u64 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnsignedTx {
+    pub chain_id: u64,
+    pub from: Address,
+    pub to: Address,
+    pub nonce: u64,
+    pub value: U256,
+    pub data: Bytes,
+    pub gas_limit: u64,
+    /// legacy 交易时即 gasPrice
+    pub max_fee_per_gas: u128,
+    /// legacy 交易时忽略
+    pub max_priority_fee_per_gas: u128,
+    /// 链不支持 EIP-1559 时发 EIP-155 legacy 交易
+    pub legacy: bool,
+    /// 手续费拆分（L2 执行费 / L1 数据费），供确认页展示
+    #[serde(default)]
+    pub cost: FeeBreakdown,
+    pub signing_hash: B256,
+}
+
+impl UnsignedTx {
+    pub fn to_eip1559(&self) -> TxEip1559 {
+        TxEip1559 {
+            chain_id: self.chain_id,
+            nonce: self.nonce,
+            gas_limit: self.gas_limit,
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+            to: TxKind::Call(self.to),
+            value: self.value,
+            input: self.data.clone(),
+            access_list: AccessList::default(),
+        }
+    }
+
+    pub fn to_legacy(&self) -> TxLegacy {
+        TxLegacy {
+            chain_id: Some(self.chain_id),
+            nonce: self.nonce,
+            gas_price: self.max_fee_per_gas,
+            gas_limit: self.gas_limit,
+            to: TxKind::Call(self.to),
+            value: self.value,
+            input: self.data.clone(),
+        }
+    }
+
+    pub fn compute_signing_hash(&self) -> B256 {
+        if self.legacy {
+            self.to_legacy().signature_hash()
+        } else {
+            eip1559_signing_hash(&self.to_eip1559())
+        }
+    }
+
+    /// 最多花费的手续费（gas_limit × max_fee）
+    pub fn max_fee_cost(&self) -> U256 {
+        U256::from(self.gas_limit) * U256::from(self.max_fee_per_gas)
+    }
+
+    /// 未签名交易的类型化编码，OP Stack 按它计算 L1 数据费
+    pub fn encoded_for_signing(&self) -> Vec<u8> {
+        if self.legacy {
+            self.to_legacy().encoded_for_signing()
+        } else {
+            self.to_eip1559().encoded_for_signing()
+        }
+    }
+}
+
+pub fn buffered_gas(estimate: u64) -> u64 {
+    estimate.saturating_add(estimate * GAS_LIMIT_BUFFER_PCT / 100)
+}
+
+/// 取 nonce，按链能力选择交易类型，费用用 fee 这一档
+pub async fn prepare_tx(
+    client: &Client,
+    url: &str,
+    caps: &ChainCapabilities,
+    fee: &TierFee,
+    from: Address,
+    to: Address,
+    value: U256,
+    data: Bytes,
+    gas_limit: u64,
+) -> Result<UnsignedTx, AppError> {
+    let mut tx = base_tx(client, url, caps, fee, from, to, value, data, gas_limit).await?;
+    // 普通发送只影响展示的手续费，L1 / 数据费查询失败时只计执行费
+    let estimate_value = tx.value;
+    if let Err(e) = apply_l2_costs(client, url, caps, &mut tx, estimate_value).await {
+        eprintln!("L2 fee component unavailable: {}", e);
+        tx.cost = FeeBreakdown::new(tx.gas_limit, 0, tx.max_fee_per_gas, U256::ZERO, L1FeeSource::None);
+    }
+    tx.signing_hash = tx.compute_signing_hash();
+    Ok(tx)
+}
+
+/// 读取 nonce 并填好费率，不含 L2 费用拆分与签名哈希
+async fn base_tx(
+    client: &Client,
+    url: &str,
+    caps: &ChainCapabilities,
+    fee: &TierFee,
+    from: Address,
+    to: Address,
+    value: U256,
+    data: Bytes,
+    gas_limit: u64,
+) -> Result<UnsignedTx, AppError> {
+    let nonce = get_nonce(client.clone(), url, &format!("{:#x}", from), "pending").await?;
+    Ok(UnsignedTx {
+        chain_id: caps.chain_id,
+        from,
+        to,
+        nonce,
+        value,
+        data,
+        gas_limit,
+        max_fee_per_gas: fee.max_fee_per_gas,
+        max_priority_fee_per_gas: fee.max_priority_fee_per_gas.min(fee.max_fee_per_gas),
+        legacy: !caps.eip1559,
+        cost: FeeBreakdown::default(),
+        signing_hash: B256::ZERO,
+    })
+}
+
+/// 按 L2 类型调整 gas 并算出费用拆分；L1 / 数据费查询失败时返回错误，由调用方决定是否降级。
+/// estimate_value 用于节点估算（Arbitrum / Linea），转出全部余额时传 0，避免节点因余额不足拒绝估算
+async fn apply_l2_costs(
+    client: &Client,
+    url: &str,
+    caps: &ChainCapabilities,
+    tx: &mut UnsignedTx,
+    estimate_value: U256,
+) -> Result<(), AppError> {
+    let (mut l1_gas, mut l1_data_fee, mut source) = (0, U256::ZERO, L1FeeSource::None);
+    match caps.l2 {
+        Some(L2Type::OpStack) => {
+            let fee = op_l1_fee(client, url, &tx.encoded_for_signing()).await?;
+            l1_data_fee = with_l1_buffer(fee);
+            source = L1FeeSource::OpGasPriceOracle;
+        }
+        Some(L2Type::Arbitrum) => {
+            let components = arbitrum_gas_components(client, url, &tx.from, &tx.to, estimate_value, &tx.data).await?;
+            // eth_estimateGas 在 Arbitrum 上已含 L1 部分；用了兜底 gas 时在这里补足
+            tx.gas_limit = tx.gas_limit.max(buffered_gas(components.gas_estimate));
+            l1_gas = components.gas_estimate_for_l1;
+            source = L1FeeSource::ArbitrumNodeInterface;
+        }
+        Some(L2Type::Linea) => {
+            let estimate = linea_estimate_gas(client, url, &tx.from, &tx.to, estimate_value, &tx.data).await?;
+            // 预言机的 tip 不含数据成本，必须至少用节点给出的值
+            tx.gas_limit = tx.gas_limit.max(estimate.gas_limit);
+            tx.max_priority_fee_per_gas = tx.max_priority_fee_per_gas.max(estimate.priority_fee_per_gas);
+            let floor = estimate.base_fee_per_gas.saturating_mul(2).saturating_add(tx.max_priority_fee_per_gas);
+            tx.max_fee_per_gas = tx.max_fee_per_gas.max(floor);
+            if tx.legacy {
+                tx.max_priority_fee_per_gas = tx.max_fee_per_gas;
+            }
+            source = L1FeeSource::LineaEstimateGas;
+        }
+        Some(L2Type::ZkRollup) | None => {}
+    }
+    tx.cost = FeeBreakdown::new(tx.gas_limit, l1_gas, tx.max_fee_per_gas, l1_data_fee, source);
+    Ok(())
+}
+
+/// 65 字节签名，校验恢复出的地址与交易 from 一致后组装成原始交易
+pub async fn assemble_signed(tx: &UnsignedTx, signature_hex: &str) -> Result<String, AppError> {
+    let raw = hex::decode(signature_hex.trim_start_matches("0x")).map_err(|_| AppError::Parse("invalid signature hex"))?;
+    let sig = Signature::from_raw(&raw).map_err(|_| AppError::Parse("invalid signature"))?;
+    // 以重新计算的哈希为准，不信任前端传回的 signing_hash
+    let hash = tx.compute_signing_hash();
+    if sig.recover_address_from_prehash(&hash).ok() != Some(tx.from) {
+        return Err(AppError::TxSignerMismatch);
+    }
+    if tx.legacy {
+        build_155_elp(tx.to_legacy(), sig).await
+    } else {
+        build_1559_elp(tx.to_eip1559(), sig).await
+    }
+}
+
+fn parse_address(value: &str) -> Result<Address, AppError> {
+    value.parse::<Address>().map_err(|_| AppError::Parse("invalid address"))
+}
+
+/// 估算 gas（含上浮）并生成待签名交易；value 为十六进制 wei
+#[tauri::command]
+pub async fn tx_prepare(
+    from: String,
+    to: String,
+    value: String,
+    data: Option<String>,
+    chain_id: u64,
+    rpc_url: String,
+    fee_tier: Option<FeeTier>,
+    state: State<'_, AppState>,
+) -> Result<UnsignedTx, AppError> {
+    let (from, to, value) = (parse_address(&from)?, parse_address(&to)?, str_to_u256(&value)?);
+    let data = Bytes::from(hex::decode(data.as_deref().unwrap_or("0x").trim_start_matches("0x"))?);
+    let client = state.https_client.lock().await.clone();
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let fees = estimate_fees_for_chain(&client, &rpc_url, chain_id, &state).await?;
+    let gas = estimate_gas_from(
+        client.clone(),
+        &rpc_url,
+        &format!("{:#x}", from),
+        &format!("{:#x}", to),
+        &format!("{:#x}", value),
+        &format!("0x{}", hex::encode(&data)),
+    )
+    .await?
+    .saturating_to::<u64>();
+    prepare_tx(&client, &rpc_url, &caps, fees.tier(fee_tier.unwrap_or(FeeTier::Normal)), from, to, value, data, buffered_gas(gas))
+        .await
+}
+
+/// 转出全部原生币：金额 = 余额 − 全部手续费（L2 含 L1 数据费）；
+/// L1 / 数据费拿不到时报错，否则少算的费用会让交易因余额不足失败
+#[tauri::command]
+pub async fn tx_prepare_send_max(
+    from: String,
+    to: String,
+    chain_id: u64,
+    rpc_url: String,
+    fee_tier: Option<FeeTier>,
+    state: State<'_, AppState>,
+) -> Result<UnsignedTx, AppError> {
+    let (from, to) = (parse_address(&from)?, parse_address(&to)?);
+    let client = state.https_client.lock().await.clone();
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let fees = estimate_fees_for_chain(&client, &rpc_url, chain_id, &state).await?;
+    let balance = str_to_u256(&get_balance(client.clone(), &rpc_url, &format!("{:#x}", from), "pending").await?)?;
+    // 用 0 金额估算，避免节点因余额不足拒绝估算
+    let gas = estimate_gas_from(client.clone(), &rpc_url, &format!("{:#x}", from), &format!("{:#x}", to), "0x0", "0x")
+        .await?
+        .saturating_to::<u64>();
+    // 先按全部余额构造，L1 数据费按最长的编码估算，只会偏高
+    let fee = fees.tier(fee_tier.unwrap_or(FeeTier::Normal));
+    let mut tx = base_tx(&client, &rpc_url, &caps, fee, from, to, balance, Bytes::new(), buffered_gas(gas)).await?;
+    apply_l2_costs(&client, &rpc_url, &caps, &mut tx, U256::ZERO)
+        .await
+        .map_err(|e| AppError::L2FeeUnavailable(e.to_string()))?;
+    tx.value = max_sendable(balance, &tx.cost).ok_or(AppError::TxInsufficientFunds)?;
+    tx.signing_hash = tx.compute_signing_hash();
+    Ok(tx)
+}
+
+/// signature 为对 tx.signing_hash 的 65 字节签名；返回交易哈希
+#[tauri::command]
+pub async fn tx_send(
+    tx: UnsignedTx,
+    signature: String,
+    rpc_url: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let raw_tx = assemble_signed(&tx, &signature).await?;
+    let client = state.https_client.lock().await.clone();
+    send_raw_transaction(client, &rpc_url, &raw_tx).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::capability::builtin_capabilities;
+    use crate::utils::test_http;
+    use alloy_primitives::address;
+
+    fn tx(legacy: bool) -> UnsignedTx {
+        UnsignedTx {
+            chain_id: 1,
+            from: address!("0x1111111111111111111111111111111111111111"),
+            to: address!("0x2222222222222222222222222222222222222222"),
+            nonce: 3,
+            value: U256::ZERO,
+            data: Bytes::new(),
+            gas_limit: 21_000,
+            max_fee_per_gas: 2_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+            legacy,
+            cost: FeeBreakdown::default(),
+            signing_hash: B256::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_signing_hash_depends_on_type() {
+        assert_ne!(tx(true).compute_signing_hash(), tx(false).compute_signing_hash());
+        assert_eq!(tx(false).max_fee_cost(), U256::from(42_000_000_000_000u64));
+        assert_eq!(buffered_gas(100_000), 120_000);
+    }
+
+    #[tokio::test]
+    async fn test_assemble_rejects_wrong_signer() {
+        let sig = format!("0x{}1b", "11".repeat(64));
+        assert!(assemble_signed(&tx(false), &sig).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prepare_adds_op_l1_fee() {
+        // nonce 与 getL1Fee 都读到 5000
+        let body = test_http::rpc_result(format!("0x{:064x}", 5_000));
+        let base = test_http::serve(vec![("/", body.as_str())]);
+        let fee = TierFee {
+            tier: FeeTier::Normal,
+            max_fee_per_gas: 1_000,
+            max_priority_fee_per_gas: 10,
+            expected_inclusion_ms: 2_000,
+            capped: false,
+        };
+        let t = tx(false);
+        let caps = builtin_capabilities(10).unwrap();
+        let prepared = prepare_tx(&Client::new(), &format!("{base}/"), &caps, &fee, t.from, t.to, U256::ZERO, Bytes::new(), 21_000)
+            .await
+            .unwrap();
+        assert_eq!(prepared.nonce, 5_000);
+        assert_eq!(prepared.cost.source, L1FeeSource::OpGasPriceOracle);
+        assert_eq!(prepared.cost.l1_data_fee, U256::from(6_250));
+        assert_eq!(prepared.cost.total_fee, U256::from(21_000_000u64 + 6_250));
+
+        // 预言机不可用：普通发送降级为只计执行费，转出全部余额则必须报错
+        let mut unpriced = base_tx(&Client::new(), &format!("{base}/"), &caps, &fee, t.from, t.to, U256::ZERO, Bytes::new(), 21_000)
+            .await
+            .unwrap();
+        let down = test_http::unreachable();
+        assert!(apply_l2_costs(&Client::new(), &down, &caps, &mut unpriced, U256::ZERO).await.is_err());
+    }
+}
@@ -2,7 +2,7 @@ use super::{
     actions::{self, V4Action},
     contracts::IUniversalRouter,
 };
-use crate::permit2::IAllowanceTransfer;
+use crate::evm::permit2::IAllowanceTransfer;
 use alloy_primitives::{Address, Bytes, U256};
 use alloy_sol_types::{SolCall, SolType, sol_data};
 
@@ -2,6 +2,7 @@ use alloy_primitives::{Address, Bytes, aliases::U24};
 use std::fmt::Display;
 
 use super::FeeTier;
+use crate::evm::capability::ChainCapabilities;
 use primitives::EVMChain;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -74,9 +75,25 @@ impl BasePair {
     }
 }
 
-pub fn get_base_pair(chain: &EVMChain, weth_as_native: bool) -> Option<BasePair> {
+/// chain_id → EVMChain（钱包内置支持的链）
+pub fn evm_chain_from_id(chain_id: u64) -> Option<EVMChain> {
+    Some(match chain_id {
+        1 => EVMChain::Ethereum,
+        10 => EVMChain::Optimism,
+        56 => EVMChain::SmartChain,
+        137 => EVMChain::Polygon,
+        8453 => EVMChain::Base,
+        42161 => EVMChain::Arbitrum,
+        43114 => EVMChain::AvalancheC,
+        59144 => EVMChain::Linea,
+        _ => return None,
+    })
+}
+
+/// 包装原生币地址取自链能力表
+pub fn get_base_pair(chain: &EVMChain, caps: &ChainCapabilities, weth_as_native: bool) -> Option<BasePair> {
     let native = if weth_as_native {
-        chain.weth_contract()?.parse().ok()?
+        caps.weth?
     } else {
         Address::ZERO
     };
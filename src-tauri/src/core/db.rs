@@ -7,11 +7,21 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tauri::Manager;
 // 引入自定义错误类型
-use crate::data::tx::TransactionHistoryEntry;
+use crate::data::tx::{TransactionHistoryEntry, TransactionHistoryEntryV1};
 use crate::error::AppError;
 
 pub type DbResult<T> = Result<T, AppError>;
 
+/// 交易历史当前 schema 版本（v2: receipt 状态 / 方法名 / Transfer 腿 / 标注）
+pub const TX_HISTORY_SCHEMA_VERSION: u64 = 2;
+const TX_HISTORY_SCHEMA_KEY: &str = "tx_history_schema_version";
+/// 签名历史当前 schema 版本（v2: 哈希链）
+pub const MSG_HISTORY_SCHEMA_VERSION: u64 = 2;
+const MSG_HISTORY_SCHEMA_KEY: &str = "msg_history_schema_version";
+/// 地址簿当前 schema 版本（v2: ens 字段 + 小写地址 key）
+pub const ADDRESSBOOK_SCHEMA_VERSION: u64 = 2;
+const ADDRESSBOOK_SCHEMA_KEY: &str = "addressbook_schema_version";
+
 pub struct AppDB {
     pub db: Arc<DBWithThreadMode<MultiThreaded>>,
 }
@@ -45,6 +55,8 @@ impl AppDB {
             TableKind::AddressBook.as_str(),
             TableKind::TxHistory.as_str(),
             TableKind::MsgHistory.as_str(),
+            TableKind::Registry.as_str(),
+            TableKind::Cache.as_str(),
         ];
 
         let cfs: Vec<_> = cf_names
@@ -56,8 +68,54 @@ impl AppDB {
         let db = DBWithThreadMode::<MultiThreaded>::open_cf_descriptors(&opts, &path, cfs)
             .map_err(|e| AppError::DbWriteError(e.to_string()))?;
 
+        // 5. schema 迁移
+        Self::migrate(&db)?;
+
         Ok(Self { db: Arc::new(db) })
     }
+
+    /// 按 config 中记录的版本号依次执行迁移
+    fn migrate(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<()> {
+        Self::migrate_table(db, TX_HISTORY_SCHEMA_KEY, TX_HISTORY_SCHEMA_VERSION, |db, from| match from {
+            1 => TxHistoryManager::new(db).migrate_v1_to_v2(),
+            _ => Ok(()),
+        })?;
+        Self::migrate_table(db, MSG_HISTORY_SCHEMA_KEY, MSG_HISTORY_SCHEMA_VERSION, |db, from| match from {
+            1 => crate::data::msg::migrate_legacy(db),
+            _ => Ok(()),
+        })?;
+        Self::migrate_table(db, ADDRESSBOOK_SCHEMA_KEY, ADDRESSBOOK_SCHEMA_VERSION, |db, from| match from {
+            1 => crate::data::addr::migrate_v1_to_v2(db),
+            _ => Ok(()),
+        })?;
+        Ok(())
+    }
+
+    /// 从记录的版本（缺省为 1）逐级执行 step(from) 直到 target，最后写回版本号
+    fn migrate_table(
+        db: &DBWithThreadMode<MultiThreaded>,
+        version_key: &str,
+        target: u64,
+        step: impl Fn(&DBWithThreadMode<MultiThreaded>, u64) -> DbResult<()>,
+    ) -> DbResult<()> {
+        let config = TableManager::new(db, TableKind::Config)?;
+        let key = config.key_from_str(version_key);
+        let version = config.get::<u64>(&key)?.unwrap_or(1);
+        for from in version..target {
+            step(db, from)?;
+        }
+        if version != target {
+            config.set(&key, &target)?;
+        }
+        Ok(())
+    }
+}
+
+/// Cache 表中带过期时间的值
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct Expiring<T> {
+    pub value: T,
+    pub expires_at: u64,
 }
 
 // ========== 表分类 ==========
@@ -69,6 +127,8 @@ pub enum TableKind {
     AddressBook,
     TxHistory,
     MsgHistory,
+    Registry, // 可更新的注册表：合约标签 / 链 / 代币列表等
+    Cache,    // 可重建的远程数据：汇率 / ENS / 元数据等，丢失只影响离线展示
 }
 
 impl TableKind {
@@ -80,6 +140,8 @@ impl TableKind {
             TableKind::AddressBook => "addressbook",
             TableKind::TxHistory => "txhistory",
             TableKind::MsgHistory => "msghistory",
+            TableKind::Registry => "registry",
+            TableKind::Cache => "cache",
         }
     }
 }
@@ -178,6 +240,122 @@ impl<'a> TableManager<'a> {
     }
 }
 
+impl<'a> TableManager<'a> {
+    /// 带 key 的前缀遍历（迁移 / 批量清理用），解码失败的项跳过
+    pub fn list_with_keys<T: Deserialize<'static> + bincode::Decode<()>>(
+        &self,
+    ) -> DbResult<Vec<(Vec<u8>, T)>> {
+        let mut items = Vec::new();
+        let mut prefix_bytes = Vec::new();
+        prefix_bytes.extend_from_slice(self.prefix.as_bytes());
+        prefix_bytes.push(b':');
+
+        let iter = self.db.prefix_iterator_cf(&self.cf, &prefix_bytes);
+        for item in iter {
+            match item {
+                Ok((key, value)) => {
+                    if !key.starts_with(&prefix_bytes) {
+                        break;
+                    }
+                    if let Ok((item, _)) =
+                        bincode::decode_from_slice::<T, _>(&value, bincode::config::standard())
+                    {
+                        items.push((key.to_vec(), item));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Iterator error: {}", e);
+                    continue;
+                }
+            }
+        }
+        Ok(items)
+    }
+
+    /// 读取未过期的缓存值；过期视为不存在
+    pub fn get_unexpired<T: Deserialize<'static> + bincode::Decode<()>>(
+        &self,
+        field: &[u8],
+        now: u64,
+    ) -> DbResult<Option<T>> {
+        Ok(self
+            .get::<Expiring<T>>(field)?
+            .filter(|e| e.expires_at > now)
+            .map(|e| e.value))
+    }
+
+    pub fn set_expiring<T: Serialize + bincode::Encode>(
+        &self,
+        field: &[u8],
+        value: T,
+        expires_at: u64,
+    ) -> DbResult<()> {
+        self.set(field, &Expiring { value, expires_at })
+    }
+
+    /// 只遍历 key_from_str(field_prefix) 开头的项，用于同一张表内的子命名空间
+    pub fn list_by_prefix<T: Deserialize<'static> + bincode::Decode<()>>(
+        &self,
+        field_prefix: &str,
+    ) -> DbResult<Vec<T>> {
+        let prefix_bytes = self.key_from_str(field_prefix);
+        let mut items = Vec::new();
+        let iter = self
+            .db
+            .iterator_cf(&self.cf, IteratorMode::From(&prefix_bytes, Direction::Forward));
+        for item in iter {
+            match item {
+                Ok((key, value)) => {
+                    if !key.starts_with(&prefix_bytes) {
+                        break;
+                    }
+                    match bincode::decode_from_slice::<T, _>(&value, bincode::config::standard()) {
+                        Ok((item, _)) => items.push(item),
+                        Err(e) => eprintln!("Failed to decode item: {}", e),
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Iterator error: {}", e);
+                    continue;
+                }
+            }
+        }
+        Ok(items)
+    }
+
+    pub fn batch_delete(&self, keys: &[Vec<u8>]) -> DbResult<()> {
+        let mut batch = WriteBatch::default();
+        for key in keys {
+            batch.delete_cf(&self.cf, key);
+        }
+        self.write(batch)
+    }
+
+    /// 把写入放进 batch（可跨表），由 write 一次提交
+    pub fn batch_set<T: Serialize + bincode::Encode>(
+        &self,
+        batch: &mut WriteBatch,
+        field: &[u8],
+        value: &T,
+    ) -> DbResult<()> {
+        let data = bincode::encode_to_vec(value, bincode::config::standard())
+            .map_err(|e| AppError::DbSerializationError(e.to_string()))?;
+        batch.put_cf(&self.cf, field, &data);
+        Ok(())
+    }
+
+    pub fn batch_remove(&self, batch: &mut WriteBatch, field: &[u8]) {
+        batch.delete_cf(&self.cf, field);
+    }
+
+    /// 原子提交 batch，其中可以包含其他表的写入
+    pub fn write(&self, batch: WriteBatch) -> DbResult<()> {
+        self.db
+            .write(&batch)
+            .map_err(|e| AppError::DbWriteError(e.to_string()))
+    }
+}
+
 pub struct TxHistoryManager<'a> {
     db: &'a DBWithThreadMode<MultiThreaded>,
 }
@@ -200,7 +378,7 @@ impl<'a> TxHistoryManager<'a> {
     }
 
     pub fn insert(&self, item: &TransactionHistoryEntry) -> DbResult<()> {
-        let key = Self::make_key(item.chain_id, item.timestamp, &item.tx_hash);
+        let key = Self::make_key(item.chain_id, item.timestamp.unwrap_or_default(), &item.hash.to_string());
         let value = bincode::encode_to_vec(item, bincode::config::standard())
             .map_err(|e| AppError::DbSerializationError(e.to_string()))?;
         self.db
@@ -282,7 +460,7 @@ impl<'a> TxHistoryManager<'a> {
                         bincode::config::standard(),
                     ) {
                         Ok((item, _)) => {
-                            if item.tx_hash == id {
+                            if item.hash.to_string().eq_ignore_ascii_case(id) {
                                 return Ok(Some(item));
                             }
                         }
@@ -319,7 +497,7 @@ impl<'a> TxHistoryManager<'a> {
                         bincode::config::standard(),
                     ) {
                         Ok((item, _)) => {
-                            if item.tx_hash == id {
+                            if item.hash.to_string().eq_ignore_ascii_case(id) {
                                 self.db
                                     .delete(&key)
                                     .map_err(|e| AppError::DbWriteError(e.to_string()))?;
@@ -341,11 +519,57 @@ impl<'a> TxHistoryManager<'a> {
         Ok(())
     }
 
+    /// v1 → v2：旧记录按 v1 解码后补空字段重写，已是 v2 的跳过
+    pub fn migrate_v1_to_v2(&self) -> DbResult<()> {
+        let mut prefix = Vec::new();
+        prefix.extend_from_slice("tx".as_bytes());
+        prefix.push(b':');
+
+        let iter = self.db.prefix_iterator(&prefix);
+        let mut batch = WriteBatch::default();
+
+        for item in iter {
+            match item {
+                Ok((key, value)) => {
+                    if !key.starts_with(&prefix) {
+                        break;
+                    }
+                    let config = bincode::config::standard();
+                    if bincode::decode_from_slice::<TransactionHistoryEntry, _>(&value, config)
+                        .is_ok_and(|(_, read)| read == value.len())
+                    {
+                        continue;
+                    }
+                    match bincode::decode_from_slice::<TransactionHistoryEntryV1, _>(&value, config) {
+                        Ok((old, _)) => {
+                            let new: TransactionHistoryEntry = old.into();
+                            let data = bincode::encode_to_vec(&new, config)
+                                .map_err(|e| AppError::DbSerializationError(e.to_string()))?;
+                            batch.put(&key, &data);
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to migrate transaction history entry: {}", e);
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Iterator error: {}", e);
+                    continue;
+                }
+            }
+        }
+
+        self.db
+            .write(&batch)
+            .map_err(|e| AppError::DbWriteError(e.to_string()))
+    }
+
     /// 批量插入：高效导入交易历史
     pub fn batch_insert(&self, items: &[TransactionHistoryEntry]) -> DbResult<()> {
         let mut batch = WriteBatch::default();
         for item in items {
-            let key = Self::make_key(item.chain_id, item.timestamp, &item.tx_hash);
+            let key = Self::make_key(item.chain_id, item.timestamp.unwrap_or_default(), &item.hash.to_string());
             let data = bincode::encode_to_vec(item, bincode::config::standard())
                 .map_err(|e| AppError::DbSerializationError(e.to_string()))?;
             batch.put(key, &data);
@@ -375,7 +599,7 @@ impl<'a> TxHistoryManager<'a> {
                         bincode::config::standard(),
                     ) {
                         Ok((item, _)) => {
-                            if ids.contains(&item.tx_hash) {
+                            if ids.iter().any(|id| item.hash.to_string().eq_ignore_ascii_case(id)) {
                                 batch.delete(&key);
                             }
                         }
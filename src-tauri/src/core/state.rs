@@ -14,13 +14,19 @@ use crate::core::vault::{VaultType,vault_get};
 use crate::core::persistent::{PersistentConfig, SessionConfig, config_get, config_batch_get};
 use crate::core::session::{SessionConfig};
 use crate::data::addr::{AddressBookEntry, addressbook_list};
+use crate::data::balance::BalanceCache;
+use crate::data::contract::ContractRegistry;
+use crate::data::erc7730::ClearSignRegistry;
+use crate::data::forex::RateService;
 use crate::data::nft::Nft;
 use crate::data::token::Token;
+use crate::data::tokenlist::load_user_tokens;
 use crate::rpc::https::create_https_client;
 use crate::helios::client::{HeliosClient, init_helios};
 use crate::ai::provider::{AiProvider};
 use crate::rpc::gateway::{GatewayManager};
-use crate::evm::chain::Chain;
+use crate::evm::chains::ChainRegistry;
+use crate::evm::fourbyte::SignatureDb;
 use crate::apps::{Apps};
 use crate::error::AppError;
 
@@ -33,6 +39,11 @@ pub struct AppState {
     pub user_tokens: Arc<Mutex<Option<Vec<Token>>>>,
     pub user_nfts: Arc<Mutex<Option<Vec<Nft>>>>,
     pub active_dapp_host: Arc<Mutex<Option<String>>>, 
+    pub contract_registry: Arc<Mutex<ContractRegistry>>,
+    pub chain_registry: Arc<Mutex<ChainRegistry>>,
+    pub clear_sign: Arc<Mutex<ClearSignRegistry>>,
+    pub rate_service: Arc<Mutex<RateService>>,
+    pub balance_cache: Arc<Mutex<BalanceCache>>,
 
     //sync to js
     pub config: Arc<Mutex<Config>>,
@@ -66,7 +77,13 @@ impl AppState {
             }
         };
         let accounts = account_list(None, appdb.clone())?;
-        let address_books = Vec::new();
+        let address_books = addressbook_list(None, appdb.clone())?;
+        let contract_registry = ContractRegistry::load(appdb.db.as_ref())?;
+        let chain_registry = ChainRegistry::load(appdb.db.as_ref())?;
+        let clear_sign = ClearSignRegistry::load(appdb.db.as_ref())?;
+        SignatureDb::load(appdb.db.as_ref())?.install();
+        let rate_service = RateService::load(appdb.db.as_ref())?;
+        let user_tokens = load_user_tokens(appdb.db.as_ref())?;
         
         // 初始化 Helios 客户端
         let helios_client = tauri::async_runtime::block_on(async {
@@ -86,9 +103,14 @@ impl AppState {
             https_client: Arc::new(Mutex::new(create_https_client())),  
             helios_client: Arc::new(Mutex::new(helios_client)),
             gateway_manager: Arc::new(Mutex::new(GatewayManager::default())),
-            user_tokens: Arc::new(Mutex::new(None)),
+            user_tokens: Arc::new(Mutex::new(Some(user_tokens))),
             user_nfts: Arc::new(Mutex::new(None)),
             active_dapp_host: Arc::new(Mutex::new(None)),
+            contract_registry: Arc::new(Mutex::new(contract_registry)),
+            chain_registry: Arc::new(Mutex::new(chain_registry)),
+            clear_sign: Arc::new(Mutex::new(clear_sign)),
+            rate_service: Arc::new(Mutex::new(rate_service)),
+            balance_cache: Arc::new(Mutex::new(BalanceCache::default())),
 
             config: Arc::new(Mutex::new(config)),
             ai_providers: Arc::new(Mutex::new(None)),
@@ -1,10 +1,16 @@
 use serde::{Deserialize, Serialize};
 use tauri::{ WebviewWindow};
 use tauri::Emitter;
+use tauri::Manager;
+use alloy_primitives::Address;
 
 use tauri::State;
 use crate::error::AppError;
+use crate::core::db::AppDB;
 use crate::core::state::{AppState, get_persistent_config};
+use crate::data::erc7730::{ClearSignView, FormatContext, known_names};
+use crate::data::poison::{RecipientCheck, own_addresses, recipient_report, token_recipient};
+use crate::data::tokenlist::token_meta_map;
 
 #[derive(Debug, Deserialize)]
 pub struct WebviewEthRequest {
@@ -60,6 +66,40 @@ fn simulate_sign(_payload: &SignRequest) -> Result<String, String> {
     Ok("0xSIMULATED_SIGNATURE".to_string())
 }
 
+/// eth_sendTransaction 的实际收款方：代币转账取 calldata 里的 to，否则为 params[0].to；
+/// 检查失败不阻断签名流程
+async fn recipient_check_for(app_handle: &tauri::AppHandle, req: &SignRequest) -> Option<RecipientCheck> {
+    let tx = req.params.get(0)?;
+    let data = tx
+        .get("data")
+        .or_else(|| tx.get("input"))
+        .and_then(|d| d.as_str())
+        .and_then(|d| hex::decode(d.trim_start_matches("0x")).ok())
+        .unwrap_or_default();
+    let to = match token_recipient(&data) {
+        Some(recipient) => recipient,
+        None => tx.get("to")?.as_str()?.parse::<Address>().ok()?,
+    };
+    let appdb = app_handle.state::<AppDB>();
+    let state = app_handle.state::<AppState>();
+    let chain_id = (*state.current_chain_id.lock().await)?;
+    let own = own_addresses(&state.accounts.lock().await);
+    let book = state.address_books.lock().await.clone();
+    recipient_report(appdb.db.as_ref(), &own, &book, chain_id, to).ok()
+}
+
+/// ERC-7730 描述渲染出的意图与字段；没有匹配的描述时为 None
+async fn clear_sign_for(app_handle: &tauri::AppHandle, req: &SignRequest) -> Option<ClearSignView> {
+    let appdb = app_handle.state::<AppDB>();
+    let state = app_handle.state::<AppState>();
+    let chain_id = (*state.current_chain_id.lock().await)?;
+    let tokens = token_meta_map(appdb.db.as_ref()).ok()?;
+    let names = known_names(&state, chain_id).await;
+    let native_symbol = state.chain_registry.lock().await.native_symbol(chain_id);
+    let ctx = FormatContext { chain_id, native_symbol, tokens: Some(&tokens), names: Some(&names), ..Default::default() };
+    state.clear_sign.lock().await.clear_sign_request(&req.method, &req.params, &ctx)
+}
+
 #[tauri::command]
 pub async fn sign_transaction(
     app_handle: tauri::AppHandle,
@@ -74,7 +114,11 @@ pub async fn sign_transaction(
         }
     }
 
-    // 2. show confirmation modal in the shell window (emit event)
+    // 2. 收款地址投毒检查，结果随弹窗一起展示
+    let recipient_check = recipient_check_for(&app_handle, &req).await;
+    let clear_sign = clear_sign_for(&app_handle, &req).await;
+
+    // 3. show confirmation modal in the shell window (emit event)
     // We emit an event "SHELL_SHOW_MODAL" to the specific dapp window; the shell listens and shows UI
     let payload = serde_json::json!({
         "title": "签名请求",
@@ -82,6 +126,8 @@ pub async fn sign_transaction(
         "origin": req.origin.clone().unwrap_or_default(),
         "reqId": req.id,
         "data": req.params,
+        "recipientCheck": recipient_check,
+        "clearSign": clear_sign,
     });
     // Assuming your dapp window label is "dapp"
     let _ = app_handle.emit("SHELL_SHOW_MODAL", &payload);
@@ -0,0 +1,342 @@
+// 合约 ABI 库：按 (chain_id, address) 存储，来源为 Sourcify 兼容的元数据服务或用户上传。
+// 代理合约（EIP-1967 / EIP-1822 / Beacon）读取实现槽，用实现合约的 ABI 解码
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::error::AppError;
+use crate::evm::calldata::{DecodedCall, decode_call};
+use crate::rpc::method::{eth_call, get_storage_at};
+use crate::utils::time::now_s;
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::{Address, B256, b256};
+use alloy_sol_types::{SolCall, sol};
+use bincode::{Decode, Encode};
+use reqwest::Client;
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tauri::State;
+
+pub const DEFAULT_SOURCIFY_SERVER: &str = "https://sourcify.dev/server";
+/// bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 = b256!("0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+/// bytes32(uint256(keccak256("eip1967.proxy.beacon")) - 1)
+pub const EIP1967_BEACON_SLOT: B256 = b256!("0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50");
+/// keccak256("PROXIABLE")
+pub const EIP1822_PROXIABLE_SLOT: B256 = b256!("0xc5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7");
+
+const KEY_PREFIX: &str = "abi";
+const FETCH_TIMEOUT_SECS: u64 = 20;
+
+sol! {
+    interface IBeacon {
+        function implementation() external view returns (address);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AbiSource {
+    Sourcify,
+    User,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct AbiEntry {
+    pub chain_id: u64,
+    pub address: Address,
+    /// JSON ABI 原文
+    pub abi: String,
+    pub source: AbiSource,
+    /// Sourcify 的匹配级别（exact_match / match）
+    #[serde(default)]
+    pub match_type: Option<String>,
+    pub updated_at: u64,
+}
+
+impl AbiEntry {
+    pub fn parsed(&self) -> Result<JsonAbi, AppError> {
+        parse_abi(&self.abi)
+    }
+}
+
+pub fn parse_abi(abi: &str) -> Result<JsonAbi, AppError> {
+    serde_json::from_str(abi).map_err(|e| AppError::AbiInvalid(e.to_string()))
+}
+
+fn entry_key(mgr: &TableManager, chain_id: u64, address: &Address) -> Vec<u8> {
+    mgr.key_from_str(&format!("{KEY_PREFIX}:{chain_id}:{:#x}", address))
+}
+
+pub fn abi_load(db: &DBWithThreadMode<MultiThreaded>, chain_id: u64, address: &Address) -> DbResult<Option<AbiEntry>> {
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    mgr.get(&entry_key(&mgr, chain_id, address))
+}
+
+/// 用户上传的 ABI 优先，Sourcify 结果不覆盖它
+pub fn abi_save(db: &DBWithThreadMode<MultiThreaded>, entry: &AbiEntry) -> DbResult<()> {
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    let key = entry_key(&mgr, entry.chain_id, &entry.address);
+    if entry.source == AbiSource::Sourcify
+        && mgr.get::<AbiEntry>(&key)?.is_some_and(|e| e.source == AbiSource::User)
+    {
+        return Ok(());
+    }
+    mgr.set(&key, entry)
+}
+
+// ========== 代理解析 ==========
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    Eip1967,
+    Eip1822,
+    Beacon,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProxyInfo {
+    pub kind: ProxyKind,
+    pub implementation: Address,
+    #[serde(default)]
+    pub beacon: Option<Address>,
+}
+
+/// 存储槽里右对齐的地址；空槽为 None
+fn slot_address(word: &str) -> Option<Address> {
+    let raw = hex::decode(word.trim_start_matches("0x")).ok()?;
+    if raw.len() != 32 || raw[..12].iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some(Address::from_slice(&raw[12..])).filter(|a| !a.is_zero())
+}
+
+async fn read_slot(client: &Client, url: &str, address: &Address, slot: &B256) -> Result<Option<Address>, AppError> {
+    let word = get_storage_at(client.clone(), url, &format!("{:#x}", address), &format!("{:#x}", slot), "latest").await?;
+    Ok(slot_address(&word))
+}
+
+/// 依次检查 EIP-1967 实现槽、EIP-1967 beacon 槽、EIP-1822 PROXIABLE 槽；都为空时不是代理
+pub async fn resolve_proxy(client: &Client, url: &str, address: &Address) -> Result<Option<ProxyInfo>, AppError> {
+    if let Some(implementation) = read_slot(client, url, address, &EIP1967_IMPLEMENTATION_SLOT).await? {
+        return Ok(Some(ProxyInfo { kind: ProxyKind::Eip1967, implementation, beacon: None }));
+    }
+    if let Some(beacon) = read_slot(client, url, address, &EIP1967_BEACON_SLOT).await? {
+        let data = format!("0x{}", hex::encode(IBeacon::implementationCall {}.abi_encode()));
+        let result = eth_call(client.clone(), url, &format!("{:#x}", beacon), &data, "latest").await?;
+        let implementation = result.as_str().and_then(slot_address).ok_or(AppError::Parse("invalid beacon implementation"))?;
+        return Ok(Some(ProxyInfo { kind: ProxyKind::Beacon, implementation, beacon: Some(beacon) }));
+    }
+    if let Some(implementation) = read_slot(client, url, address, &EIP1822_PROXIABLE_SLOT).await? {
+        return Ok(Some(ProxyInfo { kind: ProxyKind::Eip1822, implementation, beacon: None }));
+    }
+    Ok(None)
+}
+
+// ========== Sourcify ==========
+
+/// v2 接口 `GET /v2/contract/{chainId}/{address}?fields=abi`，未验证的合约返回 404
+pub async fn fetch_sourcify(
+    client: &Client,
+    server: &str,
+    chain_id: u64,
+    address: &Address,
+) -> Result<Option<AbiEntry>, AppError> {
+    let url = format!("{}/v2/contract/{}/{}?fields=abi", server.trim_end_matches('/'), chain_id, address.to_checksum(None));
+    let response = client
+        .get(&url)
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(AppError::ReqwestClientConnectionError)?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let body: Value = response
+        .error_for_status()
+        .map_err(AppError::ReqwestClientConnectionError)?
+        .json()
+        .await
+        .map_err(AppError::ReqwestClientConnectionError)?;
+    let Some(abi) = body.get("abi").filter(|abi| abi.is_array()) else {
+        return Ok(None);
+    };
+    let abi = abi.to_string();
+    parse_abi(&abi)?;
+    Ok(Some(AbiEntry {
+        chain_id,
+        address: *address,
+        abi,
+        source: AbiSource::Sourcify,
+        match_type: body.get("match").and_then(Value::as_str).map(String::from),
+        updated_at: now_s(),
+    }))
+}
+
+/// 本地库 → Sourcify，拉到的结果写回本地库
+async fn lookup_or_fetch(
+    client: &Client,
+    db: &DBWithThreadMode<MultiThreaded>,
+    server: &str,
+    chain_id: u64,
+    address: &Address,
+) -> Result<Option<AbiEntry>, AppError> {
+    if let Some(entry) = abi_load(db, chain_id, address)? {
+        return Ok(Some(entry));
+    }
+    let fetched = fetch_sourcify(client, server, chain_id, address).await?;
+    if let Some(entry) = &fetched {
+        abi_save(db, entry)?;
+    }
+    Ok(fetched)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedAbi {
+    pub address: Address,
+    pub proxy: Option<ProxyInfo>,
+    /// 代理合约时优先为实现合约的 ABI，实现合约没有 ABI 时退回代理自身的
+    pub entry: Option<AbiEntry>,
+}
+
+/// 读不到代理槽（节点故障等）时按非代理处理，继续用地址自身的 ABI
+pub async fn resolve_abi(
+    client: &Client,
+    db: &DBWithThreadMode<MultiThreaded>,
+    server: &str,
+    rpc_url: Option<&str>,
+    chain_id: u64,
+    address: &Address,
+) -> Result<ResolvedAbi, AppError> {
+    let proxy = match rpc_url {
+        Some(url) => resolve_proxy(client, url, address).await.unwrap_or_else(|e| {
+            log::warn!("proxy slot read failed for {:#x} on {}: {}", address, url, e);
+            None
+        }),
+        None => None,
+    };
+    let mut entry = match &proxy {
+        Some(proxy) => lookup_or_fetch(client, db, server, chain_id, &proxy.implementation).await?,
+        None => None,
+    };
+    if entry.is_none() {
+        entry = lookup_or_fetch(client, db, server, chain_id, address).await?;
+    }
+    Ok(ResolvedAbi { address: *address, proxy, entry })
+}
+
+fn parse_address(address: &str) -> Result<Address, AppError> {
+    address.parse::<Address>().map_err(|_| AppError::Parse("invalid address"))
+}
+
+// ========== ABI STORE ==========
+#[tauri::command]
+pub async fn abi_get(
+    chain_id: u64,
+    address: String,
+    rpc_url: Option<String>,
+    server: Option<String>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<ResolvedAbi, AppError> {
+    let address = parse_address(&address)?;
+    let client = state.https_client.lock().await.clone();
+    let server = server.as_deref().unwrap_or(DEFAULT_SOURCIFY_SERVER);
+    resolve_abi(&client, appdb.db.as_ref(), server, rpc_url.as_deref(), chain_id, &address).await
+}
+
+/// 按目标合约的 ABI（代理取实现合约）解码；没有 ABI 时退回签名库
+#[tauri::command]
+pub async fn abi_decode_calldata(
+    chain_id: u64,
+    to: String,
+    calldata: String,
+    rpc_url: Option<String>,
+    server: Option<String>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<DecodedCall, AppError> {
+    let address = parse_address(&to)?;
+    let client = state.https_client.lock().await.clone();
+    let server = server.as_deref().unwrap_or(DEFAULT_SOURCIFY_SERVER);
+    let resolved = resolve_abi(&client, appdb.db.as_ref(), server, rpc_url.as_deref(), chain_id, &address).await?;
+    if let Some(call) = resolved.entry.and_then(|e| decode_call(&calldata, Some(&e.abi)).ok()) {
+        return Ok(call);
+    }
+    decode_call(&calldata, None).map_err(|e| AppError::CalldataDecodeFailed(e.to_string()))
+}
+
+#[tauri::command]
+pub fn abi_user_add(chain_id: u64, address: String, abi: String, appdb: State<AppDB>) -> Result<AbiEntry, AppError> {
+    let address = parse_address(&address)?;
+    // 统一存成紧凑 JSON
+    let abi = serde_json::to_string(&parse_abi(&abi)?)?;
+    let entry = AbiEntry { chain_id, address, abi, source: AbiSource::User, match_type: None, updated_at: now_s() };
+    abi_save(appdb.db.as_ref(), &entry)?;
+    Ok(entry)
+}
+
+#[tauri::command]
+pub fn abi_delete(chain_id: u64, address: String, appdb: State<AppDB>) -> Result<(), AppError> {
+    let address = parse_address(&address)?;
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    mgr.delete(&entry_key(&mgr, chain_id, &address))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn abi_list(chain_id: Option<u64>, appdb: State<AppDB>) -> Result<Vec<AbiEntry>, AppError> {
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    let prefix = match chain_id {
+        Some(id) => format!("{KEY_PREFIX}:{id}:"),
+        None => format!("{KEY_PREFIX}:"),
+    };
+    Ok(mgr.list_by_prefix(&prefix)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_http;
+    use alloy_primitives::address;
+
+    const PROXY: Address = address!("0x1111111111111111111111111111111111111111");
+    const IMPL: Address = address!("0x2222222222222222222222222222222222222222");
+
+    #[test]
+    fn test_slot_address() {
+        assert_eq!(slot_address(&format!("{:#x}", IMPL.into_word())), Some(IMPL));
+        assert_eq!(slot_address(&format!("{:#x}", B256::ZERO)), None);
+        // 高位非零的槽不是地址
+        assert_eq!(slot_address(&format!("{:#x}", B256::repeat_byte(0x01))), None);
+        assert_eq!(slot_address("0x"), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_eip1967_proxy() {
+        let body = test_http::rpc_result(format!("{:#x}", IMPL.into_word()));
+        let base = test_http::serve(vec![("/", body.as_str())]);
+        let proxy = resolve_proxy(&Client::new(), &format!("{base}/"), &PROXY).await.unwrap().unwrap();
+        assert_eq!(proxy, ProxyInfo { kind: ProxyKind::Eip1967, implementation: IMPL, beacon: None });
+
+        let empty = test_http::rpc_result(format!("{:#x}", B256::ZERO));
+        let base = test_http::serve(vec![("/", empty.as_str())]);
+        assert!(resolve_proxy(&Client::new(), &format!("{base}/"), &PROXY).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_sourcify() {
+        let path = format!("/v2/contract/1/{}?fields=abi", IMPL.to_checksum(None));
+        let body = r#"{"abi":[{"type":"function","name":"upgradeTo","inputs":[{"name":"newImplementation","type":"address"}],"outputs":[],"stateMutability":"nonpayable"}],"match":"exact_match","chainId":"1"}"#;
+        let base = test_http::serve(vec![(path.as_str(), body)]);
+        let client = Client::new();
+
+        let entry = fetch_sourcify(&client, &base, 1, &IMPL).await.unwrap().unwrap();
+        assert_eq!(entry.source, AbiSource::Sourcify);
+        assert_eq!(entry.match_type.as_deref(), Some("exact_match"));
+        assert_eq!(entry.parsed().unwrap().functions().next().unwrap().name, "upgradeTo");
+        // 未验证的合约
+        assert!(fetch_sourcify(&client, &base, 1, &PROXY).await.unwrap().is_none());
+    }
+}
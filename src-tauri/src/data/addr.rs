@@ -1,29 +1,248 @@
-use crate::core::db::{AppDB, TableKind, TableManager};
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
 use crate::error::AppError;
+use crate::evm::address::{LOOKALIKE_PREFIX_LEN, LOOKALIKE_SUFFIX_LEN, is_lookalike};
+use crate::utils::csv::{csv_line, parse_csv};
 use alloy_primitives::Address;
 use bincode::{Decode, Encode};
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded, WriteBatch};
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
+/// 导入导出格式版本，字段变动时递增
+pub const ADDRESSBOOK_EXPORT_VERSION: u32 = 1;
+const CSV_HEADER: [&str; 6] = ["name", "address", "category", "memo", "chain_ids", "ens"];
+
 #[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, PartialEq)]
 pub struct AddressBookEntry {
     pub name: String,
     pub address: Address,
     pub category: String,
     pub memo: Option<String>,
-    pub chain_ids: Option<Vec<u64>>, // not eoa
+    pub chain_ids: Option<Vec<u64>>, // 合约联系人所在链；None 表示全链（EOA）
+    pub ens: Option<String>,
+}
+
+/// v1 记录（无 ens 字段），仅用于迁移
+#[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct AddressBookEntryV1 {
+    pub name: String,
+    pub address: Address,
+    pub category: String,
+    pub memo: Option<String>,
+    pub chain_ids: Option<Vec<u64>>,
+}
+
+impl From<AddressBookEntryV1> for AddressBookEntry {
+    fn from(old: AddressBookEntryV1) -> Self {
+        Self {
+            name: old.name,
+            address: old.address,
+            category: old.category,
+            memo: old.memo,
+            chain_ids: old.chain_ids,
+            ens: None,
+        }
+    }
 }
 
 impl AddressBookEntry {
     pub fn new() -> Self {
         Self {
             name: "".to_string(),
-            address: Address::zero(),
+            address: Address::ZERO,
             category: "Contact".to_string(),
             memo: None,
             chain_ids: None,
+            ens: None,
+        }
+    }
+
+    /// 联系人是否适用于该链
+    pub fn on_chain(&self, chain_id: u64) -> bool {
+        match &self.chain_ids {
+            Some(ids) => ids.contains(&chain_id),
+            None => true,
         }
     }
+
+    /// 名称 / ENS / 地址 / 备注 模糊匹配（不区分大小写）
+    pub fn matches(&self, query: &str) -> bool {
+        let q = query.trim().to_lowercase();
+        if q.is_empty() {
+            return true;
+        }
+        let q_hex = q.trim_start_matches("0x");
+        self.name.to_lowercase().contains(&q)
+            || self.ens.as_deref().is_some_and(|e| e.to_lowercase().contains(&q))
+            || (!q_hex.is_empty() && hex::encode(self.address.as_slice()).contains(q_hex))
+            || self.memo.as_deref().is_some_and(|m| m.to_lowercase().contains(&q))
+    }
+}
+
+/// 以小写十六进制地址作为 key，同一地址只保留一条
+fn entry_key(mgr: &TableManager, address: &Address) -> Vec<u8> {
+    mgr.key_from_str(&format!("{:#x}", address))
+}
+
+/// 插入检查结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AddressBookInsertResult {
+    pub added: bool,
+    /// 同地址或同名的已有条目
+    pub duplicates: Vec<AddressBookEntry>,
+    /// 前后缀相同、中段不同的已有条目（疑似投毒地址）
+    pub lookalikes: Vec<AddressBookEntry>,
+}
+
+/// 导入导出的 JSON 结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AddressBookExport {
+    pub version: u32,
+    pub entries: Vec<AddressBookEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressBookFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AddressBookImportReport {
+    pub imported: usize,
+    /// 已存在（同地址）被跳过
+    pub skipped: usize,
+    /// 与已有条目相似而被拒绝的地址
+    pub lookalikes: Vec<Address>,
+    /// 无法解析的行（CSV 行号从 1 开始，含表头）
+    pub invalid: Vec<String>,
+}
+
+/// 对照现有条目检查重复与相似地址
+pub fn check_entry(existing: &[AddressBookEntry], entry: &AddressBookEntry) -> AddressBookInsertResult {
+    let name = entry.name.trim().to_lowercase();
+    let duplicates = existing
+        .iter()
+        .filter(|e| e.address == entry.address || (!name.is_empty() && e.name.trim().to_lowercase() == name))
+        .cloned()
+        .collect();
+    let lookalikes = existing
+        .iter()
+        .filter(|e| is_lookalike(&e.address, &entry.address, LOOKALIKE_PREFIX_LEN, LOOKALIKE_SUFFIX_LEN))
+        .cloned()
+        .collect();
+    AddressBookInsertResult { added: false, duplicates, lookalikes }
+}
+
+pub fn search_entries(
+    entries: &[AddressBookEntry],
+    query: &str,
+    chain_id: Option<u64>,
+) -> Vec<AddressBookEntry> {
+    entries
+        .iter()
+        .filter(|e| chain_id.is_none_or(|id| e.on_chain(id)))
+        .filter(|e| e.matches(query))
+        .cloned()
+        .collect()
+}
+
+pub fn entries_to_csv(entries: &[AddressBookEntry]) -> String {
+    let mut out = csv_line(&CSV_HEADER.iter().map(|h| h.to_string()).collect::<Vec<_>>());
+    out.push_str("\r\n");
+    for e in entries {
+        let chain_ids = e
+            .chain_ids
+            .as_ref()
+            .map(|ids| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(";"))
+            .unwrap_or_default();
+        out.push_str(&csv_line(&[
+            e.name.clone(),
+            e.address.to_checksum(None),
+            e.category.clone(),
+            e.memo.clone().unwrap_or_default(),
+            chain_ids,
+            e.ens.clone().unwrap_or_default(),
+        ]));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// 按表头列名解析，列顺序可变；返回 (条目, 无法解析的行描述)
+pub fn entries_from_csv(input: &str) -> Result<(Vec<AddressBookEntry>, Vec<String>), AppError> {
+    let rows = parse_csv(input);
+    let Some(header) = rows.first() else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+    let col = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let (Some(name_i), Some(addr_i)) = (col("name"), col("address")) else {
+        return Err(AppError::Parse("address book csv requires name and address columns"));
+    };
+    let (category_i, memo_i, chains_i, ens_i) = (col("category"), col("memo"), col("chain_ids"), col("ens"));
+    let cell = |row: &Vec<String>, i: Option<usize>| {
+        i.and_then(|i| row.get(i)).map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+    };
+
+    let mut entries = Vec::new();
+    let mut invalid = Vec::new();
+    for (line, row) in rows.iter().enumerate().skip(1) {
+        let Some(address) = cell(row, Some(addr_i)).and_then(|a| a.parse::<Address>().ok()) else {
+            invalid.push(format!("line {}: invalid address", line + 1));
+            continue;
+        };
+        let chain_ids = match cell(row, chains_i) {
+            Some(raw) => match raw.split(';').map(|id| id.trim().parse::<u64>()).collect() {
+                Ok(ids) => Some(ids),
+                Err(_) => {
+                    invalid.push(format!("line {}: invalid chain_ids", line + 1));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        entries.push(AddressBookEntry {
+            name: cell(row, Some(name_i)).unwrap_or_default(),
+            address,
+            category: cell(row, category_i).unwrap_or_else(|| "Contact".to_string()),
+            memo: cell(row, memo_i),
+            chain_ids,
+            ens: cell(row, ens_i),
+        });
+    }
+    Ok((entries, invalid))
+}
+
+/// v1 → v2：补 ens 字段，同时把旧 key 统一为小写地址；
+/// 删除与写入在同一个 WriteBatch 中提交（新旧 key 相同时后写入的生效），中途失败不会丢数据
+pub fn migrate_v1_to_v2(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<()> {
+    let mgr = TableManager::new(db, TableKind::AddressBook)?;
+    let old = mgr.list_with_keys::<AddressBookEntryV1>()?;
+    if old.is_empty() {
+        return Ok(());
+    }
+    let mut batch = WriteBatch::default();
+    for (key, _) in &old {
+        mgr.batch_remove(&mut batch, key);
+    }
+    for (_, entry) in old {
+        let entry = AddressBookEntry::from(entry);
+        mgr.batch_set(&mut batch, &entry_key(&mgr, &entry.address), &entry)?;
+    }
+    mgr.write(batch)
+}
+
+fn load_all(appdb: &AppDB) -> DbResult<Vec<AddressBookEntry>> {
+    TableManager::new(appdb.db.as_ref(), TableKind::AddressBook)?.list::<AddressBookEntry>()
+}
+
+/// 写库后刷新 AppState 中的地址簿缓存
+fn sync_state(appdb: &AppDB, state: &AppState) -> DbResult<Vec<AddressBookEntry>> {
+    let list = load_all(appdb)?;
+    *state.address_books.blocking_lock() = list.clone();
+    Ok(list)
 }
 
 // ========== ADDRESSBOOK ==========
@@ -32,9 +251,7 @@ pub fn addressbook_list(
     category: Option<String>,
     appdb: State<AppDB>,
 ) -> Result<Vec<AddressBookEntry>, AppError> {
-    let db = appdb.db.as_ref();
-    let mgr = TableManager::new(db, TableKind::AddressBook)?;
-    let list = mgr.list::<AddressBookEntry>()?;
+    let list = load_all(&appdb)?;
     Ok(match category {
         Some(cat) => list.into_iter().filter(|a| a.category == cat).collect(),
         None => list,
@@ -42,20 +259,151 @@ pub fn addressbook_list(
 }
 
 #[tauri::command]
-pub fn addressbook_add(entry: AddressBookEntry, appdb: State<AppDB>) -> Result<(), AppError> {
-    let db = appdb.db.as_ref();
-    let mgr = TableManager::new(db, TableKind::AddressBook)?;
-    // 创建二进制 key
-    let key = mgr.key_from_str(&entry.address);
-    mgr.set(&key, &entry)
+pub fn addressbook_search(
+    query: String,
+    chain_id: Option<u64>,
+    state: State<AppState>,
+) -> Result<Vec<AddressBookEntry>, AppError> {
+    let entries = state.address_books.blocking_lock();
+    Ok(search_entries(&entries, &query, chain_id))
 }
 
+/// 新增 / 更新联系人。存在重复或相似地址时默认不写入，
+/// 由前端展示提示后带 force = true 重新提交
 #[tauri::command]
-pub fn addressbook_delete(address: String, appdb: State<AppDB>) -> Result<(), AppError> {
-    let db = appdb.db.as_ref();
-    let mgr = TableManager::new(db, TableKind::AddressBook)?;
-    // 创建二进制 key
-    let key = mgr.key_from_str(&address);
-    mgr.delete(&key)
+pub fn addressbook_add(
+    entry: AddressBookEntry,
+    force: Option<bool>,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<AddressBookInsertResult, AppError> {
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::AddressBook)?;
+    let existing = mgr.list::<AddressBookEntry>()?;
+    let mut result = check_entry(&existing, &entry);
+    if !force.unwrap_or(false) && (!result.duplicates.is_empty() || !result.lookalikes.is_empty()) {
+        return Ok(result);
+    }
+    mgr.set(&entry_key(&mgr, &entry.address), &entry)?;
+    sync_state(&appdb, &state)?;
+    result.added = true;
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn addressbook_delete(
+    address: String,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let address = address
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::AddressBook)?;
+    mgr.delete(&entry_key(&mgr, &address))?;
+    sync_state(&appdb, &state)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn addressbook_export(
+    format: AddressBookFormat,
+    appdb: State<AppDB>,
+) -> Result<String, AppError> {
+    let mut entries = load_all(&appdb)?;
+    entries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    match format {
+        AddressBookFormat::Csv => Ok(entries_to_csv(&entries)),
+        AddressBookFormat::Json => serde_json::to_string_pretty(&AddressBookExport {
+            version: ADDRESSBOOK_EXPORT_VERSION,
+            entries,
+        })
+        .map_err(AppError::JsonParseError),
+    }
 }
 
+/// 导入：同地址跳过，相似地址拒绝，其余写入
+#[tauri::command]
+pub fn addressbook_import(
+    format: AddressBookFormat,
+    data: String,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<AddressBookImportReport, AppError> {
+    let (entries, invalid) = match format {
+        AddressBookFormat::Csv => entries_from_csv(&data)?,
+        AddressBookFormat::Json => {
+            let export: AddressBookExport = serde_json::from_str(&data).map_err(AppError::JsonParseError)?;
+            if export.version > ADDRESSBOOK_EXPORT_VERSION {
+                return Err(AppError::Parse("unsupported address book export version"));
+            }
+            (export.entries, Vec::new())
+        }
+    };
+
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::AddressBook)?;
+    let mut existing = mgr.list::<AddressBookEntry>()?;
+    let mut report = AddressBookImportReport { invalid, ..Default::default() };
+    for entry in entries {
+        if existing.iter().any(|e| e.address == entry.address) {
+            report.skipped += 1;
+            continue;
+        }
+        if !check_entry(&existing, &entry).lookalikes.is_empty() {
+            report.lookalikes.push(entry.address);
+            continue;
+        }
+        mgr.set(&entry_key(&mgr, &entry.address), &entry)?;
+        existing.push(entry);
+        report.imported += 1;
+    }
+    sync_state(&appdb, &state)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    fn contact(name: &str, address: Address, chain_ids: Option<Vec<u64>>) -> AddressBookEntry {
+        AddressBookEntry { name: name.to_string(), address, chain_ids, ..AddressBookEntry::new() }
+    }
+
+    #[test]
+    fn test_check_entry_duplicates_and_lookalikes() {
+        let alice = contact("Alice", address!("0xd8da6bf26964af9d7eed9e03e53415d37aa96045"), None);
+        let existing = vec![alice.clone()];
+
+        let same = check_entry(&existing, &contact("alice", address!("0x1111111111111111111111111111111111111111"), None));
+        assert_eq!(same.duplicates.len(), 1);
+
+        // 前 4 / 后 4 位相同，中段不同
+        let poison = contact("Bob", address!("0xd8da000000000000000000000000000000006045"), None);
+        let res = check_entry(&existing, &poison);
+        assert!(res.duplicates.is_empty());
+        assert_eq!(res.lookalikes, vec![alice]);
+    }
+
+    #[test]
+    fn test_search_per_chain() {
+        let mut vitalik = contact("Vitalik", address!("0xd8da6bf26964af9d7eed9e03e53415d37aa96045"), None);
+        vitalik.ens = Some("vitalik.eth".to_string());
+        let router = contact("Router", address!("0x66a9893cc07d91d95644aedd05d03f95e1dba8af"), Some(vec![1, 8453]));
+        let entries = vec![vitalik, router];
+
+        assert_eq!(search_entries(&entries, "VITALIK.ETH", None).len(), 1);
+        assert_eq!(search_entries(&entries, "0x66a989", Some(8453))[0].name, "Router");
+        assert!(search_entries(&entries, "router", Some(137)).is_empty());
+        assert_eq!(search_entries(&entries, "", Some(137)).len(), 1);
+    }
+
+    #[test]
+    fn test_csv_roundtrip() {
+        let mut e = contact("Ops, multisig", address!("0x66a9893cc07d91d95644aedd05d03f95e1dba8af"), Some(vec![1, 10]));
+        e.memo = Some("say \"hi\"".to_string());
+        let csv = entries_to_csv(&[e.clone()]);
+        let (parsed, invalid) = entries_from_csv(&format!("{csv}name,not-an-address,,,,\r\n")).unwrap();
+        assert_eq!(parsed, vec![e]);
+        assert_eq!(invalid, vec!["line 3: invalid address".to_string()]);
+    }
+}
@@ -0,0 +1,373 @@
+// 余额引擎：所有账户 × (原生币 + 已跟踪代币) 通过 Multicall3 aggregate3 批量查询，
+// 按 gas 预算分块并固定在同一区块；结果按区块缓存，新区块到来时刷新。
+// 只依赖普通 JSON-RPC（eth_blockNumber / eth_call），任意 RPC 模式都可用，不需要索引器
+use crate::core::state::AppState;
+use crate::data::poison::own_addresses;
+use crate::data::token::Token;
+use crate::error::AppError;
+use crate::evm::assets::AssetsType;
+use crate::rpc::method::{block_number, eth_call, get_balance};
+use crate::rpc::multicall3::{Call, IMulticall3, Multicall3};
+use crate::utils::num::str_to_u256;
+use crate::utils::time::now_s;
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::{SolCall, sol};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+
+/// 单次 balanceOf 的 gas 上界（冷读 + 代理转发）
+pub const BALANCE_OF_GAS: u64 = 35_000;
+/// Multicall3.getEthBalance
+pub const ETH_BALANCE_GAS: u64 = 5_000;
+/// 单次 eth_call 的 gas 预算；多数节点的 eth_call 上限是 50M，留足余量
+pub const MULTICALL_GAS_BUDGET: u64 = 20_000_000;
+/// 单块调用数上限，控制请求体大小
+pub const MAX_CALLS_PER_CHUNK: usize = 500;
+/// 新区块轮询间隔（秒）
+pub const HEAD_POLL_SECS: u64 = 4;
+/// 前端监听的事件名
+pub const BALANCES_UPDATED_EVENT: &str = "balances:updated";
+
+sol! {
+    interface IERC20Balance {
+        function balanceOf(address owner) external view returns (uint256);
+    }
+}
+
+/// 一项余额查询；token 为 None 表示原生币
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct BalanceQuery {
+    pub owner: Address,
+    pub token: Option<Address>,
+}
+
+impl BalanceQuery {
+    fn gas(&self) -> u64 {
+        if self.token.is_some() { BALANCE_OF_GAS } else { ETH_BALANCE_GAS }
+    }
+
+    fn to_call(&self, multicall: &Multicall3) -> Call {
+        match self.token {
+            Some(token) => Call::new(token, &IERC20Balance::balanceOfCall { owner: self.owner }),
+            None => multicall.self_call(&IMulticall3::getEthBalanceCall { addr: self.owner }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BalanceEntry {
+    pub owner: Address,
+    pub token: Option<Address>,
+    /// None 表示调用失败（非 ERC-20、合约自毁等）
+    pub balance: Option<U256>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BalanceSnapshot {
+    pub chain_id: u64,
+    pub block_number: u64,
+    pub entries: Vec<BalanceEntry>,
+    pub fetched_at: u64,
+}
+
+impl BalanceSnapshot {
+    pub fn balance_of(&self, owner: &Address, token: Option<&Address>) -> Option<U256> {
+        self.entries
+            .iter()
+            .find(|e| e.owner == *owner && e.token.as_ref() == token)
+            .and_then(|e| e.balance)
+    }
+
+    /// 快照是否包含全部查询
+    pub fn covers(&self, queries: &[BalanceQuery]) -> bool {
+        let have: HashSet<BalanceQuery> =
+            self.entries.iter().map(|e| BalanceQuery { owner: e.owner, token: e.token }).collect();
+        queries.iter().all(|q| have.contains(q))
+    }
+}
+
+/// owners × (原生币 + tokens)，去重
+pub fn build_queries(owners: &[Address], tokens: &[Address]) -> Vec<BalanceQuery> {
+    let mut seen = HashSet::new();
+    let mut queries = Vec::new();
+    for owner in owners {
+        for token in std::iter::once(None).chain(tokens.iter().map(|t| Some(*t))) {
+            let query = BalanceQuery { owner: *owner, token };
+            if seen.insert(query) {
+                queries.push(query);
+            }
+        }
+    }
+    queries
+}
+
+/// 按 gas 预算和调用数切块，返回各块的 (起, 止) 下标
+pub fn chunk_by_gas(queries: &[BalanceQuery], gas_budget: u64, max_calls: usize) -> Vec<(usize, usize)> {
+    let mut chunks = Vec::new();
+    let (mut start, mut gas) = (0, 0);
+    for (i, query) in queries.iter().enumerate() {
+        if i > start && (gas + query.gas() > gas_budget || i - start >= max_calls) {
+            chunks.push((start, i));
+            start = i;
+            gas = 0;
+        }
+        gas += query.gas();
+    }
+    if start < queries.len() {
+        chunks.push((start, queries.len()));
+    }
+    chunks
+}
+
+/// 链上没有 Multicall3 或 aggregate3 整体失败时逐个查询
+async fn fetch_one_by_one(client: &Client, url: &str, block: &str, queries: &[BalanceQuery]) -> Vec<Option<U256>> {
+    let mut out = Vec::with_capacity(queries.len());
+    for query in queries {
+        let owner = format!("{:#x}", query.owner);
+        let balance = match query.token {
+            None => get_balance(client.clone(), url, &owner, block).await.ok().and_then(|b| str_to_u256(&b).ok()),
+            Some(token) => {
+                let data = format!("0x{}", hex::encode(IERC20Balance::balanceOfCall { owner: query.owner }.abi_encode()));
+                eth_call(client.clone(), url, &format!("{:#x}", token), &data, block)
+                    .await
+                    .ok()
+                    .and_then(|raw| hex::decode(raw.as_str()?.trim_start_matches("0x")).ok())
+                    .and_then(|raw| IERC20Balance::balanceOfCall::abi_decode_returns(&raw).ok())
+            }
+        };
+        out.push(balance);
+    }
+    out
+}
+
+/// 在同一区块上批量读取余额；链能力表中没有 Multicall3 时直接逐个查询
+pub async fn fetch_balances(
+    client: &Client,
+    url: &str,
+    chain_id: u64,
+    multicall3: Option<Address>,
+    queries: &[BalanceQuery],
+    block: u64,
+) -> Result<BalanceSnapshot, AppError> {
+    let block_tag = format!("0x{block:x}");
+    let Some(multicall3) = multicall3 else {
+        let balances = fetch_one_by_one(client, url, &block_tag, queries).await;
+        let entries = queries
+            .iter()
+            .zip(balances)
+            .map(|(q, balance)| BalanceEntry { owner: q.owner, token: q.token, balance })
+            .collect();
+        return Ok(BalanceSnapshot { chain_id, block_number: block, entries, fetched_at: now_s() });
+    };
+    let multicall = Multicall3::new(client.clone(), url).with_address(multicall3).at_block(block);
+    let mut entries = Vec::with_capacity(queries.len());
+    for (start, end) in chunk_by_gas(queries, MULTICALL_GAS_BUDGET, MAX_CALLS_PER_CHUNK) {
+        let chunk = &queries[start..end];
+        let calls: Vec<Call> = chunk.iter().map(|q| q.to_call(&multicall)).collect();
+        let balances: Vec<Option<U256>> = match multicall.aggregate3(&calls).await {
+            Ok(results) => chunk
+                .iter()
+                .zip(results)
+                .map(|(q, r)| match q.token {
+                    Some(_) => r.decode::<IERC20Balance::balanceOfCall>(),
+                    None => r.decode::<IMulticall3::getEthBalanceCall>(),
+                })
+                .collect(),
+            // 网络错误直接返回，只有解码失败（通常是未部署 Multicall3）才回退
+            Err(AppError::Parse(_)) => fetch_one_by_one(client, url, &block_tag, chunk).await,
+            Err(e) => return Err(e),
+        };
+        entries.extend(
+            chunk
+                .iter()
+                .zip(balances)
+                .map(|(q, balance)| BalanceEntry { owner: q.owner, token: q.token, balance }),
+        );
+    }
+    Ok(BalanceSnapshot { chain_id, block_number: block, entries, fetched_at: now_s() })
+}
+
+/// 某条链上需要跟踪的 ERC-20（来自 user_tokens）
+pub fn tracked_tokens(tokens: &[Token], chain_id: u64) -> Vec<Address> {
+    tokens
+        .iter()
+        .filter(|t| t.chain_id == chain_id && t.assets_type != Some(AssetsType::Currency) && !t.address.is_zero())
+        .map(|t| t.address)
+        .collect()
+}
+
+// ========== 缓存与新区块刷新 ==========
+
+/// 按链缓存最近一个区块的快照，以及各链的新区块监听任务
+#[derive(Default)]
+pub struct BalanceCache {
+    snapshots: HashMap<u64, BalanceSnapshot>,
+    watchers: HashMap<u64, tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl BalanceCache {
+    /// 同一区块且覆盖全部查询时命中
+    pub fn get(&self, chain_id: u64, head: u64, queries: &[BalanceQuery]) -> Option<&BalanceSnapshot> {
+        self.snapshots
+            .get(&chain_id)
+            .filter(|s| s.block_number == head && s.covers(queries))
+    }
+
+    pub fn insert(&mut self, snapshot: BalanceSnapshot) {
+        self.snapshots.insert(snapshot.chain_id, snapshot);
+    }
+}
+
+/// 默认查询：全部账户 × 该链的可见代币
+async fn default_queries(state: &AppState, chain_id: u64) -> Vec<BalanceQuery> {
+    let owners = own_addresses(&state.accounts.lock().await);
+    let tokens = state
+        .user_tokens
+        .lock()
+        .await
+        .as_deref()
+        .map(|t| tracked_tokens(t, chain_id))
+        .unwrap_or_default();
+    build_queries(&owners, &tokens)
+}
+
+/// 取当前区块的余额，缓存命中则不再请求
+async fn current_balances(
+    state: &AppState,
+    chain_id: u64,
+    rpc_url: &str,
+    queries: &[BalanceQuery],
+    force: bool,
+) -> Result<BalanceSnapshot, AppError> {
+    let client = state.https_client.lock().await.clone();
+    let head = block_number(client.clone(), rpc_url).await?;
+    if !force {
+        if let Some(hit) = state.balance_cache.lock().await.get(chain_id, head, queries) {
+            return Ok(hit.clone());
+        }
+    }
+    let multicall3 = state.chain_registry.lock().await.capabilities(chain_id).multicall3;
+    let snapshot = fetch_balances(&client, rpc_url, chain_id, multicall3, queries, head).await?;
+    state.balance_cache.lock().await.insert(snapshot.clone());
+    Ok(snapshot)
+}
+
+fn parse_addresses(list: Vec<String>) -> Result<Vec<Address>, AppError> {
+    list.iter()
+        .map(|a| a.parse::<Address>().map_err(|_| AppError::Parse("invalid address")))
+        .collect()
+}
+
+/// owners / tokens 为空时使用全部账户与已跟踪代币
+#[tauri::command]
+pub async fn balances_get(
+    chain_id: u64,
+    rpc_url: String,
+    owners: Option<Vec<String>>,
+    tokens: Option<Vec<String>>,
+    force: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<BalanceSnapshot, AppError> {
+    let queries = match (owners, tokens) {
+        (None, None) => default_queries(&state, chain_id).await,
+        (owners, tokens) => {
+            let owners = match owners {
+                Some(list) => parse_addresses(list)?,
+                None => own_addresses(&state.accounts.lock().await),
+            };
+            build_queries(&owners, &parse_addresses(tokens.unwrap_or_default())?)
+        }
+    };
+    current_balances(&state, chain_id, &rpc_url, &queries, force.unwrap_or(false)).await
+}
+
+/// 开启 / 关闭某条链的新区块监听；每个新区块刷新一次并推送 balances:updated
+#[tauri::command]
+pub async fn balances_watch(
+    chain_id: u64,
+    rpc_url: String,
+    enabled: bool,
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let mut cache = state.balance_cache.lock().await;
+    if let Some(handle) = cache.watchers.remove(&chain_id) {
+        handle.abort();
+    }
+    if !enabled {
+        return Ok(());
+    }
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut last_block = 0;
+        loop {
+            let state = app_handle.state::<AppState>();
+            let client = state.https_client.lock().await.clone();
+            if let Ok(head) = block_number(client, &rpc_url).await {
+                if head > last_block {
+                    let queries = default_queries(&state, chain_id).await;
+                    match current_balances(&state, chain_id, &rpc_url, &queries, false).await {
+                        Ok(snapshot) => {
+                            last_block = snapshot.block_number;
+                            let _ = app_handle.emit(BALANCES_UPDATED_EVENT, &snapshot);
+                        }
+                        Err(e) => eprintln!("balance refresh failed on chain {}: {}", chain_id, e),
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(HEAD_POLL_SECS)).await;
+        }
+    });
+    cache.watchers.insert(chain_id, handle);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    const ALICE: Address = address!("0x1111111111111111111111111111111111111111");
+    const BOB: Address = address!("0x2222222222222222222222222222222222222222");
+    const USDC: Address = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+
+    #[test]
+    fn test_build_and_chunk_queries() {
+        let queries = build_queries(&[ALICE, BOB, ALICE], &[USDC, USDC]);
+        assert_eq!(queries.len(), 4);
+        assert_eq!(queries[0], BalanceQuery { owner: ALICE, token: None });
+
+        // 预算只够一个 balanceOf + 一个原生币查询
+        let chunks = chunk_by_gas(&queries, BALANCE_OF_GAS + ETH_BALANCE_GAS, 100);
+        assert_eq!(chunks, vec![(0, 2), (2, 4)]);
+        // 调用数上限
+        assert_eq!(chunk_by_gas(&queries, u64::MAX, 3), vec![(0, 3), (3, 4)]);
+        // 单个查询超出预算也要单独成块，不能死循环
+        assert_eq!(chunk_by_gas(&queries[1..2], 1, 10), vec![(0, 1)]);
+        assert!(chunk_by_gas(&[], MULTICALL_GAS_BUDGET, 10).is_empty());
+    }
+
+    #[test]
+    fn test_cache_hits_only_same_block_and_coverage() {
+        let queries = build_queries(&[ALICE], &[USDC]);
+        let snapshot = BalanceSnapshot {
+            chain_id: 1,
+            block_number: 100,
+            entries: vec![
+                BalanceEntry { owner: ALICE, token: None, balance: Some(U256::from(5)) },
+                BalanceEntry { owner: ALICE, token: Some(USDC), balance: None },
+            ],
+            fetched_at: 0,
+        };
+        assert_eq!(snapshot.balance_of(&ALICE, None), Some(U256::from(5)));
+        assert_eq!(snapshot.balance_of(&ALICE, Some(&USDC)), None);
+
+        let mut cache = BalanceCache::default();
+        cache.insert(snapshot);
+        assert!(cache.get(1, 100, &queries).is_some());
+        assert!(cache.get(1, 101, &queries).is_none());
+        assert!(cache.get(1, 100, &build_queries(&[BOB], &[])).is_none());
+    }
+}
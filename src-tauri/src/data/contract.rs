@@ -1,14 +1,58 @@
-use alloy_primitives::{address, Address};
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::eips::eip191::hash_eip191_bytes;
+use crate::error::AppError;
+use crate::rpc::method::get_code;
+use alloy_primitives::{Address, B256, Signature, keccak256};
+use bincode::{Decode, Encode};
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use tauri::State;
 
-#[derive(Debug, Clone)]
+/// 随版本发布的内置注册表，视为可信，不校验签名
+const BUILTIN_BUNDLE: &str = include_str!("contracts.json");
+
+/// 远程注册表的签名地址（EIP-191 personal_sign 签 payload 原文），发布构建时通过
+/// ZENO_REGISTRY_SIGNERS（逗号分隔的地址）注入；未注入时拒绝所有远程更新，只使用内置表
+pub static REGISTRY_SIGNERS: Lazy<Vec<Address>> =
+    Lazy::new(|| parse_signers(option_env!("ZENO_REGISTRY_SIGNERS").unwrap_or_default()));
+
+/// 逗号分隔的地址列表；写错的条目记日志后跳过，不 panic。
+/// 全部无效时得到空表，远程更新会以 RegistrySignersMissing 失败
+pub fn parse_signers(raw: &str) -> Vec<Address> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| match s.parse::<Address>() {
+            Ok(addr) => Some(addr),
+            Err(e) => {
+                log::warn!("ZENO_REGISTRY_SIGNERS: skipping invalid address {:?}: {}", s, e);
+                None
+            }
+        })
+        .collect()
+}
+
+const BUNDLE_KEY: &str = "contract_bundle";
+const USER_KEY_PREFIX: &str = "contract";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
 pub struct ContractEntry {
+    pub chain_id: u64,
     pub address: Address,
-    pub name: &'static str,
-    pub chain_id: u64,           // 直接用 u64，告别 Chain 枚举
+    pub name: String,
     pub contract_type: ContractType,
+    /// keccak256(runtime code)；设置后展示标签前需 eth_getCode 校验
+    #[serde(default)]
+    pub code_hash: Option<B256>,
+    #[serde(default)]
+    pub source: EntrySource,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(rename_all = "lowercase")]
 pub enum ContractType {
     Erc20,
     Erc721,
@@ -19,72 +63,310 @@ pub enum ContractType {
     Other,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EntrySource {
+    #[default]
+    Bundle,
+    User,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractBundle {
+    pub version: u64,
+    pub issued_at: u64,
+    pub entries: Vec<ContractEntry>,
+}
+
+/// 远程下发格式：payload 为 ContractBundle 的 JSON 原文，签名针对原文字节，
+/// 避免 JSON 规范化带来的歧义
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct SignedContractBundle {
+    pub payload: String,
+    pub signature: String,
+}
+
+/// 校验 payload 原文的 EIP-191 签名来自 signers 之一；其它签名下发的数据包也用它
+pub fn verify_payload_signature(payload: &str, signature: &str, signers: &[Address]) -> Result<(), AppError> {
+    if signers.is_empty() {
+        return Err(AppError::RegistrySignersMissing);
+    }
+    let raw = hex::decode(signature.trim_start_matches("0x"))?;
+    let digest = hash_eip191_bytes(payload.as_bytes());
+    let recovered = Signature::from_raw(&raw)
+        .ok()
+        .and_then(|sig| sig.recover_address_from_prehash(&digest).ok())
+        .ok_or(AppError::RegistrySignatureInvalid)?;
+    if !signers.contains(&recovered) {
+        return Err(AppError::RegistrySignatureInvalid);
+    }
+    Ok(())
+}
+
+/// 校验签名并解析 payload
+pub fn verify_bundle(signed: &SignedContractBundle, signers: &[Address]) -> Result<ContractBundle, AppError> {
+    verify_payload_signature(&signed.payload, &signed.signature, signers)?;
+    Ok(serde_json::from_str(&signed.payload)?)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CodeCheck {
+    /// 未设置 code hash
+    Unpinned,
+    Matched,
+    Mismatch { actual: B256 },
+    /// 地址上没有代码（未部署 / 已自毁 / 链不对）
+    NoCode,
+    /// 有 pin 但本次未能拉取代码
+    Unverified,
+}
+
+pub fn check_code(entry: &ContractEntry, code_hex: &str) -> CodeCheck {
+    let Some(pinned) = entry.code_hash else {
+        return CodeCheck::Unpinned;
+    };
+    let code = hex::decode(code_hex.trim_start_matches("0x")).unwrap_or_default();
+    if code.is_empty() {
+        return CodeCheck::NoCode;
+    }
+    let actual = keccak256(&code);
+    if actual == pinned { CodeCheck::Matched } else { CodeCheck::Mismatch { actual } }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ContractRegistry {
+    pub version: u64,
+    entries: HashMap<(u64, Address), ContractEntry>,
+}
+
+impl ContractRegistry {
+    pub fn from_bundle(bundle: ContractBundle) -> Self {
+        let mut registry = Self { version: bundle.version, entries: HashMap::new() };
+        for mut entry in bundle.entries {
+            entry.source = EntrySource::Bundle;
+            registry.entries.insert((entry.chain_id, entry.address), entry);
+        }
+        registry
+    }
+
+    pub fn builtin() -> Self {
+        let bundle: ContractBundle =
+            serde_json::from_str(BUILTIN_BUNDLE).expect("builtin contracts.json is valid");
+        Self::from_bundle(bundle)
+    }
+
+    /// 内置表 → 已存储的更新包（版本更高且签名有效）→ 用户条目，后者覆盖前者
+    pub fn load(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Self> {
+        let mgr = TableManager::new(db, TableKind::Registry)?;
+        let mut registry = Self::builtin();
+        if let Some(signed) = mgr.get::<SignedContractBundle>(&mgr.key_from_str(BUNDLE_KEY))? {
+            match verify_bundle(&signed, &REGISTRY_SIGNERS) {
+                Ok(bundle) if bundle.version > registry.version => registry = Self::from_bundle(bundle),
+                Ok(_) => {}
+                Err(e) => eprintln!("Ignoring stored contract bundle: {}", e),
+            }
+        }
+        for entry in mgr.list_by_prefix::<ContractEntry>(&format!("{USER_KEY_PREFIX}:"))? {
+            registry.overlay(entry);
+        }
+        Ok(registry)
+    }
+
+    pub fn overlay(&mut self, mut entry: ContractEntry) {
+        entry.source = EntrySource::User;
+        self.entries.insert((entry.chain_id, entry.address), entry);
+    }
+
+    pub fn get(&self, chain_id: u64, address: &Address) -> Option<&ContractEntry> {
+        self.entries.get(&(chain_id, *address))
+    }
+
+    pub fn list(&self, chain_id: Option<u64>) -> Vec<ContractEntry> {
+        let mut list: Vec<ContractEntry> = self
+            .entries
+            .values()
+            .filter(|e| chain_id.is_none_or(|id| e.chain_id == id))
+            .cloned()
+            .collect();
+        list.sort_by(|a, b| (a.chain_id, &a.name).cmp(&(b.chain_id, &b.name)));
+        list
+    }
+}
+
+fn user_key(mgr: &TableManager, chain_id: u64, address: &Address) -> Vec<u8> {
+    mgr.key_from_str(&format!("{USER_KEY_PREFIX}:{chain_id}:{:#x}", address))
+}
+
+/// 确认页展示用：注册表条目 + code hash 校验结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractLabel {
+    pub entry: ContractEntry,
+    pub code_check: CodeCheck,
+    pub registry_version: u64,
+}
+
+impl ContractLabel {
+    /// 有 pin 却未通过校验时标签不可信，UI 应显示警告而非名称
+    pub fn trusted(&self) -> bool {
+        matches!(self.code_check, CodeCheck::Unpinned | CodeCheck::Matched)
+    }
+}
+
+// ========== CONTRACT REGISTRY ==========
+#[tauri::command]
+pub async fn contract_lookup(
+    chain_id: u64,
+    address: String,
+    rpc_url: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<ContractLabel>, AppError> {
+    let address = address
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let (entry, registry_version) = {
+        let registry = state.contract_registry.lock().await;
+        match registry.get(chain_id, &address) {
+            Some(entry) => (entry.clone(), registry.version),
+            None => return Ok(None),
+        }
+    };
+
+    let code_check = match (&entry.code_hash, rpc_url) {
+        (Some(_), Some(url)) => {
+            let client = state.https_client.lock().await.clone();
+            let code = get_code(client, &url, &format!("{:#x}", address), "latest").await?;
+            check_code(&entry, &code)
+        }
+        (Some(_), None) => CodeCheck::Unverified,
+        (None, _) => CodeCheck::Unpinned,
+    };
+    Ok(Some(ContractLabel { entry, code_check, registry_version }))
+}
+
+#[tauri::command]
+pub fn contract_list(
+    chain_id: Option<u64>,
+    state: State<AppState>,
+) -> Result<Vec<ContractEntry>, AppError> {
+    Ok(state.contract_registry.blocking_lock().list(chain_id))
+}
 
-// 还需验证地址
-pub const HOT_CONTRACTS: &[ContractEntry] = &[
-    // ==================== Ethereum Mainnet (chain_id: 1) ====================
-    ContractEntry { address: address!("0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D"), name: "Uniswap V2 Router", chain_id: 1, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45"), name: "Uniswap V3 SwapRouter", chain_id: 1, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xE592427A0AEce92De3Edee1F18E0157C05861564"), name: "Uniswap V3 SwapRouter02", chain_id: 1, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F"), name: "SushiSwap Router", chain_id: 1, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x1111111254EEB25477B68fb85Ed929f73A960582"), name: "1inch Aggregation Router V5", chain_id: 1, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x111111125421CA6dc452d289314280a0f8842A65"), name: "1inch Aggregation Router V6", chain_id: 1, contract_type: ContractType::Defi },
-    // Lending
-    ContractEntry { address: address!("0x87870Bca3F3fD6335C3F4ce8392D69350B4fA4E2"), name: "Aave V3 Pool", chain_id: 1, contract_type: ContractType::Defi },
-    // ENS
-    ContractEntry { address: address!("0x00000000000C2E074eC69A0dF927485996509C85"), name: "ENS Registry", chain_id: 1, contract_type: ContractType::Other },
-    ContractEntry { address: address!("0x4976fb03C49E672A32967b4fB7D2b0b8d6202018"), name: "ENS Public Resolver", chain_id: 1, contract_type: ContractType::Other },
-
-    // ==================== BSC (chain_id: 56) ====================
-    ContractEntry { address: address!("0x10ED43C718714eb63d5aA57B78B54704E256024E"), name: "PancakeSwap V2 Router", chain_id: 56, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x13f4EA83D0bd40E75C8222255bc855a974568Dd4"), name: "PancakeSwap V3 Smart Router", chain_id: 56, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x0BFbCF9fa4f9C56B0F40a671Ad40E0805A091865"), name: "PancakeSwap V3 Factory", chain_id: 56, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xCDe540d7eAFE93aC5fE6233Bee57E1270D3E330F"), name: "BakerySwap Router", chain_id: 56, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x1111111254EEB25477B68fb85Ed929f73A960582"), name: "1inch Aggregation Router V5", chain_id: 56, contract_type: ContractType::Defi },
-    // Lending
-    ContractEntry { address: address!("0xfd5840Cd36d94D91a69e9BC85c0e18f0d6d0d77e"), name: "Venus Core Pool", chain_id: 56, contract_type: ContractType::Defi },
-
-    // ==================== Polygon (chain_id: 137) ====================
-    ContractEntry { address: address!("0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45"), name: "Uniswap V3 SwapRouter", chain_id: 137, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xa5E0829CaCEd8fFDD4De3c43696c57F7D7A678ff"), name: "QuickSwap Router", chain_id: 137, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x1111111254EEB25477B68fb85Ed929f73A960582"), name: "1inch Aggregation Router V5", chain_id: 137, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x111111125421CA6dc452d289314280a0f8842A65"), name: "1inch Aggregation Router V6", chain_id: 137, contract_type: ContractType::Defi },
-    // Lending
-    ContractEntry { address: address!("0x794a61358D6845594F94dc1DB02A252b5b4814aD"), name: "Aave V3 Pool", chain_id: 137, contract_type: ContractType::Defi },
-
-    // ==================== Arbitrum One (chain_id: 42161) ====================
-    ContractEntry { address: address!("0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45"), name: "Uniswap V3 SwapRouter", chain_id: 42161, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xE592427A0AEce92De3Edee1F18E0157C05861564"), name: "Uniswap V3 SwapRouter02", chain_id: 42161, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xb1026b8e7276e7ac75410f1fcbbe21796e8f7526"), name: "Camelot V3 Router", chain_id: 42161, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x1111111254EEB25477B68fb85Ed929f73A960582"), name: "1inch Aggregation Router V5", chain_id: 42161, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x111111125421CA6dc452d289314280a0f8842A65"), name: "1inch Aggregation Router V6", chain_id: 42161, contract_type: ContractType::Defi },
-    // Lending
-    ContractEntry { address: address!("0x794a61358D6845594F94dc1DB02A252b5b4814aD"), name: "Aave V3 Pool", chain_id: 42161, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xF4B1486DD74aB73aB2d0aB1f8E2f2eF9e3bB5D6F"), name: "Radiant Capital Lending", chain_id: 42161, contract_type: ContractType::Defi },
-
-    // ==================== Base (chain_id: 8453) ====================
-    ContractEntry { address: address!("0x33128a8fC17869897dcE68Ed026d694621f6FDfD"), name: "Uniswap V3 SwapRouter (Base)", chain_id: 8453, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xcF77a3Ba9A5CA399B7423c4B6fF69E0A1689eE57"), name: "Aerodrome Finance Router", chain_id: 8453, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x2626664c2603336EAC6A6944964d4C7c8A0C7f3f"), name: "BaseSwap V2 Router", chain_id: 8453, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x1111111254EEB25477B68fb85Ed929f73A960582"), name: "1inch Aggregation Router V5", chain_id: 8453, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x111111125421CA6dc452d289314280a0f8842A65"), name: "1inch Aggregation Router V6", chain_id: 8453, contract_type: ContractType::Defi },
-    // Lending
-    ContractEntry { address: address!("0x794a61358D6845594F94dc1DB02A252b5b4814aD"), name: "Aave V3 Pool", chain_id: 8453, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x0A7C4d8e318d3b0e6A76C9f2bE92d4fE8c8C8C8C"), name: "Compound V3 (Base USDC)", chain_id: 8453, contract_type: ContractType::Defi },
-
-    // ==================== Linea (chain_id: 59144) ====================
-    ContractEntry { address: address!("0x111111125421CA6dc452d289314280a0f8842A65"), name: "1inch Aggregation Router V6", chain_id: 59144, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x80aC24f71a7E5fC1fC82C0dC2a5aE1bB5d1487c4"), name: "SyncSwap Classic Router", chain_id: 59144, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0xC02a4f2D5A3E9E5F5bB8d5A3E9E5F5bB8d5A3E9E"), name: "SyncSwap V3 Router", chain_id: 59144, contract_type: ContractType::Defi },
-    ContractEntry { address: address!("0x3b6d9a46a23d2d7b0d9e0a67a5d8b8e4d03b1a0a"), name: "HorizonDEX Router", chain_id: 59144, contract_type: ContractType::Defi },
-    // Lending
-    ContractEntry { address: address!("0x2e06F53D4B45D3e4d8D9263c9f734aBc74D9D8aB"), name: "ZeroLend Lending Pool", chain_id: 59144, contract_type: ContractType::Defi },
-];
-
-// 可选：快速查找函数
-impl ContractEntry {
-    pub fn is_hot_contract(chain_id: u64, address: Address) -> Option<&'static ContractEntry> {
-        HOT_CONTRACTS.iter().find(|c| c.chain_id == chain_id && c.address == address)
+/// 安装远程下发的签名注册表；版本号必须递增
+#[tauri::command]
+pub fn contract_registry_update(
+    bundle: SignedContractBundle,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<u64, AppError> {
+    let parsed = verify_bundle(&bundle, &REGISTRY_SIGNERS)?;
+    let current = state.contract_registry.blocking_lock().version;
+    if parsed.version <= current {
+        return Err(AppError::RegistryVersionRollback(current, parsed.version));
     }
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    mgr.set(&mgr.key_from_str(BUNDLE_KEY), &bundle)?;
+    let registry = ContractRegistry::load(db)?;
+    let version = registry.version;
+    *state.contract_registry.blocking_lock() = registry;
+    Ok(version)
 }
 
+#[tauri::command]
+pub fn contract_user_add(
+    entry: ContractEntry,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    let entry = ContractEntry { source: EntrySource::User, ..entry };
+    mgr.set(&user_key(&mgr, entry.chain_id, &entry.address), &entry)?;
+    state.contract_registry.blocking_lock().overlay(entry);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn contract_user_delete(
+    chain_id: u64,
+    address: String,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let address = address
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    mgr.delete(&user_key(&mgr, chain_id, &address))?;
+    // 删除用户条目后可能需要恢复被覆盖的内置条目，整体重载
+    *state.contract_registry.blocking_lock() = ContractRegistry::load(db)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, b256};
+
+    // 私钥 0x01 对应地址，仅用于测试签名
+    const TEST_SIGNER: Address = address!("0x7e5f4552091a69125d5dfcb7b8c2659029395bdf");
+    const PAYLOAD: &str = r#"{"version":2,"issued_at":1760832000,"entries":[{"chain_id":1,"address":"0x1111111111111111111111111111111111111111","name":"Test Router","contract_type":"defi","code_hash":null}]}"#;
+    const SIGNATURE: &str = "0xd47644539acec3da5e3ecf5fe8863c628a9c97e8b71e9ea9167a6f4f83c03c322bfd3616652c13a24eb1f3d5160438606f5a7717183321824d0cecfc7d69dc8d1b";
+
+    #[test]
+    fn test_builtin_bundle_parses() {
+        let registry = ContractRegistry::builtin();
+        assert!(registry.version >= 1);
+        let permit2 = registry.get(1, &crate::evm::address::known::PERMIT2).unwrap();
+        assert_eq!(permit2.name, "Permit2");
+    }
+
+    #[test]
+    fn test_verify_signed_bundle() {
+        let signed = SignedContractBundle { payload: PAYLOAD.to_string(), signature: SIGNATURE.to_string() };
+        let bundle = verify_bundle(&signed, &[TEST_SIGNER]).unwrap();
+        assert_eq!(bundle.version, 2);
+        assert_eq!(bundle.entries[0].name, "Test Router");
+
+        assert!(matches!(verify_bundle(&signed, &[]), Err(AppError::RegistrySignersMissing)));
+        let tampered = SignedContractBundle { payload: PAYLOAD.replace("Test Router", "Evil Router"), ..signed };
+        assert!(verify_bundle(&tampered, &[TEST_SIGNER]).is_err());
+    }
+
+    #[test]
+    fn test_only_listed_signers_accepted() {
+        let signed = SignedContractBundle { payload: PAYLOAD.to_string(), signature: SIGNATURE.to_string() };
+        let signers = parse_signers(" 0x2B5AD5c4795c026514f8317c7a215E218DcCD6cF, 0xnot-an-address, 0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf ,");
+        assert_eq!(signers.len(), 2);
+        assert!(verify_bundle(&signed, &signers).is_ok());
+        // 私钥 0x02 对应地址，不是本包的签名者
+        let not_listed = &signers[..1];
+        assert!(matches!(verify_bundle(&signed, not_listed), Err(AppError::RegistrySignatureInvalid)));
+        // 配置全部写错：不 panic，按未配置签名者处理
+        let broken = parse_signers("0x1234,zeno");
+        assert!(matches!(verify_bundle(&signed, &broken), Err(AppError::RegistrySignersMissing)));
+    }
+
+    #[test]
+    fn test_code_hash_pin_and_user_overlay() {
+        let target = address!("0x1111111111111111111111111111111111111111");
+        let mut entry = ContractEntry {
+            chain_id: 1,
+            address: target,
+            name: "Pinned".to_string(),
+            contract_type: ContractType::Other,
+            code_hash: Some(b256!("0x7efcce47028dabcb0d42f3a7eda8820bf6f7f4e618398c2547d52f703cafb073")),
+            source: EntrySource::Bundle,
+        };
+        assert_eq!(check_code(&entry, "0x6001600055"), CodeCheck::Matched);
+        assert!(matches!(check_code(&entry, "0x6002600055"), CodeCheck::Mismatch { .. }));
+        assert_eq!(check_code(&entry, "0x"), CodeCheck::NoCode);
+
+        let mut registry = ContractRegistry::builtin();
+        entry.name = "My Router".to_string();
+        registry.overlay(entry);
+        let got = registry.get(1, &target).unwrap();
+        assert_eq!((got.name.as_str(), got.source), ("My Router", EntrySource::User));
+    }
+}
@@ -1,14 +1,100 @@
+use crate::actions::uniswap::router::{evm_chain_from_id, get_base_pair};
+use crate::evm::capability::ChainCapabilities;
+use crate::evm::chains::{SUPPORTED_CHAIN_IDS, is_testnet, native_symbol};
+use alloy_primitives::Address;
+use bincode::{Decode, Encode};
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug,Serialize, Deserialize)]
+/// 主流原生币的 USD 价格
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Encode, Decode, PartialEq)]
 pub struct CurrencyUsdPrice {
-	timestamp: u64,
+    pub timestamp: u64,
     #[serde(rename = "BTC")]
-	btc: f64,
+    pub btc: f64,
     #[serde(rename = "ETH")]
-	eth: f64,
+    pub eth: f64,
     #[serde(rename = "BNB")]
-	bnb: f64,
+    pub bnb: f64,
     #[serde(rename = "POL")]
-	pol: f64,
+    pub pol: f64,
+}
+
+impl CurrencyUsdPrice {
+    /// CoinGecko simple/price 的 id，顺序与字段对应
+    pub const COINGECKO_IDS: [(&'static str, &'static str); 4] = [
+        ("BTC", "bitcoin"),
+        ("ETH", "ethereum"),
+        ("BNB", "binancecoin"),
+        ("POL", "polygon-ecosystem-token"),
+    ];
+
+    /// 按原生币符号（BTC / ETH / BNB / POL）取 USD 价格
+    pub fn price_of(&self, symbol: &str) -> Option<f64> {
+        let price = match symbol {
+            "BTC" => self.btc,
+            "ETH" => self.eth,
+            "BNB" => self.bnb,
+            "POL" => self.pol,
+            _ => return None,
+        };
+        (price > 0.0).then_some(price)
+    }
+
+    /// 按 (链, 合约) 取 USD 价格，token 为 Address::ZERO 表示原生币。
+    /// 只认内置主网链上的原生币、链能力表里的包装原生币、USDC / USDT（按 1:1）与 BTC 包装币；
+    /// 代币符号由合约自报，任何人都能起名 USDC，所以不按符号定价，未知合约返回 None
+    pub fn token_price(&self, chain_id: u64, token: Address, caps: &ChainCapabilities) -> Option<f64> {
+        if !SUPPORTED_CHAIN_IDS.contains(&chain_id) || is_testnet(chain_id) {
+            return None;
+        }
+        let native = self.price_of(native_symbol(chain_id));
+        if token.is_zero() {
+            return native;
+        }
+        let pair = get_base_pair(&evm_chain_from_id(chain_id)?, caps, true)?;
+        if token == pair.native {
+            native
+        } else if pair.stables.contains(&token) {
+            Some(1.0)
+        } else if pair.alternatives.contains(&token) {
+            self.price_of("BTC")
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, symbol: &str, price: f64) {
+        match symbol {
+            "BTC" => self.btc = price,
+            "ETH" => self.eth = price,
+            "BNB" => self.bnb = price,
+            "POL" => self.pol = price,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::capability::builtin_capabilities;
+    use alloy_primitives::address;
+
+    #[test]
+    fn test_token_price_by_contract() {
+        let prices = CurrencyUsdPrice { timestamp: 1, btc: 100_000.0, eth: 4_000.0, bnb: 600.0, pol: 0.5 };
+        let caps = builtin_capabilities(1).unwrap();
+        let usdc = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let weth = address!("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+        let wbtc = address!("0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599");
+        assert_eq!(prices.token_price(1, Address::ZERO, &caps), Some(4_000.0));
+        assert_eq!(prices.token_price(1, weth, &caps), Some(4_000.0));
+        assert_eq!(prices.token_price(1, usdc, &caps), Some(1.0));
+        assert_eq!(prices.token_price(1, wbtc, &caps), Some(100_000.0));
+        // 自称 USDC 的其它合约、其它链上的同一地址、测试网都不定价
+        assert_eq!(prices.token_price(1, Address::repeat_byte(0x11), &caps), None);
+        assert_eq!(prices.token_price(56, usdc, &builtin_capabilities(56).unwrap()), None);
+        assert_eq!(prices.token_price(11155111, Address::ZERO, &builtin_capabilities(11155111).unwrap()), None);
+        assert_eq!(prices.token_price(56, Address::ZERO, &builtin_capabilities(56).unwrap()), Some(600.0));
+    }
 }
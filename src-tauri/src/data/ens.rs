@@ -1,28 +1,933 @@
-use helios::{rpc::Http, types::Address, ens::Ens};
-use cached::proc_macro::cached;
-use std::time::Duration;
+// ENS 解析：ENSIP-10 通配解析 + EIP-3668 CCIP-read 链下查询，
+// 文本记录 / 多币种地址 / 头像（ENSIP-12），ENSIP-19 的 L2 主名称与 L2 注册表，结果写入 Cache 表带 TTL。
+// 名称只做小写 + 基本校验，未实现完整的 ENSIP-15 规范化
+use crate::core::db::{AppDB, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::data::meta::{GatewayConfig, fetch_metadata, gateway_url, substitute_id};
+use crate::error::AppError;
+use crate::evm::chains::{SUPPORTED_CHAIN_IDS, is_testnet};
+use crate::rpc::method::{chain_id, eth_call};
+use crate::utils::time::now_s;
+use alloy_primitives::{Address, B256, Bytes, FixedBytes, U256, address, keccak256};
+use alloy_sol_types::{SolCall, SolError, SolType, sol, sol_data};
+use bincode::{Decode, Encode};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tauri::State;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+/// 主网 ENS Registry（ENSRegistryWithFallback）
+pub const ENS_REGISTRY: Address = address!("0x00000000000C2E074eC69A0dF927485996509C85");
+/// ENS 缓存有效期（秒）
+pub const ENS_CACHE_TTL_SECS: u64 = 600;
+/// 同一次解析中最多跟随的 OffchainLookup 次数（EIP-3668 建议有上限）
+pub const MAX_CCIP_REDIRECTS: usize = 4;
+/// ENSIP-10 IExtendedResolver
+pub const EXTENDED_RESOLVER_INTERFACE: [u8; 4] = [0x90, 0x61, 0xb9, 0x23];
+/// SLIP-44 ETH
+pub const COIN_TYPE_ETH: u64 = 60;
+/// 默认拉取的文本记录
+pub const DEFAULT_TEXT_KEYS: &[&str] = &["avatar", "url", "com.twitter", "com.github", "description", "email"];
 
-/// 用 cached 宏做缓存，TTL 10 分钟
-#[cached(time = 600, result = true)]
-async fn resolve_ens_helios(name: &str, rpc_url: &str) -> Result<Address> {
-    // 创建 HTTP provider
-    let client = Http::new(rpc_url)?;
-    let ens = Ens::new(client);
+sol! {
+    interface IENSRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
 
-    let address = ens.name(name).await?;
-    Ok(address)
+    interface IERC165 {
+        function supportsInterface(bytes4 interfaceID) external view returns (bool);
+    }
+
+    interface IExtendedResolver {
+        function resolve(bytes memory name, bytes memory data) external view returns (bytes memory);
+    }
+
+    interface IAddrResolver {
+        function addr(bytes32 node) external view returns (address);
+    }
+
+    interface IAddressResolver {
+        function addr(bytes32 node, uint256 coinType) external view returns (bytes memory);
+    }
+
+    interface ITextResolver {
+        function text(bytes32 node, string calldata key) external view returns (string memory);
+    }
+
+    interface INameResolver {
+        function name(bytes32 node) external view returns (string memory);
+    }
+
+    error OffchainLookup(address sender, string[] urls, bytes callData, bytes4 callbackFunction, bytes extraData);
+
+    interface IERC721Avatar {
+        function ownerOf(uint256 tokenId) external view returns (address);
+        function tokenURI(uint256 tokenId) external view returns (string memory);
+    }
+
+    interface IERC1155Avatar {
+        function balanceOf(address account, uint256 id) external view returns (uint256);
+        function uri(uint256 id) external view returns (string memory);
+    }
 }
 
-/// 反向解析 address -> ENS name
-/// cached(time = 600, result = true) 表示缓存 10 分钟
-#[cached(time = 600, result = true)]
-async fn reverse_lookup_helios(address: Address, rpc_url: &str) -> Result<Option<String>> {
-    let client = Http::new(rpc_url)?;
-    let ens = Ens::new(client);
+// ========== 名称编码 ==========
 
-    let name = ens.reverse(address).await?;
+/// 小写 + 去空白，拒绝空标签
+pub fn normalize_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim().trim_end_matches('.').to_lowercase();
+    if name.is_empty() || name.split('.').any(|l| l.is_empty() || l.chars().any(char::is_whitespace)) {
+        return Err(AppError::EnsInvalidName);
+    }
     Ok(name)
 }
 
+/// EIP-137 namehash
+pub fn namehash(name: &str) -> B256 {
+    let mut node = B256::ZERO;
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        let label_hash = keccak256(label.as_bytes());
+        node = keccak256([node.as_slice(), label_hash.as_slice()].concat());
+    }
+    node
+}
+
+/// DNS wire format（ENSIP-10 resolve 的 name 参数）
+pub fn dns_encode(name: &str) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::with_capacity(name.len() + 2);
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 255 {
+            return Err(AppError::EnsInvalidName);
+        }
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+    Ok(out)
+}
+
+/// 反向解析节点名：<addr hex>.addr.reverse
+pub fn reverse_name(address: &Address) -> String {
+    format!("{}.addr.reverse", hex::encode(address.as_slice()))
+}
+
+/// ENSIP-11：EVM 链的 coinType
+pub fn evm_coin_type(chain_id: u64) -> u64 {
+    if chain_id == 1 { COIN_TYPE_ETH } else { 0x8000_0000 | chain_id }
+}
+
+// ========== CCIP-read ==========
+
+/// 请求 CCIP 网关：url 含 {data} 用 GET，否则 POST；4xx 直接失败，其余错误尝试下一个
+pub async fn ccip_fetch(client: &Client, urls: &[String], sender: Address, call_data: &[u8]) -> Result<Bytes, AppError> {
+    let sender_hex = format!("{:#x}", sender);
+    let data_hex = format!("0x{}", hex::encode(call_data));
+    let mut last_err = AppError::EnsCcipError("no gateway url".to_string());
+    for url in urls {
+        let href = url.replace("{sender}", &sender_hex).replace("{data}", &data_hex);
+        let request = if url.contains("{data}") {
+            client.get(&href)
+        } else {
+            client.post(&href).json(&json!({ "data": data_hex, "sender": sender_hex }))
+        };
+        let response = match request.send().await {
+            Ok(r) => r,
+            Err(e) => {
+                last_err = AppError::ReqwestClientConnectionError(e);
+                continue;
+            }
+        };
+        let status = response.status();
+        if status.is_client_error() {
+            return Err(AppError::EnsCcipError(format!("gateway returned {status}")));
+        }
+        if !status.is_success() {
+            last_err = AppError::EnsCcipError(format!("gateway returned {status}"));
+            continue;
+        }
+        let body: Value = response.json().await.map_err(AppError::ReqwestClientConnectionError)?;
+        let data = body["data"]
+            .as_str()
+            .ok_or_else(|| AppError::EnsCcipError("gateway response missing data".to_string()))?;
+        return Ok(hex::decode(data.trim_start_matches("0x"))?.into());
+    }
+    Err(last_err)
+}
+
+/// callbackFunction(bytes response, bytes extraData)
+pub fn encode_ccip_callback(selector: FixedBytes<4>, response: &Bytes, extra_data: &Bytes) -> Vec<u8> {
+    let mut data = selector.to_vec();
+    data.extend(<(sol_data::Bytes, sol_data::Bytes)>::abi_encode_params(&(response.clone(), extra_data.clone())));
+    data
+}
+
+// ========== 解析客户端 ==========
+
+pub struct EnsClient {
+    client: Client,
+    url: String,
+    registry: Address,
+}
+
+impl EnsClient {
+    pub fn new(client: Client, url: &str) -> Self {
+        Self { client, url: url.to_string(), registry: ENS_REGISTRY }
+    }
+
+    pub fn with_registry(mut self, registry: Address) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    async fn eth_call_raw(&self, to: Address, data: &[u8]) -> Result<Bytes, AppError> {
+        let result = eth_call(
+            self.client.clone(),
+            &self.url,
+            &format!("{:#x}", to),
+            &format!("0x{}", hex::encode(data)),
+            "latest",
+        )
+        .await?;
+        Ok(hex::decode(result.as_str().unwrap_or("0x").trim_start_matches("0x"))?.into())
+    }
+
+    /// eth_call，遇到 OffchainLookup revert 时按 EIP-3668 走网关并回调
+    pub async fn ccip_call(&self, to: Address, data: Vec<u8>) -> Result<Bytes, AppError> {
+        let mut data = data;
+        for _ in 0..=MAX_CCIP_REDIRECTS {
+            let revert = match self.eth_call_raw(to, &data).await {
+                Ok(result) => return Ok(result),
+                Err(AppError::RpcReverted(revert)) => revert,
+                Err(e) => return Err(e),
+            };
+            let revert = hex::decode(revert.trim_start_matches("0x"))?;
+            let Ok(lookup) = OffchainLookup::abi_decode(&revert) else {
+                return Err(AppError::RpcReverted(format!("0x{}", hex::encode(revert))));
+            };
+            if lookup.sender != to {
+                return Err(AppError::EnsCcipError("OffchainLookup sender mismatch".to_string()));
+            }
+            let response = ccip_fetch(&self.client, &lookup.urls, lookup.sender, &lookup.callData).await?;
+            data = encode_ccip_callback(lookup.callbackFunction, &response, &lookup.extraData);
+        }
+        Err(AppError::EnsCcipError("too many OffchainLookup redirects".to_string()))
+    }
+
+    async fn call<C: SolCall>(&self, to: Address, call: &C) -> Result<Option<C::Return>, AppError> {
+        let result = self.ccip_call(to, call.abi_encode()).await?;
+        if result.is_empty() {
+            return Ok(None);
+        }
+        Ok(C::abi_decode_returns(&result).ok())
+    }
+
+    /// 从完整名称逐级向上找 resolver，返回 (resolver, 是否精确匹配)
+    pub async fn find_resolver(&self, name: &str) -> Result<Option<(Address, bool)>, AppError> {
+        let labels: Vec<&str> = name.split('.').collect();
+        for i in 0..labels.len() {
+            let node = namehash(&labels[i..].join("."));
+            let resolver = self
+                .call(self.registry, &IENSRegistry::resolverCall { node })
+                .await?
+                .unwrap_or_default();
+            if !resolver.is_zero() {
+                return Ok(Some((resolver, i == 0)));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn supports_extended(&self, resolver: Address) -> bool {
+        let call = IERC165::supportsInterfaceCall { interfaceID: EXTENDED_RESOLVER_INTERFACE.into() };
+        matches!(self.call(resolver, &call).await, Ok(Some(true)))
+    }
+
+    /// 查找名称的 resolver 并探测 IExtendedResolver，同一名称的多条记录共用一次查找
+    pub async fn bind(&self, name: &str) -> Result<BoundName<'_>, AppError> {
+        let resolver = match self.find_resolver(name).await? {
+            Some((address, exact)) => Some(NameResolver { address, exact, extended: self.supports_extended(address).await }),
+            None => None,
+        };
+        Ok(BoundName { ens: self, name: name.to_string(), resolver })
+    }
+
+    pub async fn resolve_record<C: SolCall>(&self, name: &str, call: &C) -> Result<Option<C::Return>, AppError> {
+        self.bind(name).await?.record(call).await
+    }
+
+    pub async fn addr(&self, name: &str) -> Result<Option<Address>, AppError> {
+        self.bind(name).await?.addr().await
+    }
+
+    /// 多币种地址（ENSIP-9 / 11），返回原始字节
+    pub async fn coin_addr(&self, name: &str, coin_type: u64) -> Result<Option<Bytes>, AppError> {
+        self.bind(name).await?.coin_addr(coin_type).await
+    }
+
+    pub async fn text(&self, name: &str, key: &str) -> Result<Option<String>, AppError> {
+        self.bind(name).await?.text(key).await
+    }
+
+    /// 反向记录（未做正向校验，展示前必须用 verified_primary_name）
+    pub async fn reverse_record(&self, address: &Address) -> Result<Option<String>, AppError> {
+        let name = reverse_name(address);
+        let node = namehash(&name);
+        let found = self.resolve_record(&name, &INameResolver::nameCall { node }).await?;
+        Ok(found.filter(|n| !n.is_empty()))
+    }
+
+    /// 反向名称必须能正向解析回同一地址，否则视为没有主名称
+    pub async fn verified_primary_name(&self, address: &Address) -> Result<Option<String>, AppError> {
+        let Some(name) = self.reverse_record(address).await? else {
+            return Ok(None);
+        };
+        let Ok(normalized) = normalize_name(&name) else {
+            return Ok(None);
+        };
+        let forward = self.addr(&normalized).await?;
+        Ok((forward == Some(*address)).then_some(normalized))
+    }
+
+    /// 正向解析，并检查该地址的反向记录是否指回同一名称
+    pub async fn resolve_verified(&self, name: &str) -> Result<ResolvedName, AppError> {
+        let address = self.addr(name).await?;
+        let primary = match address {
+            Some(address) => self.reverse_record(&address).await?.and_then(|n| normalize_name(&n).ok()).as_deref() == Some(name),
+            None => false,
+        };
+        Ok(ResolvedName { name: name.to_string(), address, primary })
+    }
+
+    /// ENSIP-12 头像解析；NFT 头像需名称所指地址持有该 NFT
+    pub async fn avatar(&self, record: &str, owner: Option<Address>) -> Result<Avatar, AppError> {
+        let mut avatar = Avatar { record: record.to_string(), url: None, nft: None };
+        let Some(nft) = parse_nft_avatar(record) else {
+            avatar.url = gateway_url(record);
+            return Ok(avatar);
+        };
+
+        // 只能在当前 RPC 所在链上校验所有权与取 tokenURI，不能假定 rpc_url 就是主网
+        let mut nft = nft;
+        if chain_id(self.client.clone(), &self.url).await? == nft.chain_id {
+            nft.owned = match (owner, nft.standard) {
+                (Some(owner), NftStandard::Erc721) => self
+                    .call(nft.contract, &IERC721Avatar::ownerOfCall { tokenId: nft.token_id })
+                    .await?
+                    .map(|holder| holder == owner),
+                (Some(owner), NftStandard::Erc1155) => self
+                    .call(nft.contract, &IERC1155Avatar::balanceOfCall { account: owner, id: nft.token_id })
+                    .await?
+                    .map(|balance| balance > U256::ZERO),
+                (None, _) => None,
+            };
+            if nft.owned == Some(true) {
+                let uri = match nft.standard {
+                    NftStandard::Erc721 => self.call(nft.contract, &IERC721Avatar::tokenURICall { tokenId: nft.token_id }).await?,
+                    NftStandard::Erc1155 => self
+                        .call(nft.contract, &IERC1155Avatar::uriCall { id: nft.token_id })
+                        .await?
+                        .map(|uri| substitute_id(&uri, nft.token_id)),
+                };
+                if let Some(uri) = uri {
+                    avatar.url = fetch_metadata(&self.client, &GatewayConfig::default(), &uri)
+                        .await
+                        .and_then(|m| m.image)
+                        .as_deref()
+                        .and_then(gateway_url);
+                }
+            }
+        }
+        avatar.nft = Some(nft);
+        Ok(avatar)
+    }
+}
+
+/// 名称的 resolver 及其能力
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NameResolver {
+    pub address: Address,
+    /// resolver 设置在名称本身而非父级
+    pub exact: bool,
+    pub extended: bool,
+}
+
+/// 已查好 resolver 的名称，读取多条记录时复用
+pub struct BoundName<'a> {
+    ens: &'a EnsClient,
+    name: String,
+    resolver: Option<NameResolver>,
+}
+
+impl BoundName<'_> {
+    /// ENSIP-10：resolver 支持 IExtendedResolver 时一律走 resolve(dnsName, data)，
+    /// 否则只有精确匹配的 resolver 才可直接调用
+    pub async fn record<C: SolCall>(&self, call: &C) -> Result<Option<C::Return>, AppError> {
+        let Some(resolver) = self.resolver else {
+            return Ok(None);
+        };
+        if resolver.extended {
+            let wrapped = IExtendedResolver::resolveCall {
+                name: dns_encode(&self.name)?.into(),
+                data: call.abi_encode().into(),
+            };
+            let Some(inner) = self.ens.call(resolver.address, &wrapped).await? else {
+                return Ok(None);
+            };
+            if inner.is_empty() {
+                return Ok(None);
+            }
+            return Ok(C::abi_decode_returns(&inner).ok());
+        }
+        if !resolver.exact {
+            return Ok(None);
+        }
+        self.ens.call(resolver.address, call).await
+    }
+
+    pub async fn addr(&self) -> Result<Option<Address>, AppError> {
+        let node = namehash(&self.name);
+        let addr = self.record(&IAddrResolver::addrCall { node }).await?;
+        Ok(addr.filter(|a| !a.is_zero()))
+    }
+
+    pub async fn coin_addr(&self, coin_type: u64) -> Result<Option<Bytes>, AppError> {
+        let node = namehash(&self.name);
+        let call = IAddressResolver::addrCall { node, coinType: U256::from(coin_type) };
+        let raw = self.record(&call).await?;
+        Ok(raw.filter(|b| !b.is_empty()))
+    }
+
+    pub async fn text(&self, key: &str) -> Result<Option<String>, AppError> {
+        let node = namehash(&self.name);
+        let call = ITextResolver::textCall { node, key: key.to_string() };
+        let text = self.record(&call).await?;
+        Ok(text.filter(|t| !t.is_empty()))
+    }
+}
+
+// ========== 多链（ENSIP-19 / 链上注册表）==========
+
+/// ENSIP-19 默认 EVM coinType（chainId = 0），未设置链专属地址时回退到它
+pub const DEFAULT_EVM_COIN_TYPE: u64 = 0x8000_0000;
+
+/// 部署在 L2 上的名称注册表：后缀匹配的名称可在该链直接解析，
+/// 不必经主网 resolver + CCIP 网关中转
+pub const CHAIN_NAME_REGISTRIES: &[(u64, &str, Address)] = &[
+    // Basenames Registry
+    (8453, "base.eth", address!("0xb94704422c2a1e396835a571837aa5ae53285a95")),
+    // Linea Names Registry
+    (59144, "linea.eth", address!("0x50130b669B28C339991d8676FA73CF122a121267")),
+];
+
+pub fn chain_registry_for(name: &str) -> Option<(u64, Address)> {
+    CHAIN_NAME_REGISTRIES
+        .iter()
+        .find(|(_, suffix, _)| name == *suffix || name.ends_with(&format!(".{suffix}")))
+        .map(|(chain_id, _, registry)| (*chain_id, *registry))
+}
+
+/// ENSIP-19 反向名称：主网 addr.reverse，其他链 {coinType hex}.reverse
+pub fn chain_reverse_name(address: &Address, chain_id: u64) -> String {
+    if chain_id == 1 {
+        return reverse_name(address);
+    }
+    format!("{}.{:x}.reverse", hex::encode(address.as_slice()), evm_coin_type(chain_id))
+}
+
+pub fn default_reverse_name(address: &Address) -> String {
+    format!("{}.default.reverse", hex::encode(address.as_slice()))
+}
+
+/// 地址从哪里解析出来
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NameSource {
+    /// 主网 resolver 上该链的 coinType 记录（主网即 addr(node)）
+    ChainCoin { coin_type: u64 },
+    /// 主网 resolver 上的 ENSIP-19 默认 EVM 地址
+    DefaultCoin,
+    /// 直接读取 L2 上的名称注册表
+    ChainRegistry { registry: Address },
+}
+
+/// 名称在某条链上的解析结果
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct ChainResolution {
+    pub chain_id: u64,
+    pub address: Address,
+    pub source: NameSource,
+}
+
+/// 发送流程用：收款链上的地址 + 其它链上的解析情况
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecipientName {
+    pub name: String,
+    pub chain_id: u64,
+    /// 收款链上应使用的地址
+    pub address: Option<Address>,
+    /// 实际给出该地址的解析结果（链与来源）
+    pub resolved_on: Option<ChainResolution>,
+    pub resolutions: Vec<ChainResolution>,
+    /// 同一名称在不同链上指向不同地址
+    pub divergent: bool,
+    /// 收款链上没有地址，只能参考其它链 —— 前端应阻止直接发送
+    pub missing_on_chain: bool,
+    /// 解析失败的链及错误；这些链既不算有地址也不算没有
+    pub errors: Vec<(u64, String)>,
+}
+
+/// 多链解析结果；单条链失败记入 errors，不影响其它链
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainResolutions {
+    pub resolutions: Vec<ChainResolution>,
+    pub errors: Vec<(u64, String)>,
+}
+
+/// 汇总多链解析结果；收款链无结果时不回退到其它链的地址
+pub fn summarize_resolutions(name: &str, chain_id: u64, all: ChainResolutions) -> RecipientName {
+    let ChainResolutions { resolutions, errors } = all;
+    let resolved_on = resolutions.iter().find(|r| r.chain_id == chain_id).cloned();
+    let mut distinct: Vec<Address> = resolutions.iter().map(|r| r.address).collect();
+    distinct.sort();
+    distinct.dedup();
+    RecipientName {
+        name: name.to_string(),
+        chain_id,
+        address: resolved_on.as_ref().map(|r| r.address),
+        missing_on_chain: resolved_on.is_none() && !resolutions.is_empty(),
+        resolved_on,
+        divergent: distinct.len() > 1,
+        resolutions,
+        errors,
+    }
+}
+
+fn evm_address_bytes(raw: &Bytes) -> Option<Address> {
+    (raw.len() == 20).then(|| Address::from_slice(raw)).filter(|a| !a.is_zero())
+}
+
+impl EnsClient {
+    /// 通过主网 resolver 取名称在某条链上的地址（ENSIP-9/11/19）
+    pub async fn chain_addr(&self, name: &str, chain_id: u64) -> Result<Option<ChainResolution>, AppError> {
+        if chain_id == 1 {
+            let addr = self.addr(name).await?;
+            return Ok(addr.map(|address| ChainResolution {
+                chain_id,
+                address,
+                source: NameSource::ChainCoin { coin_type: COIN_TYPE_ETH },
+            }));
+        }
+        let coin_type = evm_coin_type(chain_id);
+        if let Some(address) = self.coin_addr(name, coin_type).await?.as_ref().and_then(evm_address_bytes) {
+            return Ok(Some(ChainResolution { chain_id, address, source: NameSource::ChainCoin { coin_type } }));
+        }
+        let fallback = self.coin_addr(name, DEFAULT_EVM_COIN_TYPE).await?;
+        Ok(fallback
+            .as_ref()
+            .and_then(evm_address_bytes)
+            .map(|address| ChainResolution { chain_id, address, source: NameSource::DefaultCoin }))
+    }
+
+    /// 某条链上的主名称：先读链专属反向记录，再回退 default.reverse，
+    /// 最后用该链的正向地址校验
+    pub async fn chain_primary_name(&self, address: &Address, chain_id: u64) -> Result<Option<String>, AppError> {
+        if chain_id == 1 {
+            return self.verified_primary_name(address).await;
+        }
+        let mut candidate = None;
+        for reverse in [chain_reverse_name(address, chain_id), default_reverse_name(address)] {
+            let node = namehash(&reverse);
+            if let Some(name) = self.resolve_record(&reverse, &INameResolver::nameCall { node }).await? {
+                if !name.is_empty() {
+                    candidate = Some(name);
+                    break;
+                }
+            }
+        }
+        let Some(name) = candidate.and_then(|n| normalize_name(&n).ok()) else {
+            return Ok(None);
+        };
+        let forward = self.chain_addr(&name, chain_id).await?;
+        Ok((forward.map(|r| r.address) == Some(*address)).then_some(name))
+    }
+}
+
+/// 在 L2 注册表上直接解析（需要该链的 RPC）
+async fn registry_resolution(
+    client: &Client,
+    rpc_url: &str,
+    chain_id: u64,
+    registry: Address,
+    name: &str,
+) -> Result<Option<ChainResolution>, AppError> {
+    let l2 = EnsClient::new(client.clone(), rpc_url).with_registry(registry);
+    let addr = l2.addr(name).await?;
+    Ok(addr.map(|address| ChainResolution { chain_id, address, source: NameSource::ChainRegistry { registry } }))
+}
+
+/// 在所有支持的主网链上解析名称。
+/// 名称有链上注册表且提供了该链 RPC 时直接读 L2，其余经主网 resolver；
+/// 某条链失败只记入 errors，其它链照常解析
+pub async fn resolve_all_chains(
+    client: &Client,
+    mainnet_rpc: &str,
+    rpc_urls: &HashMap<u64, String>,
+    name: &str,
+) -> ChainResolutions {
+    let ens = EnsClient::new(client.clone(), mainnet_rpc);
+    let registry = chain_registry_for(name);
+    let mut all = ChainResolutions::default();
+    for chain_id in SUPPORTED_CHAIN_IDS.into_iter().filter(|c| !is_testnet(*c)) {
+        let direct = match (registry, rpc_urls.get(&chain_id)) {
+            (Some((registry_chain, registry)), Some(url)) if registry_chain == chain_id => {
+                // L2 节点不可用时退回主网路径
+                registry_resolution(client, url, chain_id, registry, name).await.ok().flatten()
+            }
+            _ => None,
+        };
+        let resolution = match direct {
+            Some(r) => Ok(Some(r)),
+            None => ens.chain_addr(name, chain_id).await,
+        };
+        match resolution {
+            Ok(r) => all.resolutions.extend(r),
+            Err(e) => all.errors.push((chain_id, e.to_string())),
+        }
+    }
+    all
+}
+
+// ========== 头像 ==========
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NftStandard {
+    Erc721,
+    Erc1155,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct NftAvatar {
+    pub chain_id: u64,
+    pub standard: NftStandard,
+    pub contract: Address,
+    pub token_id: U256,
+    /// None 表示无法校验（跨链或名称无地址）
+    pub owned: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct Avatar {
+    /// avatar 文本记录原文
+    pub record: String,
+    /// 可直接加载的 http(s) / data: 地址；NFT 未通过所有权校验时为空
+    pub url: Option<String>,
+    pub nft: Option<NftAvatar>,
+}
+
+/// eip155:1/erc721:0xb47e…/2430
+pub fn parse_nft_avatar(record: &str) -> Option<NftAvatar> {
+    let rest = record.trim().strip_prefix("eip155:")?;
+    let (chain, rest) = rest.split_once('/')?;
+    let (standard, rest) = rest.split_once(':')?;
+    let (contract, token_id) = rest.split_once('/')?;
+    Some(NftAvatar {
+        chain_id: chain.parse().ok()?,
+        standard: match standard.to_lowercase().as_str() {
+            "erc721" => NftStandard::Erc721,
+            "erc1155" => NftStandard::Erc1155,
+            _ => return None,
+        },
+        contract: contract.parse().ok()?,
+        token_id: token_id.parse().ok()?,
+        owned: None,
+    })
+}
+
+// ========== 缓存 + 命令 ==========
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct EnsProfile {
+    pub name: String,
+    pub address: Option<Address>,
+    pub texts: Vec<(String, String)>,
+    /// (coinType, 原始地址字节)
+    pub coins: Vec<(u64, Bytes)>,
+    pub avatar: Option<Avatar>,
+    /// 单条记录的失败（记录名如 addr / text:url / coin:60 / avatar, 错误信息），不影响其它记录
+    pub errors: Vec<(String, String)>,
+    pub resolved_at: u64,
+}
+
+/// 名称 → 地址；primary 表示该地址的反向记录指回同一名称
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct ResolvedName {
+    pub name: String,
+    pub address: Option<Address>,
+    pub primary: bool,
+}
+
+fn cache_key(mgr: &TableManager, kind: &str, id: &str) -> Vec<u8> {
+    mgr.key_from_str(&format!("ens:{kind}:{id}"))
+}
+
+async fn ens_client(state: &AppState, rpc_url: &str) -> EnsClient {
+    EnsClient::new(state.https_client.lock().await.clone(), rpc_url)
+}
+
+/// 名称 → 地址，附带反向记录是否一致（不一致时前端需提示）
+#[tauri::command]
+pub async fn ens_resolve(
+    name: String,
+    rpc_url: String,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<ResolvedName, AppError> {
+    let name = normalize_name(&name)?;
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Cache)?;
+    let key = cache_key(&mgr, "resolved", &name);
+    let now = now_s();
+    if let Some(cached) = mgr.get_unexpired::<ResolvedName>(&key, now)? {
+        return Ok(cached);
+    }
+    let resolved = ens_client(&state, &rpc_url).await.resolve_verified(&name).await?;
+    mgr.set_expiring(&key, resolved.clone(), now + ENS_CACHE_TTL_SECS)?;
+    Ok(resolved)
+}
+
+/// 地址 → 主名称（仅返回正反向一致的名称）。
+/// chain_id 非主网时按 ENSIP-19 读该链的主名称；rpc_url 始终是主网节点
+#[tauri::command]
+pub async fn ens_lookup_address(
+    address: String,
+    rpc_url: String,
+    chain_id: Option<u64>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    let address = address
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let chain_id = chain_id.unwrap_or(1);
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Cache)?;
+    let key = cache_key(&mgr, "name", &format!("{chain_id}:{:#x}", address));
+    let now = now_s();
+    if let Some(cached) = mgr.get_unexpired::<Option<String>>(&key, now)? {
+        return Ok(cached);
+    }
+    let name = ens_client(&state, &rpc_url).await.chain_primary_name(&address, chain_id).await?;
+    mgr.set_expiring(&key, name.clone(), now + ENS_CACHE_TTL_SECS)?;
+    Ok(name)
+}
+
+/// 发送流程：解析收款名称在目标链上的地址，并附带其它链的解析结果用于提示差异。
+/// rpc_urls 可选提供各 L2 的节点，用于直接读取链上注册表
+#[tauri::command]
+pub async fn ens_resolve_recipient(
+    name: String,
+    chain_id: u64,
+    rpc_url: String,
+    rpc_urls: Option<HashMap<u64, String>>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<RecipientName, AppError> {
+    let name = normalize_name(&name)?;
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Cache)?;
+    let key = cache_key(&mgr, "chains", &name);
+    let now = now_s();
+    let all = match mgr.get_unexpired::<Vec<ChainResolution>>(&key, now)? {
+        Some(resolutions) => ChainResolutions { resolutions, errors: Vec::new() },
+        None => {
+            let client = state.https_client.lock().await.clone();
+            let all = resolve_all_chains(&client, &rpc_url, &rpc_urls.unwrap_or_default(), &name).await;
+            // 有链失败时不缓存，下次重新解析
+            if all.errors.is_empty() {
+                mgr.set_expiring(&key, all.resolutions.clone(), now + ENS_CACHE_TTL_SECS)?;
+            }
+            all
+        }
+    };
+    Ok(summarize_resolutions(&name, chain_id, all))
+}
+
+/// 逐条读取资料记录；resolver 只查一次，单条失败记入 errors
+pub async fn load_profile(ens: &EnsClient, name: &str, text_keys: &[String], coin_types: &[u64], now: u64) -> Result<EnsProfile, AppError> {
+    let bound = ens.bind(name).await?;
+    let mut profile = EnsProfile { name: name.to_string(), resolved_at: now, ..Default::default() };
+    match bound.addr().await {
+        Ok(address) => profile.address = address,
+        Err(e) => profile.errors.push(("addr".to_string(), e.to_string())),
+    }
+    for key in text_keys {
+        match bound.text(key).await {
+            Ok(Some(value)) => profile.texts.push((key.clone(), value)),
+            Ok(None) => {}
+            Err(e) => profile.errors.push((format!("text:{key}"), e.to_string())),
+        }
+    }
+    for &coin_type in coin_types {
+        match bound.coin_addr(coin_type).await {
+            Ok(Some(raw)) => profile.coins.push((coin_type, raw)),
+            Ok(None) => {}
+            Err(e) => profile.errors.push((format!("coin:{coin_type}"), e.to_string())),
+        }
+    }
+    if let Some((_, record)) = profile.texts.iter().find(|(k, _)| k == "avatar") {
+        match ens.avatar(record, profile.address).await {
+            Ok(avatar) => profile.avatar = Some(avatar),
+            Err(e) => profile.errors.push(("avatar".to_string(), e.to_string())),
+        }
+    }
+    Ok(profile)
+}
+
+/// 完整资料：地址、文本记录、多币种地址与头像；有记录失败时不写缓存
+#[tauri::command]
+pub async fn ens_profile(
+    name: String,
+    rpc_url: String,
+    text_keys: Option<Vec<String>>,
+    coin_types: Option<Vec<u64>>,
+    force: Option<bool>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<EnsProfile, AppError> {
+    let name = normalize_name(&name)?;
+    let text_keys = text_keys.unwrap_or_else(|| DEFAULT_TEXT_KEYS.iter().map(|k| k.to_string()).collect());
+    let coin_types = coin_types.unwrap_or_default();
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Cache)?;
+    // 不同的 key 组合分别缓存
+    let key = cache_key(&mgr, "records", &format!("{name}|{}|{:?}", text_keys.join(","), coin_types));
+    let now = now_s();
+    if !force.unwrap_or(false) {
+        if let Some(cached) = mgr.get_unexpired::<EnsProfile>(&key, now)? {
+            return Ok(cached);
+        }
+    }
+
+    let ens = ens_client(&state, &rpc_url).await;
+    let profile = load_profile(&ens, &name, &text_keys, &coin_types, now).await?;
+    if profile.errors.is_empty() {
+        mgr.set_expiring(&key, profile.clone(), now + ENS_CACHE_TTL_SECS)?;
+    }
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_http;
+    use alloy_primitives::b256;
+
+    #[test]
+    fn test_namehash_and_dns_encode() {
+        // EIP-137 测试向量
+        assert_eq!(namehash(""), B256::ZERO);
+        assert_eq!(namehash("eth"), b256!("0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"));
+        assert_eq!(namehash("foo.eth"), b256!("0xde9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"));
+
+        assert_eq!(dns_encode("foo.eth").unwrap(), b"\x03foo\x03eth\x00".to_vec());
+        assert!(dns_encode("foo..eth").is_err());
+        assert_eq!(normalize_name(" Vitalik.ETH ").unwrap(), "vitalik.eth");
+        assert_eq!(evm_coin_type(8453), 0x8000_2105);
+    }
+
+    #[test]
+    fn test_offchain_lookup_roundtrip() {
+        let sender = address!("0x1111111111111111111111111111111111111111");
+        let lookup = OffchainLookup {
+            sender,
+            urls: vec!["https://gw.example/{sender}/{data}.json".to_string()],
+            callData: Bytes::from(vec![0xde, 0xad]),
+            callbackFunction: FixedBytes::from([0x12, 0x34, 0x56, 0x78]),
+            extraData: Bytes::from(vec![0x01]),
+        };
+        let decoded = OffchainLookup::abi_decode(&lookup.abi_encode()).unwrap();
+        assert_eq!(decoded.sender, sender);
+        assert_eq!(decoded.urls, lookup.urls);
+
+        let callback = encode_ccip_callback(decoded.callbackFunction, &Bytes::from(vec![0xbe, 0xef]), &decoded.extraData);
+        assert_eq!(&callback[..4], &[0x12, 0x34, 0x56, 0x78]);
+        let (response, extra) =
+            <(sol_data::Bytes, sol_data::Bytes)>::abi_decode_params(&callback[4..]).unwrap();
+        assert_eq!((response.to_vec(), extra.to_vec()), (vec![0xbe, 0xef], vec![0x01]));
+    }
+
+    #[tokio::test]
+    async fn test_ccip_fetch_get_and_fallthrough() {
+        let sender = address!("0x1111111111111111111111111111111111111111");
+        let base = test_http::serve(vec![(
+            "/gw/0x1111111111111111111111111111111111111111/0xdead.json",
+            r#"{"data":"0xbeef"}"#,
+        )]);
+        let urls = vec![
+            format!("{}/{{sender}}/{{data}}.json", test_http::unreachable()),
+            format!("{base}/gw/{{sender}}/{{data}}.json"),
+        ];
+        let data = ccip_fetch(&Client::new(), &urls, sender, &[0xde, 0xad]).await.unwrap();
+        assert_eq!(data.to_vec(), vec![0xbe, 0xef]);
+
+        // 4xx 立即失败，不再尝试后续网关
+        let urls = vec![format!("{base}/missing/{{data}}"), urls[1].clone()];
+        assert!(ccip_fetch(&Client::new(), &urls, sender, &[0xde, 0xad]).await.is_err());
+    }
+
+    #[test]
+    fn test_l2_reverse_names_and_divergence() {
+        let addr = address!("0xd8da6bf26964af9d7eed9e03e53415d37aa96045");
+        assert_eq!(chain_reverse_name(&addr, 1), "d8da6bf26964af9d7eed9e03e53415d37aa96045.addr.reverse");
+        assert_eq!(chain_reverse_name(&addr, 8453), "d8da6bf26964af9d7eed9e03e53415d37aa96045.80002105.reverse");
+        assert_eq!(default_reverse_name(&addr), "d8da6bf26964af9d7eed9e03e53415d37aa96045.default.reverse");
+        assert_eq!(chain_registry_for("alice.base.eth").map(|r| r.0), Some(8453));
+        assert!(chain_registry_for("alicebase.eth").is_none());
+
+        let other = address!("0x1111111111111111111111111111111111111111");
+        let on = |chain_id, address| ChainResolution { chain_id, address, source: NameSource::DefaultCoin };
+        let ok = |resolutions| ChainResolutions { resolutions, errors: Vec::new() };
+        let same = summarize_resolutions("alice.eth", 8453, ok(vec![on(1, addr), on(8453, addr)]));
+        assert_eq!(same.address, Some(addr));
+        assert!(!same.divergent && !same.missing_on_chain);
+
+        let split = summarize_resolutions("alice.eth", 8453, ok(vec![on(1, addr), on(8453, other)]));
+        assert_eq!(split.resolved_on.map(|r| r.address), Some(other));
+        assert!(split.divergent);
+
+        // 收款链上没有记录时不借用主网地址
+        let missing = summarize_resolutions("alice.eth", 59144, ok(vec![on(1, addr)]));
+        assert_eq!(missing.address, None);
+        assert!(missing.missing_on_chain);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_all_chains_reports_failed_chains() {
+        // 主网节点不可用：每条链各记一条错误，而不是整体失败
+        let all = resolve_all_chains(&Client::new(), &test_http::unreachable(), &HashMap::new(), "alice.eth").await;
+        let chains = SUPPORTED_CHAIN_IDS.into_iter().filter(|c| !is_testnet(*c)).count();
+        assert!(all.resolutions.is_empty());
+        assert_eq!(all.errors.len(), chains);
+        assert!(all.errors.iter().all(|(_, e)| !e.is_empty()));
+    }
+
+    #[test]
+    fn test_avatar_records() {
+        let nft = parse_nft_avatar("eip155:1/erc721:0xb47e3cd837dDF8e4c57F05d70Ab865de6e193BBB/2430").unwrap();
+        assert_eq!((nft.chain_id, nft.standard, nft.token_id), (1, NftStandard::Erc721, U256::from(2430)));
+        assert!(parse_nft_avatar("https://example.com/a.png").is_none());
+        assert_eq!(gateway_url("ipfs://QmHash/1.png").unwrap(), "https://ipfs.io/ipfs/QmHash/1.png");
+    }
+
+    #[tokio::test]
+    async fn test_nft_avatar_not_verified_on_other_chain() {
+        // RPC 实际在 Base 上：主网 NFT 的所有权无法校验，不能当作已持有
+        let base = test_http::serve(vec![("/", test_http::rpc_result("0x2105").as_str())]);
+        let ens = EnsClient::new(Client::new(), &base);
+        let record = "eip155:1/erc721:0xb47e3cd837dDF8e4c57F05d70Ab865de6e193BBB/2430";
+        let avatar = ens.avatar(record, Some(Address::repeat_byte(0x11))).await.unwrap();
+        assert_eq!(avatar.nft.map(|n| n.owned), Some(None));
+        assert!(avatar.url.is_none());
+    }
+}
@@ -0,0 +1,768 @@
+// ERC-7730 clear signing：按 (链, 合约, selector) 或 EIP-712 (domain, primaryType) 匹配描述文件，
+// 把调用参数渲染成意图 + 带格式的字段（代币数量、地址名、日期等），供签名确认页展示。
+// 内置描述随版本发布（erc7730.json），用户也可以加载自己的描述
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::data::contract::EntrySource;
+use crate::data::export::TokenMetaMap;
+use crate::error::AppError;
+use crate::utils::num::format_units;
+use crate::utils::time::format_utc;
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::{Function, Param};
+use alloy_primitives::{Address, U256, keccak256};
+use bincode::{Decode, Encode};
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use tauri::State;
+
+/// 随版本发布的内置描述，视为可信
+const BUILTIN_DESCRIPTORS: &str = include_str!("erc7730.json");
+
+const USER_KEY_PREFIX: &str = "erc7730";
+
+// ========== 描述文件 ==========
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Deployment {
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+    pub address: Address,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Binding {
+    pub deployments: Vec<Deployment>,
+    /// EIP-712：签名数据的 domain 需包含这些字段
+    #[serde(default)]
+    pub domain: Option<Map<String, Value>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DescriptorContext {
+    #[serde(default)]
+    pub contract: Option<Binding>,
+    #[serde(default)]
+    pub eip712: Option<Binding>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormatSpec {
+    #[serde(default)]
+    pub intent: Option<Value>,
+    #[serde(default)]
+    pub fields: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DisplaySpec {
+    #[serde(default)]
+    pub formats: HashMap<String, FormatSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Descriptor {
+    pub context: DescriptorContext,
+    pub display: DisplaySpec,
+}
+
+/// 字段展开 $ref 后的形态；fields 非空时为分组，path 作为子字段的前缀
+#[derive(Debug, Clone, Default, Deserialize)]
+struct FieldSpec {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    value: Option<Value>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    params: Map<String, Value>,
+    #[serde(default)]
+    fields: Vec<Value>,
+    /// 取不到值时跳过该字段；未标记的字段取不到值时整个格式退回原始展示
+    #[serde(default)]
+    optional: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct UserDescriptor {
+    pub id: String,
+    pub json: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadedDescriptor {
+    pub id: String,
+    pub source: EntrySource,
+    /// 原文，`$.` 引用在其上解析
+    pub raw: Value,
+    pub descriptor: Descriptor,
+    /// 被本描述覆盖了绑定的其它描述（id, 来源），insert 时填写
+    pub overrides: Vec<(String, EntrySource)>,
+}
+
+impl LoadedDescriptor {
+    pub fn overrides_builtin(&self) -> bool {
+        self.overrides.iter().any(|(_, source)| *source == EntrySource::Bundle)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DescriptorSummary {
+    pub id: String,
+    pub source: EntrySource,
+    pub owner: Option<String>,
+    pub formats: Vec<String>,
+    /// 被本描述覆盖的描述 id；用户描述覆盖内置描述时 UI 需提示
+    pub overrides: Vec<String>,
+}
+
+fn descriptor_id(json: &str) -> String {
+    hex::encode(&keccak256(json.as_bytes())[..8])
+}
+
+pub fn parse_descriptor(raw: Value, source: EntrySource) -> Result<LoadedDescriptor, AppError> {
+    let descriptor: Descriptor =
+        serde_json::from_value(raw.clone()).map_err(|e| AppError::ClearSignDescriptorInvalid(e.to_string()))?;
+    if descriptor.context.contract.is_none() && descriptor.context.eip712.is_none() {
+        return Err(AppError::ClearSignDescriptorInvalid("context needs contract or eip712".into()));
+    }
+    if let Some(binding) = &descriptor.context.contract {
+        for key in descriptor.display.formats.keys() {
+            Function::parse(key).map_err(|e| AppError::ClearSignDescriptorInvalid(format!("{key}: {e}")))?;
+        }
+        if binding.deployments.is_empty() {
+            return Err(AppError::ClearSignDescriptorInvalid("no deployments".into()));
+        }
+    }
+    Ok(LoadedDescriptor { id: descriptor_id(&raw.to_string()), source, raw, descriptor, overrides: Vec::new() })
+}
+
+// ========== 注册表 ==========
+
+/// calldata 格式的 key 是带参数名的函数签名；EIP-712 的 key 是 primaryType（可带 encodeType 的参数部分）
+#[derive(Debug, Clone, Default)]
+pub struct ClearSignRegistry {
+    descriptors: Vec<LoadedDescriptor>,
+    calldata: HashMap<(u64, Address, [u8; 4]), (usize, String)>,
+    typed: HashMap<(u64, Address, String), (usize, String)>,
+}
+
+impl ClearSignRegistry {
+    pub fn builtin() -> Self {
+        let list: Vec<Value> = serde_json::from_str(BUILTIN_DESCRIPTORS).expect("builtin erc7730.json is valid");
+        let mut registry = Self::default();
+        for raw in list {
+            match parse_descriptor(raw, EntrySource::Bundle) {
+                Ok(loaded) => registry.insert(loaded),
+                Err(e) => eprintln!("Skipping builtin descriptor: {}", e),
+            }
+        }
+        registry
+    }
+
+    /// 内置描述 → 用户描述，后者覆盖同一绑定
+    pub fn load(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Self> {
+        let mgr = TableManager::new(db, TableKind::Registry)?;
+        let mut registry = Self::builtin();
+        for user in mgr.list_by_prefix::<UserDescriptor>(&format!("{USER_KEY_PREFIX}:"))? {
+            match serde_json::from_str(&user.json).map_err(AppError::from).and_then(|raw| parse_descriptor(raw, EntrySource::User)) {
+                Ok(loaded) => registry.insert(loaded),
+                Err(e) => eprintln!("Skipping user descriptor {}: {}", user.id, e),
+            }
+        }
+        Ok(registry)
+    }
+
+    /// 同一描述（id 相同）重复加载时忽略；覆盖其它描述的绑定时记在 overrides 中，不静默替换
+    pub fn insert(&mut self, mut loaded: LoadedDescriptor) {
+        if self.descriptors.iter().any(|d| d.id == loaded.id) {
+            return;
+        }
+        let index = self.descriptors.len();
+        let mut replaced = Vec::new();
+        let context = &loaded.descriptor.context;
+        for key in loaded.descriptor.display.formats.keys() {
+            if let Some(binding) = &context.contract {
+                let Ok(function) = Function::parse(key) else { continue };
+                for d in &binding.deployments {
+                    let previous = self.calldata.insert((d.chain_id, d.address, function.selector().0), (index, key.clone()));
+                    replaced.extend(previous.map(|(i, _)| i));
+                }
+            }
+            if let Some(binding) = &context.eip712 {
+                let primary = key.split('(').next().unwrap_or(key).trim().to_string();
+                for d in &binding.deployments {
+                    let previous = self.typed.insert((d.chain_id, d.address, primary.clone()), (index, key.clone()));
+                    replaced.extend(previous.map(|(i, _)| i));
+                }
+            }
+        }
+        replaced.sort_unstable();
+        replaced.dedup();
+        loaded.overrides = replaced
+            .into_iter()
+            .map(|i| (self.descriptors[i].id.clone(), self.descriptors[i].source))
+            .collect();
+        self.descriptors.push(loaded);
+    }
+
+    pub fn list(&self) -> Vec<DescriptorSummary> {
+        self.descriptors
+            .iter()
+            .map(|d| DescriptorSummary {
+                id: d.id.clone(),
+                source: d.source,
+                owner: d.raw.pointer("/metadata/owner").and_then(Value::as_str).map(String::from),
+                formats: d.descriptor.display.formats.keys().cloned().collect(),
+                overrides: d.overrides.iter().map(|(id, _)| id.clone()).collect(),
+            })
+            .collect()
+    }
+
+    pub fn for_calldata(&self, chain_id: u64, to: &Address, selector: [u8; 4]) -> Option<(&LoadedDescriptor, &str)> {
+        let (index, key) = self.calldata.get(&(chain_id, *to, selector))?;
+        Some((&self.descriptors[*index], key))
+    }
+
+    pub fn for_typed_data(&self, chain_id: u64, verifying_contract: &Address, primary_type: &str, domain: &Value) -> Option<(&LoadedDescriptor, &str)> {
+        let (index, key) = self.typed.get(&(chain_id, *verifying_contract, primary_type.to_string()))?;
+        let loaded = &self.descriptors[*index];
+        let expected = loaded.descriptor.context.eip712.as_ref()?.domain.as_ref();
+        // 描述里声明的 domain 字段必须一致，防止同地址不同 domain 的签名被套用
+        if expected.is_some_and(|expected| expected.iter().any(|(k, v)| domain.get(k) != Some(v))) {
+            return None;
+        }
+        Some((loaded, key))
+    }
+}
+
+// ========== 渲染 ==========
+
+/// 渲染所需的外部信息；names 为地址簿与合约注册表中的名称
+#[derive(Debug, Clone, Default)]
+pub struct FormatContext<'a> {
+    pub chain_id: u64,
+    pub from: Option<Address>,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub native_symbol: String,
+    pub tokens: Option<&'a TokenMetaMap>,
+    pub names: Option<&'a HashMap<Address, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DisplayField {
+    pub label: String,
+    pub value: String,
+    pub format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClearSignView {
+    pub intent: String,
+    pub owner: Option<String>,
+    pub fields: Vec<DisplayField>,
+    /// 描述来源：内置或用户加载
+    pub source: EntrySource,
+    /// 用户描述覆盖了同一绑定的内置描述
+    pub overrides_builtin: bool,
+}
+
+/// 把解码后的参数转成按名字索引的 JSON，供 path 选取
+fn to_json(value: &DynSolValue, components: &[Param]) -> Value {
+    match value {
+        DynSolValue::Address(a) => Value::String(a.to_checksum(None)),
+        DynSolValue::Bool(b) => Value::Bool(*b),
+        DynSolValue::Uint(v, _) => Value::String(v.to_string()),
+        DynSolValue::Int(v, _) => Value::String(v.to_string()),
+        DynSolValue::Bytes(b) => Value::String(format!("0x{}", hex::encode(b))),
+        DynSolValue::FixedBytes(b, size) => Value::String(format!("0x{}", hex::encode(&b[..*size]))),
+        DynSolValue::String(s) => Value::String(s.clone()),
+        DynSolValue::Array(items) | DynSolValue::FixedArray(items) => {
+            Value::Array(items.iter().map(|v| to_json(v, components)).collect())
+        }
+        DynSolValue::Tuple(items) => params_to_json(components, items),
+        other => Value::String(format!("{other:?}")),
+    }
+}
+
+fn params_to_json(params: &[Param], values: &[DynSolValue]) -> Value {
+    let mut map = Map::new();
+    for (i, (param, value)) in params.iter().zip(values).enumerate() {
+        let name = if param.name.is_empty() { i.to_string() } else { param.name.clone() };
+        map.insert(name, to_json(value, &param.components));
+    }
+    Value::Object(map)
+}
+
+/// 路径段：名字、`[i]`（负数从末尾数）、`[]`（展开数组）
+fn select(value: &Value, segments: &[&str]) -> Option<Value> {
+    let Some((head, rest)) = segments.split_first() else {
+        return Some(value.clone());
+    };
+    if *head == "[]" {
+        let items = value.as_array()?;
+        return Some(Value::Array(items.iter().filter_map(|v| select(v, rest)).collect()));
+    }
+    if let Some(index) = head.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let items = value.as_array()?;
+        let index: i64 = index.parse().ok()?;
+        let index = if index < 0 { items.len().checked_sub(index.unsigned_abs() as usize)? } else { index as usize };
+        return select(items.get(index)?, rest);
+    }
+    select(value.get(*head)?, rest)
+}
+
+struct Roots<'a> {
+    data: &'a Value,
+    container: Value,
+    descriptor: &'a Value,
+}
+
+impl Roots<'_> {
+    /// `#.` 结构化数据、`@.` 交易容器（from / to / value / chainId）、`$.` 描述文件本身；无前缀视为 `#.`
+    fn resolve(&self, path: &str) -> Option<Value> {
+        let (root, path) = if let Some(p) = path.strip_prefix("@.") {
+            (&self.container, p)
+        } else if let Some(p) = path.strip_prefix("$.") {
+            (self.descriptor, p)
+        } else {
+            (self.data, path.strip_prefix("#.").unwrap_or(path))
+        };
+        let segments: Vec<&str> = path.split('.').filter(|s| !s.is_empty()).collect();
+        select(root, &segments)
+    }
+
+    /// 参数值可以是 `$.` 引用（常量、枚举表）
+    fn param(&self, params: &Map<String, Value>, name: &str) -> Option<Value> {
+        match params.get(name)? {
+            Value::String(s) if s.starts_with("$.") => self.resolve(s),
+            other => Some(other.clone()),
+        }
+    }
+}
+
+fn as_u256(value: &Value) -> Option<U256> {
+    match value {
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        },
+        Value::Number(n) => n.as_u64().map(U256::from),
+        _ => None,
+    }
+}
+
+fn as_address(value: &Value) -> Option<Address> {
+    value.as_str()?.parse().ok()
+}
+
+fn raw_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(raw_string).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+fn address_name(address: Address, ctx: &FormatContext) -> String {
+    let checksum = address.to_checksum(None);
+    match ctx.names.and_then(|names| names.get(&address)) {
+        Some(name) => format!("{name} ({checksum})"),
+        None => checksum,
+    }
+}
+
+fn format_duration(secs: U256) -> String {
+    let secs: u64 = secs.saturating_to();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+fn format_value(value: &Value, format: &str, params: &Map<String, Value>, roots: &Roots, ctx: &FormatContext) -> String {
+    // [] 展开后的数组逐个格式化
+    if let Value::Array(items) = value {
+        return items.iter().map(|v| format_value(v, format, params, roots, ctx)).collect::<Vec<_>>().join(", ");
+    }
+    if format == "addressName" {
+        return as_address(value).map(|a| address_name(a, ctx)).unwrap_or_else(|| raw_string(value));
+    }
+    let Some(number) = as_u256(value).filter(|_| format != "raw") else {
+        return raw_string(value);
+    };
+    match format {
+        "amount" => format!("{} {}", format_units(number, 18), ctx.native_symbol),
+        "tokenAmount" => {
+            let threshold = roots.param(params, "threshold").as_ref().and_then(as_u256);
+            if threshold.is_some_and(|t| number >= t) {
+                let message = roots.param(params, "message").and_then(|m| m.as_str().map(String::from));
+                return message.unwrap_or_else(|| "Unlimited".to_string());
+            }
+            let token = match params.get("tokenPath").and_then(Value::as_str) {
+                Some(path) => roots.resolve(path),
+                None => roots.param(params, "token"),
+            }
+            .as_ref()
+            .and_then(as_address);
+            let meta = token.and_then(|t| ctx.tokens.and_then(|tokens| tokens.get(&(ctx.chain_id, t))));
+            match (token, meta) {
+                (_, Some((symbol, decimals))) => format!("{} {}", format_units(number, *decimals), symbol),
+                (Some(token), None) => format!("{} (token {})", number, token.to_checksum(None)),
+                (None, None) => number.to_string(),
+            }
+        }
+        "date" => match roots.param(params, "encoding").as_ref().and_then(Value::as_str) {
+            Some("blockheight") => format!("block {number}"),
+            _ => format!("{} UTC", format_utc(number.saturating_to())),
+        },
+        "duration" => format_duration(number),
+        "unit" => {
+            let decimals = roots.param(params, "decimals").as_ref().and_then(as_u256).map(|d| d.saturating_to()).unwrap_or(0);
+            let base = roots.param(params, "base").and_then(|b| b.as_str().map(String::from)).unwrap_or_default();
+            format!("{}{}", format_units(number, decimals), base)
+        }
+        "enum" => roots
+            .param(params, "$ref")
+            .and_then(|table| table.get(number.to_string()).and_then(Value::as_str).map(String::from))
+            .unwrap_or_else(|| number.to_string()),
+        "nftName" => {
+            let collection = params
+                .get("collectionPath")
+                .and_then(Value::as_str)
+                .and_then(|p| roots.resolve(p))
+                .as_ref()
+                .and_then(as_address);
+            match collection {
+                Some(c) => format!("{} #{}", address_name(c, ctx), number),
+                None => format!("#{number}"),
+            }
+        }
+        _ => number.to_string(),
+    }
+}
+
+/// 声明的字段解析不出来时返回 None，避免展示缺项的“明文”
+fn render_fields(fields: &[Value], prefix: &str, roots: &Roots, ctx: &FormatContext, out: &mut Vec<DisplayField>) -> Option<()> {
+    for field in fields {
+        let spec = expand_ref(field, roots)?;
+        let path = spec.path.as_deref().map(|p| match prefix {
+            "" => p.to_string(),
+            _ if p.starts_with("@.") || p.starts_with("$.") || p.starts_with("#.") => p.to_string(),
+            _ => format!("{prefix}.{p}"),
+        });
+        if !spec.fields.is_empty() {
+            render_fields(&spec.fields, path.as_deref().unwrap_or(prefix), roots, ctx, out)?;
+            continue;
+        }
+        let value = match (&spec.value, &path) {
+            (Some(constant), _) => Some(constant.clone()),
+            (None, Some(path)) => roots.resolve(path),
+            (None, None) => None,
+        };
+        let value = match value {
+            Some(value) => value,
+            None if spec.optional => continue,
+            None => return None,
+        };
+        let format = spec.format.unwrap_or_else(|| "raw".to_string());
+        out.push(DisplayField {
+            label: spec.label.or(path).unwrap_or_default(),
+            value: format_value(&value, &format, &spec.params, roots, ctx),
+            format,
+        });
+    }
+    Some(())
+}
+
+/// 展开 `$ref`：字段自身的键覆盖定义，params 合并
+fn expand_ref(field: &Value, roots: &Roots) -> Option<FieldSpec> {
+    let mut merged = match field.get("$ref").and_then(Value::as_str).and_then(|r| roots.resolve(r)) {
+        Some(Value::Object(def)) => def,
+        _ => Map::new(),
+    };
+    for (k, v) in field.as_object()? {
+        if k == "$ref" {
+            continue;
+        }
+        if let (Some(Value::Object(base)), Value::Object(extra)) = (merged.get_mut(k).filter(|_| k == "params"), v) {
+            base.extend(extra.iter().map(|(k, v)| (k.clone(), v.clone())));
+            continue;
+        }
+        merged.insert(k.clone(), v.clone());
+    }
+    serde_json::from_value(Value::Object(merged)).ok()
+}
+
+fn intent_string(intent: Option<&Value>, fallback: &str) -> String {
+    match intent {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Object(map)) => map.iter().map(|(k, v)| format!("{k} {}", raw_string(v))).collect::<Vec<_>>().join(", "),
+        _ => fallback.to_string(),
+    }
+}
+
+fn render(loaded: &LoadedDescriptor, key: &str, data: &Value, container: Value, ctx: &FormatContext) -> Option<ClearSignView> {
+    let spec = loaded.descriptor.display.formats.get(key)?;
+    let roots = Roots { data, container, descriptor: &loaded.raw };
+    let mut fields = Vec::new();
+    render_fields(&spec.fields, "", &roots, ctx, &mut fields)?;
+    Some(ClearSignView {
+        intent: intent_string(spec.intent.as_ref(), key.split('(').next().unwrap_or(key)),
+        owner: loaded.raw.pointer("/metadata/owner").and_then(Value::as_str).map(String::from),
+        fields,
+        source: loaded.source,
+        overrides_builtin: loaded.overrides_builtin(),
+    })
+}
+
+fn container(ctx: &FormatContext) -> Value {
+    json!({
+        "from": ctx.from.map(|a| a.to_checksum(None)),
+        "to": ctx.to.map(|a| a.to_checksum(None)),
+        "value": ctx.value.to_string(),
+        "chainId": ctx.chain_id,
+    })
+}
+
+impl ClearSignRegistry {
+    /// ctx.to 为被调用的合约
+    pub fn clear_sign_calldata(&self, calldata: &[u8], ctx: &FormatContext) -> Option<ClearSignView> {
+        let selector: [u8; 4] = calldata.get(..4)?.try_into().ok()?;
+        let (loaded, key) = self.for_calldata(ctx.chain_id, ctx.to.as_ref()?, selector)?;
+        let function = Function::parse(key).ok()?;
+        let values = function.abi_decode_input(&calldata[4..]).ok()?;
+        let data = params_to_json(&function.inputs, &values);
+        render(loaded, key, &data, container(ctx), ctx)
+    }
+
+    /// typed_data 为 eth_signTypedData_v4 的 JSON（domain / primaryType / message）
+    pub fn clear_sign_typed_data(&self, typed_data: &Value, ctx: &FormatContext) -> Option<ClearSignView> {
+        let domain = typed_data.get("domain")?;
+        let verifying_contract = domain.get("verifyingContract").and_then(as_address)?;
+        let chain_id = domain.get("chainId").and_then(as_u256).map(|c| c.saturating_to()).unwrap_or(ctx.chain_id);
+        let primary_type = typed_data.get("primaryType")?.as_str()?;
+        let (loaded, key) = self.for_typed_data(chain_id, &verifying_contract, primary_type, domain)?;
+        let ctx = FormatContext { chain_id, to: Some(verifying_contract), ..ctx.clone() };
+        render(loaded, key, typed_data.get("message")?, container(&ctx), &ctx)
+    }
+
+    /// 按 JSON-RPC 方法取出交易或签名数据；没有匹配的描述时为 None
+    pub fn clear_sign_request(&self, method: &str, params: &Value, ctx: &FormatContext) -> Option<ClearSignView> {
+        match method {
+            "eth_sendTransaction" | "eth_signTransaction" => {
+                let tx = params.get(0)?;
+                let data = tx.get("data").or_else(|| tx.get("input")).and_then(Value::as_str).unwrap_or("0x");
+                let calldata = hex::decode(data.trim_start_matches("0x")).ok()?;
+                let ctx = FormatContext {
+                    from: tx.get("from").and_then(as_address).or(ctx.from),
+                    to: tx.get("to").and_then(as_address),
+                    value: tx.get("value").and_then(as_u256).unwrap_or_default(),
+                    ..ctx.clone()
+                };
+                self.clear_sign_calldata(&calldata, &ctx)
+            }
+            "eth_signTypedData_v4" | "eth_signTypedData_v3" | "eth_signTypedData" => {
+                // params = [address, typedData]，typedData 可能是 JSON 字符串
+                let typed = params.get(1)?;
+                let typed = match typed {
+                    Value::String(s) => serde_json::from_str(s).ok()?,
+                    other => other.clone(),
+                };
+                self.clear_sign_typed_data(&typed, ctx)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 地址簿 + 合约注册表中的名称，后者只取当前链
+pub async fn known_names(state: &AppState, chain_id: u64) -> HashMap<Address, String> {
+    let mut names: HashMap<Address, String> = state
+        .contract_registry
+        .lock()
+        .await
+        .list(Some(chain_id))
+        .into_iter()
+        .map(|e| (e.address, e.name))
+        .collect();
+    for entry in state.address_books.lock().await.iter() {
+        if entry.chain_ids.as_ref().is_none_or(|ids| ids.contains(&chain_id)) {
+            names.insert(entry.address, entry.name.clone());
+        }
+    }
+    names
+}
+
+fn user_key(mgr: &TableManager, id: &str) -> Vec<u8> {
+    mgr.key_from_str(&format!("{USER_KEY_PREFIX}:{id}"))
+}
+
+// ========== CLEAR SIGNING ==========
+#[tauri::command]
+pub fn clearsign_descriptor_list(state: State<AppState>) -> Result<Vec<DescriptorSummary>, AppError> {
+    Ok(state.clear_sign.blocking_lock().list())
+}
+
+#[tauri::command]
+pub fn clearsign_descriptor_add(json: String, appdb: State<AppDB>, state: State<AppState>) -> Result<String, AppError> {
+    let raw: Value = serde_json::from_str(&json)?;
+    let loaded = parse_descriptor(raw, EntrySource::User)?;
+    let id = loaded.id.clone();
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    mgr.set(&user_key(&mgr, &id), &UserDescriptor { id: id.clone(), json: loaded.raw.to_string() })?;
+    state.clear_sign.blocking_lock().insert(loaded);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn clearsign_descriptor_delete(id: String, appdb: State<AppDB>, state: State<AppState>) -> Result<(), AppError> {
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    mgr.delete(&user_key(&mgr, &id))?;
+    // 删除后可能需要恢复被覆盖的内置描述，整体重载
+    *state.clear_sign.blocking_lock() = ClearSignRegistry::load(db)?;
+    Ok(())
+}
+
+/// 与签名确认页相同的渲染，供前端预览
+#[tauri::command]
+pub async fn clearsign_preview(
+    chain_id: u64,
+    method: String,
+    params: Value,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<Option<ClearSignView>, AppError> {
+    let tokens = crate::data::tokenlist::token_meta_map(appdb.db.as_ref())?;
+    let names = known_names(&state, chain_id).await;
+    let native_symbol = state.chain_registry.lock().await.native_symbol(chain_id);
+    let ctx = FormatContext { chain_id, native_symbol, tokens: Some(&tokens), names: Some(&names), ..Default::default() };
+    Ok(state.clear_sign.lock().await.clear_sign_request(&method, &params, &ctx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::selector::IERC20;
+    use alloy_primitives::address;
+    use alloy_sol_types::SolCall;
+
+    const USDC: Address = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+    const PERMIT2: Address = address!("0x000000000022D473030F116dDEE9F6B43aC78BA3");
+    const ROUTER: Address = address!("0x66a9893cC07D91D95644AEDD05D03f95e1dBA8Af");
+
+    fn tokens() -> TokenMetaMap {
+        HashMap::from([((1, USDC), ("USDC".to_string(), 6))])
+    }
+
+    #[test]
+    fn test_builtin_descriptors() {
+        let list: Vec<Value> = serde_json::from_str(BUILTIN_DESCRIPTORS).unwrap();
+        let registry = ClearSignRegistry::builtin();
+        assert_eq!(registry.list().len(), list.len());
+        let transfer = IERC20::transferCall::SELECTOR;
+        assert!(registry.for_calldata(1, &USDC, transfer).is_some());
+        assert!(registry.for_calldata(10, &USDC, transfer).is_none());
+        assert!(parse_descriptor(json!({"context": {}, "display": {"formats": {}}}), EntrySource::User).is_err());
+    }
+
+    #[test]
+    fn test_clear_sign_erc20_calls() {
+        let registry = ClearSignRegistry::builtin();
+        let tokens = tokens();
+        let names = HashMap::from([(ROUTER, "Uniswap Router".to_string())]);
+        let ctx = FormatContext { chain_id: 1, native_symbol: "ETH".into(), tokens: Some(&tokens), names: Some(&names), ..Default::default() };
+        let params = json!([{
+            "to": USDC.to_string(),
+            "data": format!("0x{}", hex::encode(IERC20::transferCall { to: ROUTER, value: U256::from(1_500_000) }.abi_encode())),
+        }]);
+        let view = registry.clear_sign_request("eth_sendTransaction", &params, &ctx).unwrap();
+        assert_eq!(view.intent, "Send");
+        assert_eq!(view.fields[0], DisplayField { label: "Amount".into(), value: "1.5 USDC".into(), format: "tokenAmount".into() });
+        assert_eq!(view.fields[1].value, format!("Uniswap Router ({})", ROUTER.to_checksum(None)));
+
+        let approve = IERC20::approveCall { spender: ROUTER, value: U256::MAX }.abi_encode();
+        let view = registry.clear_sign_calldata(&approve, &FormatContext { to: Some(USDC), ..ctx.clone() }).unwrap();
+        assert_eq!(view.fields[1].value, "Unlimited");
+        assert_eq!((view.source, view.overrides_builtin), (EntrySource::Bundle, false));
+    }
+
+    #[test]
+    fn test_user_descriptor_override_is_visible() {
+        let mut registry = ClearSignRegistry::builtin();
+        let builtin_count = registry.list().len();
+        let user = json!({
+            "context": {"contract": {"deployments": [{"chainId": 1, "address": USDC.to_string()}]}},
+            "display": {"formats": {"transfer(address to,uint256 value)": {"intent": "Pay", "fields": []}}}
+        });
+        let loaded = parse_descriptor(user.clone(), EntrySource::User).unwrap();
+        registry.insert(loaded);
+        // 重复加载同一描述不产生重复条目
+        registry.insert(parse_descriptor(user, EntrySource::User).unwrap());
+        let list = registry.list();
+        assert_eq!(list.len(), builtin_count + 1);
+        assert_eq!(list.last().unwrap().overrides.len(), 1);
+
+        let transfer = IERC20::transferCall { to: ROUTER, value: U256::from(1) }.abi_encode();
+        let ctx = FormatContext { chain_id: 1, to: Some(USDC), ..Default::default() };
+        let view = registry.clear_sign_calldata(&transfer, &ctx).unwrap();
+        assert_eq!(view.intent, "Pay");
+        assert_eq!((view.source, view.overrides_builtin), (EntrySource::User, true));
+    }
+
+    #[test]
+    fn test_unresolved_field_falls_back_to_raw() {
+        let descriptor = |optional: bool| {
+            json!({
+                "context": {"contract": {"deployments": [{"chainId": 1, "address": USDC.to_string()}]}},
+                "display": {"formats": {"transfer(address to,uint256 value)": {"intent": "Pay", "fields": [
+                    {"path": "to", "label": "To"},
+                    {"path": "memo", "label": "Memo", "optional": optional}
+                ]}}}
+            })
+        };
+        let transfer = IERC20::transferCall { to: ROUTER, value: U256::from(1) }.abi_encode();
+        let ctx = FormatContext { chain_id: 1, to: Some(USDC), ..Default::default() };
+
+        let mut registry = ClearSignRegistry::builtin();
+        registry.insert(parse_descriptor(descriptor(false), EntrySource::User).unwrap());
+        assert!(registry.clear_sign_calldata(&transfer, &ctx).is_none());
+
+        registry.insert(parse_descriptor(descriptor(true), EntrySource::User).unwrap());
+        let view = registry.clear_sign_calldata(&transfer, &ctx).unwrap();
+        assert_eq!(view.fields.len(), 1);
+        assert_eq!(view.fields[0].label, "To");
+    }
+
+    #[test]
+    fn test_clear_sign_permit2_typed_data() {
+        let registry = ClearSignRegistry::builtin();
+        let tokens = tokens();
+        let ctx = FormatContext { chain_id: 1, tokens: Some(&tokens), ..Default::default() };
+        let typed = json!({
+            "domain": {"name": "Permit2", "chainId": 1, "verifyingContract": PERMIT2.to_string()},
+            "primaryType": "PermitSingle",
+            "message": {
+                "details": {"token": USDC.to_string(), "amount": "2500000", "expiration": "1767225600", "nonce": "0"},
+                "spender": ROUTER.to_string(),
+                "sigDeadline": 1767225600u64
+            }
+        });
+        let params = json!(["0x0000000000000000000000000000000000000001", typed.to_string()]);
+        let view = registry.clear_sign_request("eth_signTypedData_v4", &params, &ctx).unwrap();
+        assert_eq!(view.owner.as_deref(), Some("Uniswap Permit2"));
+        assert_eq!(view.fields[0].label, "Spender");
+        assert_eq!(view.fields[1].value, "2.5 USDC");
+        assert_eq!(view.fields[2].value, "2026-01-01 00:00:00 UTC");
+        assert_eq!(view.fields[3].label, "Signature deadline");
+
+        // domain 名称不符时不套用描述
+        let mut spoofed = typed.clone();
+        spoofed["domain"]["name"] = json!("Not Permit2");
+        assert!(registry.clear_sign_typed_data(&spoofed, &ctx).is_none());
+    }
+}
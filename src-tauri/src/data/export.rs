@@ -0,0 +1,522 @@
+// 交易历史导出（税务 / 记账）
+// 直接读本地 DB，不依赖区块浏览器；按预设格式输出 CSV
+
+use crate::core::account::Account;
+use crate::core::db::{AppDB, TableKind, TableManager, TxHistoryManager};
+use crate::data::addr::AddressBookEntry;
+use crate::data::tx::{ReceiptStatus, TokenStandard, TokenTransferLeg, TransactionHistoryEntry};
+use crate::data::tokenlist::token_meta_map;
+use crate::error::AppError;
+use crate::evm::chains::native_symbol;
+use crate::utils::csv::csv_line;
+use crate::utils::num::format_units;
+use crate::utils::time::format_utc;
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tauri::State;
+
+/// 常见加密税务工具的 CSV 格式。
+/// 带法币列的格式（Generic 的 Fiat Value、Koinly 的 Net Worth Amount）按交易时的价格估值；
+/// 有资产转移但 prices 里没有对应币种价格（含 NFT 与 decimals 未知的代币）时填 "unpriced"，不留空
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportPreset {
+    Generic,
+    Koinly,
+    CoinTracker,
+    CoinLedger,
+}
+
+/// 没有价格时法币列的占位
+pub const UNPRICED: &str = "unpriced";
+
+/// 某条链原生币或代币在某个时间点的法币价格（由前端或汇率服务提供）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FiatPricePoint {
+    pub chain_id: u64,
+    /// ERC-20 合约；None 为原生币
+    #[serde(default)]
+    pub token: Option<Address>,
+    pub timestamp: u64,
+    pub price: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TxExportRequest {
+    pub preset: ExportPreset,
+    pub chain_ids: Vec<u64>,
+    pub accounts: Option<Vec<Address>>, // None = 全部本地账户
+    pub from: Option<u64>,              // 起始时间（秒）
+    pub to: Option<u64>,                // 结束时间（秒）
+    pub fiat: Option<String>,
+    pub prices: Option<Vec<FiatPricePoint>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    In,
+    Out,
+    SelfTransfer,
+}
+
+/// 腿的估值依据：原生币、decimals 已知的 ERC-20，或无法估值（NFT / 未知代币）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LegAsset {
+    Native,
+    Token(Address),
+    Unpriced,
+}
+
+/// 一条转账腿：数量（已按 decimals 缩放）+ 币种
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leg {
+    pub amount: String,
+    pub currency: String,
+    pub asset: LegAsset,
+}
+
+/// 与格式无关的中间行，再由 preset 渲染成具体列
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportRow {
+    pub timestamp: u64,
+    pub chain_id: u64,
+    pub hash: String,
+    pub account: Address,
+    pub counterparty: Address,
+    pub direction: Direction,
+    pub sent: Option<Leg>,
+    pub received: Option<Leg>,
+    pub fee: Option<Leg>,
+    pub fiat_value: Option<f64>,
+    pub label: Option<String>,
+}
+
+/// token 元数据：(chain_id, 合约) → (symbol, decimals)
+pub type TokenMetaMap = HashMap<(u64, Address), (String, u8)>;
+
+fn token_leg(leg: &TokenTransferLeg, chain_id: u64, tokens: &TokenMetaMap) -> Leg {
+    match leg.standard {
+        TokenStandard::Erc20 => match tokens.get(&(chain_id, leg.token)) {
+            Some((symbol, decimals)) => Leg {
+                amount: format_units(leg.amount, *decimals),
+                currency: symbol.clone(),
+                asset: LegAsset::Token(leg.token),
+            },
+            None => Leg { amount: leg.amount.to_string(), currency: leg.token.to_string(), asset: LegAsset::Unpriced },
+        },
+        // NFT：currency 用 "合约#tokenId"
+        TokenStandard::Erc721 | TokenStandard::Erc1155 => Leg {
+            amount: leg.amount.to_string(),
+            currency: format!("{}#{}", leg.token, leg.token_id.unwrap_or_default()),
+            asset: LegAsset::Unpriced,
+        },
+    }
+}
+
+/// 把一条历史记录转换成导出行；与所选账户无关的记录返回空
+/// 多条转账腿（如 swap）按 sent/received 配对成多行，手续费只记在第一行；
+/// 每行的法币价值取发出腿的估值，发出腿无价格时取收到腿
+pub fn build_rows(
+    entry: &TransactionHistoryEntry,
+    accounts: &[Address],
+    labels: &HashMap<Address, String>,
+    prices: &[FiatPricePoint],
+    tokens: &TokenMetaMap,
+) -> Vec<ExportRow> {
+    let is_out = accounts.contains(&entry.from);
+    let native_in = accounts.contains(&entry.to);
+    let token_in = entry.token_transfers.iter().any(|l| accounts.contains(&l.to));
+    let direction = match (is_out, native_in || token_in) {
+        (true, _) if native_in => Direction::SelfTransfer,
+        (true, _) => Direction::Out,
+        (false, true) => Direction::In,
+        (false, false) => return Vec::new(),
+    };
+    let (account, counterparty) = match direction {
+        Direction::In if accounts.contains(&entry.to) => (entry.to, entry.from),
+        Direction::In => (
+            entry.token_transfers.iter().find(|l| accounts.contains(&l.to)).map(|l| l.to).unwrap_or(entry.to),
+            entry.from,
+        ),
+        _ => (entry.from, entry.to),
+    };
+
+    let symbol = native_symbol(entry.chain_id).to_string();
+    let mut sent = Vec::new();
+    let mut received = Vec::new();
+
+    // 失败的交易只有手续费，没有资产转移
+    if entry.status != Some(ReceiptStatus::Reverted) {
+        let own_from = accounts.contains(&entry.from);
+        let own_to = accounts.contains(&entry.to);
+        // 只有原生币从本账户转出或转入本账户时才计入（别人付给合约、我们只收到代币的不算）
+        if !entry.value.is_zero() && own_from != own_to {
+            let leg = Leg { amount: format_units(entry.value, 18), currency: symbol.clone(), asset: LegAsset::Native };
+            if own_from {
+                sent.push(leg);
+            } else {
+                received.push(leg);
+            }
+        }
+        for leg in &entry.token_transfers {
+            let own_from = accounts.contains(&leg.from);
+            let own_to = accounts.contains(&leg.to);
+            if own_from && !own_to {
+                sent.push(token_leg(leg, entry.chain_id, tokens));
+            } else if own_to && !own_from {
+                received.push(token_leg(leg, entry.chain_id, tokens));
+            }
+        }
+    }
+
+    // 只有发送方付 gas；优先用 receipt 里的实付手续费
+    let fee_wei = entry.fee_paid.or(match (entry.gas_price, entry.gas_used) {
+        (Some(price), Some(used)) => Some(price.saturating_mul(used)),
+        _ => None,
+    });
+    let fee = match (is_out, fee_wei) {
+        (true, Some(wei)) => Some(Leg { amount: format_units(wei, 18), currency: symbol, asset: LegAsset::Native }),
+        _ => None,
+    };
+
+    let timestamp = entry.timestamp.unwrap_or_default();
+    let value_of = |leg: &Option<Leg>| leg.as_ref().and_then(|l| leg_value(l, prices, entry.chain_id, timestamp));
+
+    // 用户标签优先，其次地址簿名称
+    let mut label_parts: Vec<String> = entry.labels.clone();
+    if let Some(name) = labels.get(&counterparty) {
+        label_parts.push(name.clone());
+    }
+    if let Some(method) = entry.method.as_ref().and_then(|m| m.name.clone()) {
+        label_parts.push(method);
+    }
+    if let Some(note) = &entry.note {
+        label_parts.push(note.clone());
+    }
+    let label = (!label_parts.is_empty()).then(|| label_parts.join(" | "));
+
+    let count = sent.len().max(received.len()).max(1);
+    let mut sent = sent.into_iter();
+    let mut received = received.into_iter();
+    (0..count)
+        .map(|i| {
+            let (sent, received) = (sent.next(), received.next());
+            ExportRow {
+                timestamp,
+                chain_id: entry.chain_id,
+                hash: entry.hash.to_string(),
+                account,
+                counterparty,
+                direction,
+                fiat_value: value_of(&sent).or_else(|| value_of(&received)),
+                sent,
+                received,
+                fee: if i == 0 { fee.clone() } else { None },
+                label: label.clone(),
+            }
+        })
+        .collect()
+}
+
+/// 取 timestamp 当时（之前最近一个点）的价格；token 为 None 时是原生币
+fn price_at(prices: &[FiatPricePoint], chain_id: u64, token: Option<Address>, timestamp: u64) -> Option<f64> {
+    prices
+        .iter()
+        .filter(|p| p.chain_id == chain_id && p.token == token && p.timestamp <= timestamp)
+        .max_by_key(|p| p.timestamp)
+        .map(|p| p.price)
+}
+
+// 仅用于法币估值展示
+fn leg_value(leg: &Leg, prices: &[FiatPricePoint], chain_id: u64, timestamp: u64) -> Option<f64> {
+    let token = match leg.asset {
+        LegAsset::Native => None,
+        LegAsset::Token(token) => Some(token),
+        LegAsset::Unpriced => return None,
+    };
+    let price = price_at(prices, chain_id, token, timestamp)?;
+    Some(leg.amount.parse::<f64>().ok()? * price)
+}
+
+fn leg_amount(leg: &Option<Leg>) -> String {
+    leg.as_ref().map(|l| l.amount.clone()).unwrap_or_default()
+}
+
+fn leg_currency(leg: &Option<Leg>) -> String {
+    leg.as_ref().map(|l| l.currency.clone()).unwrap_or_default()
+}
+
+/// 有资产转移却没有价格时填 UNPRICED；只有手续费的行留空
+fn fiat_str(row: &ExportRow) -> String {
+    match row.fiat_value {
+        Some(v) => format!("{:.2}", v),
+        None if row.sent.is_some() || row.received.is_some() => UNPRICED.to_string(),
+        None => String::new(),
+    }
+}
+
+fn direction_tag(row: &ExportRow) -> &'static str {
+    if row.sent.is_some() && row.received.is_some() {
+        return "trade";
+    }
+    match row.direction {
+        Direction::In => "receive",
+        Direction::Out => "send",
+        Direction::SelfTransfer => "self",
+    }
+}
+
+/// Koinly 用 "YYYY-MM-DD HH:MM UTC"，CoinTracker 用 "MM/DD/YYYY HH:MM:SS"，其余同 format_utc；
+/// 时间戳超出可表示范围时原样输出秒数
+fn preset_date(timestamp: u64, preset: ExportPreset) -> String {
+    let Some(dt) = i64::try_from(timestamp).ok().and_then(|t| time::OffsetDateTime::from_unix_timestamp(t).ok()) else {
+        return timestamp.to_string();
+    };
+    let (y, mo, d, h, mi, sec) = (dt.year(), dt.month() as u8, dt.day(), dt.hour(), dt.minute(), dt.second());
+    match preset {
+        ExportPreset::Koinly => format!("{y:04}-{mo:02}-{d:02} {h:02}:{mi:02} UTC"),
+        ExportPreset::CoinTracker => format!("{mo:02}/{d:02}/{y:04} {h:02}:{mi:02}:{sec:02}"),
+        ExportPreset::Generic | ExportPreset::CoinLedger => format_utc(timestamp),
+    }
+}
+
+/// 渲染为指定格式的 CSV（含表头）
+pub fn render_csv(rows: &[ExportRow], preset: ExportPreset, fiat: &str) -> String {
+    let header: &[&str] = match preset {
+        ExportPreset::Generic => &[
+            "Date (UTC)", "Chain ID", "Tx Hash", "Account", "Counterparty", "Direction",
+            "Sent Amount", "Sent Currency", "Received Amount", "Received Currency",
+            "Fee Amount", "Fee Currency", "Fiat Value", "Fiat Currency", "Label",
+        ],
+        ExportPreset::Koinly => &[
+            "Date", "Sent Amount", "Sent Currency", "Received Amount", "Received Currency",
+            "Fee Amount", "Fee Currency", "Net Worth Amount", "Net Worth Currency",
+            "Label", "Description", "TxHash",
+        ],
+        ExportPreset::CoinTracker => &[
+            "Date", "Received Quantity", "Received Currency", "Sent Quantity", "Sent Currency",
+            "Fee Amount", "Fee Currency", "Tag",
+        ],
+        ExportPreset::CoinLedger => &[
+            "Date (UTC)", "Platform (Optional)", "Asset Sent", "Amount Sent", "Asset Received",
+            "Amount Received", "Fee Currency (Optional)", "Fee Amount (Optional)", "Type",
+            "Description (Optional)", "TxHash (Optional)",
+        ],
+    };
+
+    let mut out = csv_line(&header.iter().map(|h| h.to_string()).collect::<Vec<_>>());
+    out.push('\n');
+
+    for row in rows {
+        let date = preset_date(row.timestamp, preset);
+        let label = row.label.clone().unwrap_or_default();
+        let fields = match preset {
+            ExportPreset::Generic => vec![
+                date,
+                row.chain_id.to_string(),
+                row.hash.clone(),
+                row.account.to_string(),
+                row.counterparty.to_string(),
+                direction_tag(row).to_string(),
+                leg_amount(&row.sent),
+                leg_currency(&row.sent),
+                leg_amount(&row.received),
+                leg_currency(&row.received),
+                leg_amount(&row.fee),
+                leg_currency(&row.fee),
+                fiat_str(row),
+                fiat.to_string(),
+                label,
+            ],
+            ExportPreset::Koinly => vec![
+                date,
+                leg_amount(&row.sent),
+                leg_currency(&row.sent),
+                leg_amount(&row.received),
+                leg_currency(&row.received),
+                leg_amount(&row.fee),
+                leg_currency(&row.fee),
+                fiat_str(row),
+                if row.fiat_value.is_some() { fiat.to_string() } else { String::new() },
+                String::new(),
+                label,
+                row.hash.clone(),
+            ],
+            ExportPreset::CoinTracker => vec![
+                date,
+                leg_amount(&row.received),
+                leg_currency(&row.received),
+                leg_amount(&row.sent),
+                leg_currency(&row.sent),
+                leg_amount(&row.fee),
+                leg_currency(&row.fee),
+                String::new(),
+            ],
+            ExportPreset::CoinLedger => vec![
+                date,
+                format!("chain:{}", row.chain_id),
+                leg_currency(&row.sent),
+                leg_amount(&row.sent),
+                leg_currency(&row.received),
+                leg_amount(&row.received),
+                leg_currency(&row.fee),
+                leg_amount(&row.fee),
+                match direction_tag(row) {
+                    "trade" => "Trade".to_string(),
+                    "receive" => "Deposit".to_string(),
+                    "send" => "Withdrawal".to_string(),
+                    _ => "Transfer".to_string(),
+                },
+                label,
+                row.hash.clone(),
+            ],
+        };
+        out.push_str(&csv_line(&fields));
+        out.push('\n');
+    }
+    out
+}
+
+// ========== Export ==========
+#[tauri::command]
+pub fn tx_export_csv(request: TxExportRequest, appdb: State<AppDB>) -> Result<String, AppError> {
+    let db = appdb.db.as_ref();
+
+    let accounts: Vec<Address> = match request.accounts {
+        Some(list) => list,
+        None => TableManager::new(db, TableKind::Account)?
+            .list::<Account>()?
+            .iter()
+            .filter_map(|a| Address::from_str(&a.address).ok())
+            .collect(),
+    };
+
+    let labels: HashMap<Address, String> = TableManager::new(db, TableKind::AddressBook)?
+        .list::<AddressBookEntry>()?
+        .into_iter()
+        .map(|e| (e.address, e.name))
+        .collect();
+
+    let prices = request.prices.unwrap_or_default();
+    let tokens = token_meta_map(db)?;
+    let mgr = TxHistoryManager::new(db);
+    let mut rows = Vec::new();
+    for chain_id in &request.chain_ids {
+        for entry in mgr.range(*chain_id, request.from, request.to)? {
+            rows.extend(build_rows(&entry, &accounts, &labels, &prices, &tokens));
+        }
+    }
+    rows.sort_by_key(|r| r.timestamp);
+
+    let fiat = request.fiat.unwrap_or_else(|| "USD".to_string());
+    Ok(render_csv(&rows, request.preset, &fiat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tx::TransactionHistoryEntryV1;
+    use alloy_primitives::{TxHash, U256, address};
+
+    fn entry(from: Address, to: Address, value: u64) -> TransactionHistoryEntry {
+        TransactionHistoryEntryV1 {
+            chain_id: 1,
+            hash: TxHash::ZERO,
+            block_number: 1,
+            nonce: U256::ZERO,
+            from,
+            to,
+            value: U256::from(value),
+            gas_price: Some(U256::from(1_000_000_000u64)),
+            gas_used: Some(U256::from(21_000u64)),
+            timestamp: Some(1_700_000_000),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_build_row_direction_and_fee() {
+        let me = address!("0x1111111111111111111111111111111111111111");
+        let other = address!("0x2222222222222222222222222222222222222222");
+        let prices = vec![FiatPricePoint { chain_id: 1, token: None, timestamp: 1_600_000_000, price: 2000.0 }];
+
+        let tokens = TokenMetaMap::new();
+
+        let out = build_rows(&entry(me, other, 500_000_000_000_000_000), &[me], &HashMap::new(), &prices, &tokens);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].direction, Direction::Out);
+        assert_eq!(out[0].sent.as_ref().unwrap().amount, "0.5");
+        assert_eq!(out[0].fee.as_ref().unwrap().amount, "0.000021");
+        assert_eq!(out[0].fiat_value, Some(1000.0));
+
+        let incoming = build_rows(&entry(other, me, 1), &[me], &HashMap::new(), &[], &tokens);
+        assert_eq!(incoming[0].direction, Direction::In);
+        assert!(incoming[0].fee.is_none());
+
+        assert!(build_rows(&entry(other, other, 1), &[me], &HashMap::new(), &[], &tokens).is_empty());
+    }
+
+    #[test]
+    fn test_build_rows_swap_legs() {
+        let me = address!("0x1111111111111111111111111111111111111111");
+        let router = address!("0x3333333333333333333333333333333333333333");
+        let usdc = address!("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let mut swap = entry(me, router, 1_000_000_000_000_000_000);
+        swap.token_transfers.push(TokenTransferLeg {
+            standard: TokenStandard::Erc20,
+            token: usdc,
+            from: router,
+            to: me,
+            token_id: None,
+            amount: U256::from(2_500_000_000u64),
+            log_index: 0,
+        });
+        swap.labels.push("dca".to_string());
+        let mut tokens = TokenMetaMap::new();
+        tokens.insert((1, usdc), ("USDC".to_string(), 6));
+
+        let rows = build_rows(&swap, &[me], &HashMap::new(), &[], &tokens);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].direction, Direction::Out);
+        assert_eq!(direction_tag(&rows[0]), "trade");
+        assert_eq!(rows[0].sent.as_ref().unwrap().currency, "ETH");
+        assert_eq!(rows[0].received.as_ref().unwrap().amount, "2500");
+        assert_eq!(rows[0].received.as_ref().unwrap().currency, "USDC");
+        assert_eq!(rows[0].label.as_deref(), Some("dca"));
+
+        // 别人付 ETH 给合约、代币转给我们：只记收到的代币
+        let other = address!("0x2222222222222222222222222222222222222222");
+        let mut gifted = swap.clone();
+        gifted.from = other;
+        let prices = vec![FiatPricePoint { chain_id: 1, token: None, timestamp: 1_600_000_000, price: 2000.0 }];
+        let rows = build_rows(&gifted, &[me], &HashMap::new(), &prices, &tokens);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].direction, Direction::In);
+        assert!(rows[0].sent.is_none());
+        assert_eq!(rows[0].received.as_ref().unwrap().currency, "USDC");
+        assert_eq!(rows[0].fiat_value, None);
+        assert!(render_csv(&rows, ExportPreset::Generic, "USD").contains(UNPRICED));
+
+        // 代币腿按该代币当时的价格估值
+        let usdc_price = FiatPricePoint { chain_id: 1, token: Some(usdc), timestamp: 1_600_000_000, price: 0.99 };
+        let rows = build_rows(&gifted, &[me], &HashMap::new(), &[usdc_price], &tokens);
+        assert_eq!(rows[0].fiat_value, Some(2500.0 * 0.99));
+        assert!(!render_csv(&rows, ExportPreset::Koinly, "USD").contains(UNPRICED));
+    }
+
+    #[test]
+    fn test_preset_dates() {
+        assert_eq!(preset_date(1_700_000_000, ExportPreset::Koinly), "2023-11-14 22:13 UTC");
+        assert_eq!(preset_date(1_700_000_000, ExportPreset::CoinTracker), "11/14/2023 22:13:20");
+        assert_eq!(preset_date(1_700_000_000, ExportPreset::Generic), "2023-11-14 22:13:20");
+        // 超出范围的时间戳不能 panic
+        assert_eq!(preset_date(u64::MAX, ExportPreset::Koinly), u64::MAX.to_string());
+        assert_eq!(preset_date(300_000_000_000, ExportPreset::CoinTracker), "300000000000");
+        let mut row = build_rows(&entry(Address::ZERO, Address::repeat_byte(1), 1), &[Address::ZERO], &HashMap::new(), &[], &TokenMetaMap::new())
+            .remove(0);
+        row.timestamp = u64::MAX;
+        assert!(render_csv(&[row], ExportPreset::CoinTracker, "USD").contains(&u64::MAX.to_string()));
+    }
+}
@@ -1,72 +1,478 @@
-
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::data::currency::CurrencyUsdPrice;
+use crate::error::AppError;
+use crate::rpc::https::get_json;
+use crate::utils::num::format_units;
+use crate::utils::time::now_s;
+use alloy_primitives::{Address, U256};
+use bincode::{Decode, Encode};
+use reqwest::Client;
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tauri::State;
+
+/// 支持的法币（代码, 名称），汇率均相对 USD
+pub const SUPPORTED_FIATS: &[(&str, &str)] = &[
+    // --- 北美 / 欧洲 ---
+    ("USD", "美元"),
+    ("EUR", "欧元"),
+    ("GBP", "英镑"),
+    ("CHF", "瑞士法郎"),
+    ("CAD", "加拿大元"),
+    ("AUD", "澳大利亚元"),
+    ("NZD", "新西兰元"),
+    // --- 东亚 ---
+    ("JPY", "日元"),
+    ("CNY", "人民币"),
+    ("KRW", "韩元"),
+    // --- 东南亚 ---
+    ("SGD", "新加坡元"),
+    ("VND", "越南盾"),
+    ("MYR", "马来西亚林吉特"),
+    ("IDR", "印尼盾"),
+    ("THB", "泰铢"),
+    ("PHP", "菲律宾比索"),
+    // --- 南亚 ---
+    ("INR", "印度卢比"),
+    ("PKR", "巴基斯坦卢比"),
+    // --- 南美 ---
+    ("VES", "委内瑞拉玻利瓦尔"),
+    ("ARS", "阿根廷比索"),
+    ("BRL", "巴西雷亚尔"),
+    ("CLP", "智利比索"),
+    ("COP", "哥伦比亚比索"),
+    ("PEN", "秘鲁新索尔"),
+];
 
-// 免费api： https://api.exchangerate-api.com/v4/latest/USD
+/// 缓存有效期（秒），过期后下次请求会尝试刷新
+pub const DEFAULT_TTL_SECS: u64 = 600;
 
-let url = "https://api.exchangerate.host/latest?base=USD";
-let rates = reqwest::get(url).await?.json::<Rates>().await?;
+const FIAT_CACHE_KEY: &str = "forex:fiat";
+const CRYPTO_CACHE_KEY: &str = "forex:crypto";
+const PROVIDERS_KEY: &str = "forex_providers";
 
-/// 法币汇率（相对 USD 或其它主货币）
-#[derive(Debug, Serialize, Deserialize)]
+/// 法币汇率（1 USD = rates[code]）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Encode, Decode, PartialEq)]
 pub struct FiatRates {
-    /// 时间戳（秒）
-    pub timestamp: f64,
+    /// 数据源给出的时间戳（秒）
+    pub timestamp: u64,
+    pub rates: HashMap<String, f64>,
+}
 
-    // --- 北美 / 欧洲 ---
-    #[serde(rename = "USD")]  // 美元
-    pub usd: f64,
-    #[serde(rename = "EUR")]  // 欧元
-    pub eur: f64,
-    #[serde(rename = "GBP")]  // 英镑
-    pub gbp: f64,
-    #[serde(rename = "CHF")]  // 瑞士法郎
-    pub chf: f64,
-    #[serde(rename = "CAD")]  // 加拿大元
-    pub cad: f64,
-    #[serde(rename = "AUD")]  // 澳大利亚元
-    pub aud: f64,
-    #[serde(rename = "NZD")]  // 新西兰元
-    pub nzd: f64,
+impl FiatRates {
+    pub fn rate(&self, fiat: &str) -> Option<f64> {
+        let fiat = fiat.to_uppercase();
+        if fiat == "USD" {
+            return Some(1.0);
+        }
+        self.rates.get(&fiat).copied().filter(|r| *r > 0.0)
+    }
+}
 
-    // --- 东亚 ---
-    #[serde(rename = "JPY")]  // 日元
-    pub jpy: f64,
-    #[serde(rename = "CNY")]  // 人民币
-    pub cny: f64,
-    #[serde(rename = "KRW")]  // 韩元
-    pub krw: f64,
+/// 法币汇率数据源；base_url 可改，便于自建镜像或测试
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FiatRateProvider {
+    /// https://api.exchangerate-api.com/v4/latest/USD
+    ExchangeRateApi { base_url: String },
+    /// https://open.er-api.com/v6/latest/USD
+    OpenErApi { base_url: String },
+    /// https://api.frankfurter.app/latest?from=USD（ECB 数据，币种较少）
+    Frankfurter { base_url: String },
+}
 
-    // --- 东南亚 ---
-    #[serde(rename = "SGD")]  // 新加坡元
-    pub sgd: f64,
-    #[serde(rename = "VND")]  // 越南盾
-    pub vnd: f64,
-    #[serde(rename = "MYR")]  // 马来西亚林吉特
-    pub myr: f64,
-    #[serde(rename = "IDR")]  // 印尼盾
-    pub idr: f64,
-    #[serde(rename = "THB")]  // 泰铢
-    pub thb: f64,
-    #[serde(rename = "PHP")]  // 菲律宾比索
-    pub php: f64,
+impl FiatRateProvider {
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::ExchangeRateApi { base_url: "https://api.exchangerate-api.com".to_string() },
+            Self::OpenErApi { base_url: "https://open.er-api.com".to_string() },
+            Self::Frankfurter { base_url: "https://api.frankfurter.app".to_string() },
+        ]
+    }
 
-    // --- 南亚 ---
-    #[serde(rename = "INR")]  // 印度卢比
-    pub inr: f64,
-    #[serde(rename = "PKR")]  // 巴基斯坦卢比
-    pub pkr: f64,
+    pub fn url(&self) -> String {
+        match self {
+            Self::ExchangeRateApi { base_url } => format!("{}/v4/latest/USD", base_url.trim_end_matches('/')),
+            Self::OpenErApi { base_url } => format!("{}/v6/latest/USD", base_url.trim_end_matches('/')),
+            Self::Frankfurter { base_url } => format!("{}/latest?from=USD", base_url.trim_end_matches('/')),
+        }
+    }
 
-    // --- 南美 ---
-    #[serde(rename = "VES")]  // 委内瑞拉玻利瓦尔
-    pub ves: f64,
-    #[serde(rename = "ARS")]  // 阿根廷比索
-    pub ars: f64,
-    #[serde(rename = "BRL")]  // 巴西雷亚尔
-    pub brl: f64,
-    #[serde(rename = "CLP")]  // 智利比索
-    pub clp: f64,
-    #[serde(rename = "COP")]  // 哥伦比亚比索
-    pub cop: f64,
-    #[serde(rename = "PEN")]  // 秘鲁新索尔
-    pub pen: f64,
+    pub fn parse(&self, body: &Value, now: u64) -> Option<FiatRates> {
+        let timestamp = match self {
+            Self::ExchangeRateApi { .. } => body["time_last_updated"].as_u64(),
+            Self::OpenErApi { .. } => {
+                if body["result"].as_str() != Some("success") {
+                    return None;
+                }
+                body["time_last_update_unix"].as_u64()
+            }
+            Self::Frankfurter { .. } => None,
+        }
+        .unwrap_or(now);
+        let rates: HashMap<String, f64> = body["rates"]
+            .as_object()?
+            .iter()
+            .filter_map(|(code, rate)| rate.as_f64().map(|r| (code.to_uppercase(), r)))
+            .collect();
+        (!rates.is_empty()).then_some(FiatRates { timestamp, rates })
+    }
+}
+
+/// 原生币 USD 价格数据源
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CryptoPriceProvider {
+    /// https://api.coingecko.com/api/v3/simple/price
+    CoinGecko { base_url: String },
+}
+
+impl CryptoPriceProvider {
+    pub fn defaults() -> Vec<Self> {
+        vec![Self::CoinGecko { base_url: "https://api.coingecko.com".to_string() }]
+    }
+
+    pub fn url(&self) -> String {
+        match self {
+            Self::CoinGecko { base_url } => {
+                let ids: Vec<&str> = CurrencyUsdPrice::COINGECKO_IDS.iter().map(|(_, id)| *id).collect();
+                format!(
+                    "{}/api/v3/simple/price?ids={}&vs_currencies=usd",
+                    base_url.trim_end_matches('/'),
+                    ids.join(",")
+                )
+            }
+        }
+    }
+
+    pub fn parse(&self, body: &Value, now: u64) -> Option<CurrencyUsdPrice> {
+        match self {
+            Self::CoinGecko { .. } => {
+                let mut prices = CurrencyUsdPrice { timestamp: now, ..Default::default() };
+                let mut found = false;
+                for (symbol, id) in CurrencyUsdPrice::COINGECKO_IDS {
+                    if let Some(price) = body[id]["usd"].as_f64() {
+                        prices.set(symbol, price);
+                        found = true;
+                    }
+                }
+                found.then_some(prices)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct RateProviders {
+    pub fiat: Vec<FiatRateProvider>,
+    pub crypto: Vec<CryptoPriceProvider>,
+}
+
+impl Default for RateProviders {
+    fn default() -> Self {
+        Self { fiat: FiatRateProvider::defaults(), crypto: CryptoPriceProvider::defaults() }
+    }
+}
+
+/// 返回给前端的汇率快照，附带新鲜度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateSnapshot {
+    pub fiat: Option<FiatRates>,
+    pub crypto: Option<CurrencyUsdPrice>,
+    /// 本次是否从网络拿到了新数据
+    pub live: bool,
+    /// 最旧一项数据（按数据源时间戳）距今秒数
+    pub age_secs: u64,
+    /// 超过 TTL 未从数据源成功拉取（与数据源自身的更新频率无关）
+    pub stale: bool,
+}
+
+/// 汇率服务：按顺序尝试数据源，内存 TTL 缓存，最后一次成功的结果落库供离线使用。
+/// TTL 按本地拉取时间计算：数据源的时间戳可能一天才更新一次，不能拿来判断缓存是否过期
+#[derive(Debug, Clone)]
+pub struct RateService {
+    pub providers: RateProviders,
+    pub ttl_secs: u64,
+    fiat: Option<FiatRates>,
+    crypto: Option<CurrencyUsdPrice>,
+    /// 本次运行中最后一次成功拉取的时间；从库里读出的旧数据为 None，视为需要刷新
+    fiat_fetched_at: Option<u64>,
+    crypto_fetched_at: Option<u64>,
+}
+
+impl Default for RateService {
+    fn default() -> Self {
+        Self {
+            providers: RateProviders::default(),
+            ttl_secs: DEFAULT_TTL_SECS,
+            fiat: None,
+            crypto: None,
+            fiat_fetched_at: None,
+            crypto_fetched_at: None,
+        }
+    }
+}
+
+impl RateService {
+    /// 读取已保存的数据源配置与最后一次汇率
+    pub fn load(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Self> {
+        let registry = TableManager::new(db, TableKind::Registry)?;
+        let cache = TableManager::new(db, TableKind::Cache)?;
+        Ok(Self {
+            providers: registry.get(&registry.key_from_str(PROVIDERS_KEY))?.unwrap_or_default(),
+            ttl_secs: DEFAULT_TTL_SECS,
+            fiat: cache.get(&cache.key_from_str(FIAT_CACHE_KEY))?,
+            crypto: cache.get(&cache.key_from_str(CRYPTO_CACHE_KEY))?,
+            fiat_fetched_at: None,
+            crypto_fetched_at: None,
+        })
+    }
+
+    pub fn persist(&self, db: &DBWithThreadMode<MultiThreaded>) -> DbResult<()> {
+        let cache = TableManager::new(db, TableKind::Cache)?;
+        if let Some(fiat) = &self.fiat {
+            cache.set(&cache.key_from_str(FIAT_CACHE_KEY), fiat)?;
+        }
+        if let Some(crypto) = &self.crypto {
+            cache.set(&cache.key_from_str(CRYPTO_CACHE_KEY), crypto)?;
+        }
+        Ok(())
+    }
+
+    pub fn snapshot(&self, now: u64, live: bool) -> RateSnapshot {
+        let oldest = [self.fiat.as_ref().map(|f| f.timestamp), self.crypto.as_ref().map(|c| c.timestamp)]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(0);
+        let age_secs = now.saturating_sub(oldest);
+        RateSnapshot {
+            fiat: self.fiat.clone(),
+            crypto: self.crypto.clone(),
+            live,
+            age_secs,
+            stale: !self.fresh(self.fiat_fetched_at, now) || !self.fresh(self.crypto_fetched_at, now),
+        }
+    }
+
+    fn fresh(&self, ts: Option<u64>, now: u64) -> bool {
+        ts.is_some_and(|ts| now.saturating_sub(ts) <= self.ttl_secs)
+    }
+
+    /// 缓存未过期直接返回；否则依次请求数据源，全部失败时退回旧数据并标记 stale
+    pub async fn refresh(&mut self, client: &Client, now: u64, force: bool) -> Result<RateSnapshot, AppError> {
+        let fiat_fresh = self.fiat.is_some() && self.fresh(self.fiat_fetched_at, now);
+        let crypto_fresh = self.crypto.is_some() && self.fresh(self.crypto_fetched_at, now);
+        if !force && fiat_fresh && crypto_fresh {
+            return Ok(self.snapshot(now, false));
+        }
+
+        let mut live = false;
+        let mut last_err = None;
+        if force || !fiat_fresh {
+            match fetch_first(client, &self.providers.fiat, |p| p.url(), |p, body| p.parse(body, now)).await {
+                Ok(rates) => {
+                    self.fiat = Some(rates);
+                    self.fiat_fetched_at = Some(now);
+                    live = true;
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+        if force || !crypto_fresh {
+            match fetch_first(client, &self.providers.crypto, |p| p.url(), |p, body| p.parse(body, now)).await {
+                Ok(prices) => {
+                    self.crypto = Some(prices);
+                    self.crypto_fetched_at = Some(now);
+                    live = true;
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        match last_err {
+            Some(e) if self.fiat.is_none() && self.crypto.is_none() => Err(e),
+            _ => Ok(self.snapshot(now, live)),
+        }
+    }
+}
+
+/// 按顺序请求，返回第一个能解析的结果
+async fn fetch_first<P, T>(
+    client: &Client,
+    providers: &[P],
+    url: impl Fn(&P) -> String,
+    parse: impl Fn(&P, &Value) -> Option<T>,
+) -> Result<T, AppError> {
+    let mut last_err = AppError::Parse("no rate provider configured");
+    for provider in providers {
+        match get_json(client, &url(provider)).await {
+            Ok(body) => match parse(provider, &body) {
+                Some(parsed) => return Ok(parsed),
+                None => last_err = AppError::Parse("unexpected rate provider response"),
+            },
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
+}
+
+/// 原始数量（最小单位）→ 法币金额
+pub fn convert_amount(amount: U256, decimals: u8, usd_price: f64, fiat_rate: f64) -> f64 {
+    let units: f64 = format_units(amount, decimals).parse().unwrap_or(0.0);
+    units * usd_price * fiat_rate
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiatValue {
+    pub fiat: String,
+    pub value: f64,
+    pub usd_price: f64,
+    pub age_secs: u64,
+    pub stale: bool,
+}
+
+// ========== FOREX ==========
+#[tauri::command]
+pub async fn fiat_rates(
+    force: Option<bool>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<RateSnapshot, AppError> {
+    let client = state.https_client.lock().await.clone();
+    let mut service = state.rate_service.lock().await;
+    let snapshot = service.refresh(&client, now_s(), force.unwrap_or(false)).await?;
+    if snapshot.live {
+        service.persist(appdb.db.as_ref())?;
+    }
+    Ok(snapshot)
+}
+
+/// 把代币数量换算为配置的法币。usd_price 为空时按 (chain_id, token) 查原生币 / 包装币 / 稳定币价格，
+/// token 不传表示原生币
+#[tauri::command]
+pub async fn fiat_convert(
+    amount: String,
+    decimals: u8,
+    chain_id: u64,
+    token: Option<String>,
+    usd_price: Option<f64>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<FiatValue, AppError> {
+    let amount = U256::from_str(amount.trim()).map_err(|_| AppError::Parse("invalid amount"))?;
+    let token = match token {
+        Some(t) => t.parse::<Address>().map_err(|_| AppError::Parse("invalid token address"))?,
+        None => Address::ZERO,
+    };
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let fiat = state.config.lock().await.fiat.clone().unwrap_or_else(|| "USD".to_string());
+    let snapshot = fiat_rates(None, appdb, state).await?;
+
+    let usd_price = usd_price
+        .or_else(|| snapshot.crypto.as_ref().and_then(|c| c.token_price(chain_id, token, &caps)))
+        .ok_or(AppError::Parse("no usd price for token"))?;
+    let rate = snapshot
+        .fiat
+        .as_ref()
+        .and_then(|f| f.rate(&fiat))
+        .ok_or(AppError::Parse("no rate for configured fiat"))?;
+    Ok(FiatValue {
+        value: convert_amount(amount, decimals, usd_price, rate),
+        fiat,
+        usd_price,
+        age_secs: snapshot.age_secs,
+        stale: snapshot.stale,
+    })
+}
+
+#[tauri::command]
+pub fn fiat_set_providers(
+    providers: RateProviders,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    mgr.set(&mgr.key_from_str(PROVIDERS_KEY), &providers)?;
+    state.rate_service.blocking_lock().providers = providers;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_http;
+
+    const OPEN_ER: &str = r#"{"result":"success","time_last_update_unix":1760745600,"rates":{"USD":1,"EUR":0.86,"JPY":150.5}}"#;
+    const GECKO: &str = r#"{"bitcoin":{"usd":100000},"ethereum":{"usd":4000.5},"binancecoin":{"usd":600}}"#;
+
+    fn service(fiat_base: &str, gecko_base: &str) -> RateService {
+        RateService {
+            providers: RateProviders {
+                fiat: vec![
+                    FiatRateProvider::ExchangeRateApi { base_url: test_http::unreachable() },
+                    FiatRateProvider::OpenErApi { base_url: fiat_base.to_string() },
+                ],
+                crypto: vec![CryptoPriceProvider::CoinGecko { base_url: gecko_base.to_string() }],
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_falls_through_providers_then_serves_stale() {
+        let gecko_path = CryptoPriceProvider::CoinGecko { base_url: String::new() }.url();
+        let base = test_http::serve(vec![("/v6/latest/USD", OPEN_ER), (gecko_path.as_str(), GECKO)]);
+        let client = Client::new();
+        let now = 1_760_745_700;
+
+        let mut svc = service(&base, &base);
+        let snap = svc.refresh(&client, now, false).await.unwrap();
+        assert!(snap.live && !snap.stale);
+        assert_eq!(snap.fiat.as_ref().unwrap().rate("eur"), Some(0.86));
+        assert_eq!(snap.crypto.as_ref().unwrap().price_of("ETH"), Some(4000.5));
+
+        // 离线：数据源都不可达，返回旧数据并标记过期
+        let offline = test_http::unreachable();
+        svc.providers = service(&offline, &offline).providers;
+        let later = now + DEFAULT_TTL_SECS * 3;
+        let snap = svc.refresh(&client, later, false).await.unwrap();
+        assert!(!snap.live && snap.stale);
+        assert_eq!(snap.age_secs, later - 1_760_745_600);
+
+        // 从未成功过且离线 → 报错
+        assert!(service(&offline, &offline).refresh(&client, now, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ttl_counts_from_fetch_not_provider_timestamp() {
+        let gecko_path = CryptoPriceProvider::CoinGecko { base_url: String::new() }.url();
+        let base = test_http::serve(vec![("/v6/latest/USD", OPEN_ER), (gecko_path.as_str(), GECKO)]);
+        let client = Client::new();
+        // 数据源一天更新一次，拉取时已是一天前的数据
+        let now = 1_760_745_600 + 86_400;
+
+        let mut svc = service(&base, &base);
+        let snap = svc.refresh(&client, now, false).await.unwrap();
+        assert!(snap.live && !snap.stale);
+        assert_eq!(snap.age_secs, 86_400);
+
+        // TTL 内不再请求数据源，也不算过期
+        let offline = test_http::unreachable();
+        svc.providers = service(&offline, &offline).providers;
+        let snap = svc.refresh(&client, now + 60, false).await.unwrap();
+        assert!(!snap.live && !snap.stale);
+    }
+
+    #[test]
+    fn test_convert_amount() {
+        // 1.5 ETH * 4000 USD * 0.86 EUR/USD
+        let amount = U256::from(1_500_000_000_000_000_000u64);
+        let value = convert_amount(amount, 18, 4000.0, 0.86);
+        assert!((value - 5160.0).abs() < 1e-9);
+    }
 }
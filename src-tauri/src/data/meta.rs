@@ -1,8 +1,639 @@
-use graphql_client::{GraphQLQuery, Response}; // cargo add graphql-client reqwest
-#[derive(GraphQLQuery)]
-#[graphql(schema_path = "schema.graphql", query_path = "query.gql")]
-struct NftMetadataQuery; // e.g., query { nfts { metadataURI } }
-let query = NftMetadataQuery::build_query(...);
-let resp = reqwest::post("https://api.thegraph.com/subgraphs/name/uniswap/uniswap-v3")
-    .json(&query).send().await?;
-let uri = resp.data.nfts[0].metadata_uri; // 拉 IPFS/Arweave
\ No newline at end of file
+// NFT 元数据解析：ERC-721 tokenURI / ERC-1155 uri（{id} 替换），
+// ipfs:// / ar:// / data: / http(s) 通过可配置网关读取，按标准元数据格式解析。
+// JSON 与图片按内容哈希存到 app 数据目录，离线时图库仍可展示
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::error::AppError;
+use crate::evm::assets::AssetsType;
+use crate::rpc::method::eth_call;
+use crate::utils::time::now_s;
+use alloy_primitives::{Address, B256, U256, keccak256};
+use alloy_sol_types::{SolCall, sol};
+use base64::Engine;
+use bincode::{Decode, Encode};
+use reqwest::Client;
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{Manager, State};
+
+/// 默认网关，按顺序尝试
+pub const DEFAULT_IPFS_GATEWAYS: &[&str] = &["https://ipfs.io/ipfs/", "https://dweb.link/ipfs/"];
+pub const DEFAULT_ARWEAVE_GATEWAYS: &[&str] = &["https://arweave.net/", "https://ar-io.net/"];
+/// 单个文件的大小上限
+pub const MAX_CONTENT_BYTES: usize = 20 * 1024 * 1024;
+/// http(s) 内容可能变化，超过该时长后重新拉取；ipfs / ar 内容不可变
+pub const HTTP_CONTENT_TTL_SECS: u64 = 24 * 3600;
+/// 缓存目录（app 数据目录下）
+pub const CACHE_DIR: &str = "nftcache";
+
+const GATEWAYS_KEY: &str = "meta_gateways";
+const FETCH_TIMEOUT_SECS: u64 = 20;
+
+sol! {
+    interface IERC721Metadata {
+        function tokenURI(uint256 tokenId) external view returns (string memory);
+    }
+
+    interface IERC1155MetadataURI {
+        function uri(uint256 id) external view returns (string memory);
+    }
+}
+
+// ========== 网关 ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct GatewayConfig {
+    pub ipfs: Vec<String>,
+    pub arweave: Vec<String>,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            ipfs: DEFAULT_IPFS_GATEWAYS.iter().map(|g| g.to_string()).collect(),
+            arweave: DEFAULT_ARWEAVE_GATEWAYS.iter().map(|g| g.to_string()).collect(),
+        }
+    }
+}
+
+impl GatewayConfig {
+    pub fn load(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Self> {
+        let mgr = TableManager::new(db, TableKind::Registry)?;
+        Ok(mgr.get(&mgr.key_from_str(GATEWAYS_KEY))?.unwrap_or_default())
+    }
+
+    /// 把 URI 展开为可请求的 http(s) 地址，按网关优先级排列；data: 原样返回
+    pub fn candidates(&self, uri: &str) -> Vec<String> {
+        let uri = uri.trim();
+        let join = |gateways: &[String], path: &str| -> Vec<String> {
+            gateways
+                .iter()
+                .map(|g| format!("{}/{}", g.trim_end_matches('/'), path))
+                .collect()
+        };
+        if let Some(path) = ipfs_path(uri) {
+            return join(&self.ipfs, &path);
+        }
+        if let Some(path) = uri.strip_prefix("ar://") {
+            return join(&self.arweave, path);
+        }
+        if uri.starts_with("https://") || uri.starts_with("http://") || uri.starts_with("data:") {
+            return vec![uri.to_string()];
+        }
+        Vec::new()
+    }
+
+    /// 首选地址，供前端直接加载
+    pub fn http_url(&self, uri: &str) -> Option<String> {
+        self.candidates(uri).into_iter().next()
+    }
+}
+
+/// CIDv0（Qm 开头的 base58，46 位）或 CIDv1（b 开头的小写 base32）
+fn is_cid(segment: &str) -> bool {
+    let v0 = segment.len() == 46
+        && segment.starts_with("Qm")
+        && segment.chars().all(|c| c.is_ascii_alphanumeric() && !"0OIl".contains(c));
+    let v1 = segment.len() >= 50
+        && segment.starts_with('b')
+        && segment.chars().all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c));
+    v0 || v1
+}
+
+/// ipfs://<cid>/<path>、ipfs://ipfs/<cid>，以及写死在 http 网关上、路径以 /ipfs/<cid> 开头的地址；
+/// http 地址只有 cid 合法时才当作 IPFS（不可变）内容，否则按普通 http 处理
+fn ipfs_path(uri: &str) -> Option<String> {
+    if let Some(path) = uri.strip_prefix("ipfs://") {
+        return Some(path.trim_start_matches("ipfs/").to_string());
+    }
+    if !(uri.starts_with("https://") || uri.starts_with("http://")) {
+        return None;
+    }
+    let url = url::Url::parse(uri).ok()?;
+    let path = url.path().strip_prefix("/ipfs/")?;
+    if !is_cid(path.split('/').next()?) {
+        return None;
+    }
+    Some(match url.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    })
+}
+
+/// 默认网关下的可加载地址（ENS 头像、代币 logo 等只需要一个地址的场景）。
+/// data: 只放行图片，避免把任意内容交给前端渲染
+pub fn gateway_url(uri: &str) -> Option<String> {
+    let uri = uri.trim();
+    if uri.starts_with("data:") && !uri.starts_with("data:image/") {
+        return None;
+    }
+    GatewayConfig::default().http_url(uri)
+}
+
+/// ERC-1155 {id}：64 位小写十六进制，无 0x
+pub fn substitute_id(uri: &str, token_id: U256) -> String {
+    uri.replace("{id}", &format!("{:064x}", token_id))
+}
+
+// ========== data: URI ==========
+
+fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+/// data:[<mime>][;base64],<data> → (mime, bytes)
+pub fn decode_data_uri(uri: &str) -> Option<(String, Vec<u8>)> {
+    let rest = uri.strip_prefix("data:")?;
+    let (header, data) = rest.split_once(',')?;
+    let is_base64 = header.ends_with(";base64");
+    let mime = header.split(';').next().filter(|m| !m.is_empty()).unwrap_or("text/plain");
+    let bytes = if is_base64 {
+        base64::engine::general_purpose::STANDARD.decode(data.trim()).ok()?
+    } else {
+        percent_decode(data)
+    };
+    Some((mime.to_string(), bytes))
+}
+
+// ========== 元数据格式 ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct NftAttribute {
+    pub trait_type: Option<String>,
+    pub value: String,
+    pub display_type: Option<String>,
+}
+
+/// ERC-721 / ERC-1155 元数据（含 OpenSea 扩展字段）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct NftMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// image / image_url，或由 image_data（内联 SVG）转成的 data: URI
+    pub image: Option<String>,
+    pub animation_url: Option<String>,
+    pub external_url: Option<String>,
+    pub background_color: Option<String>,
+    pub attributes: Vec<NftAttribute>,
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+impl NftMetadata {
+    /// 宽松解析：字段类型不对时忽略该字段而不是整体失败
+    pub fn from_value(value: &Value) -> Self {
+        let text = |key: &str| value[key].as_str().map(str::to_string).filter(|s| !s.is_empty());
+        let image = text("image").or_else(|| text("image_url")).or_else(|| {
+            text("image_data").map(|svg| {
+                format!("data:image/svg+xml;base64,{}", base64::engine::general_purpose::STANDARD.encode(svg))
+            })
+        });
+        // 个别合约用 properties 代替 attributes
+        let attributes = value["attributes"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|a| {
+                        Some(NftAttribute {
+                            trait_type: a["trait_type"].as_str().map(str::to_string),
+                            value: value_to_string(&a["value"])?,
+                            display_type: a["display_type"].as_str().map(str::to_string),
+                        })
+                    })
+                    .collect()
+            })
+            .or_else(|| {
+                value["properties"].as_object().map(|props| {
+                    props
+                        .iter()
+                        .filter_map(|(k, v)| {
+                            Some(NftAttribute { trait_type: Some(k.clone()), value: value_to_string(v)?, display_type: None })
+                        })
+                        .collect()
+                })
+            })
+            .unwrap_or_default();
+        Self {
+            name: text("name"),
+            description: text("description"),
+            image,
+            animation_url: text("animation_url"),
+            external_url: text("external_url"),
+            background_color: text("background_color"),
+            attributes,
+        }
+    }
+}
+
+// ========== 内容寻址缓存 ==========
+
+/// 文件按 keccak256(内容) 命名存在 dir 下；URI → 哈希的索引存 Cache 表
+pub struct ContentStore {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct CachedContent {
+    pub hash: B256,
+    pub content_type: String,
+    pub fetched_at: u64,
+    /// ipfs / ar / data: 内容不可变，永不过期
+    pub immutable: bool,
+}
+
+impl ContentStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, AppError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(AppError::Io)?;
+        Ok(Self { dir })
+    }
+
+    pub fn open(app_handle: &tauri::AppHandle) -> Result<Self, AppError> {
+        let app_dir = app_handle.path().app_data_dir().map_err(|e| {
+            AppError::Io(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to get app data directory: {}", e),
+            ))
+        })?;
+        Self::new(app_dir.join(CACHE_DIR))
+    }
+
+    pub fn path_of(&self, hash: &B256) -> PathBuf {
+        self.dir.join(hex::encode(hash))
+    }
+
+    pub fn put(&self, bytes: &[u8]) -> Result<B256, AppError> {
+        let hash = keccak256(bytes);
+        let path = self.path_of(&hash);
+        if !path.exists() {
+            // 先写临时文件再改名，避免中断后留下半个文件
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, bytes).map_err(AppError::Io)?;
+            std::fs::rename(&tmp, &path).map_err(AppError::Io)?;
+        }
+        Ok(hash)
+    }
+
+    pub fn get(&self, hash: &B256) -> Option<Vec<u8>> {
+        std::fs::read(self.path_of(hash)).ok()
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+fn is_immutable(uri: &str) -> bool {
+    uri.starts_with("data:") || uri.starts_with("ar://") || ipfs_path(uri).is_some()
+}
+
+fn index_key(mgr: &TableManager, uri: &str) -> Vec<u8> {
+    mgr.key_from_str(&format!("meta:uri:{uri}"))
+}
+
+/// 先看 Content-Length，再分块读取，超过 MAX_CONTENT_BYTES 立即中止，不把整个响应读进内存
+async fn fetch_http(client: &Client, url: &str) -> Result<(String, Vec<u8>), AppError> {
+    let mut response = client
+        .get(url)
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(AppError::ReqwestClientConnectionError)?
+        .error_for_status()
+        .map_err(AppError::ReqwestClientConnectionError)?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or(v).trim().to_string())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    if response.content_length().is_some_and(|len| len > MAX_CONTENT_BYTES as u64) {
+        return Err(AppError::Parse("content too large"));
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(AppError::ReqwestClientConnectionError)? {
+        if bytes.len() + chunk.len() > MAX_CONTENT_BYTES {
+            return Err(AppError::Parse("content too large"));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok((content_type, bytes))
+}
+
+/// 读取 URI 内容：先查缓存，未命中或过期时按网关顺序拉取；全部失败时退回过期缓存
+pub async fn fetch_content(
+    client: &Client,
+    db: &DBWithThreadMode<MultiThreaded>,
+    store: &ContentStore,
+    gateways: &GatewayConfig,
+    uri: &str,
+    force: bool,
+) -> Result<(CachedContent, Vec<u8>), AppError> {
+    let mgr = TableManager::new(db, TableKind::Cache)?;
+    let key = index_key(&mgr, uri);
+    let now = now_s();
+    let cached = mgr
+        .get::<CachedContent>(&key)?
+        .and_then(|c| store.get(&c.hash).map(|bytes| (c, bytes)));
+    if let Some((entry, bytes)) = &cached {
+        if !force && (entry.immutable || now.saturating_sub(entry.fetched_at) < HTTP_CONTENT_TTL_SECS) {
+            return Ok((entry.clone(), bytes.clone()));
+        }
+    }
+
+    let fetched = if uri.starts_with("data:") {
+        decode_data_uri(uri).ok_or(AppError::Parse("invalid data uri"))
+    } else {
+        let candidates = gateways.candidates(uri);
+        let mut result = Err(AppError::Parse("unsupported uri scheme"));
+        for url in candidates {
+            result = fetch_http(client, &url).await;
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    };
+
+    match fetched {
+        Ok((content_type, bytes)) => {
+            let hash = store.put(&bytes)?;
+            let entry = CachedContent { hash, content_type, fetched_at: now, immutable: is_immutable(uri) };
+            mgr.set(&key, &entry)?;
+            Ok((entry, bytes))
+        }
+        Err(e) => cached.ok_or(e),
+    }
+}
+
+// ========== tokenURI ==========
+
+async fn call_string<C: SolCall<Return = String>>(
+    client: &Client,
+    rpc_url: &str,
+    contract: &Address,
+    call: &C,
+) -> Result<Option<String>, AppError> {
+    let data = format!("0x{}", hex::encode(call.abi_encode()));
+    let raw = match eth_call(client.clone(), rpc_url, &format!("{:#x}", contract), &data, "latest").await {
+        Ok(raw) => raw,
+        Err(AppError::RpcReverted(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let raw = hex::decode(raw.as_str().unwrap_or("0x").trim_start_matches("0x"))?;
+    Ok(C::abi_decode_returns(&raw).ok().filter(|s| !s.is_empty()))
+}
+
+/// 按标准读取元数据 URI；未知标准时先试 ERC-721 再试 ERC-1155
+pub async fn token_uri(
+    client: &Client,
+    rpc_url: &str,
+    contract: &Address,
+    token_id: U256,
+    standard: Option<&AssetsType>,
+) -> Result<Option<String>, AppError> {
+    let erc721 = IERC721Metadata::tokenURICall { tokenId: token_id };
+    let erc1155 = IERC1155MetadataURI::uriCall { id: token_id };
+    match standard {
+        Some(AssetsType::ERC721) => call_string(client, rpc_url, contract, &erc721).await,
+        Some(AssetsType::ERC1155) => Ok(call_string(client, rpc_url, contract, &erc1155)
+            .await?
+            .map(|uri| substitute_id(&uri, token_id))),
+        _ => match call_string(client, rpc_url, contract, &erc721).await? {
+            Some(uri) => Ok(Some(uri)),
+            None => Ok(call_string(client, rpc_url, contract, &erc1155)
+                .await?
+                .map(|uri| substitute_id(&uri, token_id))),
+        },
+    }
+}
+
+/// 只按 URI 读取并解析元数据（不缓存），供 ENS 头像等一次性场景使用
+pub async fn fetch_metadata(client: &Client, gateways: &GatewayConfig, uri: &str) -> Option<NftMetadata> {
+    let bytes = match decode_data_uri(uri) {
+        Some((_, bytes)) => bytes,
+        None => {
+            let mut found = None;
+            for url in gateways.candidates(uri) {
+                if let Ok((_, bytes)) = fetch_http(client, &url).await {
+                    found = Some(bytes);
+                    break;
+                }
+            }
+            found?
+        }
+    };
+    serde_json::from_slice::<Value>(&bytes).ok().map(|v| NftMetadata::from_value(&v))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct ResolvedNft {
+    pub chain_id: u64,
+    pub contract: Address,
+    pub token_id: U256,
+    pub token_uri: String,
+    pub metadata: NftMetadata,
+    /// 本地缓存的图片文件（前端经 convertFileSrc 走 asset 协议加载，作用域限定在 nftcache 目录）
+    pub image_path: Option<String>,
+    pub image_content_type: Option<String>,
+    pub resolved_at: u64,
+}
+
+fn nft_key(mgr: &TableManager, chain_id: u64, contract: &Address, token_id: U256) -> Vec<u8> {
+    mgr.key_from_str(&format!("meta:nft:{chain_id}:{:#x}:{token_id}", contract))
+}
+
+/// 解析并缓存一个 NFT；结果超过 HTTP_CONTENT_TTL_SECS 后重新读取 tokenURI（可能揭示或更新），
+/// 不可变内容仍命中内容缓存。RPC 或网关不可用时返回上次的结果
+pub async fn resolve_nft(
+    client: &Client,
+    db: &DBWithThreadMode<MultiThreaded>,
+    store: &ContentStore,
+    gateways: &GatewayConfig,
+    rpc_url: &str,
+    chain_id: u64,
+    contract: Address,
+    token_id: U256,
+    standard: Option<&AssetsType>,
+    force: bool,
+) -> Result<ResolvedNft, AppError> {
+    let mgr = TableManager::new(db, TableKind::Cache)?;
+    let key = nft_key(&mgr, chain_id, &contract, token_id);
+    let previous = mgr.get::<ResolvedNft>(&key)?;
+    if let Some(prev) = &previous {
+        let fresh = now_s().saturating_sub(prev.resolved_at) < HTTP_CONTENT_TTL_SECS;
+        let on_disk = prev.image_path.as_deref().is_some_and(|p| Path::new(p).exists());
+        if !force && fresh && on_disk {
+            return Ok(prev.clone());
+        }
+    }
+
+    let resolved = async {
+        let uri = token_uri(client, rpc_url, &contract, token_id, standard)
+            .await?
+            .ok_or(AppError::Parse("token has no metadata uri"))?;
+        let (_, json) = fetch_content(client, db, store, gateways, &uri, force).await?;
+        let value: Value = serde_json::from_slice(&json).map_err(AppError::JsonParseError)?;
+        let metadata = NftMetadata::from_value(&value);
+        let image = match metadata.image.as_deref() {
+            Some(image) => fetch_content(client, db, store, gateways, image, force).await.ok(),
+            None => None,
+        };
+        Ok::<_, AppError>(ResolvedNft {
+            chain_id,
+            contract,
+            token_id,
+            token_uri: uri,
+            metadata,
+            image_path: image.as_ref().map(|(c, _)| store.path_of(&c.hash).to_string_lossy().to_string()),
+            image_content_type: image.map(|(c, _)| c.content_type),
+            resolved_at: now_s(),
+        })
+    }
+    .await;
+
+    match resolved {
+        Ok(nft) => {
+            mgr.set(&key, &nft)?;
+            Ok(nft)
+        }
+        Err(e) => previous.ok_or(e),
+    }
+}
+
+// ========== 命令 ==========
+
+#[tauri::command]
+pub async fn nft_metadata(
+    chain_id: u64,
+    contract: String,
+    token_id: String,
+    rpc_url: String,
+    standard: Option<AssetsType>,
+    force: Option<bool>,
+    app_handle: tauri::AppHandle,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<ResolvedNft, AppError> {
+    let contract = contract
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let token_id = token_id
+        .parse::<U256>()
+        .map_err(|_| AppError::Parse("invalid token id"))?;
+    let db = appdb.db.as_ref();
+    let store = ContentStore::open(&app_handle)?;
+    let gateways = GatewayConfig::load(db)?;
+    let client = state.https_client.lock().await.clone();
+    resolve_nft(&client, db, &store, &gateways, &rpc_url, chain_id, contract, token_id, standard.as_ref(), force.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub fn nft_gateways_get(appdb: State<AppDB>) -> Result<GatewayConfig, AppError> {
+    Ok(GatewayConfig::load(appdb.db.as_ref())?)
+}
+
+#[tauri::command]
+pub fn nft_gateways_set(gateways: GatewayConfig, appdb: State<AppDB>) -> Result<(), AppError> {
+    let valid = |list: &[String]| !list.is_empty() && list.iter().all(|g| g.starts_with("https://") || g.starts_with("http://"));
+    if !valid(&gateways.ipfs) || !valid(&gateways.arweave) {
+        return Err(AppError::Parse("gateways must be non-empty http(s) urls"));
+    }
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    mgr.set(&mgr.key_from_str(GATEWAYS_KEY), &gateways)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_gateway_candidates() {
+        let gateways = GatewayConfig::default();
+        assert_eq!(
+            gateways.candidates("ipfs://QmHash/1.json"),
+            vec!["https://ipfs.io/ipfs/QmHash/1.json", "https://dweb.link/ipfs/QmHash/1.json"]
+        );
+        assert_eq!(gateways.http_url("ipfs://ipfs/QmHash").unwrap(), "https://ipfs.io/ipfs/QmHash");
+        // 写死的网关地址也改用配置的网关
+        let cid = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+        assert_eq!(
+            gateways.http_url(&format!("https://gateway.pinata.cloud/ipfs/{cid}/1.png")).unwrap(),
+            format!("https://ipfs.io/ipfs/{cid}/1.png")
+        );
+        // 路径里碰巧带 /ipfs/、或 cid 不合法的 http 地址不算 IPFS，也不当作不可变
+        let fake = "https://api.example.com/meta?u=/ipfs/QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+        assert_eq!(gateways.http_url(fake).unwrap(), fake);
+        assert!(!is_immutable("https://api.example.com/ipfs/latest.json"));
+        assert!(is_immutable(&format!("https://cloudflare-ipfs.com/ipfs/{cid}")));
+        assert_eq!(gateways.http_url("ar://tx123").unwrap(), "https://arweave.net/tx123");
+        assert!(gateways.candidates("javascript:alert(1)").is_empty());
+
+        assert!(gateway_url("data:image/png;base64,AA==").is_some());
+        assert!(gateway_url("data:text/html,<script>").is_none());
+        assert_eq!(
+            substitute_id("https://x.io/{id}.json", U256::from(0x4cce)),
+            "https://x.io/0000000000000000000000000000000000000000000000000000000000004cce.json"
+        );
+    }
+
+    #[test]
+    fn test_data_uri_and_metadata() {
+        let (mime, bytes) = decode_data_uri("data:application/json;base64,eyJuYW1lIjoiQSJ9").unwrap();
+        assert_eq!((mime.as_str(), bytes.as_slice()), ("application/json", br#"{"name":"A"}"#.as_slice()));
+        let (_, bytes) = decode_data_uri("data:application/json,%7B%22name%22%3A%22B%22%7D").unwrap();
+        assert_eq!(bytes, br#"{"name":"B"}"#);
+
+        let meta = NftMetadata::from_value(&json!({
+            "name": "Punk",
+            "image_data": "<svg/>",
+            "attributes": [{"trait_type": "Level", "value": 5, "display_type": "number"}, {"value": {"nested": true}}]
+        }));
+        assert_eq!(meta.image.unwrap(), "data:image/svg+xml;base64,PHN2Zy8+");
+        assert_eq!(meta.attributes.len(), 1);
+        assert_eq!(meta.attributes[0].value, "5");
+
+        let props = NftMetadata::from_value(&json!({"properties": {"color": "red"}}));
+        assert_eq!(props.attributes[0].trait_type.as_deref(), Some("color"));
+    }
+
+    #[test]
+    fn test_content_store_is_content_addressed() {
+        let dir = std::env::temp_dir().join(format!("nftcache-test-{}", std::process::id()));
+        let store = ContentStore::new(&dir).unwrap();
+        let a = store.put(b"hello").unwrap();
+        let b = store.put(b"hello").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, keccak256(b"hello"));
+        assert_eq!(store.get(&a).unwrap(), b"hello");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -5,5 +5,14 @@ pub mod forex;
 pub mod currency;
 pub mod ens;
 pub mod contract;
+pub mod abi;
+pub mod erc7730;
 pub mod nft;
 pub mod token;
+pub mod export;
+pub mod poison;
+pub mod price;
+pub mod tokenlist;
+pub mod balance;
+pub mod spam;
+pub mod meta;
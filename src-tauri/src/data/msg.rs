@@ -1,11 +1,68 @@
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use bincode::{Decode, Encode};
-use crate::core::db::{AppDB, TableKind, TableManager};
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded, WriteBatch};
+use alloy_primitives::{Address, B256, Bytes, Signature, keccak256};
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::eips::eip191::hash_eip191_bytes;
+use crate::eips::eip712::EIP712;
+use crate::utils::time::now_s;
+// 引入自定义错误类型
+use crate::error::AppError;
 
+// 链头指针存在 config 表，防止尾部被截断而不被发现
+const MSG_HISTORY_HEAD_KEY: &str = "msg_history_head";
+const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Encode, Decode, PartialEq)]
+pub enum MessageKind {
+    PersonalSign, // EIP-191
+    TypedData,    // EIP-712
+}
+
+/// 前端提交的签名记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewMessageEntry {
+    pub chain_id: u64,
+    pub kind: MessageKind,
+    pub signer: Address,
+    pub origin: Option<String>,
+    pub payload: String,   // 191: 原文或 0x hex；712: 完整 typed data JSON
+    pub signature: Bytes,
+}
 
-#[derive(Debug, Serialize, Deserialize, Clone,Encode, Decode, PartialEq)]
+/// 哈希链上的一条签名记录
+#[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, PartialEq)]
 pub struct MessageHistoryEntry {
+    pub seq: u64,
+    pub chain_id: u64,
+    pub kind: MessageKind,
+    pub signer: Address,
+    pub origin: Option<String>,
+    pub domain: Option<String>, // 712 domain（JSON），191 为空
+    pub payload: String,
+    pub msg_hash: B256,
+    pub signature: Bytes,
+    pub timestamp: u64,
+    pub prev_hash: B256,
+    pub entry_hash: B256,
+    pub redacted: bool,
+    /// 删除内容前记下的各字段承诺，删除后仍能重算 entry_hash
+    pub redacted_commitments: Option<FieldCommitments>,
+}
+
+/// 可删除字段各自的 keccak256，entry_hash 覆盖的是这些承诺而不是原文
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Encode, Decode, PartialEq)]
+pub struct FieldCommitments {
+    pub origin: B256,
+    pub domain: B256,
+    pub payload: B256,
+    pub signature: B256,
+}
+
+/// 旧版记录（以 JSON 字符串存储），仅用于迁移
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LegacyMessageHistoryEntry {
     pub chain_id: u64,
     pub msg_type: String, // "191" | "712"
     pub signer: String,
@@ -15,34 +72,285 @@ pub struct MessageHistoryEntry {
     pub timestamp: u64,
     pub status: Option<String>,
 }
-// 引入自定义错误类型
-use crate::error::AppError;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Encode, Decode, PartialEq)]
+pub struct ChainHead {
+    pub seq: u64,
+    pub entry_hash: B256,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChainReport {
+    pub ok: bool,
+    pub entries: u64,
+    pub broken_links: Vec<u64>,    // prev_hash 对不上（中间被删或插入）
+    pub tampered: Vec<u64>,        // 内容被改（entry_hash 重算不一致）
+    pub redacted: Vec<u64>,        // 用户主动删除的记录（按字段承诺校验）
+    pub head_mismatch: bool,       // 链尾被截断或替换
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SignatureCheck {
+    pub seq: u64,
+    pub recovered: Option<Address>,
+    pub signer_matches: bool,
+    pub hash_matches: bool, // 由 payload 重新计算的 msg_hash 是否一致
+}
+
+/// 按消息类型计算签名摘要
+pub fn message_digest(kind: MessageKind, payload: &str) -> Result<B256, AppError> {
+    match kind {
+        MessageKind::PersonalSign => {
+            let bytes = match payload.strip_prefix("0x").map(hex::decode) {
+                Some(Ok(raw)) => raw,
+                _ => payload.as_bytes().to_vec(),
+            };
+            Ok(hash_eip191_bytes(&bytes))
+        }
+        MessageKind::TypedData => EIP712::hash_eip712_message(payload),
+    }
+}
+
+/// 由记录当前内容计算字段承诺
+pub fn field_commitments(entry: &MessageHistoryEntry) -> FieldCommitments {
+    FieldCommitments {
+        origin: keccak256(entry.origin.as_deref().unwrap_or_default()),
+        domain: keccak256(entry.domain.as_deref().unwrap_or_default()),
+        payload: keccak256(entry.payload.as_bytes()),
+        signature: keccak256(&entry.signature),
+    }
+}
+
+/// 链上每一环的哈希：覆盖除 entry_hash / redacted 外的所有字段，可删除字段以承诺参与。
+/// 已删除的记录用删除时保存的承诺计算，缺失时按全零承诺计算（必然不符，记为篡改）
+pub fn compute_entry_hash(entry: &MessageHistoryEntry) -> B256 {
+    let commitments = if entry.redacted {
+        entry.redacted_commitments.unwrap_or_default()
+    } else {
+        field_commitments(entry)
+    };
+    let mut buf = Vec::with_capacity(256);
+    buf.extend_from_slice(entry.prev_hash.as_slice());
+    buf.extend_from_slice(&entry.seq.to_be_bytes());
+    buf.extend_from_slice(&entry.chain_id.to_be_bytes());
+    buf.push(entry.kind as u8);
+    buf.extend_from_slice(entry.signer.as_slice());
+    buf.extend_from_slice(commitments.origin.as_slice());
+    buf.extend_from_slice(commitments.domain.as_slice());
+    buf.extend_from_slice(commitments.payload.as_slice());
+    buf.extend_from_slice(entry.msg_hash.as_slice());
+    buf.extend_from_slice(commitments.signature.as_slice());
+    buf.extend_from_slice(&entry.timestamp.to_be_bytes());
+    keccak256(&buf)
+}
+
+/// 从签名中恢复签名者，并与记录的 signer / msg_hash 比对
+pub fn check_signature(entry: &MessageHistoryEntry) -> SignatureCheck {
+    let hash_matches = !entry.redacted
+        && message_digest(entry.kind, &entry.payload).is_ok_and(|h| h == entry.msg_hash);
+    let recovered = Signature::from_raw(&entry.signature)
+        .ok()
+        .and_then(|sig| sig.recover_address_from_prehash(&entry.msg_hash).ok());
+    SignatureCheck {
+        seq: entry.seq,
+        recovered,
+        signer_matches: recovered == Some(entry.signer),
+        hash_matches,
+    }
+}
+
+/// 按 seq 顺序校验整条链
+pub fn verify_chain(entries: &[MessageHistoryEntry], head: Option<ChainHead>) -> ChainReport {
+    let mut report = ChainReport {
+        ok: true,
+        entries: entries.len() as u64,
+        broken_links: Vec::new(),
+        tampered: Vec::new(),
+        redacted: Vec::new(),
+        head_mismatch: false,
+    };
+
+    let mut prev = B256::ZERO;
+    let mut expected_seq = 0u64;
+    for entry in entries {
+        if entry.seq != expected_seq || entry.prev_hash != prev {
+            report.broken_links.push(entry.seq);
+        }
+        if entry.redacted {
+            report.redacted.push(entry.seq);
+        }
+        if compute_entry_hash(entry) != entry.entry_hash {
+            report.tampered.push(entry.seq);
+        }
+        prev = entry.entry_hash;
+        expected_seq = entry.seq + 1;
+    }
+
+    report.head_mismatch = match (head, entries.last()) {
+        (Some(h), Some(last)) => h.seq != last.seq || h.entry_hash != last.entry_hash,
+        (Some(_), None) => true,
+        (None, Some(_)) => true,
+        (None, None) => false,
+    };
+    report.ok = report.broken_links.is_empty() && report.tampered.is_empty() && !report.head_mismatch;
+    report
+}
+
+fn read_head(config: &TableManager) -> DbResult<Option<ChainHead>> {
+    config.get::<ChainHead>(&config.key_from_str(MSG_HISTORY_HEAD_KEY))
+}
+
+fn sorted_entries(mgr: &TableManager) -> DbResult<Vec<MessageHistoryEntry>> {
+    // key 为 seq 的大端字节，前缀迭代天然有序
+    mgr.list::<MessageHistoryEntry>()
+}
+
+/// 接在 head 之后的下一条记录（不写库）
+fn next_entry(head: Option<ChainHead>, new: NewMessageEntry, timestamp: u64) -> DbResult<MessageHistoryEntry> {
+    let msg_hash = message_digest(new.kind, &new.payload)?;
+    let domain = match new.kind {
+        MessageKind::TypedData => serde_json::from_str::<serde_json::Value>(&new.payload)?
+            .get("domain")
+            .map(|d| d.to_string()),
+        MessageKind::PersonalSign => None,
+    };
+
+    let mut entry = MessageHistoryEntry {
+        seq: head.map(|h| h.seq + 1).unwrap_or(0),
+        chain_id: new.chain_id,
+        kind: new.kind,
+        signer: new.signer,
+        origin: new.origin,
+        domain,
+        payload: new.payload,
+        msg_hash,
+        signature: new.signature,
+        timestamp,
+        prev_hash: head.map(|h| h.entry_hash).unwrap_or(B256::ZERO),
+        entry_hash: B256::ZERO,
+        redacted: false,
+        redacted_commitments: None,
+    };
+    entry.entry_hash = compute_entry_hash(&entry);
+    Ok(entry)
+}
+
+fn head_of(entry: &MessageHistoryEntry) -> ChainHead {
+    ChainHead { seq: entry.seq, entry_hash: entry.entry_hash }
+}
+
+/// 把新记录追加到链尾；记录与链头指针在同一个 WriteBatch 中提交
+pub fn append_entry(
+    db: &DBWithThreadMode<MultiThreaded>,
+    new: NewMessageEntry,
+    timestamp: u64,
+) -> DbResult<MessageHistoryEntry> {
+    let mgr = TableManager::new(db, TableKind::MsgHistory)?;
+    let config = TableManager::new(db, TableKind::Config)?;
+    let entry = next_entry(read_head(&config)?, new, timestamp)?;
+
+    let mut batch = WriteBatch::default();
+    mgr.batch_set(&mut batch, &mgr.key_from_u64(entry.seq), &entry)?;
+    config.batch_set(&mut batch, &config.key_from_str(MSG_HISTORY_HEAD_KEY), &head_of(&entry))?;
+    mgr.write(batch)?;
+    Ok(entry)
+}
+
+/// 旧版记录的迁移计划：moved 为 (旧 key, 新记录)，kept 为无法迁移、原样保留的旧 key
+#[derive(Debug, Default)]
+struct LegacyMigration {
+    moved: Vec<(Vec<u8>, MessageHistoryEntry)>,
+    kept: Vec<Vec<u8>>,
+    head: Option<ChainHead>,
+}
+
+/// 按时间排序后依次接到 head 之后；signer 无法解析的记录保留原样，不删除
+fn plan_legacy_migration(
+    mut legacy: Vec<(Vec<u8>, LegacyMessageHistoryEntry)>,
+    mut head: Option<ChainHead>,
+) -> DbResult<LegacyMigration> {
+    let mut plan = LegacyMigration::default();
+    legacy.sort_by_key(|(_, old)| old.timestamp);
+    for (key, old) in legacy {
+        let Ok(signer) = old.signer.parse::<Address>() else {
+            plan.kept.push(key);
+            continue;
+        };
+        let kind = if old.msg_type == "712" { MessageKind::TypedData } else { MessageKind::PersonalSign };
+        let signature = old
+            .signature
+            .as_deref()
+            .and_then(|s| hex::decode(s.trim_start_matches("0x")).ok())
+            .unwrap_or_default();
+        let new = NewMessageEntry {
+            chain_id: old.chain_id,
+            kind,
+            signer,
+            origin: None,
+            payload: old.payload.unwrap_or_default(),
+            signature: signature.into(),
+        };
+        let entry = next_entry(head, new, old.timestamp)?;
+        head = Some(head_of(&entry));
+        plan.moved.push((key, entry));
+    }
+    plan.head = head;
+    Ok(plan)
+}
+
+/// 旧版 JSON 记录 → 哈希链；
+/// 每条旧记录只有写入新记录后才删除，连同链头在同一个 WriteBatch 中提交，中途失败不会丢数据
+pub fn migrate_legacy(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<()> {
+    let mgr = TableManager::new(db, TableKind::MsgHistory)?;
+    // 旧版值是 bincode(String)，新版记录解码为 String 会失败，天然区分
+    let legacy: Vec<(Vec<u8>, LegacyMessageHistoryEntry)> = mgr
+        .list_with_keys::<String>()?
+        .into_iter()
+        .filter_map(|(key, json)| {
+            serde_json::from_str::<LegacyMessageHistoryEntry>(&json).ok().map(|old| (key, old))
+        })
+        .collect();
+    if legacy.is_empty() {
+        return Ok(());
+    }
+
+    let config = TableManager::new(db, TableKind::Config)?;
+    let plan = plan_legacy_migration(legacy, read_head(&config)?)?;
+    let mut batch = WriteBatch::default();
+    for (old_key, entry) in &plan.moved {
+        mgr.batch_remove(&mut batch, old_key);
+        mgr.batch_set(&mut batch, &mgr.key_from_u64(entry.seq), entry)?;
+    }
+    if let Some(head) = plan.head {
+        config.batch_set(&mut batch, &config.key_from_str(MSG_HISTORY_HEAD_KEY), &head)?;
+    }
+    mgr.write(batch)
+}
+
+// ========== Message History ==========
 #[tauri::command]
-pub fn message_add(
-    chain_id: u64,
-    hash: String,
-    entry: MessageHistoryEntry,
-    appdb: State<AppDB>,
-) -> Result<(), AppError> {
+pub fn message_add(entry: NewMessageEntry, appdb: State<AppDB>) -> Result<MessageHistoryEntry, AppError> {
     let db = appdb.db.as_ref();
-    let mgr = TableManager::new(db, TableKind::MsgHistory)?;
-    let mut key = mgr.key_from_u64(chain_id);
-    key.push(b':');
-    key.extend_from_slice(&hash.as_bytes());
-    let entry_str = serde_json::to_string(&entry).map_err(AppError::JsonParseError)?;
-    mgr.set(&key, &entry_str)
+    append_entry(db, entry, now_s())
 }
 
+/// 删除只抹掉内容，保留哈希，链仍可校验
 #[tauri::command]
-pub fn message_delete(chain_id: u64, hash: String, appdb: State<AppDB>) -> Result<(), AppError> {
+pub fn message_delete(seq: u64, appdb: State<AppDB>) -> Result<(), AppError> {
     let db = appdb.db.as_ref();
     let mgr = TableManager::new(db, TableKind::MsgHistory)?;
-    let mut key = Vec::new();
-    key.extend_from_slice(&chain_id.to_be_bytes());
-    key.push(b':');
-    key.extend_from_slice(&hash.as_bytes());
-    // 创建二进制 key
-    mgr.delete(&key)
+    let key = mgr.key_from_u64(seq);
+    let mut entry = mgr.get::<MessageHistoryEntry>(&key)?.ok_or(AppError::DbKeyNotFound)?;
+    if entry.redacted {
+        return Ok(());
+    }
+    entry.redacted_commitments = Some(field_commitments(&entry));
+    entry.payload = REDACTED.to_string();
+    entry.domain = None;
+    entry.origin = None;
+    entry.signature = Bytes::new();
+    entry.redacted = true;
+    mgr.set(&key, &entry)
 }
 
 #[tauri::command]
@@ -52,25 +360,155 @@ pub fn message_list(
 ) -> Result<Vec<MessageHistoryEntry>, AppError> {
     let db = appdb.db.as_ref();
     let mgr = TableManager::new(db, TableKind::MsgHistory)?;
-    let list = mgr.list::<String>()?; // 存储的是JSON字符串
-    let mut result = Vec::new();
-    for item_str in list {
-        match serde_json::from_str::<MessageHistoryEntry>(&item_str) {
-            Ok(item) => {
-                if let Some(ref c) = chain_id {
-                    if item.chain_id == *c {
-                        result.push(item);
-                    }
-                } else {
-                    result.push(item);
-                }
-            }
-            Err(e) => {
-                // 跳过无法解析的项，记录错误但不中断
-                eprintln!("Failed to parse message history entry: {}", e);
-                continue;
-            }
-        }
+    let list = sorted_entries(&mgr)?;
+    Ok(match chain_id {
+        Some(c) => list.into_iter().filter(|e| e.chain_id == c).collect(),
+        None => list,
+    })
+}
+
+/// 校验整条哈希链是否被删改
+#[tauri::command]
+pub fn message_verify_chain(appdb: State<AppDB>) -> Result<ChainReport, AppError> {
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::MsgHistory)?;
+    let config = TableManager::new(db, TableKind::Config)?;
+    Ok(verify_chain(&sorted_entries(&mgr)?, read_head(&config)?))
+}
+
+/// 重新恢复签名者并比对
+#[tauri::command]
+pub fn message_reverify(seq: u64, appdb: State<AppDB>) -> Result<SignatureCheck, AppError> {
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::MsgHistory)?;
+    let entry = mgr
+        .get::<MessageHistoryEntry>(&mgr.key_from_u64(seq))?
+        .ok_or(AppError::DbKeyNotFound)?;
+    Ok(check_signature(&entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(n: u64) -> Vec<MessageHistoryEntry> {
+        let mut prev = B256::ZERO;
+        (0..n)
+            .map(|seq| {
+                let payload = format!("hello {seq}");
+                let mut e = MessageHistoryEntry {
+                    seq,
+                    chain_id: 1,
+                    kind: MessageKind::PersonalSign,
+                    signer: Address::ZERO,
+                    origin: Some("https://app.example".into()),
+                    domain: None,
+                    msg_hash: message_digest(MessageKind::PersonalSign, &payload).unwrap(),
+                    payload,
+                    signature: Bytes::new(),
+                    timestamp: 1_700_000_000 + seq,
+                    prev_hash: prev,
+                    entry_hash: B256::ZERO,
+                    redacted: false,
+                    redacted_commitments: None,
+                };
+                e.entry_hash = compute_entry_hash(&e);
+                prev = e.entry_hash;
+                e
+            })
+            .collect()
+    }
+
+    fn chain_head(entries: &[MessageHistoryEntry]) -> Option<ChainHead> {
+        entries.last().map(head_of)
+    }
+
+    fn redact(entry: &mut MessageHistoryEntry) {
+        entry.redacted_commitments = Some(field_commitments(entry));
+        entry.payload = REDACTED.to_string();
+        entry.origin = None;
+        entry.signature = Bytes::new();
+        entry.redacted = true;
+    }
+
+    #[test]
+    fn test_verify_chain_detects_edits_and_deletions() {
+        let entries = chain(4);
+        let head = chain_head(&entries);
+        assert!(verify_chain(&entries, head).ok);
+
+        let mut edited = entries.clone();
+        edited[1].payload = "evil".into();
+        let report = verify_chain(&edited, head);
+        assert_eq!(report.tampered, vec![1]);
+        assert!(!report.ok);
+
+        let mut deleted = entries.clone();
+        deleted.remove(2);
+        assert_eq!(verify_chain(&deleted, head).broken_links, vec![3]);
+
+        let truncated = entries[..3].to_vec();
+        assert!(verify_chain(&truncated, head).head_mismatch);
+    }
+
+    #[test]
+    fn test_redacted_entries_still_verify() {
+        let mut entries = chain(3);
+        let head = chain_head(&entries);
+        redact(&mut entries[1]);
+        let report = verify_chain(&entries, head);
+        assert!(report.ok);
+        assert_eq!(report.redacted, vec![1]);
+
+        // 标记为已删除不能掩盖对其余字段的修改
+        let mut forged = entries.clone();
+        forged[1].timestamp += 1;
+        assert_eq!(verify_chain(&forged, head).tampered, vec![1]);
+
+        // 没有承诺却声称已删除
+        let mut forged = chain(3);
+        forged[2].redacted = true;
+        assert_eq!(verify_chain(&forged, head).tampered, vec![2]);
+    }
+
+    #[test]
+    fn test_legacy_migration_keeps_unparsable_rows() {
+        let legacy = |signer: &str, timestamp| LegacyMessageHistoryEntry {
+            chain_id: 1,
+            msg_type: "191".into(),
+            signer: signer.into(),
+            msg_hash: String::new(),
+            payload: Some("hello".into()),
+            signature: Some("0x1234".into()),
+            timestamp,
+            status: None,
+        };
+        let rows = vec![
+            (b"old:b".to_vec(), legacy("not-an-address", 2)),
+            (b"old:a".to_vec(), legacy("0x1111111111111111111111111111111111111111", 1)),
+        ];
+        let plan = plan_legacy_migration(rows, None).unwrap();
+        // 坏记录留在原 key 上，不进入删除列表
+        assert_eq!(plan.kept, vec![b"old:b".to_vec()]);
+        assert_eq!(plan.moved.len(), 1);
+        assert_eq!(plan.moved[0].0, b"old:a".to_vec());
+        assert_eq!(plan.head, chain_head(&[plan.moved[0].1.clone()]));
+
+        // 无法生成新记录（typed data 不是 JSON）时整体失败，什么都不写
+        let mut bad = legacy("0x1111111111111111111111111111111111111111", 3);
+        bad.msg_type = "712".into();
+        assert!(plan_legacy_migration(vec![(b"old:c".to_vec(), bad)], None).is_err());
+    }
+
+    #[test]
+    fn test_personal_sign_digest() {
+        // "hello" 的 EIP-191 摘要
+        let digest = message_digest(MessageKind::PersonalSign, "hello").unwrap();
+        assert_eq!(
+            digest.to_string(),
+            "0x50b2c43fd39106bafbba0da34fc430e1f91e3c96ea2acee2bc34119f92b37750"
+        );
+        // hex 形式按原始字节计算
+        assert_eq!(message_digest(MessageKind::PersonalSign, "0x68656c6c6f").unwrap(), digest);
     }
-    Ok(result)
 }
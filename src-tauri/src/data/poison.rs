@@ -0,0 +1,364 @@
+// 地址投毒检测：
+// 攻击者用前后缀相同的靓号地址向用户转入 0 额 / 粉尘，
+// 期望用户下次从历史记录里复制到假地址。这里在发送前比对收款地址，
+// 并在历史中标记可疑的入账。
+use crate::core::account::Account;
+use crate::core::db::{AppDB, DbResult, TxHistoryManager};
+use crate::core::state::AppState;
+use crate::data::addr::AddressBookEntry;
+use crate::data::export::TokenMetaMap;
+use crate::data::tokenlist::token_meta_map;
+use crate::data::tx::TransactionHistoryEntry;
+use crate::error::AppError;
+use crate::evm::address::{LOOKALIKE_PREFIX_LEN, LOOKALIKE_SUFFIX_LEN, is_lookalike};
+use crate::evm::selector::{IERC20, IERC721, IERC1155};
+use alloy_primitives::{Address, TxHash, U256};
+use alloy_sol_types::SolCall;
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::State;
+
+/// 历史记录中可疑入账的标签
+pub const POISONING_LABEL: &str = "address_poisoning";
+/// 原生币粉尘阈值（wei），0.00001 ETH
+pub const NATIVE_DUST_WEI: u64 = 10_000_000_000_000;
+/// 代币粉尘阈值为 10^-TOKEN_DUST_DIGITS 个代币（0.01），按 decimals 换算成最小单位
+pub const TOKEN_DUST_DIGITS: u8 = 2;
+/// 代币列表里查不到 decimals 时按 18 位处理
+pub const DEFAULT_TOKEN_DECIMALS: u8 = 18;
+
+/// decimals 位精度代币的粉尘阈值（最小单位）
+pub fn token_dust_units(decimals: u8) -> U256 {
+    U256::from(10u64).pow(U256::from(decimals.saturating_sub(TOKEN_DUST_DIGITS)))
+}
+
+/// 从代币转账 calldata 中取出真正的收款方：
+/// ERC-20 transfer / transferFrom（ERC-721 transferFrom 同选择器）、ERC-721 / ERC-1155 safeTransferFrom
+pub fn token_recipient(data: &[u8]) -> Option<Address> {
+    let selector: [u8; 4] = data.get(..4)?.try_into().ok()?;
+    match selector {
+        IERC20::transferCall::SELECTOR => IERC20::transferCall::abi_decode(data).ok().map(|c| c.to),
+        IERC20::transferFromCall::SELECTOR => IERC20::transferFromCall::abi_decode(data).ok().map(|c| c.to),
+        IERC721::safeTransferFrom_0Call::SELECTOR => IERC721::safeTransferFrom_0Call::abi_decode(data).ok().map(|c| c.to),
+        IERC721::safeTransferFrom_1Call::SELECTOR => IERC721::safeTransferFrom_1Call::abi_decode(data).ok().map(|c| c.to),
+        IERC1155::safeTransferFromCall::SELECTOR => IERC1155::safeTransferFromCall::abi_decode(data).ok().map(|c| c.to),
+        IERC1155::safeBatchTransferFromCall::SELECTOR => {
+            IERC1155::safeBatchTransferFromCall::abi_decode(data).ok().map(|c| c.to)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterpartySource {
+    AddressBook,
+    OwnAccount,
+    History,
+}
+
+/// 一个可信地址及其来源
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KnownAddress {
+    pub address: Address,
+    pub source: CounterpartySource,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecipientCheck {
+    pub recipient: Address,
+    /// 收款地址本身就是已知地址
+    pub known: Option<KnownAddress>,
+    /// 与收款地址相似但不同的已知地址
+    pub lookalikes: Vec<KnownAddress>,
+    /// 收款地址曾向本账户发起过可疑入账
+    pub flagged_in_history: bool,
+}
+
+impl RecipientCheck {
+    pub fn is_suspicious(&self) -> bool {
+        self.known.is_none() && (!self.lookalikes.is_empty() || self.flagged_in_history)
+    }
+}
+
+/// 汇总可信地址：地址簿、自有账户，以及自有账户主动发起交易的对手方。
+/// 别人发给我们的交易不算 —— 投毒者正是从那里混进来的
+pub fn collect_known(
+    book: &[AddressBookEntry],
+    own: &[Address],
+    history: &[TransactionHistoryEntry],
+    chain_id: u64,
+) -> Vec<KnownAddress> {
+    let mut known: HashMap<Address, KnownAddress> = HashMap::new();
+    for tx in history.iter().filter(|tx| own.contains(&tx.from)) {
+        let targets = std::iter::once(tx.to).chain(
+            tx.token_transfers.iter().filter(|leg| own.contains(&leg.from)).map(|leg| leg.to),
+        );
+        for to in targets.filter(|to| !to.is_zero() && !own.contains(to)) {
+            known.entry(to).or_insert(KnownAddress { address: to, source: CounterpartySource::History, label: None });
+        }
+    }
+    // 优先级：自有账户 > 地址簿 > 历史
+    for entry in book.iter().filter(|e| e.on_chain(chain_id)) {
+        known.insert(
+            entry.address,
+            KnownAddress {
+                address: entry.address,
+                source: CounterpartySource::AddressBook,
+                label: Some(entry.name.clone()),
+            },
+        );
+    }
+    for addr in own {
+        known.insert(*addr, KnownAddress { address: *addr, source: CounterpartySource::OwnAccount, label: None });
+    }
+    known.into_values().collect()
+}
+
+pub fn check_recipient(
+    recipient: Address,
+    known: &[KnownAddress],
+    history: &[TransactionHistoryEntry],
+) -> RecipientCheck {
+    let mut lookalikes: Vec<KnownAddress> = known
+        .iter()
+        .filter(|k| is_lookalike(&k.address, &recipient, LOOKALIKE_PREFIX_LEN, LOOKALIKE_SUFFIX_LEN))
+        .cloned()
+        .collect();
+    lookalikes.sort_by_key(|k| k.source as u8);
+    let flagged_in_history = history.iter().any(|tx| {
+        tx.labels.iter().any(|l| l == POISONING_LABEL)
+            && (tx.from == recipient || tx.token_transfers.iter().any(|leg| leg.from == recipient || leg.to == recipient))
+    });
+    RecipientCheck {
+        recipient,
+        known: known.iter().find(|k| k.address == recipient).cloned(),
+        lookalikes,
+        flagged_in_history,
+    }
+}
+
+fn is_lookalike_of_known(addr: &Address, known: &[KnownAddress]) -> bool {
+    known
+        .iter()
+        .any(|k| is_lookalike(&k.address, addr, LOOKALIKE_PREFIX_LEN, LOOKALIKE_SUFFIX_LEN))
+}
+
+/// 判断一条非本人发起的交易是否为投毒：
+/// - 原生币：相似地址转入 0 额或粉尘
+/// - 代币：涉及自有账户的 Transfer，对手方是相似地址且金额为 0 或粉尘（按代币 decimals 换算）
+///   （含伪造的 transferFrom(victim, lookalike, 0)）
+pub fn is_poisoning(
+    tx: &TransactionHistoryEntry,
+    own: &[Address],
+    known: &[KnownAddress],
+    tokens: &TokenMetaMap,
+) -> bool {
+    if own.contains(&tx.from) {
+        return false;
+    }
+    let native = own.contains(&tx.to)
+        && tx.value <= U256::from(NATIVE_DUST_WEI)
+        && is_lookalike_of_known(&tx.from, known);
+    let token = tx.token_transfers.iter().any(|leg| {
+        let counterparty = if own.contains(&leg.to) {
+            leg.from
+        } else if own.contains(&leg.from) {
+            leg.to
+        } else {
+            return false;
+        };
+        let decimals = tokens.get(&(tx.chain_id, leg.token)).map_or(DEFAULT_TOKEN_DECIMALS, |(_, d)| *d);
+        leg.amount <= token_dust_units(decimals) && is_lookalike_of_known(&counterparty, known)
+    });
+    native || token
+}
+
+/// 给历史中的可疑入账打标签，返回新标记的交易
+pub fn mark_history(
+    history: &mut [TransactionHistoryEntry],
+    own: &[Address],
+    known: &[KnownAddress],
+    tokens: &TokenMetaMap,
+) -> Vec<TxHash> {
+    let mut marked = Vec::new();
+    for tx in history.iter_mut() {
+        if tx.labels.iter().any(|l| l == POISONING_LABEL) || !is_poisoning(tx, own, known, tokens) {
+            continue;
+        }
+        tx.labels.push(POISONING_LABEL.to_string());
+        marked.push(tx.hash);
+    }
+    marked
+}
+
+pub fn own_addresses(accounts: &[Account]) -> Vec<Address> {
+    accounts.iter().filter_map(|a| a.address.parse::<Address>().ok()).collect()
+}
+
+/// 发送前检查（供 UI 与 dApp 签名弹窗共用）
+pub fn recipient_report(
+    db: &DBWithThreadMode<MultiThreaded>,
+    own: &[Address],
+    book: &[AddressBookEntry],
+    chain_id: u64,
+    recipient: Address,
+) -> DbResult<RecipientCheck> {
+    let history = TxHistoryManager::new(db).range(chain_id, None, None)?;
+    let known = collect_known(book, own, &history, chain_id);
+    Ok(check_recipient(recipient, &known, &history))
+}
+
+#[tauri::command]
+pub fn recipient_check(
+    chain_id: u64,
+    recipient: String,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<RecipientCheck, AppError> {
+    let recipient = recipient
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let own = own_addresses(&state.accounts.blocking_lock());
+    let book = state.address_books.blocking_lock().clone();
+    recipient_report(appdb.db.as_ref(), &own, &book, chain_id, recipient)
+}
+
+/// 扫描某条链的历史并标记投毒入账
+#[tauri::command]
+pub fn tx_scan_poisoning(
+    chain_id: u64,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<Vec<TxHash>, AppError> {
+    let own = own_addresses(&state.accounts.blocking_lock());
+    let book = state.address_books.blocking_lock().clone();
+    let mgr = TxHistoryManager::new(appdb.db.as_ref());
+    let mut history = mgr.range(chain_id, None, None)?;
+    let known = collect_known(&book, &own, &history, chain_id);
+    let tokens = token_meta_map(appdb.db.as_ref())?;
+    let marked = mark_history(&mut history, &own, &known, &tokens);
+    let updated: Vec<TransactionHistoryEntry> =
+        history.into_iter().filter(|tx| marked.contains(&tx.hash)).collect();
+    mgr.batch_insert(&updated)?;
+    Ok(marked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tx::{TokenStandard, TokenTransferLeg, TransactionHistoryEntryV1};
+    use alloy_primitives::address;
+
+    const ME: Address = address!("0x1111111111111111111111111111111111111111");
+    const FRIEND: Address = address!("0xd8da6bf26964af9d7eed9e03e53415d37aa96045");
+    const FAKE: Address = address!("0xd8da00000000000000000000000000000bad6045");
+
+    fn tx(from: Address, to: Address, value: u64, hash_byte: u8) -> TransactionHistoryEntry {
+        TransactionHistoryEntryV1 {
+            chain_id: 1,
+            hash: TxHash::repeat_byte(hash_byte),
+            block_number: 1,
+            nonce: U256::ZERO,
+            from,
+            to,
+            value: U256::from(value),
+            gas_price: None,
+            gas_used: None,
+            timestamp: Some(1_700_000_000),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_recipient_lookalike_of_past_counterparty() {
+        let history = vec![tx(ME, FRIEND, 1_000_000_000_000_000_000, 1)];
+        let known = collect_known(&[], &[ME], &history, 1);
+
+        let ok = check_recipient(FRIEND, &known, &history);
+        assert!(!ok.is_suspicious());
+        assert_eq!(ok.known.unwrap().source, CounterpartySource::History);
+
+        let bad = check_recipient(FAKE, &known, &history);
+        assert!(bad.is_suspicious());
+        assert_eq!(bad.lookalikes[0].address, FRIEND);
+    }
+
+    #[test]
+    fn test_mark_dust_and_zero_transfer_from() {
+        let usdt = address!("0xdac17f958d2ee523a2206206994597c13d831ec7");
+        let mut zero_transfer = tx(FAKE, usdt, 0, 3);
+        zero_transfer.token_transfers.push(TokenTransferLeg {
+            standard: TokenStandard::Erc20,
+            token: usdt,
+            from: ME,
+            to: FAKE,
+            token_id: None,
+            amount: U256::ZERO,
+            log_index: 0,
+        });
+        let mut history = vec![
+            tx(ME, FRIEND, 1_000_000_000_000_000_000, 1),
+            tx(FAKE, ME, 1, 2),
+            zero_transfer,
+            tx(FRIEND, ME, 1, 4), // 真实对手方的小额转入不标记
+        ];
+        let known = collect_known(&[], &[ME], &history, 1);
+        let marked = mark_history(&mut history, &[ME], &known, &TokenMetaMap::new());
+        assert_eq!(marked, vec![TxHash::repeat_byte(2), TxHash::repeat_byte(3)]);
+        assert!(history[1].labels.contains(&POISONING_LABEL.to_string()));
+
+        // 历史中被标记后，即便不再相似也会提示
+        assert!(check_recipient(FAKE, &[], &history).flagged_in_history);
+    }
+
+    #[test]
+    fn test_dust_scaled_by_decimals() {
+        let weth = address!("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let usdc = address!("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let leg = |token: Address, amount: u64, hash_byte: u8| {
+            let mut entry = tx(FAKE, token, 0, hash_byte);
+            entry.token_transfers.push(TokenTransferLeg {
+                standard: TokenStandard::Erc20,
+                token,
+                from: FAKE,
+                to: ME,
+                token_id: None,
+                amount: U256::from(amount),
+                log_index: 0,
+            });
+            entry
+        };
+        let tokens = TokenMetaMap::from([((1, weth), ("WETH".to_string(), 18)), ((1, usdc), ("USDC".to_string(), 6))]);
+        let known = collect_known(&[], &[ME], &[tx(ME, FRIEND, 1, 1)], 1);
+        // 0.001 WETH 仍是粉尘；旧的固定 10_000 最小单位会漏掉
+        assert!(is_poisoning(&leg(weth, 1_000_000_000_000_000, 2), &[ME], &known, &tokens));
+        // 5 USDC 不是粉尘
+        assert!(!is_poisoning(&leg(usdc, 5_000_000, 3), &[ME], &known, &tokens));
+        assert!(is_poisoning(&leg(usdc, 5_000, 4), &[ME], &known, &tokens));
+        assert_eq!(token_dust_units(0), U256::from(1));
+    }
+
+    #[test]
+    fn test_token_recipient_from_calldata() {
+        let erc20 = IERC20::transferCall { to: FAKE, value: U256::from(1) }.abi_encode();
+        assert_eq!(token_recipient(&erc20), Some(FAKE));
+        let from = IERC20::transferFromCall { from: ME, to: FAKE, value: U256::ZERO }.abi_encode();
+        assert_eq!(token_recipient(&from), Some(FAKE));
+        let nft = IERC721::safeTransferFrom_0Call { from: ME, to: FAKE, tokenId: U256::from(7) }.abi_encode();
+        assert_eq!(token_recipient(&nft), Some(FAKE));
+        let multi = IERC1155::safeTransferFromCall {
+            from: ME,
+            to: FAKE,
+            id: U256::from(1),
+            amount: U256::from(1),
+            data: Default::default(),
+        }
+        .abi_encode();
+        assert_eq!(token_recipient(&multi), Some(FAKE));
+        let approve = IERC20::approveCall { spender: FAKE, value: U256::MAX }.abi_encode();
+        assert_eq!(token_recipient(&approve), None);
+    }
+}
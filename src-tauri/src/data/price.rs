@@ -0,0 +1,375 @@
+// 链上定价：优先 Chainlink 喂价，其次 Uniswap V3 TWAP（对 BasePair 稳定币），
+// 全部经 Multicall3 批量读取，不依赖中心化价格 API
+use crate::actions::uniswap::router::{evm_chain_from_id, get_base_pair};
+use crate::core::state::AppState;
+use crate::error::AppError;
+use crate::evm::capability::ChainCapabilities;
+use crate::rpc::multicall3::{Call, IMulticall3, MULTICALL3_ADDR, Multicall3};
+use alloy_primitives::{Address, I256, U256, address, aliases::U24};
+use alloy_sol_types::sol;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+sol! {
+    interface IAggregatorV3 {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (
+            uint80 roundId,
+            int256 answer,
+            uint256 startedAt,
+            uint256 updatedAt,
+            uint80 answeredInRound
+        );
+    }
+
+    interface IUniswapV3Factory {
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool);
+    }
+
+    interface IUniswapV3Pool {
+        function liquidity() external view returns (uint128);
+        function observe(uint32[] calldata secondsAgos) external view returns (
+            int56[] memory tickCumulatives,
+            uint160[] memory secondsPerLiquidityCumulativeX128s
+        );
+    }
+
+    interface IERC20Decimals {
+        function decimals() external view returns (uint8);
+    }
+}
+
+/// TWAP 窗口（秒）
+pub const TWAP_WINDOW_SECS: u32 = 1800;
+/// 低于该流动性的池子只给 Low 置信度（启发式，按 sqrt(x*y) 原始单位）
+pub const MIN_TWAP_LIQUIDITY: u128 = 1_000_000_000_000_000;
+const FEE_TIERS: [u32; 4] = [500, 3000, 10000, 100];
+
+/// Chainlink USD 喂价（Address::ZERO 表示原生币）
+pub struct ChainlinkFeed {
+    pub chain_id: u64,
+    pub token: Address,
+    pub feed: Address,
+    /// 喂价心跳（秒），超过即视为过期
+    pub heartbeat: u64,
+}
+
+pub const CHAINLINK_FEEDS: &[ChainlinkFeed] = &[
+    // Ethereum
+    ChainlinkFeed { chain_id: 1, token: Address::ZERO, feed: address!("0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419"), heartbeat: 3600 },
+    ChainlinkFeed { chain_id: 1, token: address!("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"), feed: address!("0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419"), heartbeat: 3600 },
+    ChainlinkFeed { chain_id: 1, token: address!("0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"), feed: address!("0xF4030086522a5bEEa4988F8cA5B36dbC97BeE88c"), heartbeat: 3600 },
+    ChainlinkFeed { chain_id: 1, token: address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"), feed: address!("0x8fFfFfd4AfB6115b954Bd326cbe7B4BA576818f6"), heartbeat: 86400 },
+    ChainlinkFeed { chain_id: 1, token: address!("0xdAC17F958D2ee523a2206206994597C13D831ec7"), feed: address!("0x3E7d1eAB13ad0104d2750B8863b489D65364e32D"), heartbeat: 86400 },
+    ChainlinkFeed { chain_id: 1, token: address!("0x6B175474E89094C44Da98b954EedeAC495271d0F"), feed: address!("0xAed0c38402a5d19df6E4c03F4E2DceD6e29c1ee9"), heartbeat: 3600 },
+    ChainlinkFeed { chain_id: 1, token: address!("0x514910771AF9Ca656af840dff83E8264EcF986CA"), feed: address!("0x2c1d072e956AFFC0D435Cb7AC38EF18d24d9127c"), heartbeat: 3600 },
+    // Optimism / Base / Arbitrum：ETH
+    ChainlinkFeed { chain_id: 10, token: Address::ZERO, feed: address!("0x13e3Ee699D1909E989722E753853AE30b17e08c5"), heartbeat: 1200 },
+    ChainlinkFeed { chain_id: 8453, token: Address::ZERO, feed: address!("0x71041dddad3595F9CEd3DcCFBe3D1F4b0a16Bb70"), heartbeat: 1200 },
+    ChainlinkFeed { chain_id: 42161, token: Address::ZERO, feed: address!("0x639Fe6ab55C921f74e7fac1ee960C0B6293ba612"), heartbeat: 86400 },
+    // BSC：BNB
+    ChainlinkFeed { chain_id: 56, token: Address::ZERO, feed: address!("0x0567F2323251f0Aab15c8dFb1967E4e8A7D42aeE"), heartbeat: 60 },
+    // Polygon：POL（原 MATIC/USD）
+    ChainlinkFeed { chain_id: 137, token: Address::ZERO, feed: address!("0xAB594600376Ec9fD91F8e885dADF0CE036862dE0"), heartbeat: 60 },
+];
+
+/// Uniswap V3 Factory
+pub fn uniswap_v3_factory(chain_id: u64) -> Option<Address> {
+    Some(match chain_id {
+        1 | 10 | 137 | 42161 => address!("0x1F98431c8aD98523631AE4a59f267346ea31F984"),
+        8453 => address!("0x33128a8fC17869897dcE68Ed026d694621f6FDfD"),
+        56 => address!("0xdB1d10011AD0Ff90774D0C6Bb92e5C5c8b4461F7"),
+        _ => return None,
+    })
+}
+
+pub fn chainlink_feed(chain_id: u64, token: &Address) -> Option<&'static ChainlinkFeed> {
+    CHAINLINK_FEEDS.iter().find(|f| f.chain_id == chain_id && f.token == *token)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    Chainlink,
+    UniswapV3Twap,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenPrice {
+    pub token: Address,
+    pub usd: f64,
+    pub source: PriceSource,
+    pub confidence: Confidence,
+    /// 数据对应的链上时间（秒）
+    pub updated_at: u64,
+    pub age_secs: u64,
+    pub stale: bool,
+}
+
+/// Chainlink 结果 → 价格；answer ≤ 0 或轮次未完成视为无效
+pub fn chainlink_price(
+    token: Address,
+    answer: I256,
+    decimals: u8,
+    updated_at: u64,
+    round_complete: bool,
+    now: u64,
+    heartbeat: u64,
+) -> Option<TokenPrice> {
+    if answer <= I256::ZERO || updated_at == 0 {
+        return None;
+    }
+    let usd = answer.to_string().parse::<f64>().ok()? / 10f64.powi(decimals as i32);
+    let age_secs = now.saturating_sub(updated_at);
+    let stale = !round_complete || age_secs > heartbeat;
+    Some(TokenPrice {
+        token,
+        usd,
+        source: PriceSource::Chainlink,
+        confidence: if stale { Confidence::Low } else { Confidence::High },
+        updated_at,
+        age_secs,
+        stale,
+    })
+}
+
+/// tickCumulatives → 平均 tick，负数向下取整（同 Uniswap OracleLibrary.consult）
+pub fn twap_tick(cumulative_old: i64, cumulative_new: i64, window: u32) -> i32 {
+    let delta = cumulative_new - cumulative_old;
+    let mut tick = delta / window as i64;
+    if delta < 0 && delta % window as i64 != 0 {
+        tick -= 1;
+    }
+    tick as i32
+}
+
+/// tick → 以 token1 计价的 token0 价格（已按精度换算）
+pub fn tick_to_price(tick: i32, decimals0: u8, decimals1: u8) -> f64 {
+    1.0001f64.powi(tick) * 10f64.powi(decimals0 as i32 - decimals1 as i32)
+}
+
+/// token 对稳定币池的 TWAP → token 的 USD 价格（稳定币按 1 USD）
+pub fn twap_usd_price(token: Address, stable: Address, tick: i32, token_decimals: u8, stable_decimals: u8) -> f64 {
+    if token < stable {
+        tick_to_price(tick, token_decimals, stable_decimals)
+    } else {
+        1.0 / tick_to_price(tick, stable_decimals, token_decimals)
+    }
+}
+
+/// 一个 token 的 TWAP 候选池
+struct PoolCandidate {
+    token_index: usize,
+    stable: Address,
+    pool: Address,
+}
+
+/// 批量定价：第一轮读喂价 / 查池子 / 精度 / 区块时间，第二轮对池子 observe
+pub async fn fetch_prices(
+    multicall: &Multicall3,
+    caps: &ChainCapabilities,
+    tokens: &[Address],
+) -> Result<Vec<Option<TokenPrice>>, AppError> {
+    let chain_id = caps.chain_id;
+    let stables = evm_chain_from_id(chain_id)
+        .and_then(|chain| get_base_pair(&chain, caps, true))
+        .map(|pair| pair.stables)
+        .unwrap_or_default();
+    let factory = uniswap_v3_factory(chain_id);
+
+    // ===== 第一轮 =====
+    let mut calls = vec![multicall.self_call(&IMulticall3::getCurrentBlockTimestampCall {})];
+    for stable in &stables {
+        calls.push(Call::new(*stable, &IERC20Decimals::decimalsCall {}));
+    }
+    // (token_index, 调用起点, 是否 chainlink)
+    let mut plan = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let start = calls.len();
+        if let Some(feed) = chainlink_feed(chain_id, token) {
+            calls.push(Call::new(feed.feed, &IAggregatorV3::latestRoundDataCall {}));
+            calls.push(Call::new(feed.feed, &IAggregatorV3::decimalsCall {}));
+            plan.push((start, true));
+            continue;
+        }
+        calls.push(Call::new(*token, &IERC20Decimals::decimalsCall {}));
+        if let Some(factory) = factory {
+            for stable in stables.iter().filter(|s| *s != token) {
+                for fee in FEE_TIERS {
+                    calls.push(Call::new(
+                        factory,
+                        &IUniswapV3Factory::getPoolCall { tokenA: *token, tokenB: *stable, fee: U24::from(fee) },
+                    ));
+                }
+            }
+        }
+        plan.push((start, false));
+    }
+    let results = multicall.aggregate3(&calls).await?;
+
+    let now = results[0]
+        .decode::<IMulticall3::getCurrentBlockTimestampCall>()
+        .map(|t| t.to::<u64>())
+        .ok_or(AppError::Parse("getCurrentBlockTimestamp failed"))?;
+    let stable_decimals: Vec<Option<u8>> = (0..stables.len())
+        .map(|i| results[1 + i].decode::<IERC20Decimals::decimalsCall>())
+        .collect();
+
+    let mut prices: Vec<Option<TokenPrice>> = vec![None; tokens.len()];
+    let mut token_decimals: Vec<Option<u8>> = vec![None; tokens.len()];
+    let mut candidates = Vec::new();
+    for (i, (start, is_feed)) in plan.into_iter().enumerate() {
+        if is_feed {
+            let feed = chainlink_feed(chain_id, &tokens[i]).expect("planned feed");
+            let round = results[start].decode::<IAggregatorV3::latestRoundDataCall>();
+            let decimals = results[start + 1].decode::<IAggregatorV3::decimalsCall>();
+            if let (Some(round), Some(decimals)) = (round, decimals) {
+                prices[i] = chainlink_price(
+                    tokens[i],
+                    round.answer,
+                    decimals,
+                    round.updatedAt.to::<u64>(),
+                    round.answeredInRound >= round.roundId,
+                    now,
+                    feed.heartbeat,
+                );
+            }
+            continue;
+        }
+        token_decimals[i] = results[start].decode::<IERC20Decimals::decimalsCall>();
+        let mut offset = start + 1;
+        if factory.is_none() {
+            continue;
+        }
+        for stable in stables.iter().filter(|s| **s != tokens[i]) {
+            for _ in FEE_TIERS {
+                if let Some(pool) = results[offset].decode::<IUniswapV3Factory::getPoolCall>() {
+                    if !pool.is_zero() {
+                        candidates.push(PoolCandidate { token_index: i, stable: *stable, pool });
+                    }
+                }
+                offset += 1;
+            }
+        }
+    }
+    if candidates.is_empty() {
+        return Ok(prices);
+    }
+
+    // ===== 第二轮：observe + liquidity =====
+    let mut calls = Vec::with_capacity(candidates.len() * 2);
+    for c in &candidates {
+        calls.push(Call::new(
+            c.pool,
+            &IUniswapV3Pool::observeCall { secondsAgos: vec![TWAP_WINDOW_SECS, 0] },
+        ));
+        calls.push(Call::new(c.pool, &IUniswapV3Pool::liquidityCall {}));
+    }
+    let results = multicall.aggregate3(&calls).await?;
+
+    // 每个 token 取流动性最大的池子
+    let mut best: Vec<Option<(u128, TokenPrice)>> = vec![None; tokens.len()];
+    for (j, c) in candidates.iter().enumerate() {
+        let Some(observed) = results[2 * j].decode::<IUniswapV3Pool::observeCall>() else { continue };
+        let liquidity = results[2 * j + 1].decode::<IUniswapV3Pool::liquidityCall>().unwrap_or(0);
+        let (Some(token_dec), Some(stable_dec)) = (
+            token_decimals[c.token_index],
+            stables.iter().position(|s| *s == c.stable).and_then(|k| stable_decimals[k]),
+        ) else {
+            continue;
+        };
+        if observed.tickCumulatives.len() != 2 {
+            continue;
+        }
+        let tick = twap_tick(observed.tickCumulatives[0], observed.tickCumulatives[1], TWAP_WINDOW_SECS);
+        let usd = twap_usd_price(tokens[c.token_index], c.stable, tick, token_dec, stable_dec);
+        if !usd.is_finite() || usd <= 0.0 {
+            continue;
+        }
+        if best[c.token_index].as_ref().is_some_and(|(l, _)| *l >= liquidity) {
+            continue;
+        }
+        let price = TokenPrice {
+            token: tokens[c.token_index],
+            usd,
+            source: PriceSource::UniswapV3Twap,
+            confidence: if liquidity >= MIN_TWAP_LIQUIDITY { Confidence::Medium } else { Confidence::Low },
+            updated_at: now,
+            age_secs: 0,
+            stale: false,
+        };
+        best[c.token_index] = Some((liquidity, price));
+    }
+    for (i, b) in best.into_iter().enumerate() {
+        if prices[i].is_none() {
+            prices[i] = b.map(|(_, p)| p);
+        }
+    }
+    Ok(prices)
+}
+
+/// 批量查询 token 的 USD 价格；原生币传零地址。查不到的位置为 null
+#[tauri::command]
+pub async fn token_prices(
+    chain_id: u64,
+    tokens: Vec<String>,
+    rpc_url: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Option<TokenPrice>>, AppError> {
+    let tokens = tokens
+        .iter()
+        .map(|t| t.parse::<Address>().map_err(|_| AppError::Parse("invalid token address")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let client = state.https_client.lock().await.clone();
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let multicall = Multicall3::new(client, &rpc_url).with_address(caps.multicall3.unwrap_or(MULTICALL3_ADDR));
+    fetch_prices(&multicall, &caps, &tokens).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chainlink_staleness() {
+        let eth = Address::ZERO;
+        let answer = I256::try_from(400_012_345_678i64).unwrap(); // 8 位精度
+        let fresh = chainlink_price(eth, answer, 8, 1_000, true, 1_600, 3600).unwrap();
+        assert!((fresh.usd - 4000.12345678).abs() < 1e-6);
+        assert_eq!((fresh.confidence, fresh.stale), (Confidence::High, false));
+
+        let old = chainlink_price(eth, answer, 8, 1_000, true, 10_000, 3600).unwrap();
+        assert_eq!((old.confidence, old.stale, old.age_secs), (Confidence::Low, true, 9_000));
+        assert!(chainlink_price(eth, answer, 8, 1_000, false, 1_600, 3600).unwrap().stale);
+        assert!(chainlink_price(eth, I256::ZERO, 8, 1_000, true, 1_600, 3600).is_none());
+    }
+
+    #[test]
+    fn test_twap_tick_rounding() {
+        assert_eq!(twap_tick(0, 1800 * 5, 1800), 5);
+        assert_eq!(twap_tick(-100, -1900, 1800), -1);
+        assert_eq!(twap_tick(0, -1801, 1800), -2);
+    }
+
+    #[test]
+    fn test_twap_usd_price_both_orderings() {
+        let usdc = address!("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let weth = address!("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        // USDC/WETH 池 token0 = USDC；ETH ≈ 4000 时 tick ≈ 193384
+        let eth_usd = twap_usd_price(weth, usdc, 193_384, 18, 6);
+        assert!((eth_usd / 4000.0 - 1.0).abs() < 1e-3, "{eth_usd}");
+
+        // token 地址小于稳定币：price = tick_to_price(tick, token_dec, stable_dec)
+        let small = address!("0x0000000000000000000000000000000000000001");
+        let p = twap_usd_price(small, usdc, 0, 6, 6);
+        assert!((p - 1.0).abs() < 1e-12);
+    }
+}
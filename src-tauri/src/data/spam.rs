@@ -0,0 +1,549 @@
+// 垃圾 / 诈骗代币识别：
+// 列表收录、名称与符号特征（网址、"claim"、仿冒已知代币的符号）、合约年龄、持有人数、
+// 以及模拟转账是否可行，加权成 0-100 的分数；超过阈值默认隐藏。
+// 用户可以覆盖判定，覆盖项存 Registry 表；链上检查结果存 Cache 表
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::data::token::Token;
+use crate::data::tokenlist::{TokenSource, load_views};
+use crate::error::AppError;
+use crate::evm::chains::approx_block_time_ms;
+use crate::rpc::ankr::advance::get_token_holders_count_by_ankr;
+use crate::rpc::ankr::models::AnkrBlockchain;
+use crate::rpc::method::{block_number, eth_call, eth_call_from, get_code};
+use crate::utils::time::now_s;
+use alloy_primitives::{Address, U256, address};
+use alloy_sol_types::{SolCall, sol};
+use bincode::{Decode, Encode};
+use reqwest::Client;
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::State;
+
+/// 达到该分数默认隐藏
+pub const SPAM_THRESHOLD: u8 = 60;
+/// 低于该分数视为正常
+pub const SUSPICIOUS_THRESHOLD: u8 = 30;
+/// 合约部署不足该时长视为新合约
+pub const YOUNG_CONTRACT_SECS: u64 = 7 * 24 * 3600;
+/// 持有人少于该数视为可疑
+pub const FEW_HOLDERS: u64 = 50;
+/// 链上检查结果的缓存时长
+pub const FACTS_TTL_SECS: u64 = 24 * 3600;
+/// 检查过程中有 RPC 失败时的缓存时长，避免把不完整的结果留一整天
+pub const FACTS_RETRY_TTL_SECS: u64 = 10 * 60;
+/// 模拟转账的收款地址（不会被代币合约特殊对待的普通地址）
+pub const SIMULATION_RECIPIENT: Address = address!("0x000000000000000000000000000000000000dEaD");
+
+const OVERRIDE_KEY_PREFIX: &str = "spamoverride";
+
+/// 名称 / 符号中的引流关键词
+const BAIT_KEYWORDS: &[&str] = &["claim", "reward", "airdrop", "voucher", "visit", "redeem", "bonus", "gift", "free"];
+const URL_MARKERS: &[&str] = &["http", "www.", ".com", ".io", ".org", ".net", ".xyz", ".app", ".finance", "t.me", ".gift", ".site"];
+
+sol! {
+    interface IERC20Transfer {
+        function balanceOf(address owner) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpamSignal {
+    /// 在启用的代币列表中
+    OnTokenList { list: String },
+    UrlInName,
+    BaitKeyword { keyword: String },
+    /// 符号与列表中的代币相同（或形近），但地址不同
+    LookalikeSymbol { of: String, confusable: bool },
+    YoungContract { age_secs: u64 },
+    FewHolders { holders: u64 },
+    NotTransferable,
+    NoCode,
+}
+
+impl SpamSignal {
+    /// 正数加分（更可疑），负数减分
+    pub fn weight(&self) -> i32 {
+        match self {
+            SpamSignal::OnTokenList { .. } => -100,
+            SpamSignal::UrlInName => 50,
+            SpamSignal::BaitKeyword { .. } => 35,
+            SpamSignal::LookalikeSymbol { confusable: true, .. } => 60,
+            SpamSignal::LookalikeSymbol { confusable: false, .. } => 45,
+            SpamSignal::YoungContract { .. } => 15,
+            SpamSignal::FewHolders { .. } => 20,
+            SpamSignal::NotTransferable => 60,
+            SpamSignal::NoCode => 40,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpamVerdict {
+    Legit,
+    Suspicious,
+    Spam,
+}
+
+pub fn verdict_for(score: u8) -> SpamVerdict {
+    if score >= SPAM_THRESHOLD {
+        SpamVerdict::Spam
+    } else if score >= SUSPICIOUS_THRESHOLD {
+        SpamVerdict::Suspicious
+    } else {
+        SpamVerdict::Legit
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct SpamOverride {
+    pub chain_id: u64,
+    pub address: Address,
+    pub verdict: SpamVerdict,
+}
+
+/// 需要 RPC / 索引器的检查结果；None 表示无法判断
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct TokenFacts {
+    pub has_code: Option<bool>,
+    pub age_secs: Option<u64>,
+    pub holders: Option<u64>,
+    pub transferable: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SpamReport {
+    pub chain_id: u64,
+    pub address: Address,
+    pub score: u8,
+    pub signals: Vec<SpamSignal>,
+    pub verdict: SpamVerdict,
+    /// 用户覆盖了自动判定
+    pub overridden: bool,
+    pub hidden: bool,
+}
+
+// ========== 名称特征 ==========
+
+/// 把常见的西里尔 / 希腊 / 全角形近字符折叠为拉丁字母，去掉零宽字符，转大写
+pub fn symbol_skeleton(symbol: &str) -> String {
+    symbol
+        .chars()
+        .filter(|c| !matches!(c, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}'))
+        .map(|c| match c {
+            // 全角 ASCII
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            'а' | 'А' | 'α' | 'Α' => 'A',
+            'в' | 'В' | 'β' | 'Β' => 'B',
+            'с' | 'С' | 'ϲ' | 'Ϲ' => 'C',
+            'е' | 'Е' | 'ε' | 'Ε' => 'E',
+            'н' | 'Н' | 'Η' => 'H',
+            'і' | 'І' | 'ι' | 'Ι' | 'ӏ' | 'Ӏ' => 'I',
+            'ј' | 'Ј' => 'J',
+            'к' | 'К' | 'κ' | 'Κ' => 'K',
+            'м' | 'М' | 'Μ' => 'M',
+            'Ν' => 'N',
+            'о' | 'О' | 'ο' | 'Ο' | '0' => 'O',
+            'р' | 'Р' | 'ρ' | 'Ρ' => 'P',
+            'ѕ' | 'Ѕ' => 'S',
+            'т' | 'Т' | 'τ' | 'Τ' => 'T',
+            'υ' | 'Υ' | 'у' | 'У' => 'Y',
+            'х' | 'Х' | 'χ' | 'Χ' => 'X',
+            'Ζ' => 'Z',
+            _ => c,
+        })
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// 列表里已知的代币：(链, 符号骨架) → 原符号。
+/// 按链区分：别的链上的同名代币（如各链原生的 USDC）不构成仿冒
+#[derive(Debug, Default)]
+pub struct KnownTokens {
+    listed: HashMap<(u64, Address), String>,
+    by_symbol: HashMap<(u64, String), String>,
+}
+
+impl KnownTokens {
+    pub fn insert(&mut self, chain_id: u64, address: Address, symbol: &str, list: &str) {
+        self.listed.insert((chain_id, address), list.to_string());
+        self.by_symbol
+            .entry((chain_id, symbol_skeleton(symbol)))
+            .or_insert_with(|| symbol.to_string());
+    }
+
+    pub fn load(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Self> {
+        let mut known = Self::default();
+        for view in load_views(db)? {
+            let list = view.sources.iter().find_map(|s| match s {
+                TokenSource::List(name) => Some(name.clone()),
+                TokenSource::Custom => None,
+            });
+            if let Some(list) = list {
+                known.insert(view.token.chain_id, view.token.address, &view.token.symbol, &list);
+            }
+        }
+        Ok(known)
+    }
+}
+
+/// 不需要网络的信号
+pub fn static_signals(token: &Token, known: &KnownTokens) -> Vec<SpamSignal> {
+    if let Some(list) = known.listed.get(&(token.chain_id, token.address)) {
+        return vec![SpamSignal::OnTokenList { list: list.clone() }];
+    }
+    let mut signals = Vec::new();
+    let text = format!("{} {}", token.name, token.symbol).to_lowercase();
+    if URL_MARKERS.iter().any(|m| text.contains(m)) {
+        signals.push(SpamSignal::UrlInName);
+    }
+    if let Some(keyword) = BAIT_KEYWORDS.iter().find(|k| text.contains(*k)) {
+        signals.push(SpamSignal::BaitKeyword { keyword: keyword.to_string() });
+    }
+    if let Some(symbol) = known.by_symbol.get(&(token.chain_id, symbol_skeleton(&token.symbol))) {
+        // 能走到这里说明地址不在列表中
+        signals.push(SpamSignal::LookalikeSymbol {
+            of: symbol.clone(),
+            confusable: !token.symbol.eq_ignore_ascii_case(symbol),
+        });
+    }
+    signals
+}
+
+pub fn fact_signals(facts: &TokenFacts) -> Vec<SpamSignal> {
+    let mut signals = Vec::new();
+    if facts.has_code == Some(false) {
+        signals.push(SpamSignal::NoCode);
+    }
+    if let Some(age_secs) = facts.age_secs.filter(|a| *a < YOUNG_CONTRACT_SECS) {
+        signals.push(SpamSignal::YoungContract { age_secs });
+    }
+    if let Some(holders) = facts.holders.filter(|h| *h < FEW_HOLDERS) {
+        signals.push(SpamSignal::FewHolders { holders });
+    }
+    if facts.transferable == Some(false) {
+        signals.push(SpamSignal::NotTransferable);
+    }
+    signals
+}
+
+/// 打分；列表收录的代币不参与链上信号（列表方已审核）
+pub fn score_token(
+    token: &Token,
+    known: &KnownTokens,
+    facts: &TokenFacts,
+    user_override: Option<SpamVerdict>,
+) -> SpamReport {
+    let mut signals = static_signals(token, known);
+    if !matches!(signals.first(), Some(SpamSignal::OnTokenList { .. })) {
+        signals.extend(fact_signals(facts));
+    }
+    let score = signals.iter().map(SpamSignal::weight).sum::<i32>().clamp(0, 100) as u8;
+    let verdict = user_override.unwrap_or_else(|| verdict_for(score));
+    SpamReport {
+        chain_id: token.chain_id,
+        address: token.address,
+        score,
+        signals,
+        verdict,
+        overridden: user_override.is_some(),
+        hidden: verdict == SpamVerdict::Spam,
+    }
+}
+
+// ========== 链上检查 ==========
+
+/// 二分 eth_getCode 找部署区块；非归档节点查不到旧状态时返回 None
+pub async fn deploy_block(client: &Client, url: &str, token: &Address, head: u64) -> Option<u64> {
+    let addr = format!("{:#x}", token);
+    let has_code = |block: u64| {
+        let client = client.clone();
+        let addr = addr.clone();
+        async move {
+            get_code(client, url, &addr, &format!("0x{block:x}"))
+                .await
+                .map(|code| code.len() > 2)
+        }
+    };
+    if !has_code(head).await.ok()? {
+        return None;
+    }
+    let (mut lo, mut hi) = (0u64, head);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if has_code(mid).await.ok()? {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Some(lo)
+}
+
+/// 以持有人身份模拟把全部余额转给普通地址；revert 或返回 false 视为不可转
+pub async fn simulate_transfer(client: &Client, url: &str, token: &Address, holder: &Address) -> Option<bool> {
+    let to = format!("{:#x}", token);
+    let balance_call = IERC20Transfer::balanceOfCall { owner: *holder };
+    let raw = eth_call(client.clone(), url, &to, &format!("0x{}", hex::encode(balance_call.abi_encode())), "latest")
+        .await
+        .ok()?;
+    let raw = hex::decode(raw.as_str()?.trim_start_matches("0x")).ok()?;
+    let balance = IERC20Transfer::balanceOfCall::abi_decode_returns(&raw).ok()?;
+    if balance == U256::ZERO {
+        return None;
+    }
+    let transfer = IERC20Transfer::transferCall { to: SIMULATION_RECIPIENT, amount: balance };
+    let data = format!("0x{}", hex::encode(transfer.abi_encode()));
+    match eth_call_from(client.clone(), url, &format!("{:#x}", holder), &to, &data, "latest").await {
+        Ok(result) => {
+            let raw = hex::decode(result.as_str().unwrap_or("0x").trim_start_matches("0x")).ok()?;
+            // USDT 等不返回值的代币视为成功
+            Some(raw.is_empty() || IERC20Transfer::transferCall::abi_decode_returns(&raw).unwrap_or(false))
+        }
+        Err(AppError::RpcReverted(_)) => Some(false),
+        Err(_) => None,
+    }
+}
+
+/// 收集链上检查结果；第二项为 false 表示有 RPC 失败，结果不完整
+pub async fn collect_facts(
+    client: &Client,
+    rpc_url: &str,
+    ankr_url: Option<&str>,
+    token: &Token,
+    holder: Option<&Address>,
+) -> (TokenFacts, bool) {
+    let mut facts = TokenFacts::default();
+    let Ok(head) = block_number(client.clone(), rpc_url).await else {
+        return (facts, false);
+    };
+    let mut complete = true;
+    match deploy_block(client, rpc_url, &token.address, head).await {
+        Some(deployed) => {
+            facts.has_code = Some(true);
+            facts.age_secs = Some((head - deployed) * approx_block_time_ms(token.chain_id) / 1000);
+        }
+        None => match get_code(client.clone(), rpc_url, &format!("{:#x}", token.address), "latest").await {
+            Ok(code) => {
+                facts.has_code = Some(code.len() > 2);
+                // 有代码却没找到部署区块，说明二分查找中途失败
+                complete = code.len() <= 2;
+            }
+            Err(_) => complete = false,
+        },
+    }
+    if let (Some(ankr_url), Some(chain)) = (ankr_url, AnkrBlockchain::from_chain_id(token.chain_id)) {
+        match get_token_holders_count_by_ankr(client, ankr_url, chain.as_str(), &format!("{:#x}", token.address)).await {
+            Ok(holders) => facts.holders = holders,
+            Err(_) => complete = false,
+        }
+    }
+    if let Some(holder) = holder {
+        facts.transferable = simulate_transfer(client, rpc_url, &token.address, holder).await;
+    }
+    (facts, complete)
+}
+
+// ========== 存储 ==========
+
+fn override_key(mgr: &TableManager, chain_id: u64, address: &Address) -> Vec<u8> {
+    mgr.key_from_str(&format!("{OVERRIDE_KEY_PREFIX}:{chain_id}:{:#x}", address))
+}
+
+fn facts_key(mgr: &TableManager, chain_id: u64, address: &Address) -> Vec<u8> {
+    mgr.key_from_str(&format!("spam:{chain_id}:{:#x}", address))
+}
+
+pub fn load_overrides(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<HashMap<(u64, Address), SpamVerdict>> {
+    Ok(TableManager::new(db, TableKind::Registry)?
+        .list_by_prefix::<SpamOverride>(&format!("{OVERRIDE_KEY_PREFIX}:"))?
+        .into_iter()
+        .map(|o| ((o.chain_id, o.address), o.verdict))
+        .collect())
+}
+
+/// 只用本地信息和已缓存的链上检查结果过滤，供列表展示时同步调用
+pub fn visible_tokens(db: &DBWithThreadMode<MultiThreaded>, tokens: Vec<Token>) -> DbResult<Vec<Token>> {
+    let known = KnownTokens::load(db)?;
+    let overrides = load_overrides(db)?;
+    let cache = TableManager::new(db, TableKind::Cache)?;
+    let now = now_s();
+    let mut visible = Vec::new();
+    for token in tokens {
+        let facts = cache
+            .get_unexpired::<TokenFacts>(&facts_key(&cache, token.chain_id, &token.address), now)?
+            .unwrap_or_default();
+        let user_override = overrides.get(&(token.chain_id, token.address)).copied();
+        if !score_token(&token, &known, &facts, user_override).hidden {
+            visible.push(token);
+        }
+    }
+    Ok(visible)
+}
+
+// ========== 命令 ==========
+
+/// 给一组代币打分（通常是索引器返回的持仓）。
+/// holder 用于模拟转账；ankr_url 提供时查询持有人数
+#[tauri::command]
+pub async fn spam_classify(
+    tokens: Vec<Token>,
+    rpc_url: String,
+    holder: Option<String>,
+    ankr_url: Option<String>,
+    force: Option<bool>,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SpamReport>, AppError> {
+    let holder = holder
+        .map(|h| h.parse::<Address>().map_err(|_| AppError::Parse("invalid address")))
+        .transpose()?;
+    let db = appdb.db.as_ref();
+    let known = KnownTokens::load(db)?;
+    let overrides = load_overrides(db)?;
+    let cache = TableManager::new(db, TableKind::Cache)?;
+    let client = state.https_client.lock().await.clone();
+    let now = now_s();
+
+    let mut reports = Vec::with_capacity(tokens.len());
+    for token in &tokens {
+        let user_override = overrides.get(&(token.chain_id, token.address)).copied();
+        // 已收录或已被用户判定的不再做链上检查
+        let needs_facts = user_override.is_none() && !known.listed.contains_key(&(token.chain_id, token.address));
+        let facts = if needs_facts {
+            let key = facts_key(&cache, token.chain_id, &token.address);
+            match cache.get_unexpired::<TokenFacts>(&key, now)? {
+                Some(facts) if !force.unwrap_or(false) => facts,
+                _ => {
+                    let (facts, complete) = collect_facts(&client, &rpc_url, ankr_url.as_deref(), token, holder.as_ref()).await;
+                    let ttl = if complete { FACTS_TTL_SECS } else { FACTS_RETRY_TTL_SECS };
+                    cache.set_expiring(&key, facts, now + ttl)?;
+                    facts
+                }
+            }
+        } else {
+            TokenFacts::default()
+        };
+        reports.push(score_token(token, &known, &facts, user_override));
+    }
+    Ok(reports)
+}
+
+/// 按已有信息过滤掉判定为垃圾的代币（不发网络请求）
+#[tauri::command]
+pub fn spam_filter(tokens: Vec<Token>, appdb: State<AppDB>) -> Result<Vec<Token>, AppError> {
+    Ok(visible_tokens(appdb.db.as_ref(), tokens)?)
+}
+
+/// 设置用户判定；verdict 为空时恢复自动判定
+#[tauri::command]
+pub fn spam_override_set(
+    chain_id: u64,
+    address: String,
+    verdict: Option<SpamVerdict>,
+    appdb: State<AppDB>,
+) -> Result<(), AppError> {
+    let address = address
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    let key = override_key(&mgr, chain_id, &address);
+    match verdict {
+        Some(verdict) => mgr.set(&key, &SpamOverride { chain_id, address, verdict })?,
+        None => mgr.delete(&key)?,
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn spam_override_list(appdb: State<AppDB>) -> Result<Vec<SpamOverride>, AppError> {
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    Ok(mgr.list_by_prefix(&format!("{OVERRIDE_KEY_PREFIX}:"))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::token::IntoInterToken;
+    use crate::data::tokenlist::TokenInfo;
+    use crate::utils::test_http;
+
+    const USDC: Address = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+    const FAKE: Address = address!("0x3333333333333333333333333333333333333333");
+
+    fn token(address: Address, name: &str, symbol: &str) -> Token {
+        TokenInfo {
+            chain_id: 1,
+            address,
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            decimals: 6,
+            logo_uri: None,
+            tags: Vec::new(),
+        }
+        .into_inter()
+    }
+
+    fn known() -> KnownTokens {
+        let mut known = KnownTokens::default();
+        known.insert(1, USDC, "USDC", "Test List");
+        known
+    }
+
+    #[test]
+    fn test_skeleton_folds_confusables() {
+        // 西里尔 С、全角 Ｕ、零宽空格
+        assert_eq!(symbol_skeleton("USDС"), "USDC");
+        assert_eq!(symbol_skeleton("ＵSD\u{200B}C"), "USDC");
+        assert_eq!(symbol_skeleton("usdc"), "USDC");
+    }
+
+    #[test]
+    fn test_static_signals_and_scores() {
+        let known = known();
+        let listed = score_token(&token(USDC, "USD Coin", "USDC"), &known, &TokenFacts::default(), None);
+        assert_eq!((listed.score, listed.verdict), (0, SpamVerdict::Legit));
+
+        // 形近符号 + 网址
+        let bait = score_token(&token(FAKE, "Visit usdc-claim.com", "USDС"), &known, &TokenFacts::default(), None);
+        assert!(bait.signals.contains(&SpamSignal::UrlInName));
+        assert!(bait.signals.contains(&SpamSignal::LookalikeSymbol { of: "USDC".to_string(), confusable: true }));
+        assert!(bait.hidden);
+
+        // 只和同一条链上的已知代币比较
+        let mut elsewhere = token(FAKE, "USD Coin", "USDC");
+        elsewhere.chain_id = 8453;
+        let report = score_token(&elsewhere, &known, &TokenFacts::default(), None);
+        assert!(!report.signals.iter().any(|s| matches!(s, SpamSignal::LookalikeSymbol { .. })));
+
+        // 名称正常，但新合约且转不出去
+        let facts = TokenFacts { has_code: Some(true), age_secs: Some(3600), holders: Some(10), transferable: Some(false) };
+        let honeypot = score_token(&token(FAKE, "Pepe Coin", "PEPE2"), &known, &facts, None);
+        assert_eq!(honeypot.score, 95);
+        assert_eq!(honeypot.verdict, SpamVerdict::Spam);
+    }
+
+    #[test]
+    fn test_user_override_wins() {
+        let known = known();
+        let spam = token(FAKE, "Claim rewards at t.me/x", "GIFT");
+        let report = score_token(&spam, &known, &TokenFacts::default(), Some(SpamVerdict::Legit));
+        assert!(report.score >= SPAM_THRESHOLD);
+        assert!(report.overridden && !report.hidden);
+
+        let normal = token(FAKE, "Some Token", "SOME");
+        assert!(score_token(&normal, &known, &TokenFacts::default(), Some(SpamVerdict::Spam)).hidden);
+    }
+
+    #[tokio::test]
+    async fn test_failed_facts_marked_incomplete() {
+        // 节点不可用时结果不完整，调用方只做短时缓存
+        let (facts, complete) = collect_facts(&Client::new(), &test_http::unreachable(), None, &token(FAKE, "Some Token", "SOME"), None).await;
+        assert_eq!(facts, TokenFacts::default());
+        assert!(!complete);
+    }
+}
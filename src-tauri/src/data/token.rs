@@ -1,9 +1,9 @@
-
+use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
-use crate::evm::address::Address;
+use alloy_primitives::Address;
 use crate::evm::assets::AssetsType;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Encode, Decode)]
 pub struct Token {
     pub chain_id: u64,
     pub address: Address,
@@ -0,0 +1,605 @@
+// 代币列表：导入 tokenlists.org 格式的列表（校验 + 语义化版本），
+// 用户自定义代币与隐藏 / 置顶偏好，合并后写入 AppState.user_tokens。
+// 列表、自定义代币、偏好都存 Registry 表，按 key 前缀区分
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::data::export::TokenMetaMap;
+use crate::data::meta::gateway_url;
+use crate::data::token::{IntoInterToken, Token};
+use crate::error::AppError;
+use crate::evm::assets::AssetsType;
+use crate::rpc::https::get_json;
+use crate::utils::time::now_s;
+use alloy_primitives::Address;
+use bincode::{Decode, Encode};
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use tauri::State;
+
+const LIST_KEY_PREFIX: &str = "tokenlist";
+const CUSTOM_KEY_PREFIX: &str = "token";
+const PREF_KEY_PREFIX: &str = "tokenpref";
+
+// tokenlists.org schema 的限制
+const MAX_LIST_NAME_LEN: usize = 30;
+const MAX_TOKEN_NAME_LEN: usize = 60;
+const MAX_SYMBOL_LEN: usize = 20;
+const MAX_TOKENS: usize = 10_000;
+const LOGO_SCHEMES: &[&str] = &["https://", "http://", "ipfs://", "ar://"];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct TokenListVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl fmt::Display for TokenListVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub chain_id: u64,
+    pub address: Address,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    #[serde(default, rename = "logoURI", skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl TokenInfo {
+    /// 除地址外的展示字段是否变化（用于 diff）
+    fn same_details(&self, other: &TokenInfo) -> bool {
+        self.name == other.name
+            && self.symbol == other.symbol
+            && self.decimals == other.decimals
+            && self.logo_uri == other.logo_uri
+            && self.tags == other.tags
+    }
+}
+
+impl IntoInterToken for TokenInfo {
+    fn into_inter(self) -> Token {
+        Token {
+            chain_id: self.chain_id,
+            address: self.address,
+            name: self.name,
+            symbol: self.symbol,
+            decimals: self.decimals as u64,
+            logo_url: self.logo_uri.as_deref().and_then(gateway_url),
+            assets_type: Some(AssetsType::ERC20),
+            contract_address: Some(self.address),
+        }
+    }
+}
+
+/// tokenlists.org 列表（忽略 extensions / tokenMap 等未使用字段）
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct TokenList {
+    pub name: String,
+    pub timestamp: String,
+    pub version: TokenListVersion,
+    pub tokens: Vec<TokenInfo>,
+    #[serde(default, rename = "logoURI", skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+}
+
+fn valid_logo(uri: &str) -> bool {
+    LOGO_SCHEMES.iter().any(|s| uri.starts_with(s))
+}
+
+/// 按 schema 规则校验，返回全部问题而不是遇到第一个就停
+pub fn validate_list(list: &TokenList) -> Vec<String> {
+    let mut errors = Vec::new();
+    if list.name.is_empty() || list.name.chars().count() > MAX_LIST_NAME_LEN {
+        errors.push(format!("list name must be 1-{MAX_LIST_NAME_LEN} characters"));
+    }
+    if list.tokens.is_empty() || list.tokens.len() > MAX_TOKENS {
+        errors.push(format!("list must contain 1-{MAX_TOKENS} tokens"));
+    }
+    if let Some(logo) = &list.logo_uri {
+        if !valid_logo(logo) {
+            errors.push(format!("invalid list logoURI: {logo}"));
+        }
+    }
+    let mut seen = HashSet::new();
+    for token in &list.tokens {
+        let id = format!("{}:{:#x}", token.chain_id, token.address);
+        if token.chain_id == 0 {
+            errors.push(format!("{id}: chainId must be positive"));
+        }
+        if token.name.is_empty() || token.name.chars().count() > MAX_TOKEN_NAME_LEN {
+            errors.push(format!("{id}: name must be 1-{MAX_TOKEN_NAME_LEN} characters"));
+        }
+        if token.symbol.is_empty() || token.symbol.chars().count() > MAX_SYMBOL_LEN || token.symbol.contains(char::is_whitespace) {
+            errors.push(format!("{id}: invalid symbol {:?}", token.symbol));
+        }
+        if let Some(logo) = &token.logo_uri {
+            if !valid_logo(logo) {
+                errors.push(format!("{id}: invalid logoURI"));
+            }
+        }
+        if !seen.insert((token.chain_id, token.address)) {
+            errors.push(format!("{id}: duplicate token"));
+        }
+    }
+    errors
+}
+
+pub fn parse_list(json: &str) -> Result<TokenList, AppError> {
+    let list: TokenList = serde_json::from_str(json).map_err(|e| AppError::TokenListInvalid(e.to_string()))?;
+    let errors = validate_list(&list);
+    if !errors.is_empty() {
+        return Err(AppError::TokenListInvalid(errors.join("; ")));
+    }
+    Ok(list)
+}
+
+// ========== 版本与 diff ==========
+
+/// 按 token list 版本规则：删除 → major，新增 → minor，修改 → patch
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionBump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+pub fn version_bump(from: &TokenListVersion, to: &TokenListVersion) -> VersionBump {
+    if to.major > from.major {
+        VersionBump::Major
+    } else if to.major == from.major && to.minor > from.minor {
+        VersionBump::Minor
+    } else if to.major == from.major && to.minor == from.minor && to.patch > from.patch {
+        VersionBump::Patch
+    } else {
+        VersionBump::None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenChange {
+    pub before: TokenInfo,
+    pub after: TokenInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenListDiff {
+    pub url: String,
+    pub previous_version: Option<TokenListVersion>,
+    pub version: TokenListVersion,
+    pub added: Vec<TokenInfo>,
+    pub removed: Vec<TokenInfo>,
+    pub changed: Vec<TokenChange>,
+    /// 内容变化要求的最小版本升级
+    pub required_bump: VersionBump,
+    /// 发布方的版本号升级不足（例如删了代币却只升 minor），前端应提示
+    pub under_versioned: bool,
+}
+
+impl TokenListDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+pub fn diff_lists(url: &str, old: Option<&TokenList>, new: &TokenList) -> TokenListDiff {
+    let old_tokens: HashMap<(u64, Address), &TokenInfo> = old
+        .map(|l| l.tokens.iter().map(|t| ((t.chain_id, t.address), t)).collect())
+        .unwrap_or_default();
+    let new_keys: HashSet<(u64, Address)> = new.tokens.iter().map(|t| (t.chain_id, t.address)).collect();
+
+    let mut diff = TokenListDiff {
+        url: url.to_string(),
+        previous_version: old.map(|l| l.version),
+        version: new.version,
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+        required_bump: VersionBump::None,
+        under_versioned: false,
+    };
+    for token in &new.tokens {
+        match old_tokens.get(&(token.chain_id, token.address)) {
+            None => diff.added.push(token.clone()),
+            Some(before) if !before.same_details(token) => {
+                diff.changed.push(TokenChange { before: (*before).clone(), after: token.clone() })
+            }
+            _ => {}
+        }
+    }
+    if let Some(old) = old {
+        diff.removed = old
+            .tokens
+            .iter()
+            .filter(|t| !new_keys.contains(&(t.chain_id, t.address)))
+            .cloned()
+            .collect();
+        diff.required_bump = if !diff.removed.is_empty() {
+            VersionBump::Major
+        } else if !diff.added.is_empty() {
+            VersionBump::Minor
+        } else if !diff.changed.is_empty() {
+            VersionBump::Patch
+        } else {
+            VersionBump::None
+        };
+        diff.under_versioned = version_bump(&old.version, &new.version) < diff.required_bump;
+    }
+    diff
+}
+
+// ========== 存储 ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct StoredTokenList {
+    /// 来源地址；手动粘贴的列表为 "local:<name>"
+    pub url: String,
+    pub list: TokenList,
+    pub enabled: bool,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq, Default)]
+pub struct TokenPreference {
+    pub chain_id: u64,
+    pub address: Address,
+    pub hidden: bool,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", content = "name", rename_all = "lowercase")]
+pub enum TokenSource {
+    List(String),
+    Custom,
+}
+
+/// 前端展示用：合并后的代币 + 来源与偏好
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenView {
+    pub token: Token,
+    pub sources: Vec<TokenSource>,
+    pub hidden: bool,
+    pub pinned: bool,
+}
+
+fn list_key(mgr: &TableManager, url: &str) -> Vec<u8> {
+    mgr.key_from_str(&format!("{LIST_KEY_PREFIX}:{url}"))
+}
+
+fn custom_key(mgr: &TableManager, chain_id: u64, address: &Address) -> Vec<u8> {
+    mgr.key_from_str(&format!("{CUSTOM_KEY_PREFIX}:{chain_id}:{:#x}", address))
+}
+
+fn pref_key(mgr: &TableManager, chain_id: u64, address: &Address) -> Vec<u8> {
+    mgr.key_from_str(&format!("{PREF_KEY_PREFIX}:{chain_id}:{:#x}", address))
+}
+
+pub fn load_lists(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Vec<StoredTokenList>> {
+    TableManager::new(db, TableKind::Registry)?.list_by_prefix(&format!("{LIST_KEY_PREFIX}:"))
+}
+
+/// 合并启用的列表、自定义代币与偏好；自定义代币覆盖列表中的同地址条目。
+/// 置顶在前，其余按 (chain_id, symbol) 排序
+pub fn merge_tokens(
+    lists: &[StoredTokenList],
+    custom: &[Token],
+    prefs: &[TokenPreference],
+) -> Vec<TokenView> {
+    let mut merged: HashMap<(u64, Address), TokenView> = HashMap::new();
+    for stored in lists.iter().filter(|l| l.enabled) {
+        for info in &stored.list.tokens {
+            let view = merged.entry((info.chain_id, info.address)).or_insert_with(|| TokenView {
+                token: info.clone().into_inter(),
+                sources: Vec::new(),
+                hidden: false,
+                pinned: false,
+            });
+            view.sources.push(TokenSource::List(stored.list.name.clone()));
+        }
+    }
+    for token in custom {
+        let view = merged.entry((token.chain_id, token.address)).or_insert_with(|| TokenView {
+            token: token.clone(),
+            sources: Vec::new(),
+            hidden: false,
+            pinned: false,
+        });
+        view.token = token.clone();
+        view.sources.push(TokenSource::Custom);
+    }
+    for pref in prefs {
+        if let Some(view) = merged.get_mut(&(pref.chain_id, pref.address)) {
+            view.hidden = pref.hidden;
+            view.pinned = pref.pinned;
+        }
+    }
+    let mut views: Vec<TokenView> = merged.into_values().collect();
+    views.sort_by(|a, b| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then(a.token.chain_id.cmp(&b.token.chain_id))
+            .then(a.token.symbol.to_lowercase().cmp(&b.token.symbol.to_lowercase()))
+    });
+    views
+}
+
+pub fn load_views(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Vec<TokenView>> {
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    let lists = load_lists(db)?;
+    let custom = mgr.list_by_prefix::<Token>(&format!("{CUSTOM_KEY_PREFIX}:"))?;
+    let prefs = mgr.list_by_prefix::<TokenPreference>(&format!("{PREF_KEY_PREFIX}:"))?;
+    Ok(merge_tokens(&lists, &custom, &prefs))
+}
+
+/// 可见代币（AppState.user_tokens 的内容）
+pub fn load_user_tokens(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Vec<Token>> {
+    Ok(load_views(db)?.into_iter().filter(|v| !v.hidden).map(|v| v.token).collect())
+}
+
+/// 导出用的代币元数据：包含隐藏的代币，历史里仍可能出现
+pub fn token_meta_map(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<TokenMetaMap> {
+    Ok(load_views(db)?
+        .into_iter()
+        .map(|v| ((v.token.chain_id, v.token.address), (v.token.symbol, v.token.decimals.min(u8::MAX as u64) as u8)))
+        .collect())
+}
+
+fn sync_state(db: &DBWithThreadMode<MultiThreaded>, state: &AppState) -> DbResult<()> {
+    *state.user_tokens.blocking_lock() = Some(load_user_tokens(db)?);
+    Ok(())
+}
+
+/// 写入列表并返回与旧版本的 diff；拒绝版本号回退
+fn store_list(
+    db: &DBWithThreadMode<MultiThreaded>,
+    url: &str,
+    list: TokenList,
+    now: u64,
+) -> Result<TokenListDiff, AppError> {
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    let key = list_key(&mgr, url);
+    let previous = mgr.get::<StoredTokenList>(&key)?;
+    if let Some(prev) = &previous {
+        if list.version < prev.list.version {
+            return Err(AppError::TokenListVersionRollback(prev.list.version.to_string(), list.version.to_string()));
+        }
+    }
+    let diff = diff_lists(url, previous.as_ref().map(|p| &p.list), &list);
+    let enabled = previous.map(|p| p.enabled).unwrap_or(true);
+    mgr.set(&key, &StoredTokenList { url: url.to_string(), list, enabled, updated_at: now })?;
+    Ok(diff)
+}
+
+// ========== 命令 ==========
+
+#[tauri::command]
+pub fn token_list(
+    chain_id: Option<u64>,
+    include_hidden: Option<bool>,
+    appdb: State<AppDB>,
+) -> Result<Vec<TokenView>, AppError> {
+    let include_hidden = include_hidden.unwrap_or(false);
+    Ok(load_views(appdb.db.as_ref())?
+        .into_iter()
+        .filter(|v| chain_id.is_none_or(|c| v.token.chain_id == c))
+        .filter(|v| include_hidden || !v.hidden)
+        .collect())
+}
+
+#[tauri::command]
+pub fn tokenlist_list(appdb: State<AppDB>) -> Result<Vec<StoredTokenList>, AppError> {
+    Ok(load_lists(appdb.db.as_ref())?)
+}
+
+/// 导入粘贴的列表 JSON
+#[tauri::command]
+pub fn tokenlist_import(
+    json: String,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<TokenListDiff, AppError> {
+    let list = parse_list(&json)?;
+    let url = format!("local:{}", list.name);
+    let db = appdb.db.as_ref();
+    let diff = store_list(db, &url, list, now_s())?;
+    sync_state(db, &state)?;
+    Ok(diff)
+}
+
+/// 从 URL 拉取（首次即订阅）并返回变化
+#[tauri::command]
+pub async fn tokenlist_refresh(
+    url: String,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<TokenListDiff, AppError> {
+    let href = gateway_url(&url).ok_or_else(|| AppError::TokenListInvalid(format!("unsupported url {url}")))?;
+    let client = state.https_client.lock().await.clone();
+    let body = get_json(&client, &href).await?;
+    let list = parse_list(&body.to_string())?;
+    let db = appdb.db.as_ref();
+    let diff = store_list(db, &url, list, now_s())?;
+    *state.user_tokens.lock().await = Some(load_user_tokens(db)?);
+    Ok(diff)
+}
+
+#[tauri::command]
+pub fn tokenlist_set_enabled(
+    url: String,
+    enabled: bool,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    let key = list_key(&mgr, &url);
+    let mut stored = mgr.get::<StoredTokenList>(&key)?.ok_or(AppError::DbKeyNotFound)?;
+    stored.enabled = enabled;
+    mgr.set(&key, &stored)?;
+    sync_state(db, &state)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn tokenlist_remove(url: String, appdb: State<AppDB>, state: State<AppState>) -> Result<(), AppError> {
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    mgr.delete(&list_key(&mgr, &url))?;
+    sync_state(db, &state)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn token_custom_add(token: Token, appdb: State<AppDB>, state: State<AppState>) -> Result<(), AppError> {
+    if token.symbol.is_empty() || token.decimals > u8::MAX as u64 {
+        return Err(AppError::TokenListInvalid("invalid custom token".to_string()));
+    }
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    let token = Token {
+        contract_address: Some(token.address),
+        assets_type: token.assets_type.clone().or(Some(AssetsType::ERC20)),
+        ..token
+    };
+    mgr.set(&custom_key(&mgr, token.chain_id, &token.address), &token)?;
+    sync_state(db, &state)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn token_custom_delete(
+    chain_id: u64,
+    address: String,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let address = address
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    mgr.delete(&custom_key(&mgr, chain_id, &address))?;
+    sync_state(db, &state)?;
+    Ok(())
+}
+
+/// 隐藏 / 置顶；两者都为 false 时删除偏好
+#[tauri::command]
+pub fn token_set_preference(
+    chain_id: u64,
+    address: String,
+    hidden: bool,
+    pinned: bool,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let address = address
+        .parse::<Address>()
+        .map_err(|_| AppError::Parse("invalid address"))?;
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    let key = pref_key(&mgr, chain_id, &address);
+    if hidden || pinned {
+        mgr.set(&key, &TokenPreference { chain_id, address, hidden, pinned })?;
+    } else {
+        mgr.delete(&key)?;
+    }
+    sync_state(db, &state)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    const USDC: Address = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+    const DAI: Address = address!("0x6B175474E89094C44Da98b954EedeAC495271d0F");
+
+    const LIST_V1: &str = r#"{
+        "name": "Test List",
+        "timestamp": "2026-10-01T00:00:00.000Z",
+        "version": {"major": 1, "minor": 0, "patch": 0},
+        "logoURI": "ipfs://QmList",
+        "keywords": ["test"],
+        "tokens": [
+            {"chainId": 1, "address": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", "name": "USD Coin", "symbol": "USDC", "decimals": 6, "logoURI": "https://example.com/usdc.png"},
+            {"chainId": 1, "address": "0x6B175474E89094C44Da98b954EedeAC495271d0F", "name": "Dai Stablecoin", "symbol": "DAI", "decimals": 18, "extensions": {"bridgeInfo": {}}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_and_validate() {
+        let list = parse_list(LIST_V1).unwrap();
+        assert_eq!(list.version, TokenListVersion { major: 1, minor: 0, patch: 0 });
+        assert_eq!(list.tokens.len(), 2);
+
+        let usdc = list.tokens[0].clone().into_inter();
+        assert_eq!((usdc.decimals, usdc.logo_url.as_deref()), (6, Some("https://example.com/usdc.png")));
+
+        let mut bad = list.clone();
+        bad.tokens.push(bad.tokens[0].clone());
+        bad.tokens[1].symbol = "D A I".to_string();
+        bad.tokens[0].logo_uri = Some("javascript:alert(1)".to_string());
+        assert_eq!(validate_list(&bad).len(), 3);
+    }
+
+    #[test]
+    fn test_diff_and_version_rules() {
+        let v1 = parse_list(LIST_V1).unwrap();
+        let mut v2 = v1.clone();
+        v2.version.minor = 1;
+        v2.tokens.remove(1);
+        v2.tokens[0].logo_uri = None;
+
+        let diff = diff_lists("local:Test List", Some(&v1), &v2);
+        assert_eq!(diff.removed[0].address, DAI);
+        assert_eq!(diff.changed[0].after.address, USDC);
+        assert_eq!(diff.required_bump, VersionBump::Major);
+        // 删除代币只升了 minor
+        assert!(diff.under_versioned);
+
+        let fresh = diff_lists("local:Test List", None, &v1);
+        assert_eq!((fresh.added.len(), fresh.under_versioned), (2, false));
+    }
+
+    #[test]
+    fn test_merge_custom_and_preferences() {
+        let list = StoredTokenList {
+            url: "local:Test List".to_string(),
+            list: parse_list(LIST_V1).unwrap(),
+            enabled: true,
+            updated_at: 0,
+        };
+        let custom = Token { symbol: "USDC.custom".to_string(), ..list.list.tokens[0].clone().into_inter() };
+        let prefs = [
+            TokenPreference { chain_id: 1, address: DAI, hidden: false, pinned: true },
+            TokenPreference { chain_id: 1, address: USDC, hidden: true, pinned: false },
+        ];
+        let views = merge_tokens(&[list.clone()], &[custom], &prefs);
+        assert_eq!(views[0].token.address, DAI);
+        assert!(views[0].pinned);
+        let usdc = views.iter().find(|v| v.token.address == USDC).unwrap();
+        assert_eq!(usdc.token.symbol, "USDC.custom");
+        assert_eq!(usdc.sources.len(), 2);
+        assert!(usdc.hidden);
+
+        let disabled = StoredTokenList { enabled: false, ..list };
+        assert!(merge_tokens(&[disabled], &[], &[]).is_empty());
+    }
+}
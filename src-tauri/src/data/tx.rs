@@ -1,10 +1,42 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tauri::State;
 use bincode::{Decode, Encode};
 use crate::core::db::{AppDB,TxHistoryManager};
+use crate::core::state::AppState;
+use crate::data::poison::{collect_known, mark_history, own_addresses};
+use crate::data::tokenlist::token_meta_map;
 use crate::error::AppError;
-use alloy_primitives::{U256, U128, Address,TxHash};
+use crate::evm::calldata::{DecodedCall, decode_call};
+use crate::rpc::method::{get_transaction_by_hash, get_transaction_receipt};
+use crate::utils::num::{str_to_u64, str_to_u256};
+use alloy_primitives::{U256, U128, Address, B256, TxHash, b256};
+use alloy_sol_types::{SolEvent, sol};
+use std::str::FromStr;
 
+// ERC-20 / ERC-721 Transfer(address,address,uint256)，两者 topic0 相同，靠 indexed 数量区分
+pub const TRANSFER_TOPIC: B256 =
+    b256!("0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+
+sol! {
+    event TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value);
+    event TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values);
+}
+
+/// v1 记录（迁移前的 schema），只用于读取旧数据
+#[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, PartialEq)]
+pub struct TransactionHistoryEntryV1 {
+    pub chain_id: u64,
+    pub hash: TxHash,
+    pub block_number: u64,
+    pub nonce: U256,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub gas_price: Option<U256>,
+    pub gas_used: Option<U256>,
+    pub timestamp: Option<u64>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, PartialEq)]
 pub struct TransactionHistoryEntry {
@@ -18,11 +50,236 @@ pub struct TransactionHistoryEntry {
     pub gas_price: Option<U256>,
     pub gas_used: Option<U256>,
     pub timestamp: Option<u64>,
+
+    // ====== v2: receipt ======
+    pub status: Option<ReceiptStatus>,
+    pub tx_type: Option<u8>,                 // 0 legacy / 1 2930 / 2 1559 / 3 4844 / 4 7702
+    pub effective_gas_price: Option<U256>,
+    pub fee_paid: Option<U256>,              // gas_used * effective_gas_price (+ L1 fee)
+    pub method: Option<MethodCall>,
+    pub token_transfers: Vec<TokenTransferLeg>,
+
+    // ====== v2: 用户标注 ======
+    pub labels: Vec<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Encode, Decode, PartialEq)]
+pub enum ReceiptStatus {
+    Success,
+    Reverted,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, PartialEq)]
+pub struct MethodCall {
+    pub selector: String,
+    pub name: Option<String>,
+    pub params: Vec<MethodParam>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, PartialEq)]
+pub struct MethodParam {
+    pub name: String,
+    pub r#type: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Encode, Decode, PartialEq)]
+pub enum TokenStandard {
+    Erc20,
+    Erc721,
+    Erc1155,
+}
+
+/// receipt 中解析出的一条 Transfer 事件
+#[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, PartialEq)]
+pub struct TokenTransferLeg {
+    pub standard: TokenStandard,
+    pub token: Address,
+    pub from: Address,
+    pub to: Address,
+    pub token_id: Option<U256>,  // 721 / 1155
+    pub amount: U256,            // 721 恒为 1
+    pub log_index: u64,
+}
+
+impl From<TransactionHistoryEntryV1> for TransactionHistoryEntry {
+    fn from(v1: TransactionHistoryEntryV1) -> Self {
+        Self {
+            chain_id: v1.chain_id,
+            hash: v1.hash,
+            block_number: v1.block_number,
+            nonce: v1.nonce,
+            from: v1.from,
+            to: v1.to,
+            value: v1.value,
+            gas_price: v1.gas_price,
+            gas_used: v1.gas_used,
+            timestamp: v1.timestamp,
+            status: None,
+            tx_type: None,
+            effective_gas_price: None,
+            fee_paid: None,
+            method: None,
+            token_transfers: Vec::new(),
+            labels: Vec::new(),
+            note: None,
+        }
+    }
 }
+
+impl From<DecodedCall> for MethodCall {
+    fn from(call: DecodedCall) -> Self {
+        Self {
+            selector: String::new(),
+            name: Some(call.function),
+            params: call
+                .params
+                .into_iter()
+                .map(|p| MethodParam { name: p.name, r#type: p.r#type, value: p.value })
+                .collect(),
+        }
+    }
+}
+
 pub trait IntoInterTx{
     fn into_inter(self) -> TransactionHistoryEntry;
 }
 
+// ========== Receipt / Transaction 解析 ==========
+
+fn hex_u64(v: &Value) -> Option<u64> {
+    v.as_str().and_then(|s| str_to_u64(s).ok())
+}
+
+fn hex_u256(v: &Value) -> Option<U256> {
+    v.as_str().and_then(|s| str_to_u256(s).ok())
+}
+
+/// 用 eth_getTransactionByHash 的结果补全 type / nonce / method
+pub fn apply_transaction(entry: &mut TransactionHistoryEntry, tx: &Value) {
+    if let Some(t) = hex_u64(&tx["type"]) {
+        entry.tx_type = Some(t as u8);
+    }
+    if let Some(nonce) = hex_u256(&tx["nonce"]) {
+        entry.nonce = nonce;
+    }
+    let input = tx["input"].as_str().unwrap_or("0x");
+    entry.method = decode_method(input);
+}
+
+/// 解析 calldata → MethodCall；无法解码时只保留 selector
+pub fn decode_method(input: &str) -> Option<MethodCall> {
+    let hex = input.strip_prefix("0x").unwrap_or(input);
+    if hex.len() < 8 {
+        return None;
+    }
+    let selector = format!("0x{}", &hex[..8]);
+    let mut method = match decode_call(input, None) {
+        Ok(call) => MethodCall::from(call),
+        Err(_) => MethodCall { selector: String::new(), name: None, params: Vec::new() },
+    };
+    method.selector = selector;
+    Some(method)
+}
+
+/// 用 eth_getTransactionReceipt 的结果补全状态、费用与 Transfer 事件
+pub fn apply_receipt(entry: &mut TransactionHistoryEntry, receipt: &Value) {
+    entry.status = hex_u64(&receipt["status"]).map(|s| {
+        if s == 1 { ReceiptStatus::Success } else { ReceiptStatus::Reverted }
+    });
+    if let Some(n) = hex_u64(&receipt["blockNumber"]) {
+        entry.block_number = n;
+    }
+    if let Some(used) = hex_u256(&receipt["gasUsed"]) {
+        entry.gas_used = Some(used);
+    }
+    entry.effective_gas_price = hex_u256(&receipt["effectiveGasPrice"]).or(entry.gas_price);
+
+    // OP Stack 的 receipt 额外带 l1Fee
+    let l1_fee = hex_u256(&receipt["l1Fee"]).unwrap_or_default();
+    entry.fee_paid = match (entry.gas_used, entry.effective_gas_price) {
+        (Some(used), Some(price)) => Some(used.saturating_mul(price).saturating_add(l1_fee)),
+        _ => None,
+    };
+
+    if let Some(logs) = receipt["logs"].as_array() {
+        entry.token_transfers = parse_transfer_legs(logs);
+    }
+}
+
+/// 从 logs 中提取 ERC-20 / 721 / 1155 的转账腿
+pub fn parse_transfer_legs(logs: &[Value]) -> Vec<TokenTransferLeg> {
+    let mut legs = Vec::new();
+    for log in logs {
+        let Some(token) = log["address"].as_str().and_then(|a| Address::from_str(a).ok()) else {
+            continue;
+        };
+        let topics: Vec<B256> = log["topics"]
+            .as_array()
+            .map(|t| t.iter().filter_map(|v| v.as_str().and_then(|s| B256::from_str(s).ok())).collect())
+            .unwrap_or_default();
+        let Some(topic0) = topics.first() else { continue };
+        let data = log["data"]
+            .as_str()
+            .and_then(|d| hex::decode(d.trim_start_matches("0x")).ok())
+            .unwrap_or_default();
+        let log_index = hex_u64(&log["logIndex"]).unwrap_or_default();
+
+        if *topic0 == TRANSFER_TOPIC {
+            match topics.len() {
+                // ERC-20: value 在 data 中
+                3 if data.len() >= 32 => legs.push(TokenTransferLeg {
+                    standard: TokenStandard::Erc20,
+                    token,
+                    from: Address::from_word(topics[1]),
+                    to: Address::from_word(topics[2]),
+                    token_id: None,
+                    amount: U256::from_be_slice(&data[..32]),
+                    log_index,
+                }),
+                // ERC-721: tokenId 也是 indexed
+                4 => legs.push(TokenTransferLeg {
+                    standard: TokenStandard::Erc721,
+                    token,
+                    from: Address::from_word(topics[1]),
+                    to: Address::from_word(topics[2]),
+                    token_id: Some(U256::from_be_bytes(topics[3].0)),
+                    amount: U256::from(1),
+                    log_index,
+                }),
+                _ => {}
+            }
+        } else if *topic0 == TransferSingle::SIGNATURE_HASH {
+            if let Ok(ev) = TransferSingle::decode_raw_log(topics.iter().copied(), &data) {
+                legs.push(TokenTransferLeg {
+                    standard: TokenStandard::Erc1155,
+                    token,
+                    from: ev.from,
+                    to: ev.to,
+                    token_id: Some(ev.id),
+                    amount: ev.value,
+                    log_index,
+                });
+            }
+        } else if *topic0 == TransferBatch::SIGNATURE_HASH {
+            if let Ok(ev) = TransferBatch::decode_raw_log(topics.iter().copied(), &data) {
+                for (id, value) in ev.ids.iter().zip(ev.values.iter()) {
+                    legs.push(TokenTransferLeg {
+                        standard: TokenStandard::Erc1155,
+                        token,
+                        from: ev.from,
+                        to: ev.to,
+                        token_id: Some(*id),
+                        amount: *value,
+                        log_index,
+                    });
+                }
+            }
+        }
+    }
+    legs
+}
 
 // ========== Transaction History ==========
 #[tauri::command]
@@ -83,7 +340,57 @@ pub fn tx_batch_delete(
     mgr.batch_delete(chain_id, &hashs)
 }
 
+/// 用户给交易打标签 / 写备注
+#[tauri::command]
+pub fn tx_annotate(
+    chain_id: u64,
+    hash: String,
+    labels: Vec<String>,
+    note: Option<String>,
+    appdb: State<AppDB>,
+) -> Result<(), AppError> {
+    let db = appdb.db.as_ref();
+    let mgr = TxHistoryManager::new(db);
+    let mut entry = mgr.find(chain_id, &hash)?.ok_or(AppError::DbKeyNotFound)?;
+    entry.labels = labels;
+    entry.note = note.filter(|n| !n.trim().is_empty());
+    mgr.insert(&entry)
+}
+
+/// 拉取 tx + receipt，补全状态、手续费、方法名与 Transfer 腿后回写
+#[tauri::command]
+pub async fn tx_enrich(
+    chain_id: u64,
+    hash: String,
+    rpc_url: String,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<TransactionHistoryEntry, AppError> {
+    let db = appdb.db.as_ref();
+    let mgr = TxHistoryManager::new(db);
+    let mut entry = mgr.find(chain_id, &hash)?.ok_or(AppError::DbKeyNotFound)?;
+
+    let client = state.https_client.lock().await.clone();
+    let tx = get_transaction_by_hash(client.clone(), &rpc_url, &hash).await?;
+    if !tx.is_null() {
+        apply_transaction(&mut entry, &tx);
+    }
+    let receipt = get_transaction_receipt(client, &rpc_url, &hash).await?;
+    if !receipt.is_null() {
+        apply_receipt(&mut entry, &receipt);
+    }
+
+    // 补全 Transfer 腿后再做一次投毒判断
+    let own = own_addresses(&state.accounts.lock().await);
+    let book = state.address_books.lock().await.clone();
+    let history = mgr.range(chain_id, None, None)?;
+    let known = collect_known(&book, &own, &history, chain_id);
+    let tokens = token_meta_map(db)?;
+    mark_history(std::slice::from_mut(&mut entry), &own, &known, &tokens);
 
+    mgr.insert(&entry)?;
+    Ok(entry)
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, PartialEq)]
 pub struct PendingTx {
@@ -209,3 +516,75 @@ impl TxTracker {
             .map(|v| bincode::deserialize(&v).unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_transfer_legs() {
+        let logs = vec![
+            // ERC-20 Transfer 1000000
+            json!({
+                "address": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                "topics": [
+                    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                    "0x0000000000000000000000001111111111111111111111111111111111111111",
+                    "0x0000000000000000000000002222222222222222222222222222222222222222"
+                ],
+                "data": "0x00000000000000000000000000000000000000000000000000000000000f4240",
+                "logIndex": "0x1"
+            }),
+            // ERC-721 Transfer tokenId 7
+            json!({
+                "address": "0xbc4ca0eda7647a8ab7c2061c2e118a18a936f13d",
+                "topics": [
+                    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                    "0x0000000000000000000000002222222222222222222222222222222222222222",
+                    "0x0000000000000000000000001111111111111111111111111111111111111111",
+                    "0x0000000000000000000000000000000000000000000000000000000000000007"
+                ],
+                "data": "0x",
+                "logIndex": "0x2"
+            }),
+        ];
+
+        let legs = parse_transfer_legs(&logs);
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].standard, TokenStandard::Erc20);
+        assert_eq!(legs[0].from, address!("0x1111111111111111111111111111111111111111"));
+        assert_eq!(legs[0].amount, U256::from(1_000_000u64));
+        assert_eq!(legs[1].standard, TokenStandard::Erc721);
+        assert_eq!(legs[1].token_id, Some(U256::from(7)));
+        assert_eq!(legs[1].log_index, 2);
+    }
+
+    #[test]
+    fn test_apply_receipt_fee() {
+        let mut entry: TransactionHistoryEntry = TransactionHistoryEntryV1 {
+            chain_id: 1,
+            hash: TxHash::ZERO,
+            block_number: 0,
+            nonce: U256::ZERO,
+            from: Address::ZERO,
+            to: Address::ZERO,
+            value: U256::ZERO,
+            gas_price: None,
+            gas_used: None,
+            timestamp: None,
+        }
+        .into();
+        apply_receipt(&mut entry, &json!({
+            "status": "0x0",
+            "blockNumber": "0x10",
+            "gasUsed": "0x5208",
+            "effectiveGasPrice": "0x3b9aca00",
+            "logs": []
+        }));
+        assert_eq!(entry.status, Some(ReceiptStatus::Reverted));
+        assert_eq!(entry.block_number, 16);
+        assert_eq!(entry.fee_paid, Some(U256::from(21_000u64 * 1_000_000_000u64)));
+    }
+}
@@ -1,5 +1,9 @@
 use tauri::{Manager, Window};
 use serde::{Deserialize, Serialize};
+use crate::core::db::AppDB;
+use crate::core::state::AppState;
+use crate::evm::chains::{AddEthereumChainParameter, add_chain, chain_from_params};
+use crate::utils::time::now_s;
 
 #[derive(Serialize, Deserialize)]
 struct EthRequest {
@@ -23,6 +27,7 @@ pub async fn ethereum_request(
         "personal_sign" => personal_sign(window, params).await,
         "eth_signTypedData_v4" => sign_typed_data(window, params).await,
         "eth_sendTransaction" => send_tx(window, params).await,
+        "wallet_addEthereumChain" => add_ethereum_chain(window, params).await,
         _ => Err(format!("Unsupported method: {}", method)),
     }
 }
@@ -33,6 +38,48 @@ async fn chain_id() -> Result<serde_json::Value, String> {
     Ok(format!("0x{:x}", ui.current_chain_id).into())
 }
 
+/// EIP-3085：成功返回 null。先校验参数，再弹窗由用户确认后才写入注册表；
+/// 已存在的链（含内置链）本身不变，DApp 给出的新地址只记为建议
+async fn add_ethereum_chain(
+    window: tauri::Window,
+    params: Option<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let param = params
+        .and_then(|p| p.get(0).cloned())
+        .ok_or("missing chain parameter")?;
+    let param: AddEthereumChainParameter = serde_json::from_value(param).map_err(|e| e.to_string())?;
+    let preview = chain_from_params(&param, now_s()).map_err(|e| e.to_string())?;
+    let existing = {
+        let state = window.app_handle().state::<AppState>();
+        let registry = state.chain_registry.lock().await;
+        registry.get(preview.chain_id).cloned()
+    };
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    {
+        let mut ui = APP_STATE.persistent_config.lock().await;
+        ui.pending_requests.insert(request_id.clone(), PendingRequest { sender: tx });
+    }
+    window
+        .emit(
+            "wallet:request-add-chain",
+            serde_json::json!({ "id": request_id, "chain": preview, "existing": existing }),
+        )
+        .map_err(|e| e.to_string())?;
+
+    // 用户确认时 approve_request 传 true，其余一律视为拒绝（EIP-1193 4001）
+    let approved = rx.await.map_err(|_| "channel closed")?;
+    if approved != serde_json::Value::Bool(true) {
+        return Err("4001: User rejected the request".to_string());
+    }
+
+    let appdb = window.app_handle().state::<AppDB>();
+    let state = window.app_handle().state::<AppState>();
+    add_chain(appdb.db.as_ref(), &state, &param).await.map_err(|e| e.to_string())?;
+    Ok(serde_json::Value::Null)
+}
+
 async fn accounts() -> Result<serde_json::Value, String> {
     let ui = APP_STATE.persistent_config.lock().await;
     let acc = ui.current_account.clone().map(|a| vec![a]).unwrap_or(vec![]);
@@ -1,15 +1,17 @@
-use crate::core::state::{AppState, get_current_chain, get_persistent_config};
+use crate::actions::tx::buffered_gas;
+use crate::core::state::AppState;
 use crate::error::AppError;
-use crate::rpc::https::EthRpcProvider;
+use crate::evm::gas::{FeeTier, estimate_fees_for_chain};
+use crate::rpc::method::{estimate_gas_from, get_nonce};
 use alloy_consensus::{Signed, TxEip1559};
-use alloy_eips::eip1559::BaseFeeParams;
 use alloy_eips::eip2930::AccessList;
 use alloy_primitives::{Address, Bytes,B256, Signature, ChainId,TxKind, U256, keccak256};
+use reqwest::Client;
 use serde::Deserialize;
 use serde_json::Value;
 use tauri::State;
 use hex::{encode as hex_encode};
-use alloy_rlp::{Encodable, Decodable, RlpEncodable, EMPTY_LIST_CODE};
+use alloy_rlp::{Encodable, RlpEncodable};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Eip1559TxParams {
@@ -20,39 +22,48 @@ pub struct Eip1559TxParams {
 }
 
 pub async fn build_eip1559_tx(
-    provider: EthRpcProvider,
+    client: &Client,
+    url: &str,
+    from: Address,
     params: Value,
+    fee_tier: FeeTier,
     state: State<'_, AppState>,
 ) -> Result<TxEip1559, AppError> {
-    let chain_id = get_current_chain(state.clone())?;
-    let persistent_config = get_persistent_config(state.clone())?;
-    let from = persistent_config
-        .current_account_address
-        .ok_or(AppError::Parse("Current account address not set"))?;
-
-    // 验证 chain_id
+    let chain_id = state
+        .current_chain_id
+        .lock()
+        .await
+        .ok_or(AppError::Parse("Current chain not set"))?;
 
     let params: Eip1559TxParams =
         serde_json::from_value(params).map_err(AppError::JsonParseError)?;
 
-    // 3. 获取 nonce
-    let nonce = provider
-        .get_nonce(&from, "latest")
-        .await
-        .map_err(|e| AppError::HttpsRpcError(format!("Get nonce failed: {}", e)))?;
+    // 1. 获取 nonce（含 pending 交易）
+    let nonce = get_nonce(client.clone(), url, &format!("{:#x}", from), "pending").await?;
+
+    // 2. 手续费取预言机对应档位（已含 gas_price_multiplier 与 tip 上限）
+    let fees = estimate_fees_for_chain(client, url, chain_id, &state).await?;
+    let fee = fees.tier(fee_tier);
 
-    // 4. 简化 fee 估算（使用固定值）
-    let base_fee_per_gas = 10_000_000_000u128; // 10 Gwei as example
-    let max_priority_fee_per_gas = 2_000_000_000u128; // 2 Gwei
-    let max_fee_per_gas = base_fee_per_gas + max_priority_fee_per_gas;
+    // 3. 估算 gas 并上浮
+    let gas_limit = estimate_gas_from(
+        client.clone(),
+        url,
+        &format!("{:#x}", from),
+        &format!("{:#x}", params.to),
+        &format!("{:#x}", params.value_wei),
+        &format!("0x{}", hex_encode(&params.input)),
+    )
+    .await?
+    .saturating_to::<u64>();
 
-    // 6. 构造 EIP-1559 交易请求
+    // 4. 构造 EIP-1559 交易请求
     let tx = TxEip1559 {
         chain_id,
-        nonce: nonce as u64,
-        gas_limit: 21000,
-        max_fee_per_gas: max_fee_per_gas as u128,
-        max_priority_fee_per_gas: max_priority_fee_per_gas as u128,
+        nonce,
+        gas_limit: buffered_gas(gas_limit),
+        max_fee_per_gas: fee.max_fee_per_gas,
+        max_priority_fee_per_gas: fee.max_priority_fee_per_gas.min(fee.max_fee_per_gas),
         to: TxKind::Call(params.to),
         value: params.value_wei,
         input: params.input,
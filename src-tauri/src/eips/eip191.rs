@@ -10,6 +10,12 @@ pub fn hash_eip191_message(
     Ok(digest)
 }
 
+/// personal_sign 的参数可能是 0x hex 字节，按原始字节计算
+pub fn hash_eip191_bytes(message: &[u8]) -> B256 {
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+    keccak256([prefix.as_bytes(), message].concat())
+}
+
 
 
 
@@ -0,0 +1,66 @@
+// EIP-5792 钱包批量调用（wallet_sendCalls / wallet_getCapabilities）
+// 账户能原子执行多笔调用时（EIP-7702 委托账户）把多笔调用打成一个 bundle，否则逐笔发送
+
+use alloy_primitives::{Address, Bytes, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::eips::eip7702::DELEGATION_PREFIX;
+
+pub const SEND_CALLS_VERSION: &str = "2.0.0";
+
+/// wallet_getCapabilities 中的 atomic.status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AtomicStatus {
+    /// 账户已委托给某个合约；是否真能批量执行取决于委托合约，调用方需保留逐笔发送的退路
+    Supported,
+    /// 链支持 7702，账户升级后可原子执行
+    Ready,
+    Unsupported,
+}
+
+impl AtomicStatus {
+    /// account_code 为 eth_getCode 的返回值
+    pub fn detect(chain_eip7702: bool, account_code: &str) -> Self {
+        if !chain_eip7702 {
+            AtomicStatus::Unsupported
+        } else if account_code.to_lowercase().starts_with(DELEGATION_PREFIX) {
+            AtomicStatus::Supported
+        } else {
+            AtomicStatus::Ready
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Call {
+    pub to: Address,
+    #[serde(default)]
+    pub data: Bytes,
+    #[serde(default)]
+    pub value: U256,
+}
+
+/// wallet_sendCalls 的参数（单个对象，RPC 调用时放在数组里）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendCallsParams {
+    pub version: String,
+    /// 十六进制 chain id
+    pub chain_id: String,
+    pub from: Address,
+    pub atomic_required: bool,
+    pub calls: Vec<Call>,
+}
+
+impl SendCallsParams {
+    pub fn new(chain_id: u64, from: Address, calls: Vec<Call>, atomic_required: bool) -> Self {
+        Self {
+            version: SEND_CALLS_VERSION.to_string(),
+            chain_id: format!("0x{chain_id:x}"),
+            from,
+            atomic_required,
+            calls,
+        }
+    }
+}
@@ -13,6 +13,9 @@ use hex::{encode as hex_encode};
 use alloy_rlp::{Encodable, Decodable, RlpEncodable, EMPTY_LIST_CODE};
 use alloy_sol_types::Eip712Domain; 
 
+/// EIP-7702 委托账户的代码前缀
+pub const DELEGATION_PREFIX: &str = "0xef0100";
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Eip7702TxParams {
     pub to: Address,
@@ -0,0 +1,110 @@
+// ERC-1155 转账 calldata 与接收方检查
+// 单个与批量转账分别回调 onERC1155Received / onERC1155BatchReceived
+use crate::error::AppError;
+use crate::eips::erc721::returns_magic;
+use crate::evm::selector::IERC1155;
+use crate::rpc::method::eth_call_from;
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::{SolCall, sol};
+use reqwest::Client;
+
+/// ERC-165 接口 ID
+pub const INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+/// IERC1155Receiver 的 ERC-165 接口 ID（两个回调 selector 的异或）
+pub const RECEIVER_INTERFACE_ID: [u8; 4] = [0x4e, 0x23, 0x12, 0xe0];
+pub const RECEIVED_MAGIC: [u8; 4] = [0xf2, 0x3a, 0x6e, 0x61];
+pub const BATCH_RECEIVED_MAGIC: [u8; 4] = [0xbc, 0x19, 0x7c, 0x81];
+
+sol! {
+    interface IERC1155Receiver {
+        function onERC1155Received(address operator, address from, uint256 id, uint256 value, bytes data) external returns (bytes4);
+        function onERC1155BatchReceived(address operator, address from, uint256[] ids, uint256[] values, bytes data) external returns (bytes4);
+    }
+}
+
+pub fn safe_transfer_calldata(from: Address, to: Address, id: U256, amount: U256, data: &[u8]) -> Bytes {
+    IERC1155::safeTransferFromCall { from, to, id, amount, data: Bytes::copy_from_slice(data) }
+        .abi_encode()
+        .into()
+}
+
+pub fn safe_batch_transfer_calldata(
+    from: Address,
+    to: Address,
+    ids: Vec<U256>,
+    amounts: Vec<U256>,
+    data: &[u8],
+) -> Result<Bytes, AppError> {
+    if ids.is_empty() || ids.len() != amounts.len() {
+        return Err(AppError::Parse("ids and amounts must be non-empty and of equal length"));
+    }
+    Ok(IERC1155::safeBatchTransferFromCall { from, to, ids, amounts, data: Bytes::copy_from_slice(data) }
+        .abi_encode()
+        .into())
+}
+
+/// 以 contract 身份调用 recipient 的接收回调；items 多于一项时走批量回调
+/// Some(true) 接受；Some(false) revert 或返回值不对；None 网络失败
+pub async fn receiver_accepts(
+    client: &Client,
+    url: &str,
+    contract: &Address,
+    operator: &Address,
+    recipient: &Address,
+    items: &[(U256, U256)],
+) -> Option<bool> {
+    let (data, magic) = match items {
+        [(id, value)] => {
+            let call = IERC1155Receiver::onERC1155ReceivedCall {
+                operator: *operator,
+                from: *operator,
+                id: *id,
+                value: *value,
+                data: Bytes::new(),
+            };
+            (call.abi_encode(), RECEIVED_MAGIC)
+        }
+        _ => {
+            let call = IERC1155Receiver::onERC1155BatchReceivedCall {
+                operator: *operator,
+                from: *operator,
+                ids: items.iter().map(|(id, _)| *id).collect(),
+                values: items.iter().map(|(_, value)| *value).collect(),
+                data: Bytes::new(),
+            };
+            (call.abi_encode(), BATCH_RECEIVED_MAGIC)
+        }
+    };
+    let data = format!("0x{}", hex::encode(data));
+    match eth_call_from(client.clone(), url, &format!("{:#x}", contract), &format!("{:#x}", recipient), &data, "latest").await {
+        Ok(result) => Some(returns_magic(result.as_str().unwrap_or("0x"), magic)),
+        Err(AppError::RpcReverted(_)) => Some(false),
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[test]
+    fn test_interface_ids() {
+        let xor = |a: [u8; 4], b: [u8; 4]| [a[0] ^ b[0], a[1] ^ b[1], a[2] ^ b[2], a[3] ^ b[3]];
+        assert_eq!(IERC1155Receiver::onERC1155ReceivedCall::SELECTOR, RECEIVED_MAGIC);
+        assert_eq!(IERC1155Receiver::onERC1155BatchReceivedCall::SELECTOR, BATCH_RECEIVED_MAGIC);
+        assert_eq!(xor(RECEIVED_MAGIC, BATCH_RECEIVED_MAGIC), RECEIVER_INTERFACE_ID);
+    }
+
+    #[test]
+    fn test_batch_calldata() {
+        let from = address!("0x1111111111111111111111111111111111111111");
+        let to = address!("0x2222222222222222222222222222222222222222");
+        let single = safe_transfer_calldata(from, to, U256::from(1), U256::from(5), &[]);
+        assert_eq!(&single[..4], &[0xf2, 0x42, 0x43, 0x2a]);
+        let batch = safe_batch_transfer_calldata(from, to, vec![U256::from(1), U256::from(2)], vec![U256::from(5), U256::from(6)], &[])
+            .unwrap();
+        assert_eq!(&batch[..4], &[0x2e, 0xb2, 0xc2, 0xd6]);
+        assert!(safe_batch_transfer_calldata(from, to, vec![U256::from(1)], vec![], &[]).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+// ERC-721 转账 calldata 与接收方检查
+// safeTransferFrom 会回调收款合约的 onERC721Received，返回值不是 magic 则整笔交易 revert
+use crate::error::AppError;
+use crate::evm::selector::{IERC165, IERC721};
+use crate::rpc::method::eth_call_from;
+use alloy_primitives::{Address, Bytes, FixedBytes, U256};
+use alloy_sol_types::{SolCall, sol};
+use reqwest::Client;
+
+/// ERC-165 接口 ID
+pub const INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+/// onERC721Received(address,address,uint256,bytes) 的 selector，也是要求的返回值
+pub const RECEIVED_MAGIC: [u8; 4] = [0x15, 0x0b, 0x7a, 0x02];
+
+sol! {
+    interface IERC721Receiver {
+        function onERC721Received(address operator, address from, uint256 tokenId, bytes data) external returns (bytes4);
+    }
+}
+
+/// safeTransferFrom calldata；data 为空时用三参数重载
+pub fn safe_transfer_calldata(from: Address, to: Address, token_id: U256, data: &[u8]) -> Bytes {
+    if data.is_empty() {
+        IERC721::safeTransferFrom_0Call { from, to, tokenId: token_id }.abi_encode().into()
+    } else {
+        IERC721::safeTransferFrom_1Call { from, to, tokenId: token_id, data: Bytes::copy_from_slice(data) }
+            .abi_encode()
+            .into()
+    }
+}
+
+pub fn approve_calldata(spender: Address, token_id: U256) -> Bytes {
+    IERC721::approveCall { to: spender, tokenId: token_id }.abi_encode().into()
+}
+
+/// ERC-721 与 ERC-1155 的 setApprovalForAll 签名相同
+pub fn set_approval_for_all_calldata(operator: Address, approved: bool) -> Bytes {
+    IERC721::setApprovalForAllCall { operator, approved }.abi_encode().into()
+}
+
+/// 以 contract 身份调用 recipient 的 onERC721Received，模拟 safeTransferFrom 内部的回调
+/// Some(true) 接受；Some(false) revert 或返回值不对；None 网络失败
+pub async fn receiver_accepts(
+    client: &Client,
+    url: &str,
+    contract: &Address,
+    operator: &Address,
+    recipient: &Address,
+    token_id: U256,
+) -> Option<bool> {
+    let call = IERC721Receiver::onERC721ReceivedCall {
+        operator: *operator,
+        from: *operator,
+        tokenId: token_id,
+        data: Bytes::new(),
+    };
+    let data = format!("0x{}", hex::encode(call.abi_encode()));
+    match eth_call_from(client.clone(), url, &format!("{:#x}", contract), &format!("{:#x}", recipient), &data, "latest").await {
+        Ok(result) => Some(returns_magic(result.as_str().unwrap_or("0x"), RECEIVED_MAGIC)),
+        Err(AppError::RpcReverted(_)) => Some(false),
+        Err(_) => None,
+    }
+}
+
+/// 回调返回值的前 4 字节（bytes4 左对齐）是否等于 magic
+pub fn returns_magic(result_hex: &str, magic: [u8; 4]) -> bool {
+    hex::decode(result_hex.trim_start_matches("0x"))
+        .is_ok_and(|raw| raw.len() >= 32 && raw[..4] == magic && raw[4..32].iter().all(|b| *b == 0))
+}
+
+/// ERC-165 supportsInterface；revert（未实现 ERC-165）视为不支持
+pub async fn supports_interface(client: &Client, url: &str, contract: &Address, interface_id: [u8; 4]) -> Result<bool, AppError> {
+    let call = IERC165::supportsInterfaceCall { interfaceId: FixedBytes(interface_id) };
+    let data = format!("0x{}", hex::encode(call.abi_encode()));
+    let to = format!("{:#x}", contract);
+    // from 为零地址，避免部分节点拒绝无 from 的调用
+    match eth_call_from(client.clone(), url, &format!("{:#x}", Address::ZERO), &to, &data, "latest").await {
+        Ok(result) => {
+            let raw = hex::decode(result.as_str().unwrap_or("0x").trim_start_matches("0x"))
+                .map_err(|_| AppError::Parse("invalid supportsInterface result"))?;
+            Ok(IERC165::supportsInterfaceCall::abi_decode_returns(&raw).unwrap_or(false))
+        }
+        Err(AppError::RpcReverted(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[test]
+    fn test_safe_transfer_overloads() {
+        let from = address!("0x1111111111111111111111111111111111111111");
+        let to = address!("0x2222222222222222222222222222222222222222");
+        let plain = safe_transfer_calldata(from, to, U256::from(7), &[]);
+        assert_eq!(&plain[..4], &[0x42, 0x84, 0x2e, 0x0e]);
+        assert_eq!(plain.len(), 4 + 32 * 3);
+        let with_data = safe_transfer_calldata(from, to, U256::from(7), &[0xab]);
+        assert_eq!(&with_data[..4], &[0xb8, 0x8d, 0x4f, 0xde]);
+        assert_eq!(IERC721Receiver::onERC721ReceivedCall::SELECTOR, RECEIVED_MAGIC);
+    }
+
+    #[test]
+    fn test_returns_magic() {
+        let ok = format!("0x150b7a02{}", "0".repeat(56));
+        assert!(returns_magic(&ok, RECEIVED_MAGIC));
+        assert!(!returns_magic("0x", RECEIVED_MAGIC));
+        assert!(!returns_magic(&format!("0x{}", "0".repeat(64)), RECEIVED_MAGIC));
+    }
+}
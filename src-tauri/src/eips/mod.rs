@@ -5,10 +5,11 @@ pub mod eip712;
 pub mod eip1559;
 pub mod eip1193;
 pub mod eip2612;
+pub mod eip5792;
 pub mod eip6963;
 pub mod eip7702;
 
 // pub mod erc20;
-// pub mod erc721;
-// pub mod erc1155;
+pub mod erc721;
+pub mod erc1155;
 
@@ -24,6 +24,7 @@ pub enum AppError {
     ReqwestClientBuildError(reqwest::Error),
     ReqwestClientConnectionError(reqwest::Error),
     HttpsRpcError(u64, String),
+    RpcReverted(String), // revert data hex
     
     //eip712
     Eip712MissingDomain,
@@ -50,6 +51,35 @@ pub enum AppError {
     DbKeyNotFound,
     DbAccountNotFound(u64),
     DbVaultNotFound(String),
+
+    // Registry errors
+    RegistrySignatureInvalid,
+    RegistrySignersMissing,
+    RegistryVersionRollback(u64, u64),
+    TokenListInvalid(String),
+    TokenListVersionRollback(String, String),
+
+    // ENS errors
+    EnsInvalidName,
+    EnsCcipError(String),
+
+    // NFT / 交易错误
+    NftStandardUnknown(String),
+    TxSignerMismatch,
+    TxInsufficientFunds,
+    L2FeeUnavailable(String),
+    GasMultiplierInvalid(f32),
+    SignatureInvalid(String),
+    CalldataDecodeFailed(String),
+    AbiInvalid(String),
+    ClearSignDescriptorInvalid(String),
+    SimulationFailed(String),
+
+    // 链注册表错误
+    ChainParamsInvalid(String),
+    ChainRpcMismatch(u64),
+    ChainNotFound(u64),
+    ChainBuiltinReadonly(u64),
     
     // Wallet Core errors
     WalletCoreError(String),
@@ -96,6 +126,7 @@ impl fmt::Display for AppError {
             AppError::ReqwestClientBuildError(e) => write!(f, "Failed to create reqwest client: {}", e),
             AppError::ReqwestClientConnectionError(e) => write!(f, "Reqwest client error: {}", e),
             AppError::HttpsRpcError(code, message) => write!(f, "HTTPS RPC error {}: {}", code, message),
+            AppError::RpcReverted(data) => write!(f, "Execution reverted: {}", data),
             
             //eip712
             AppError::Eip712MissingDomain => write!(f, "EIP712 domain missing"),
@@ -131,6 +162,31 @@ impl fmt::Display for AppError {
             // state errors
             AppError::AlreadyInitialized => write!(f, "Already initialized"),
             AppError::InvalidPassword => write!(f, "Invalid password"),
+            AppError::EnsInvalidName => write!(f, "Invalid ENS name"),
+            AppError::EnsCcipError(e) => write!(f, "CCIP-read failed: {}", e),
+            AppError::NftStandardUnknown(addr) => write!(f, "{} is neither ERC-721 nor ERC-1155", addr),
+            AppError::TxSignerMismatch => write!(f, "Signature does not match the transaction sender"),
+            AppError::TxInsufficientFunds => write!(f, "Balance does not cover the network fee"),
+            AppError::L2FeeUnavailable(e) => write!(f, "L1 data fee unavailable: {}", e),
+            AppError::GasMultiplierInvalid(m) => write!(f, "Gas price multiplier must be between 1.0 and 5.0, got {}", m),
+            AppError::SignatureInvalid(e) => write!(f, "Invalid function or event signature: {}", e),
+            AppError::CalldataDecodeFailed(e) => write!(f, "Failed to decode calldata: {}", e),
+            AppError::AbiInvalid(e) => write!(f, "Invalid ABI: {}", e),
+            AppError::ClearSignDescriptorInvalid(e) => write!(f, "Invalid ERC-7730 descriptor: {}", e),
+            AppError::SimulationFailed(e) => write!(f, "Simulation failed: {}", e),
+            AppError::ChainParamsInvalid(e) => write!(f, "Invalid chain parameters: {}", e),
+            AppError::ChainRpcMismatch(id) => write!(f, "No RPC url returned chain id {}", id),
+            AppError::ChainNotFound(id) => write!(f, "Chain {} is not registered", id),
+            AppError::ChainBuiltinReadonly(id) => write!(f, "Built-in chain {} cannot be modified", id),
+            AppError::RegistrySignatureInvalid => write!(f, "Registry bundle signature is invalid"),
+            AppError::RegistrySignersMissing => write!(f, "No registry signers configured for this build"),
+            AppError::RegistryVersionRollback(current, got) => {
+                write!(f, "Registry bundle version {} is not newer than {}", got, current)
+            }
+            AppError::TokenListInvalid(e) => write!(f, "Invalid token list: {}", e),
+            AppError::TokenListVersionRollback(current, got) => {
+                write!(f, "Token list version {} is older than {}", got, current)
+            }
 
             // Helios errors
             AppError::HeliosClientError(e) => write!(f, "Helios client error: {}", e),
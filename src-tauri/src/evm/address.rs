@@ -86,6 +86,20 @@ pub fn is_valid_checksum(address: &str, chain_id: Option<u64>) -> bool {
     }
 }
 
+/// 地址投毒常见手法：伪造前后缀相同、中段不同的地址
+pub const LOOKALIKE_PREFIX_LEN: usize = 4;
+pub const LOOKALIKE_SUFFIX_LEN: usize = 4;
+
+/// 判断两个不同地址是否"看起来一样"（十六进制前 prefix 位、后 suffix 位都相同）
+pub fn is_lookalike(a: &Address, b: &Address, prefix: usize, suffix: usize) -> bool {
+    if a == b {
+        return false;
+    }
+    let a = hex::encode(a.as_slice());
+    let b = hex::encode(b.as_slice());
+    a[..prefix] == b[..prefix] && a[a.len() - suffix..] == b[b.len() - suffix..]
+}
+
 // ==================== 常用常量 ====================
 pub mod known {
     use super::Address;
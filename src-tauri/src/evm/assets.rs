@@ -1,9 +1,10 @@
 
+use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 use alloy_primitives::{Address,address};
 
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Encode, Decode)]
 pub enum AssetsType {
     Currency,
     ERC20,
@@ -18,8 +19,8 @@ pub trait IntoInterAsset {
 
 pub fn mapper_assets_type(asset_type: &str) -> AssetsType {
     let asset_type = asset_type.to_lowercase();
-    match asset_type {
-        "Currency" => AssetsType::Currency,
+    match asset_type.as_str() {
+        "currency" | "native" => AssetsType::Currency,
         "erc20" |"erc-20" => AssetsType::ERC20,
         "erc721" |"erc-721" => AssetsType::ERC721,
         "erc1155" |"erc-1155" => AssetsType::ERC1155,
@@ -1,68 +1,316 @@
-use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
-use alloy_json_abi::JsonAbi;
-use alloy_primitives::hex;
-use alloy_sol_types::SolInterface;
+// calldata 解码：ERC-20 → 调用方提供的 ABI → 离线签名库（多个候选时取能干净解码的那个），
+// 并递归展开 multicall(bytes[])、Safe execTransaction / MultiSend 与 Universal Router execute 中的子调用
+use alloy_dyn_abi::{DynSolType, DynSolValue, JsonAbiExt};
+use alloy_json_abi::{Event, Function, JsonAbi};
+use alloy_primitives::{Address, B256, Bytes, U256, hex};
+use alloy_sol_types::{SolInterface, sol};
+use serde::Serialize;
 use std::error::Error;
 
-use crate::contracts::erc20::IERC20::IERC20Calls;
+use crate::error::AppError;
+use crate::evm::fourbyte::SignatureDb;
+use crate::evm::selector::IERC20::IERC20Calls;
 
-#[derive(Debug, PartialEq)]
+/// 嵌套解码的最大深度
+const MAX_NESTING_DEPTH: usize = 4;
+/// Universal Router 命令字节低 6 位为命令类型，最高位表示允许失败
+const COMMAND_TYPE_MASK: u8 = 0x3f;
+/// Universal Router 各命令的输入参数（abi.encode，不带 selector）
+const UNIVERSAL_ROUTER_COMMANDS: &[(u8, &str)] = &[
+    (0x00, "V3_SWAP_EXACT_IN(address recipient,uint256 amountIn,uint256 amountOutMin,bytes path,bool payerIsUser)"),
+    (0x01, "V3_SWAP_EXACT_OUT(address recipient,uint256 amountOut,uint256 amountInMax,bytes path,bool payerIsUser)"),
+    (0x02, "PERMIT2_TRANSFER_FROM(address token,address recipient,uint160 amount)"),
+    (0x04, "SWEEP(address token,address recipient,uint256 amountMin)"),
+    (0x05, "TRANSFER(address token,address recipient,uint256 value)"),
+    (0x06, "PAY_PORTION(address token,address recipient,uint256 bips)"),
+    (0x08, "V2_SWAP_EXACT_IN(address recipient,uint256 amountIn,uint256 amountOutMin,address[] path,bool payerIsUser)"),
+    (0x09, "V2_SWAP_EXACT_OUT(address recipient,uint256 amountOut,uint256 amountInMax,address[] path,bool payerIsUser)"),
+    (0x0a, "PERMIT2_PERMIT(((address,uint160,uint48,uint48),address,uint256) permitSingle,bytes signature)"),
+    (0x0b, "WRAP_ETH(address recipient,uint256 amountMin)"),
+    (0x0c, "UNWRAP_WETH(address recipient,uint256 amountMin)"),
+    (0x0d, "PERMIT2_TRANSFER_FROM_BATCH((address,address,uint160,address)[] transferDetails)"),
+    (0x10, "V4_SWAP(bytes actions,bytes[] params)"),
+];
+
+sol! {
+    interface INestedCalls {
+        function multicall(bytes[] data) external payable returns (bytes[] memory results);
+        function multicall(uint256 deadline, bytes[] data) external payable returns (bytes[] memory results);
+        function multicall(bytes32 previousBlockhash, bytes[] data) external payable returns (bytes[] memory results);
+        function execTransaction(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, bytes signatures) external payable returns (bool success);
+        function multiSend(bytes transactions) external payable;
+        function execute(bytes commands, bytes[] inputs, uint256 deadline) external payable;
+        function execute(bytes commands, bytes[] inputs) external payable;
+    }
+}
+
+use INestedCalls::INestedCallsCalls;
+
+#[derive(Debug, PartialEq, Serialize)]
 pub struct DecodedCallParam {
     pub name: String,
     pub r#type: String,
     pub value: String,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
 pub struct DecodedCall {
     pub function: String,
+    /// 规范签名 name(types)
+    pub signature: String,
     pub params: Vec<DecodedCallParam>,
+    /// 子调用的目标合约（Safe / MultiSend 中给出）；multicall 的子调用目标即外层合约，为 None
+    pub target: Option<String>,
+    /// Safe / MultiSend 子调用的 operation：0 为 CALL，1 为 DELEGATECALL；其余子调用为 None
+    pub operation: Option<u8>,
+    pub nested: Vec<DecodedCall>,
+}
+
+impl DecodedCall {
+    pub fn new(function: impl Into<String>, params: Vec<DecodedCallParam>) -> Self {
+        let function = function.into();
+        let types: Vec<&str> = params.iter().map(|p| p.r#type.as_str()).collect();
+        Self { signature: format!("{}({})", function, types.join(",")), function, params, target: None, operation: None, nested: Vec::new() }
+    }
+
+    fn from_values(function: &Function, values: &[DynSolValue]) -> Self {
+        Self::new(
+            function.name.clone(),
+            function
+                .inputs
+                .iter()
+                .zip(values)
+                .enumerate()
+                .map(|(i, (input, value))| DecodedCallParam {
+                    name: param_name(&input.name, i),
+                    r#type: input.selector_type().into_owned(),
+                    value: format_param_value(value),
+                })
+                .collect(),
+        )
+    }
+
+    /// 无法解码的子调用：函数名为 selector，原始数据放在 data 参数里
+    fn unknown(data: &[u8]) -> Self {
+        let function = match data.get(..4) {
+            Some(selector) => format!("0x{}", hex::encode(selector)),
+            None => "unknown".to_string(),
+        };
+        Self::new(function, vec![DecodedCallParam { name: "data".into(), r#type: "bytes".into(), value: format!("0x{}", hex::encode(data)) }])
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DecodedEvent {
+    pub event: String,
+    pub signature: String,
+    pub params: Vec<DecodedCallParam>,
+}
+
+fn param_name(name: &str, index: usize) -> String {
+    if name.is_empty() { format!("arg{index}") } else { name.to_string() }
 }
 
 pub fn decode_call(calldata: &str, abi: Option<&str>) -> Result<DecodedCall, Box<dyn Error + Send + Sync>> {
     let calldata = hex::decode(calldata)?;
+    decode_bytes(&calldata, abi, 0)
+}
 
+fn decode_bytes(calldata: &[u8], abi: Option<&str>, depth: usize) -> Result<DecodedCall, Box<dyn Error + Send + Sync>> {
     // Check minimum calldata length early
     if calldata.len() < 4 {
         return Err("Calldata too short".into());
     }
 
-    // Try ERC20 interface first if no ABI provided
-    if abi.is_none()
-        && let Ok(call) = IERC20Calls::abi_decode(&calldata)
-    {
-        return Ok(call.into());
-    }
-
-    if let Some(abi_str) = abi {
-        let abi = serde_json::from_str::<JsonAbi>(abi_str)?;
-        let selector = &calldata[..4];
-
-        for function in abi.functions() {
-            if function.selector() == selector {
-                if let Ok(params) = function.abi_decode_input(&calldata[4..]) {
-                    return Ok(DecodedCall {
-                        function: function.name.clone(),
-                        params: function
-                            .inputs
-                            .iter()
-                            .zip(params.iter())
-                            .map(|(input, output)| DecodedCallParam {
-                                name: input.name.clone(),
-                                r#type: input.ty.to_string(),
-                                value: format_param_value(output),
-                            })
-                            .collect(),
-                    });
-                } else {
-                    return Err(format!("Failed to decode function parameters for {}", function.name).into());
-                }
+    let mut call = match abi {
+        Some(abi_str) => decode_with_abi(calldata, abi_str)?,
+        // Try ERC20 interface first if no ABI provided, then the signature database
+        None => match IERC20Calls::abi_decode(calldata) {
+            Ok(call) => call.into(),
+            Err(_) => decode_with_signatures(calldata, &SignatureDb::global()).ok_or("Failed to decode calldata")?,
+        },
+    };
+    if depth < MAX_NESTING_DEPTH {
+        call.nested = nested_calls(calldata, abi, depth + 1);
+    }
+    Ok(call)
+}
+
+fn decode_with_abi(calldata: &[u8], abi_str: &str) -> Result<DecodedCall, Box<dyn Error + Send + Sync>> {
+    let abi = serde_json::from_str::<JsonAbi>(abi_str)?;
+    let selector = &calldata[..4];
+
+    for function in abi.functions() {
+        if function.selector() == selector {
+            if let Ok(params) = function.abi_decode_input(&calldata[4..]) {
+                return Ok(DecodedCall::from_values(function, &params));
+            } else {
+                return Err(format!("Failed to decode function parameters for {}", function.name).into());
             }
         }
-        return Err(format!("No matching function found for selector {:02x?}", selector).into());
     }
+    Err(format!("No matching function found for selector {:02x?}", selector).into())
+}
+
+/// 按单个签名解码；strict 时要求重新编码后与原数据逐字节一致，
+/// selector 碰撞的候选通常只能宽松解码
+pub fn decode_with_signature(signature: &str, calldata: &[u8], strict: bool) -> Option<DecodedCall> {
+    let function = Function::parse(signature).ok()?;
+    if calldata.len() < 4 || function.selector() != &calldata[..4] {
+        return None;
+    }
+    let values = function.abi_decode_input(&calldata[4..]).ok()?;
+    if strict && function.abi_encode_input(&values).ok()? != calldata {
+        return None;
+    }
+    Some(DecodedCall::from_values(&function, &values))
+}
 
-    Err("Failed to decode calldata".into())
+/// 先找能干净解码的候选，没有再退回第一个能宽松解码的
+pub fn decode_with_signatures(calldata: &[u8], db: &SignatureDb) -> Option<DecodedCall> {
+    let selector: [u8; 4] = calldata.get(..4)?.try_into().ok()?;
+    let candidates = db.functions(&selector);
+    candidates
+        .iter()
+        .find_map(|sig| decode_with_signature(sig, calldata, true))
+        .or_else(|| candidates.iter().find_map(|sig| decode_with_signature(sig, calldata, false)))
+}
+
+fn nested_calls(calldata: &[u8], abi: Option<&str>, depth: usize) -> Vec<DecodedCall> {
+    let Ok(call) = INestedCallsCalls::abi_decode(calldata) else {
+        return Vec::new();
+    };
+    match call {
+        // multicall 的子调用发往同一合约，沿用同一份 ABI
+        INestedCallsCalls::multicall_0(c) => inner_calls(&c.data, abi, depth),
+        INestedCallsCalls::multicall_1(c) => inner_calls(&c.data, abi, depth),
+        INestedCallsCalls::multicall_2(c) => inner_calls(&c.data, abi, depth),
+        INestedCallsCalls::execTransaction(c) => vec![target_call(c.operation, c.to, c.value, &c.data, depth)],
+        INestedCallsCalls::multiSend(c) => parse_multisend(&c.transactions)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(operation, to, value, data)| target_call(operation, to, value, data, depth))
+            .collect(),
+        INestedCallsCalls::execute_0(c) => universal_router_commands(&c.commands, &c.inputs),
+        INestedCallsCalls::execute_1(c) => universal_router_commands(&c.commands, &c.inputs),
+    }
+}
+
+fn inner_calls(calls: &[Bytes], abi: Option<&str>, depth: usize) -> Vec<DecodedCall> {
+    calls
+        .iter()
+        .map(|data| decode_bytes(data, abi, depth).unwrap_or_else(|_| DecodedCall::unknown(data)))
+        .collect()
+}
+
+/// 带目标地址与 operation 的子调用；data 为空时是原生币转账
+fn target_call(operation: u8, to: Address, value: U256, data: &[u8], depth: usize) -> DecodedCall {
+    let mut call = if data.is_empty() {
+        DecodedCall::new("nativeTransfer", vec![DecodedCallParam { name: "value".into(), r#type: "uint256".into(), value: value.to_string() }])
+    } else {
+        decode_bytes(data, None, depth).unwrap_or_else(|_| DecodedCall::unknown(data))
+    };
+    call.target = Some(to.to_string());
+    call.operation = Some(operation);
+    call
+}
+
+/// MultiSend 的紧凑编码：operation(1) | to(20) | value(32) | dataLength(32) | data
+pub fn parse_multisend(mut packed: &[u8]) -> Option<Vec<(u8, Address, U256, &[u8])>> {
+    const HEADER: usize = 1 + 20 + 32 + 32;
+    let mut txs = Vec::new();
+    while !packed.is_empty() {
+        if packed.len() < HEADER {
+            return None;
+        }
+        let to = Address::from_slice(&packed[1..21]);
+        let value = U256::from_be_slice(&packed[21..53]);
+        let len = usize::try_from(U256::from_be_slice(&packed[53..HEADER])).ok()?;
+        let end = HEADER.checked_add(len).filter(|end| *end <= packed.len())?;
+        txs.push((packed[0], to, value, &packed[HEADER..end]));
+        packed = &packed[end..];
+    }
+    Some(txs)
+}
+
+fn universal_router_commands(commands: &[u8], inputs: &[Bytes]) -> Vec<DecodedCall> {
+    commands
+        .iter()
+        .zip(inputs)
+        .map(|(command, input)| {
+            let command = command & COMMAND_TYPE_MASK;
+            UNIVERSAL_ROUTER_COMMANDS
+                .iter()
+                .find(|(id, _)| *id == command)
+                .and_then(|(_, signature)| {
+                    let function = Function::parse(signature).ok()?;
+                    let values = function.abi_decode_input(input).ok()?;
+                    Some(DecodedCall::from_values(&function, &values))
+                })
+                .unwrap_or_else(|| {
+                    DecodedCall::new(
+                        format!("COMMAND_0x{command:02x}"),
+                        vec![DecodedCallParam { name: "input".into(), r#type: "bytes".into(), value: format!("0x{}", hex::encode(input)) }],
+                    )
+                })
+        })
+        .collect()
+}
+
+/// 用签名库解码日志；topics[0] 为事件签名哈希
+pub fn decode_event(topics: &[B256], data: &[u8]) -> Option<DecodedEvent> {
+    let (topic0, indexed) = topics.split_first()?;
+    let candidates = SignatureDb::global().events(topic0).to_vec();
+    candidates.iter().find_map(|sig| decode_event_with_signature(sig, indexed, data))
+}
+
+pub fn decode_event_with_signature(signature: &str, indexed: &[B256], data: &[u8]) -> Option<DecodedEvent> {
+    let mut event = Event::parse(signature).ok()?;
+    // 签名不带 indexed 时按惯例假定前 n 个参数为 indexed
+    if event.inputs.iter().all(|p| !p.indexed) {
+        event.inputs.iter_mut().take(indexed.len()).for_each(|p| p.indexed = true);
+    }
+    if event.inputs.iter().filter(|p| p.indexed).count() != indexed.len() {
+        return None;
+    }
+    let body_types = event
+        .inputs
+        .iter()
+        .filter(|p| !p.indexed)
+        .map(|p| DynSolType::parse(&p.selector_type()))
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    let body = DynSolType::Tuple(body_types).abi_decode_params(data).ok()?;
+    if body.abi_encode_params() != data {
+        return None;
+    }
+    let DynSolValue::Tuple(body) = body else {
+        return None;
+    };
+    let (mut topics, mut body) = (indexed.iter(), body.iter());
+    let mut params = Vec::with_capacity(event.inputs.len());
+    for (i, input) in event.inputs.iter().enumerate() {
+        let r#type = input.selector_type().into_owned();
+        let value = if input.indexed {
+            let topic = topics.next()?;
+            match DynSolType::parse(&r#type).ok()? {
+                ty @ (DynSolType::Address | DynSolType::Bool | DynSolType::Int(_) | DynSolType::Uint(_) | DynSolType::FixedBytes(_)) => {
+                    format_param_value(&ty.abi_decode(topic.as_slice()).ok()?)
+                }
+                // 引用类型 indexed 时 topic 只是哈希
+                _ => format!("{topic:#x}"),
+            }
+        } else {
+            format_param_value(body.next()?)
+        };
+        params.push(DecodedCallParam { name: param_name(&input.name, i), r#type, value });
+    }
+    Some(DecodedEvent { event: event.name.clone(), signature: event.signature(), params })
+}
+
+#[tauri::command]
+pub fn calldata_decode(calldata: String, abi: Option<String>) -> Result<DecodedCall, AppError> {
+    decode_call(&calldata, abi.as_deref()).map_err(|e| AppError::CalldataDecodeFailed(e.to_string()))
 }
 
 pub fn format_param_value(value: &DynSolValue) -> String {
@@ -111,6 +359,22 @@ impl From<IERC20Calls> for DecodedCall {
             IERC20Calls::name(_) => ("name", vec![]),
             IERC20Calls::symbol(_) => ("symbol", vec![]),
             IERC20Calls::decimals(_) => ("decimals", vec![]),
+            IERC20Calls::totalSupply(_) => ("totalSupply", vec![]),
+            IERC20Calls::balanceOf(balance_of) => ("balanceOf", vec![("account", "address", balance_of._0.to_string())]),
+            IERC20Calls::permit(permit) => (
+                "permit",
+                vec![
+                    ("owner", "address", permit.owner.to_string()),
+                    ("spender", "address", permit.spender.to_string()),
+                    ("value", "uint256", permit.value.to_string()),
+                    ("deadline", "uint256", permit.deadline.to_string()),
+                    ("v", "uint8", permit.v.to_string()),
+                    ("r", "bytes32", permit.r.to_string()),
+                    ("s", "bytes32", permit.s.to_string()),
+                ],
+            ),
+            IERC20Calls::nonces(nonces) => ("nonces", vec![("owner", "address", nonces.owner.to_string())]),
+            IERC20Calls::DOMAIN_SEPARATOR(_) => ("DOMAIN_SEPARATOR", vec![]),
             IERC20Calls::allowance(allowance) => (
                 "allowance",
                 vec![
@@ -120,9 +384,9 @@ impl From<IERC20Calls> for DecodedCall {
             ),
         };
 
-        DecodedCall {
-            function: function.to_string(),
-            params: params
+        DecodedCall::new(
+            function,
+            params
                 .into_iter()
                 .map(|(name, r#type, value)| DecodedCallParam {
                     name: name.to_string(),
@@ -130,13 +394,15 @@ impl From<IERC20Calls> for DecodedCall {
                     value,
                 })
                 .collect(),
-        }
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::evm::selector::IERC20;
+    use alloy_sol_types::SolCall;
 
     #[test]
     fn test_decode_erc20_transfer() {
@@ -224,4 +490,114 @@ mod tests {
         assert_eq!(allowance_result.params[1].r#type, "address");
         assert_eq!(allowance_result.params[1].value, "0x271682DEB8C4E0901D1a1550aD2e64D568E69909");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_selector_collision_prefers_clean_decode() {
+        let calldata = IERC20::transferCall { to: Address::repeat_byte(0x11), value: U256::from(5) }.abi_encode();
+        let mut db = SignatureDb::builtin();
+        db.add_user(crate::evm::fourbyte::SignatureKind::Function, "many_msg_babbage(bytes1)").unwrap();
+        assert_eq!(db.functions(&[0xa9, 0x05, 0x9c, 0xbb])[0], "many_msg_babbage(bytes1)");
+
+        let decoded = decode_with_signatures(&calldata, &db).unwrap();
+        assert_eq!(decoded.signature, "transfer(address,uint256)");
+        assert_eq!(decoded.params[1].value, "5");
+    }
+
+    #[test]
+    fn test_decode_nested_multicall() {
+        let approve = |spender: u8| {
+            Bytes::from(IERC20::approveCall { spender: Address::repeat_byte(spender), value: U256::MAX }.abi_encode())
+        };
+        let calldata = INestedCalls::multicall_0Call { data: vec![approve(0x22), approve(0x33), Bytes::from(vec![0xde, 0xad, 0xbe, 0xef])] }.abi_encode();
+        let decoded = decode_call(&hex::encode(calldata), None).unwrap();
+
+        assert_eq!(decoded.function, "multicall");
+        assert_eq!(decoded.nested.len(), 3);
+        assert_eq!(decoded.nested[0].function, "approve");
+        assert_eq!(decoded.nested[1].params[0].value, Address::repeat_byte(0x33).to_string());
+        assert_eq!(decoded.nested[2].function, "0xdeadbeef");
+        assert!(decoded.nested.iter().all(|c| c.target.is_none() && c.operation.is_none()));
+    }
+
+    #[test]
+    fn test_decode_safe_multisend() {
+        let recipient = Address::repeat_byte(0x44);
+        let token = Address::repeat_byte(0x55);
+        let transfer = IERC20::transferCall { to: recipient, value: U256::from(1_000_000) }.abi_encode();
+        let mut packed = Vec::new();
+        for (operation, to, value, data) in [(0u8, recipient, U256::from(10u64.pow(18)), Vec::new()), (1u8, token, U256::ZERO, transfer)] {
+            packed.push(operation);
+            packed.extend_from_slice(to.as_slice());
+            packed.extend_from_slice(&value.to_be_bytes::<32>());
+            packed.extend_from_slice(&U256::from(data.len()).to_be_bytes::<32>());
+            packed.extend_from_slice(&data);
+        }
+        let multisend = INestedCalls::multiSendCall { transactions: packed.into() }.abi_encode();
+        let calldata = INestedCalls::execTransactionCall {
+            to: Address::repeat_byte(0x66),
+            value: U256::ZERO,
+            data: multisend.into(),
+            operation: 1,
+            safeTxGas: U256::ZERO,
+            baseGas: U256::ZERO,
+            gasPrice: U256::ZERO,
+            gasToken: Address::ZERO,
+            refundReceiver: Address::ZERO,
+            signatures: Bytes::new(),
+        }
+        .abi_encode();
+        let decoded = decode_call(&hex::encode(calldata), None).unwrap();
+
+        assert_eq!(decoded.function, "execTransaction");
+        let multisend = &decoded.nested[0];
+        assert_eq!(multisend.function, "multiSend");
+        assert_eq!(multisend.target, Some(Address::repeat_byte(0x66).to_string()));
+        assert_eq!(multisend.operation, Some(1));
+        assert_eq!(multisend.nested.len(), 2);
+        assert_eq!(multisend.nested[0].function, "nativeTransfer");
+        assert_eq!(multisend.nested[0].params[0].value, "1000000000000000000");
+        assert_eq!(multisend.nested[1].function, "transfer");
+        assert_eq!(multisend.nested[1].target, Some(token.to_string()));
+        let operations: Vec<_> = multisend.nested.iter().map(|c| c.operation).collect();
+        assert_eq!(operations, [Some(0), Some(1)]);
+        // 截断的 MultiSend 数据不展开
+        assert!(parse_multisend(&[0u8; 40]).is_none());
+    }
+
+    #[test]
+    fn test_decode_universal_router_execute() {
+        // WRAP_ETH → V3_SWAP_EXACT_IN → PAY_PORTION → SWEEP
+        let calldata = "0x3593564c000000000000000000000000000000000000000000000000000000000000006000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000006711e95700000000000000000000000000000000000000000000000000000000000000040b000604000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000004000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000e0000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000002800000000000000000000000000000000000000000000000000000000000000040000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000038d7ea4c680000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000038d7ea4c68000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000a00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000002b42000000000000000000000000000000000000060001f40b2c639c533813f4aa9d7837caf62653d097ff8500000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000600000000000000000000000000b2c639c533813f4aa9d7837caf62653d097ff850000000000000000000000007ffc3dbf3b2b50ff3a1d5523bc24bb5043837b14000000000000000000000000000000000000000000000000000000000000001900000000000000000000000000000000000000000000000000000000000000600000000000000000000000000b2c639c533813f4aa9d7837caf62653d097ff850000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000027a2d9";
+        let decoded = decode_call(calldata, None).unwrap();
+
+        assert_eq!(decoded.signature, "execute(bytes,bytes[],uint256)");
+        let commands: Vec<&str> = decoded.nested.iter().map(|c| c.function.as_str()).collect();
+        assert_eq!(commands, ["WRAP_ETH", "V3_SWAP_EXACT_IN", "PAY_PORTION", "SWEEP"]);
+        assert_eq!(decoded.nested[2].params[2].value, "25");
+        assert_eq!(decoded.nested[3].params[2].name, "amountMin");
+        assert_eq!(decoded.nested[3].params[2].value, "2597593");
+    }
+
+    #[test]
+    fn test_decode_transfer_events() {
+        let (from, to) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02));
+        let topic0 = crate::evm::fourbyte::event_topic("Transfer(address,address,uint256)").unwrap();
+
+        // ERC-20：value 在 data 里
+        let erc20 = decode_event(&[topic0, from.into_word(), to.into_word()], &U256::from(42).to_be_bytes::<32>()).unwrap();
+        assert_eq!(erc20.signature, "Transfer(address,address,uint256)");
+        assert_eq!(erc20.params[2].name, "value");
+        assert_eq!(erc20.params[2].value, "42");
+
+        // ERC-721：tokenId 是第三个 topic
+        let erc721 = decode_event(&[topic0, from.into_word(), to.into_word(), B256::from(U256::from(7))], &[]).unwrap();
+        assert_eq!(erc721.params[2].name, "tokenId");
+        assert_eq!(erc721.params[2].value, "7");
+        assert_eq!(erc721.params[1].value, to.to_string());
+
+        // 不带 indexed 的签名按前 n 个参数处理
+        let bare = decode_event_with_signature("Approval(address,address,uint256)", &[from.into_word(), to.into_word()], &U256::from(1).to_be_bytes::<32>()).unwrap();
+        assert_eq!(bare.params[0].name, "arg0");
+        assert!(decode_event(&[topic0, from.into_word()], &[]).is_none());
+    }
+}
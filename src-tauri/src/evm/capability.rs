@@ -0,0 +1,180 @@
+// 链能力元数据：交易类型、L2 类型、常用合约地址、出块时间与确认深度
+// 内置链用下表；自定义链添加时通过 RPC 探测一次，存 Registry 表（chaincap:{id}），用户可修正
+use crate::evm::address::known::PERMIT2;
+use crate::rpc::method::{block_number, get_block_by_number, get_code};
+use crate::rpc::multicall3::MULTICALL3_ADDR;
+use alloy_primitives::{Address, address};
+use bincode::{Decode, Encode};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+pub const CAPABILITY_KEY_PREFIX: &str = "chaincap";
+/// 未知链的默认值（按以太坊主网保守处理）
+pub const DEFAULT_BLOCK_TIME_MS: u64 = 12_000;
+pub const DEFAULT_FINALITY_BLOCKS: u64 = 64;
+/// 探测出块时间时回看的区块数
+const BLOCK_TIME_SAMPLE: u64 = 100;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum L2Type {
+    OpStack,
+    Arbitrum,
+    ZkRollup,
+    /// 数据成本计入 priorityFeePerGas，需用 linea_estimateGas 取费用
+    Linea,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct ChainCapabilities {
+    pub chain_id: u64,
+    /// 支持 type-2 交易；否则只发 legacy
+    pub eip1559: bool,
+    /// 支持 type-4（SetCode）交易
+    pub eip7702: bool,
+    pub l2: Option<L2Type>,
+    /// 包装原生币（WETH / WBNB / WPOL）
+    pub weth: Option<Address>,
+    pub multicall3: Option<Address>,
+    pub permit2: Option<Address>,
+    pub block_time_ms: u64,
+    /// 交易视为不可回滚所需的确认数
+    pub finality_blocks: u64,
+    /// Helios 轻客户端可用
+    pub light_client: bool,
+}
+
+impl ChainCapabilities {
+    /// 未知链：只假设 legacy 交易可用，不假设任何合约存在
+    pub fn unknown(chain_id: u64) -> Self {
+        Self {
+            chain_id,
+            eip1559: false,
+            eip7702: false,
+            l2: None,
+            weth: None,
+            multicall3: None,
+            permit2: None,
+            block_time_ms: DEFAULT_BLOCK_TIME_MS,
+            finality_blocks: DEFAULT_FINALITY_BLOCKS,
+            light_client: false,
+        }
+    }
+
+    /// 确认 confirmations 个区块大约需要的时间（毫秒）
+    pub fn finality_ms(&self) -> u64 {
+        self.finality_blocks.saturating_mul(self.block_time_ms)
+    }
+}
+
+struct Builtin {
+    chain_id: u64,
+    eip7702: bool,
+    l2: Option<L2Type>,
+    weth: Address,
+    block_time_ms: u64,
+    finality_blocks: u64,
+    light_client: bool,
+}
+
+/// 内置链全部支持 EIP-1559，并部署了 Multicall3 与 Permit2
+const BUILTINS: &[Builtin] = &[
+    Builtin { chain_id: 1, eip7702: true, l2: None, weth: address!("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"), block_time_ms: 12_000, finality_blocks: 64, light_client: true },
+    Builtin { chain_id: 10, eip7702: true, l2: Some(L2Type::OpStack), weth: address!("0x4200000000000000000000000000000000000006"), block_time_ms: 2_000, finality_blocks: 30, light_client: false },
+    Builtin { chain_id: 56, eip7702: true, l2: None, weth: address!("0xbb4cdb9cbd36b01bd1cbaebf2de08d9173bc095c"), block_time_ms: 750, finality_blocks: 15, light_client: false },
+    Builtin { chain_id: 137, eip7702: true, l2: None, weth: address!("0x0d500b1d8e8ef31e21c99d1db9a6444d3adf1270"), block_time_ms: 2_000, finality_blocks: 32, light_client: false },
+    Builtin { chain_id: 8453, eip7702: true, l2: Some(L2Type::OpStack), weth: address!("0x4200000000000000000000000000000000000006"), block_time_ms: 2_000, finality_blocks: 30, light_client: true },
+    Builtin { chain_id: 42161, eip7702: true, l2: Some(L2Type::Arbitrum), weth: address!("0x82af49447d8a07e3bd95bd0d56f35241523fbab1"), block_time_ms: 250, finality_blocks: 240, light_client: false },
+    Builtin { chain_id: 59144, eip7702: false, l2: Some(L2Type::Linea), weth: address!("0xe5d7c2a44ffddf6b295a15c148167daaaf5cf34f"), block_time_ms: 2_000, finality_blocks: 30, light_client: true },
+    Builtin { chain_id: 11155111, eip7702: true, l2: None, weth: address!("0xfff9976782d46cc05630d1f6ebab18b2324d6b14"), block_time_ms: 12_000, finality_blocks: 64, light_client: false },
+];
+
+pub fn builtin_capabilities(chain_id: u64) -> Option<ChainCapabilities> {
+    BUILTINS.iter().find(|b| b.chain_id == chain_id).map(|b| ChainCapabilities {
+        chain_id,
+        eip1559: true,
+        eip7702: b.eip7702,
+        l2: b.l2,
+        weth: Some(b.weth),
+        multicall3: Some(MULTICALL3_ADDR),
+        permit2: Some(PERMIT2),
+        block_time_ms: b.block_time_ms,
+        finality_blocks: b.finality_blocks,
+        light_client: b.light_client,
+    })
+}
+
+async fn has_code(client: &Client, url: &str, address: &Address) -> bool {
+    get_code(client.clone(), url, &format!("{:#x}", address), "latest")
+        .await
+        .is_ok_and(|code| !code.is_empty() && code != "0x")
+}
+
+fn hex_field(block: &serde_json::Value, field: &str) -> Option<u64> {
+    u64::from_str_radix(block.get(field)?.as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+/// 通过 RPC 探测自定义链：最新区块有 baseFeePerGas 即支持 EIP-1559，
+/// 在标准地址上有代码即认为部署了 Multicall3 / Permit2，出块时间取最近 100 个区块的平均值。
+/// 7702、L2 类型与 WETH 无法可靠探测，留给用户填写
+pub async fn probe_capabilities(client: &Client, url: &str, chain_id: u64) -> ChainCapabilities {
+    let mut caps = ChainCapabilities::unknown(chain_id);
+    let latest = get_block_by_number(client.clone(), url, "latest", false).await.ok();
+    if let Some(block) = &latest {
+        caps.eip1559 = block.get("baseFeePerGas").is_some_and(|v| !v.is_null());
+    }
+    if has_code(client, url, &MULTICALL3_ADDR).await {
+        caps.multicall3 = Some(MULTICALL3_ADDR);
+    }
+    if has_code(client, url, &PERMIT2).await {
+        caps.permit2 = Some(PERMIT2);
+    }
+    if let (Some(latest), Ok(head)) = (&latest, block_number(client.clone(), url).await) {
+        let earlier = head.saturating_sub(BLOCK_TIME_SAMPLE);
+        if earlier < head {
+            let old = get_block_by_number(client.clone(), url, &format!("0x{earlier:x}"), false).await.ok();
+            if let (Some(t1), Some(t0), Some(n1)) = (
+                hex_field(latest, "timestamp"),
+                old.as_ref().and_then(|b| hex_field(b, "timestamp")),
+                hex_field(latest, "number"),
+            ) {
+                if n1 > earlier && t1 > t0 {
+                    caps.block_time_ms = (t1 - t0) * 1000 / (n1 - earlier);
+                }
+            }
+        }
+    }
+    caps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::chains::SUPPORTED_CHAIN_IDS;
+    use crate::utils::test_http;
+    use serde_json::json;
+
+    #[test]
+    fn test_builtins_cover_supported_chains() {
+        for chain_id in SUPPORTED_CHAIN_IDS {
+            let caps = builtin_capabilities(chain_id).unwrap();
+            assert!(caps.eip1559 && caps.multicall3.is_some() && caps.weth.is_some());
+        }
+        assert_eq!(builtin_capabilities(8453).unwrap().l2, Some(L2Type::OpStack));
+        assert_eq!(builtin_capabilities(1).unwrap().finality_ms(), 64 * 12_000);
+        assert!(builtin_capabilities(7777).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_probe_legacy_chain() {
+        // 同一路径对所有请求返回同一个区块：没有 baseFeePerGas，区块号与时间戳不变
+        let block = test_http::rpc_result(json!({"number": "0x64", "timestamp": "0x10"}));
+        let base = test_http::serve(vec![("/", block.as_str())]);
+        let caps = probe_capabilities(&Client::new(), &format!("{base}/"), 7777).await;
+        assert!(!caps.eip1559);
+        assert_eq!(caps.block_time_ms, DEFAULT_BLOCK_TIME_MS);
+
+        let offline = probe_capabilities(&Client::new(), &test_http::unreachable(), 7777).await;
+        assert_eq!(offline, ChainCapabilities::unknown(7777));
+    }
+}
@@ -1,77 +1,576 @@
+// 链注册表：内置链 + 用户 / DApp 通过 EIP-3085 wallet_addEthereumChain 添加的链
+// 全部存 Registry 表（chain:{id}），启动时补写缺失的内置链；
+// 新 RPC 必须先通过 eth_chainId 校验，返回的 chain id 不符的地址直接丢弃；
+// DApp 为已有链提供的地址不会并入链本身，只记为建议（chainsuggest:{id}），由用户逐个采用
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::core::state::AppState;
+use crate::error::AppError;
+use crate::evm::capability::{CAPABILITY_KEY_PREFIX, ChainCapabilities, builtin_capabilities, probe_capabilities};
+use crate::rpc::method::chain_id;
+use crate::utils::time::now_s;
+use bincode::{Decode, Encode};
+use reqwest::Client;
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+use url::Url;
 
+pub const CHAIN_KEY_PREFIX: &str = "chain";
+pub const SUGGESTION_KEY_PREFIX: &str = "chainsuggest";
+/// EIP-3085 对 nativeCurrency.symbol 长度的要求
+pub const NATIVE_SYMBOL_LEN: std::ops::RangeInclusive<usize> = 2..=6;
+/// EIP-3085 要求 nativeCurrency.decimals 为 18
+pub const NATIVE_DECIMALS: u8 = 18;
 
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct NativeCurrency {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
 pub struct Chain {
-	chain_id: u64,
-	name: String,
-    currency_name: String,
-    currency_symbol: String,
-    decimals: u64,
-	block_explorers: String,
-}
-
-pub const SUPPORTED_CHAINS: [chain; 2] = [
-	chain {
-		chain_id: 1,
-		name: "Ethereum".to_string(),
-		currency_name: "Ether".to_string(),
-		currency_symbol: "ETH".to_string(),
-		decimals: 18,
-		block_explorers: "https://etherscan.io/".to_string(),
-	},
-	chain {
-		chain_id: 10,
-		name: "Optimism".to_string(),
-		currency_name: "Ether".to_string(),
-		currency_symbol: "ETH".to_string(),
-		decimals: 18,
-		block_explorers: "https://optimistic.etherscan.io/".to_string(),
-		},
-	chain {
-		chain_id: 56,
-		name: "Binance Smart Chain".to_string(),
-		currency_name: "Binance Coin".to_string(),
-		currency_symbol: "BNB".to_string(),
-		decimals: 18,
-		block_explorers: "https://bscscan.com/".to_string(),
-	},
-    chain {
-		chain_id: 137,
-		name: "Polygon Mainnet".to_string(),
-		currency_name: "Polygon".to_string(),
-		currency_symbol: "POL".to_string(),
-		decimals: 18,
-		block_explorers: "https://polygonscan.com/".to_string(),
-	},
-    chain {
-		chain_id: 8453,
-        name: "Base".to_string(),
-		currency_name: "Ether".to_string(),
-		currency_symbol: "ETH".to_string(),
-		decimals: 18,
-		block_explorers: "https://basescan.org/".to_string(),
-	},
-    chain {
-		chain_id: 42161,
-		name: "Arbitrum One".to_string(),
-		currency_name: "Ether".to_string(),
-		currency_symbol: "ETH".to_string(),
-		decimals: 18,
-		block_explorers: "https://arbiscan.io/".to_string(),
-	},
-    chain {
-		chain_id:59144,
-        name: "Linea".to_string(),
-		currency_name: "Ether".to_string(),
-		currency_symbol: "ETH".to_string(),
-		decimals: 18,
-		block_explorers: "https://lineascan.build/".to_string(),
-	},
-    chain {
-		chain_id: 11155111,
-		name: "Sepolia".to_string(),
-		currency_name: "Ether".to_string(),
-		currency_symbol: "ETH".to_string(),
-		decimals: 18,
-		block_explorers: "https://sepolia.etherscan.io/".to_string(),
-	},
-];
+    pub chain_id: u64,
+    pub name: String,
+    pub native_currency: NativeCurrency,
+    pub rpc_urls: Vec<String>,
+    pub block_explorer_urls: Vec<String>,
+    pub icon_urls: Vec<String>,
+    pub testnet: bool,
+    /// 内置链不能删除，名称与原生币不能被 DApp 改写
+    pub builtin: bool,
+    pub added_at: u64,
+}
+
+fn builtin(chain_id: u64, name: &str, currency: (&str, &str), rpc: &str, explorer: &str) -> Chain {
+    Chain {
+        chain_id,
+        name: name.to_string(),
+        native_currency: NativeCurrency {
+            name: currency.0.to_string(),
+            symbol: currency.1.to_string(),
+            decimals: NATIVE_DECIMALS,
+        },
+        rpc_urls: vec![rpc.to_string()],
+        block_explorer_urls: vec![explorer.to_string()],
+        icon_urls: Vec::new(),
+        testnet: is_testnet(chain_id),
+        builtin: true,
+        added_at: 0,
+    }
+}
+
+/// 内置链，顺序与 SUPPORTED_CHAIN_IDS 一致
+pub fn builtin_chains() -> Vec<Chain> {
+    vec![
+        builtin(1, "Ethereum", ("Ether", "ETH"), "https://ethereum.publicnode.com", "https://etherscan.io"),
+        builtin(10, "Optimism", ("Ether", "ETH"), "https://mainnet.optimism.io", "https://optimistic.etherscan.io"),
+        builtin(56, "BNB Smart Chain", ("BNB", "BNB"), "https://bsc.publicnode.com", "https://bscscan.com"),
+        builtin(137, "Polygon Mainnet", ("POL", "POL"), "https://polygon-rpc.com", "https://polygonscan.com"),
+        builtin(8453, "Base", ("Ether", "ETH"), "https://base.publicnode.com", "https://basescan.org"),
+        builtin(42161, "Arbitrum One", ("Ether", "ETH"), "https://arbitrum.drpc.org", "https://arbiscan.io"),
+        builtin(59144, "Linea", ("Linea Ether", "ETH"), "https://rpc.linea.build", "https://lineascan.build"),
+        builtin(11155111, "Sepolia", ("Sepolia Ether", "ETH"), "https://ethereum-sepolia-rpc.publicnode.com", "https://sepolia.etherscan.io"),
+    ]
+}
+
+/// chain_id → 原生币符号（内置链之外按 ETH 处理；自定义链用 ChainRegistry::native_symbol）
+pub fn native_symbol(chain_id: u64) -> &'static str {
+    match chain_id {
+        56 => "BNB",
+        137 => "POL",
+        _ => "ETH",
+    }
+}
+
+/// 平均出块时间（毫秒），用于把区块数换算成时间；自定义链用 ChainRegistry::capabilities
+pub fn approx_block_time_ms(chain_id: u64) -> u64 {
+    builtin_capabilities(chain_id)
+        .unwrap_or_else(|| ChainCapabilities::unknown(chain_id))
+        .block_time_ms
+}
+
+/// 内置链的 chain_id
+pub const SUPPORTED_CHAIN_IDS: [u64; 8] = [1, 10, 56, 137, 8453, 42161, 59144, 11155111];
+
+/// 测试网（不参与主网 ENS 解析等）
+pub fn is_testnet(chain_id: u64) -> bool {
+    matches!(chain_id, 11155111 | 17000 | 84532 | 421614 | 11155420 | 59141)
+}
+
+// ========== EIP-3085 ==========
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AddEthereumChainParameter {
+    pub chain_id: String,
+    pub chain_name: String,
+    pub native_currency: NativeCurrency,
+    pub rpc_urls: Vec<String>,
+    pub block_explorer_urls: Option<Vec<String>>,
+    pub icon_urls: Option<Vec<String>>,
+}
+
+/// 0x 开头、无前导零的十六进制
+pub fn parse_chain_id(hex: &str) -> Result<u64, AppError> {
+    let digits = hex
+        .strip_prefix("0x")
+        .ok_or_else(|| AppError::ChainParamsInvalid("chainId must be 0x-prefixed hex".into()))?;
+    if digits.is_empty() || digits.starts_with('0') {
+        return Err(AppError::ChainParamsInvalid("chainId must not have leading zeros".into()));
+    }
+    u64::from_str_radix(digits, 16).map_err(|_| AppError::ChainParamsInvalid(format!("invalid chainId {hex}")))
+}
+
+/// RPC 只接受 https；本机节点允许 http
+pub fn validate_rpc_url(raw: &str) -> Result<String, AppError> {
+    let url = Url::parse(raw).map_err(|_| AppError::ChainParamsInvalid(format!("invalid url {raw}")))?;
+    let local = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    match url.scheme() {
+        "https" => Ok(raw.to_string()),
+        "http" if local => Ok(raw.to_string()),
+        _ => Err(AppError::ChainParamsInvalid(format!("rpc url must use https: {raw}"))),
+    }
+}
+
+fn https_urls(urls: Option<&Vec<String>>, field: &str) -> Result<Vec<String>, AppError> {
+    urls.into_iter()
+        .flatten()
+        .map(|raw| match Url::parse(raw) {
+            Ok(url) if url.scheme() == "https" => Ok(raw.clone()),
+            _ => Err(AppError::ChainParamsInvalid(format!("{field} must be https urls: {raw}"))),
+        })
+        .collect()
+}
+
+/// 校验 EIP-3085 参数并转换成 Chain（rpc_urls 尚未经过 eth_chainId 校验）
+pub fn chain_from_params(params: &AddEthereumChainParameter, now: u64) -> Result<Chain, AppError> {
+    let chain_id = parse_chain_id(&params.chain_id)?;
+    let name = params.chain_name.trim();
+    if name.is_empty() {
+        return Err(AppError::ChainParamsInvalid("chainName is empty".into()));
+    }
+    let currency = &params.native_currency;
+    if !NATIVE_SYMBOL_LEN.contains(&currency.symbol.chars().count()) {
+        return Err(AppError::ChainParamsInvalid("nativeCurrency.symbol must be 2-6 characters".into()));
+    }
+    if currency.decimals != NATIVE_DECIMALS {
+        return Err(AppError::ChainParamsInvalid("nativeCurrency.decimals must be 18".into()));
+    }
+    if params.rpc_urls.is_empty() {
+        return Err(AppError::ChainParamsInvalid("rpcUrls is empty".into()));
+    }
+    let rpc_urls = params.rpc_urls.iter().map(|u| validate_rpc_url(u)).collect::<Result<Vec<_>, _>>()?;
+    Ok(Chain {
+        chain_id,
+        name: name.to_string(),
+        native_currency: currency.clone(),
+        rpc_urls,
+        block_explorer_urls: https_urls(params.block_explorer_urls.as_ref(), "blockExplorerUrls")?,
+        icon_urls: https_urls(params.icon_urls.as_ref(), "iconUrls")?,
+        testnet: is_testnet(chain_id),
+        builtin: false,
+        added_at: now,
+    })
+}
+
+/// 逐个查询 eth_chainId，返回 (通过, 未通过)
+pub async fn verify_rpc_urls(client: &Client, expected: u64, urls: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    for url in urls {
+        match chain_id(client.clone(), url).await {
+            Ok(id) if id == expected => accepted.push(url.clone()),
+            _ => rejected.push(url.clone()),
+        }
+    }
+    (accepted, rejected)
+}
+
+fn push_missing(target: &mut Vec<String>, extra: &[String]) {
+    for url in extra {
+        if !target.contains(url) {
+            target.push(url.clone());
+        }
+    }
+}
+
+/// extra 中 existing 没有的地址
+fn missing_from(existing: &[String], extra: &[String]) -> Vec<String> {
+    extra.iter().filter(|url| !existing.contains(url)).cloned().collect()
+}
+
+/// DApp 为已有链提供的地址，用户采用前不会被使用
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct ChainSuggestion {
+    pub chain_id: u64,
+    pub rpc_urls: Vec<String>,
+    pub block_explorer_urls: Vec<String>,
+    pub icon_urls: Vec<String>,
+    pub suggested_at: u64,
+}
+
+impl ChainSuggestion {
+    pub fn is_empty(&self) -> bool {
+        self.rpc_urls.is_empty() && self.block_explorer_urls.is_empty() && self.icon_urls.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddChainResult {
+    pub chain: Chain,
+    /// 链已存在，链本身不变，新地址记入 suggestion
+    pub existed: bool,
+    pub suggestion: Option<ChainSuggestion>,
+    pub rejected_rpc_urls: Vec<String>,
+}
+
+// ========== 注册表 ==========
+
+#[derive(Debug, Clone, Default)]
+pub struct ChainRegistry {
+    pub chains: BTreeMap<u64, Chain>,
+    /// 已存储的能力（自定义链探测结果或用户修正），覆盖内置表
+    pub capabilities: BTreeMap<u64, ChainCapabilities>,
+    /// DApp 对已有链建议的地址，待用户采用
+    pub suggestions: BTreeMap<u64, ChainSuggestion>,
+}
+
+fn chain_key(mgr: &TableManager, chain_id: u64) -> Vec<u8> {
+    mgr.key_from_str(&format!("{CHAIN_KEY_PREFIX}:{chain_id}"))
+}
+
+fn capability_key(mgr: &TableManager, chain_id: u64) -> Vec<u8> {
+    mgr.key_from_str(&format!("{CAPABILITY_KEY_PREFIX}:{chain_id}"))
+}
+
+fn suggestion_key(mgr: &TableManager, chain_id: u64) -> Vec<u8> {
+    mgr.key_from_str(&format!("{SUGGESTION_KEY_PREFIX}:{chain_id}"))
+}
+
+impl ChainRegistry {
+    /// 补写缺失的内置链后读出全部链
+    pub fn load(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Self> {
+        let mgr = TableManager::new(db, TableKind::Registry)?;
+        let mut registry = Self::default();
+        for chain in mgr.list_by_prefix::<Chain>(&format!("{CHAIN_KEY_PREFIX}:"))? {
+            registry.chains.insert(chain.chain_id, chain);
+        }
+        for chain in builtin_chains() {
+            if !registry.chains.contains_key(&chain.chain_id) {
+                mgr.set(&chain_key(&mgr, chain.chain_id), &chain)?;
+                registry.chains.insert(chain.chain_id, chain);
+            }
+        }
+        for caps in mgr.list_by_prefix::<ChainCapabilities>(&format!("{CAPABILITY_KEY_PREFIX}:"))? {
+            registry.capabilities.insert(caps.chain_id, caps);
+        }
+        for suggestion in mgr.list_by_prefix::<ChainSuggestion>(&format!("{SUGGESTION_KEY_PREFIX}:"))? {
+            registry.suggestions.insert(suggestion.chain_id, suggestion);
+        }
+        Ok(registry)
+    }
+
+    /// 存储值 → 内置表 → 未知链的保守默认
+    pub fn capabilities(&self, chain_id: u64) -> ChainCapabilities {
+        self.capabilities
+            .get(&chain_id)
+            .cloned()
+            .or_else(|| builtin_capabilities(chain_id))
+            .unwrap_or_else(|| ChainCapabilities::unknown(chain_id))
+    }
+
+    pub fn set_capabilities(&mut self, db: &DBWithThreadMode<MultiThreaded>, caps: ChainCapabilities) -> DbResult<()> {
+        let mgr = TableManager::new(db, TableKind::Registry)?;
+        mgr.set(&capability_key(&mgr, caps.chain_id), &caps)?;
+        self.capabilities.insert(caps.chain_id, caps);
+        Ok(())
+    }
+
+    pub fn get(&self, chain_id: u64) -> Option<&Chain> {
+        self.chains.get(&chain_id)
+    }
+
+    pub fn list(&self) -> Vec<Chain> {
+        self.chains.values().cloned().collect()
+    }
+
+    pub fn native_symbol(&self, chain_id: u64) -> String {
+        self.get(chain_id)
+            .map(|c| c.native_currency.symbol.clone())
+            .unwrap_or_else(|| native_symbol(chain_id).to_string())
+    }
+
+    /// 新链直接加入；已有链（含内置链）本身不变，不允许改名、改原生币或塞入 RPC（防止 DApp 伪装主网），
+    /// 链上没有的地址只记为建议，返回 (链, 是否已存在, 建议)
+    pub fn merge(&mut self, incoming: Chain) -> (Chain, bool, Option<ChainSuggestion>) {
+        let Some(existing) = self.chains.get(&incoming.chain_id) else {
+            self.chains.insert(incoming.chain_id, incoming.clone());
+            return (incoming, false, None);
+        };
+        let suggestion = self.suggestions.entry(incoming.chain_id).or_insert_with(|| ChainSuggestion {
+            chain_id: incoming.chain_id,
+            ..Default::default()
+        });
+        push_missing(&mut suggestion.rpc_urls, &missing_from(&existing.rpc_urls, &incoming.rpc_urls));
+        push_missing(
+            &mut suggestion.block_explorer_urls,
+            &missing_from(&existing.block_explorer_urls, &incoming.block_explorer_urls),
+        );
+        push_missing(&mut suggestion.icon_urls, &missing_from(&existing.icon_urls, &incoming.icon_urls));
+        suggestion.suggested_at = incoming.added_at;
+        let suggestion = suggestion.clone();
+        if suggestion.is_empty() {
+            self.suggestions.remove(&incoming.chain_id);
+            return (existing.clone(), true, None);
+        }
+        (existing.clone(), true, Some(suggestion))
+    }
+
+    /// 用户选中的建议地址并入链，其余建议保留
+    pub fn accept_suggestion(&mut self, chain_id: u64, urls: &[String]) -> Result<Chain, AppError> {
+        let chain = self.chains.get_mut(&chain_id).ok_or(AppError::ChainNotFound(chain_id))?;
+        let suggestion = self
+            .suggestions
+            .get_mut(&chain_id)
+            .ok_or_else(|| AppError::ChainParamsInvalid(format!("no suggested urls for chain {chain_id}")))?;
+        for url in urls {
+            let lists = [
+                (&mut suggestion.rpc_urls, &mut chain.rpc_urls),
+                (&mut suggestion.block_explorer_urls, &mut chain.block_explorer_urls),
+                (&mut suggestion.icon_urls, &mut chain.icon_urls),
+            ];
+            let Some((from, to)) = lists.into_iter().find(|(from, _)| from.contains(url)) else {
+                return Err(AppError::ChainParamsInvalid(format!("{url} is not a suggested url")));
+            };
+            from.retain(|u| u != url);
+            push_missing(to, std::slice::from_ref(url));
+        }
+        if suggestion.is_empty() {
+            self.suggestions.remove(&chain_id);
+        }
+        Ok(chain.clone())
+    }
+
+    /// 写入链本身及其建议（建议为空时删除）
+    pub fn save(&self, db: &DBWithThreadMode<MultiThreaded>, chain_id: u64) -> DbResult<()> {
+        let mgr = TableManager::new(db, TableKind::Registry)?;
+        if let Some(chain) = self.get(chain_id) {
+            mgr.set(&chain_key(&mgr, chain_id), chain)?;
+        }
+        match self.suggestions.get(&chain_id) {
+            Some(suggestion) => mgr.set(&suggestion_key(&mgr, chain_id), suggestion)?,
+            None => mgr.delete(&suggestion_key(&mgr, chain_id))?,
+        }
+        Ok(())
+    }
+}
+
+/// 校验参数与 RPC，合并进注册表并持久化；供命令与 EIP-1193 请求共用
+pub async fn add_chain(
+    db: &DBWithThreadMode<MultiThreaded>,
+    state: &AppState,
+    params: &AddEthereumChainParameter,
+) -> Result<AddChainResult, AppError> {
+    let mut chain = chain_from_params(params, now_s())?;
+    let client = state.https_client.lock().await.clone();
+    let (accepted, rejected_rpc_urls) = verify_rpc_urls(&client, chain.chain_id, &chain.rpc_urls).await;
+    if accepted.is_empty() {
+        return Err(AppError::ChainRpcMismatch(chain.chain_id));
+    }
+    chain.rpc_urls = accepted;
+
+    // 新的非内置链探测一次能力，已有的不覆盖
+    let known = {
+        let registry = state.chain_registry.lock().await;
+        registry.get(chain.chain_id).is_some() || registry.capabilities.contains_key(&chain.chain_id)
+    };
+    let probed = if known || builtin_capabilities(chain.chain_id).is_some() {
+        None
+    } else {
+        Some(probe_capabilities(&client, &chain.rpc_urls[0], chain.chain_id).await)
+    };
+
+    let mut registry = state.chain_registry.lock().await;
+    let (chain, existed, suggestion) = registry.merge(chain);
+    registry.save(db, chain.chain_id)?;
+    if let Some(caps) = probed {
+        registry.set_capabilities(db, caps)?;
+    }
+    Ok(AddChainResult { chain, existed, suggestion, rejected_rpc_urls })
+}
+
+#[tauri::command]
+pub fn chain_list(state: State<AppState>) -> Result<Vec<Chain>, AppError> {
+    Ok(state.chain_registry.blocking_lock().list())
+}
+
+/// params 为 wallet_addEthereumChain 的 params[0]
+#[tauri::command]
+pub async fn chain_add(
+    params: AddEthereumChainParameter,
+    appdb: State<'_, AppDB>,
+    state: State<'_, AppState>,
+) -> Result<AddChainResult, AppError> {
+    add_chain(appdb.db.as_ref(), &state, &params).await
+}
+
+#[tauri::command]
+pub fn chain_suggestions(state: State<AppState>) -> Result<Vec<ChainSuggestion>, AppError> {
+    Ok(state.chain_registry.blocking_lock().suggestions.values().cloned().collect())
+}
+
+/// 用户显式采用 DApp 建议的地址（RPC / 浏览器 / 图标）
+#[tauri::command]
+pub fn chain_suggestion_accept(
+    chain_id: u64,
+    urls: Vec<String>,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<Chain, AppError> {
+    let mut registry = state.chain_registry.blocking_lock();
+    let chain = registry.accept_suggestion(chain_id, &urls)?;
+    registry.save(appdb.db.as_ref(), chain_id)?;
+    Ok(chain)
+}
+
+#[tauri::command]
+pub fn chain_suggestion_dismiss(chain_id: u64, appdb: State<AppDB>, state: State<AppState>) -> Result<(), AppError> {
+    let mut registry = state.chain_registry.blocking_lock();
+    registry.suggestions.remove(&chain_id);
+    registry.save(appdb.db.as_ref(), chain_id)?;
+    Ok(())
+}
+
+/// 只能删除自定义链
+#[tauri::command]
+pub fn chain_remove(chain_id: u64, appdb: State<AppDB>, state: State<AppState>) -> Result<(), AppError> {
+    let mut registry = state.chain_registry.blocking_lock();
+    match registry.get(chain_id) {
+        None => return Err(AppError::ChainNotFound(chain_id)),
+        Some(chain) if chain.builtin => return Err(AppError::ChainBuiltinReadonly(chain_id)),
+        Some(_) => {}
+    }
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    mgr.delete(&chain_key(&mgr, chain_id))?;
+    mgr.delete(&capability_key(&mgr, chain_id))?;
+    mgr.delete(&suggestion_key(&mgr, chain_id))?;
+    registry.chains.remove(&chain_id);
+    registry.capabilities.remove(&chain_id);
+    registry.suggestions.remove(&chain_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn chain_capabilities(chain_id: u64, state: State<AppState>) -> Result<ChainCapabilities, AppError> {
+    Ok(state.chain_registry.blocking_lock().capabilities(chain_id))
+}
+
+/// 修正自定义链的能力（如补填 WETH、L2 类型）；内置链不可改
+#[tauri::command]
+pub fn chain_capabilities_set(
+    capabilities: ChainCapabilities,
+    appdb: State<AppDB>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let mut registry = state.chain_registry.blocking_lock();
+    match registry.get(capabilities.chain_id) {
+        None => return Err(AppError::ChainNotFound(capabilities.chain_id)),
+        Some(chain) if chain.builtin => return Err(AppError::ChainBuiltinReadonly(capabilities.chain_id)),
+        Some(_) => {}
+    }
+    registry.set_capabilities(appdb.db.as_ref(), capabilities)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_http;
+
+    fn params() -> AddEthereumChainParameter {
+        serde_json::from_str(
+            r#"{
+                "chainId": "0x2105",
+                "chainName": "Base (dapp)",
+                "nativeCurrency": {"name": "Fake", "symbol": "FAKE", "decimals": 18},
+                "rpcUrls": ["https://rpc.example.org"],
+                "blockExplorerUrls": ["https://explorer.example.org"],
+                "iconUrls": ["https://example.org/base.svg"]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_chain_id() {
+        assert_eq!(parse_chain_id("0x2105").unwrap(), 8453);
+        assert!(parse_chain_id("8453").is_err());
+        assert!(parse_chain_id("0x02105").is_err());
+        assert!(parse_chain_id("0x").is_err());
+        assert!(parse_chain_id("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_chain_from_params_validation() {
+        let chain = chain_from_params(&params(), 7).unwrap();
+        assert_eq!((chain.chain_id, chain.builtin, chain.added_at), (8453, false, 7));
+
+        let mut bad = params();
+        bad.native_currency.decimals = 9;
+        assert!(chain_from_params(&bad, 0).is_err());
+        let mut bad = params();
+        bad.native_currency.symbol = "X".into();
+        assert!(chain_from_params(&bad, 0).is_err());
+        let mut bad = params();
+        bad.rpc_urls = vec!["http://rpc.example.org".into()];
+        assert!(chain_from_params(&bad, 0).is_err());
+        bad.rpc_urls = vec!["http://127.0.0.1:8545".into()];
+        assert!(chain_from_params(&bad, 0).is_ok());
+        bad.rpc_urls.clear();
+        assert!(chain_from_params(&bad, 0).is_err());
+    }
+
+    #[test]
+    fn test_merge_keeps_builtin_identity() {
+        let mut registry = ChainRegistry::default();
+        for chain in builtin_chains() {
+            registry.chains.insert(chain.chain_id, chain);
+        }
+        let (merged, existed, suggestion) = registry.merge(chain_from_params(&params(), 0).unwrap());
+        assert!(existed);
+        assert_eq!(merged.name, "Base");
+        assert_eq!(registry.native_symbol(8453), "ETH");
+        assert_eq!(merged.rpc_urls, vec!["https://base.publicnode.com".to_string()]);
+        assert_eq!(registry.get(8453).unwrap().rpc_urls.len(), 1);
+        let suggestion = suggestion.unwrap();
+        assert_eq!(suggestion.rpc_urls, vec!["https://rpc.example.org".to_string()]);
+        assert_eq!(suggestion.icon_urls, vec!["https://example.org/base.svg".to_string()]);
+
+        // 只有用户选中的地址并入，其余仍是建议
+        let rpc = "https://rpc.example.org".to_string();
+        let chain = registry.accept_suggestion(8453, std::slice::from_ref(&rpc)).unwrap();
+        assert_eq!(chain.rpc_urls.len(), 2);
+        assert!(registry.suggestions[&8453].rpc_urls.is_empty());
+        assert_eq!(registry.suggestions[&8453].block_explorer_urls.len(), 1);
+        assert!(registry.accept_suggestion(8453, &["https://evil.example.org".to_string()]).is_err());
+
+        let mut custom = params();
+        custom.chain_id = "0x1b58".into();
+        let (added, existed, suggestion) = registry.merge(chain_from_params(&custom, 0).unwrap());
+        assert!(!existed && suggestion.is_none());
+        assert_eq!(registry.native_symbol(added.chain_id), "FAKE");
+    }
+
+    #[tokio::test]
+    async fn test_verify_rpc_urls() {
+        let good = test_http::serve(vec![("/", test_http::rpc_result("0x2105").as_str())]);
+        let wrong = test_http::serve(vec![("/", test_http::rpc_result("0x1").as_str())]);
+        let urls = vec![format!("{good}/"), format!("{wrong}/"), test_http::unreachable()];
+        let (accepted, rejected) = verify_rpc_urls(&Client::new(), 8453, &urls).await;
+        assert_eq!(accepted, vec![urls[0].clone()]);
+        assert_eq!(rejected.len(), 2);
+    }
+}
@@ -1,33 +1,25 @@
-use revm::{
-    primitives::{Address, U256, TxEnv, TransactTo, CfgEnv, BlockEnv},
-    db::CacheDB,
-    Context, Evm,
-};
+// 原生币转账预执行：在分叉的最新状态上跑一遍，返回实际消耗的 gas
+// 收款方是合约（或 7702 委托账户）时 receive / fallback 的开销也算在内
+use crate::error::AppError;
+use crate::revm::fork::{SimCall, Simulator};
+use alloy_primitives::{Address, Bytes, U256};
+use reqwest::Client;
 
-pub fn dry_run_eth_transfer(
+pub async fn dry_run_eth_transfer(
+    client: &Client,
+    rpc_url: &str,
+    chain_id: u64,
     from: Address,
     to: Address,
     value: U256,
-    block_env: BlockEnv,
-) -> Result<u64, String> {
-    let mut db = CacheDB::new(); // 空状态
-
-    let tx = TxEnv {
-        caller: from,
-        transact_to: TransactTo::Call(to),
-        value,
-        data: vec![],
-        gas_limit: 1_000_000,
-        ..Default::default()
-    };
-
-    let mut evm = Evm::builder()
-        .with_db(&mut db)
-        .with_block_env(block_env)
-        .with_cfg_env(CfgEnv::default())
-        .with_tx_env(tx)
-        .build();
-
-    let result = evm.transact().map_err(|e| e.to_string())?;
-    Ok(result.gas_used)
-}
\ No newline at end of file
+) -> Result<u64, AppError> {
+    let mut simulator = Simulator::fork(client, rpc_url, chain_id, None).await?;
+    let call = SimCall { from, to: Some(to), value, data: Bytes::new(), gas_limit: None, gas_price: None };
+    let outcome = tokio::task::spawn_blocking(move || simulator.simulate(&call))
+        .await
+        .map_err(|e| AppError::SimulationFailed(e.to_string()))??;
+    if !outcome.success {
+        return Err(AppError::SimulationFailed(outcome.revert_reason.unwrap_or_else(|| "transfer reverted".into())));
+    }
+    Ok(outcome.gas_used)
+}
@@ -0,0 +1,268 @@
+// 离线 4-byte 签名库：selector → 候选函数签名，topic0 → 候选事件签名
+// 内置库随版本发布（signatures.json），可安装签名更新包，用户也可以自己添加；
+// 同一 selector 有多个候选时由 calldata 解码逐个尝试，用户添加的排在最前
+use crate::core::db::{AppDB, DbResult, TableKind, TableManager};
+use crate::data::contract::{REGISTRY_SIGNERS, verify_payload_signature};
+use crate::error::AppError;
+use alloy_json_abi::{Event, Function};
+use alloy_primitives::{Address, B256};
+use bincode::{Decode, Encode};
+use once_cell::sync::Lazy;
+use rust_rocksdb::{DBWithThreadMode, MultiThreaded};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{RwLock, RwLockReadGuard};
+use tauri::State;
+
+/// 随版本发布的内置签名库，视为可信
+const BUILTIN_BUNDLE: &str = include_str!("signatures.json");
+
+const BUNDLE_KEY: &str = "signature_bundle";
+const USER_KEY_PREFIX: &str = "sig";
+
+/// 解码路径（包括交易历史解析）拿不到 AppState，签名库放在进程级全局
+static GLOBAL: Lazy<RwLock<SignatureDb>> = Lazy::new(|| RwLock::new(SignatureDb::builtin()));
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureKind {
+    Function,
+    Event,
+}
+
+impl SignatureKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SignatureKind::Function => "function",
+            SignatureKind::Event => "event",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq)]
+pub struct UserSignature {
+    pub kind: SignatureKind,
+    pub signature: String,
+}
+
+/// 签名可带参数名与 indexed，例如 `Transfer(address indexed from,address indexed to,uint256 value)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureBundle {
+    pub version: u64,
+    pub issued_at: u64,
+    #[serde(default)]
+    pub functions: Vec<String>,
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// 远程下发格式，与合约注册表相同：签名针对 payload 原文
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct SignedSignatureBundle {
+    pub payload: String,
+    pub signature: String,
+}
+
+pub fn verify_bundle(signed: &SignedSignatureBundle, signers: &[Address]) -> Result<SignatureBundle, AppError> {
+    verify_payload_signature(&signed.payload, &signed.signature, signers)?;
+    Ok(serde_json::from_str(&signed.payload)?)
+}
+
+pub fn function_selector(signature: &str) -> Result<[u8; 4], AppError> {
+    Function::parse(signature)
+        .map(|f| f.selector().0)
+        .map_err(|e| AppError::SignatureInvalid(e.to_string()))
+}
+
+pub fn event_topic(signature: &str) -> Result<B256, AppError> {
+    Event::parse(signature)
+        .map(|e| e.selector())
+        .map_err(|e| AppError::SignatureInvalid(e.to_string()))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SignatureDb {
+    pub version: u64,
+    functions: HashMap<[u8; 4], Vec<String>>,
+    events: HashMap<B256, Vec<String>>,
+}
+
+impl SignatureDb {
+    pub fn from_bundle(bundle: SignatureBundle) -> Self {
+        let mut db = Self { version: bundle.version, ..Default::default() };
+        let entries = bundle
+            .functions
+            .iter()
+            .map(|s| (SignatureKind::Function, s))
+            .chain(bundle.events.iter().map(|s| (SignatureKind::Event, s)));
+        for (kind, signature) in entries {
+            if let Err(e) = db.insert(kind, signature, false) {
+                eprintln!("Skipping signature {}: {}", signature, e);
+            }
+        }
+        db
+    }
+
+    pub fn builtin() -> Self {
+        let bundle: SignatureBundle =
+            serde_json::from_str(BUILTIN_BUNDLE).expect("builtin signatures.json is valid");
+        Self::from_bundle(bundle)
+    }
+
+    /// 内置库 → 已存储的更新包（版本更高且签名有效）→ 用户签名
+    pub fn load(db: &DBWithThreadMode<MultiThreaded>) -> DbResult<Self> {
+        let mgr = TableManager::new(db, TableKind::Registry)?;
+        let mut sigs = Self::builtin();
+        if let Some(signed) = mgr.get::<SignedSignatureBundle>(&mgr.key_from_str(BUNDLE_KEY))? {
+            match verify_bundle(&signed, &REGISTRY_SIGNERS) {
+                Ok(bundle) if bundle.version > sigs.version => sigs = Self::from_bundle(bundle),
+                Ok(_) => {}
+                Err(e) => eprintln!("Ignoring stored signature bundle: {}", e),
+            }
+        }
+        for user in mgr.list_by_prefix::<UserSignature>(&format!("{USER_KEY_PREFIX}:"))? {
+            if let Err(e) = sigs.insert(user.kind, &user.signature, true) {
+                eprintln!("Skipping user signature {}: {}", user.signature, e);
+            }
+        }
+        Ok(sigs)
+    }
+
+    /// 返回 selector / topic0 的十六进制；front 为 true 时放到候选最前（已存在则移动）
+    fn insert(&mut self, kind: SignatureKind, signature: &str, front: bool) -> Result<String, AppError> {
+        let signature = signature.trim().to_string();
+        let (list, key) = match kind {
+            SignatureKind::Function => {
+                let selector = function_selector(&signature)?;
+                (self.functions.entry(selector).or_default(), format!("0x{}", hex::encode(selector)))
+            }
+            SignatureKind::Event => {
+                let topic = event_topic(&signature)?;
+                (self.events.entry(topic).or_default(), format!("{topic:#x}"))
+            }
+        };
+        list.retain(|s| *s != signature);
+        if front {
+            list.insert(0, signature);
+        } else {
+            list.push(signature);
+        }
+        Ok(key)
+    }
+
+    pub fn add_user(&mut self, kind: SignatureKind, signature: &str) -> Result<String, AppError> {
+        self.insert(kind, signature, true)
+    }
+
+    pub fn functions(&self, selector: &[u8; 4]) -> &[String] {
+        self.functions.get(selector).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn events(&self, topic0: &B256) -> &[String] {
+        self.events.get(topic0).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn global() -> RwLockReadGuard<'static, SignatureDb> {
+        GLOBAL.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn install(self) {
+        *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = self;
+    }
+}
+
+fn user_key(mgr: &TableManager, kind: SignatureKind, signature: &str) -> Vec<u8> {
+    mgr.key_from_str(&format!("{USER_KEY_PREFIX}:{}:{}", kind.as_str(), signature.trim()))
+}
+
+// ========== SIGNATURE DB ==========
+/// selector 为 4 字节（函数）或 32 字节（事件 topic0）的十六进制
+#[tauri::command]
+pub fn signature_lookup(selector: String) -> Result<Vec<String>, AppError> {
+    let raw = hex::decode(selector.trim_start_matches("0x"))?;
+    let db = SignatureDb::global();
+    match raw.len() {
+        4 => Ok(db.functions(&[raw[0], raw[1], raw[2], raw[3]]).to_vec()),
+        32 => Ok(db.events(&B256::from_slice(&raw)).to_vec()),
+        _ => Err(AppError::Parse("selector must be 4 or 32 bytes")),
+    }
+}
+
+#[tauri::command]
+pub fn signature_user_add(
+    kind: SignatureKind,
+    signature: String,
+    appdb: State<AppDB>,
+) -> Result<String, AppError> {
+    let mut db = SignatureDb::global().clone();
+    let key = db.add_user(kind, &signature)?;
+    let mgr = TableManager::new(appdb.db.as_ref(), TableKind::Registry)?;
+    let entry = UserSignature { kind, signature: signature.trim().to_string() };
+    mgr.set(&user_key(&mgr, kind, &signature), &entry)?;
+    db.install();
+    Ok(key)
+}
+
+#[tauri::command]
+pub fn signature_user_delete(
+    kind: SignatureKind,
+    signature: String,
+    appdb: State<AppDB>,
+) -> Result<(), AppError> {
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    mgr.delete(&user_key(&mgr, kind, &signature))?;
+    SignatureDb::load(db)?.install();
+    Ok(())
+}
+
+/// 安装远程下发的签名库；版本号必须递增
+#[tauri::command]
+pub fn signature_bundle_update(
+    bundle: SignedSignatureBundle,
+    appdb: State<AppDB>,
+) -> Result<u64, AppError> {
+    let parsed = verify_bundle(&bundle, &REGISTRY_SIGNERS)?;
+    let current = SignatureDb::global().version;
+    if parsed.version <= current {
+        return Err(AppError::RegistryVersionRollback(current, parsed.version));
+    }
+    let db = appdb.db.as_ref();
+    let mgr = TableManager::new(db, TableKind::Registry)?;
+    mgr.set(&mgr.key_from_str(BUNDLE_KEY), &bundle)?;
+    let sigs = SignatureDb::load(db)?;
+    let version = sigs.version;
+    sigs.install();
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_bundle() {
+        let bundle: SignatureBundle = serde_json::from_str(BUILTIN_BUNDLE).unwrap();
+        let db = SignatureDb::from_bundle(bundle.clone());
+        let total: usize = db.functions.values().map(Vec::len).sum::<usize>() + db.events.values().map(Vec::len).sum::<usize>();
+        // 内置签名全部可解析
+        assert_eq!(total, bundle.functions.len() + bundle.events.len());
+        assert_eq!(db.functions(&[0xa9, 0x05, 0x9c, 0xbb]), ["transfer(address to,uint256 value)"]);
+        // ERC-20 与 ERC-721 的 Transfer 共用 topic0，靠 indexed 数区分
+        let transfer = event_topic("Transfer(address,address,uint256)").unwrap();
+        assert_eq!(db.events(&transfer).len(), 2);
+    }
+
+    #[test]
+    fn test_user_signature_goes_first() {
+        let mut db = SignatureDb::builtin();
+        let key = db.add_user(SignatureKind::Function, " many_msg_babbage(bytes1) ").unwrap();
+        assert_eq!(key, "0xa9059cbb");
+        assert_eq!(db.functions(&[0xa9, 0x05, 0x9c, 0xbb]), ["many_msg_babbage(bytes1)", "transfer(address to,uint256 value)"]);
+        // 重复添加只会移动位置
+        db.add_user(SignatureKind::Function, "transfer(address to,uint256 value)").unwrap();
+        assert_eq!(db.functions(&[0xa9, 0x05, 0x9c, 0xbb])[0], "transfer(address to,uint256 value)");
+        assert_eq!(db.functions(&[0xa9, 0x05, 0x9c, 0xbb]).len(), 2);
+        assert!(db.add_user(SignatureKind::Function, "not a signature").is_err());
+    }
+}
@@ -1,102 +1,424 @@
+// 手续费预言机：基于 eth_feeHistory 给出慢 / 标准 / 快三档 maxFeePerGas 与 maxPriorityFeePerGas，
+// 不支持 EIP-1559 的链（或 feeHistory 不可用时）退回 eth_gasPrice 分档。
+// 结果统一乘以配置里的 gas_price_multiplier；L2 与 BSC 上 tip 容易被抢跑机器人拉出极端值，做上限截断
+use crate::core::state::AppState;
+use crate::error::AppError;
+use crate::evm::capability::{ChainCapabilities, L2Type};
+use crate::rpc::method::{fee_history, gas_price, max_priority_fee};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
 
-use alloy::network::{Ethereum, TransactionBuilder};
-use alloy::rpc::primitives::{U128, U256};
-use alloy::rpc::types::eth::{BlockId, FeeHistory};
-use std::time::Duration;
-use tauri::async_runtime;
-
-
-
-/// 2025 年最推荐的参数
-const FEE_HISTORY_BLOCKS: u64 = 20;           // 看最近 20 个块
-const REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0]; // 保守、中等、激进
-
-pub struct PriorityFeeEstimator {
-    provider: AlloyProvider,
-}
-
-impl PriorityFeeEstimator {
-    pub fn new(rpc_url: &str) -> Self {
-        let provider = ProviderBuilder::new()
-            .on_http(rpc_url.parse().expect("invalid rpc url"));
-        Self { provider }
-    }
-
-    /// 返回 (base_fee_per_gas 当前, 推荐的 max_priority_fee_per_gas)
-    /// 第三个返回值是预估的下一块 base_fee（大多数 UI 都直接显示这个）
-    pub async fn estimate_priority_fees(&self) -> Result<(U256, U256, U256), alloy::providers::ProviderError> {
-        // 1. 先拿最新块，获取当前 baseFeePerGas
-        let latest_block = self.provider.get_block_by_number(BlockId::latest(), false).await?
-            .expect("latest block not found");
-        let current_base_fee = latest_block.header.base_fee_per_gas
-            .unwrap_or_default(); // 老链可能没有，默认为 0
-
-        // 2. 拿 fee history 计算优先费
-        let fee_history: FeeHistory = self.provider
-            .get_fee_history(
-                FEE_HISTORY_BLOCKS,
-                BlockId::latest(),
-                &REWARD_PERCENTILES,
-            )
-            .await?;
-
-        // reward[i][0] = 第 i 个块的第 10 百分位 priority fee
-        // reward[i][1] = 第 50 百分位
-        // reward[i][2] = 第 90 百分位
-        let mut percentiles = fee_history.reward.unwrap_or_default();
-
-        // 取最近 5 个块的中位数作为推荐值（最稳健）
-        let recent_rewards: Vec<U256> = percentiles
-            .iter()
-            .rev()
-            .take(5)
-            .map(|rewards| rewards[1]) // 中位数
-            .collect();
-
-        let mut recommended_priority = if recent_rewards.is_empty() {
-            U256::from(1_000_000_000u64) // 1 gwei 保底
-        } else {
-            let sum: U256 = recent_rewards.iter().sum();
-            sum / U256::from(recent_rewards.len())
-        };
-
-        // 防止极端情况太低（比如 L2 突然 0 fee）
-        if recommended_priority < U256::from(100_000_000u64) {
-            recommended_priority = U256::from(100_000_000u64); // 0.1 gwei 最低
+/// 看最近 20 个块
+pub const FEE_HISTORY_BLOCKS: u64 = 20;
+/// 依次对应慢、标准、快三档
+pub const REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+/// 配置缺省时的倍数
+pub const DEFAULT_MULTIPLIER: f32 = 1.0;
+/// 防止配置写错把手续费放大到离谱
+const MAX_MULTIPLIER: f32 = 5.0;
+
+const GWEI: u128 = 1_000_000_000;
+/// 主网等 L1 的最低 tip（0.01 gwei）
+const MIN_L1_TIP: u128 = GWEI / 100;
+/// L2 的最低 tip（0.001 gwei）
+const MIN_L2_TIP: u128 = GWEI / 1000;
+/// Polygon 节点拒绝低于 25 gwei tip 的交易
+const POLYGON_MIN_TIP: u128 = 25 * GWEI;
+/// L2 tip 最多为 base fee 的 2 倍，且不低于 0.01 gwei
+const L2_TIP_CAP_BASE_MULTIPLE: u128 = 2;
+const L2_TIP_CAP_FLOOR: u128 = GWEI / 100;
+/// BSC 出块由验证者排序，1 gwei 足够打包
+const BSC_TIP_CAP: u128 = GWEI;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeTier {
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl FeeTier {
+    pub const ALL: [FeeTier; 3] = [FeeTier::Slow, FeeTier::Normal, FeeTier::Fast];
+
+    fn index(self) -> usize {
+        match self {
+            FeeTier::Slow => 0,
+            FeeTier::Normal => 1,
+            FeeTier::Fast => 2,
         }
+    }
 
-        // 3. 计算下一块预计 base fee（EIP-1559 公式）
-        let next_base_fee = estimate_next_base_fee(&latest_block, &fee_history);
+    /// max_fee 为 base fee 预留的连续满块数（每块最多涨 12.5%）
+    fn base_fee_headroom_blocks(self) -> u32 {
+        match self {
+            FeeTier::Slow => 1,
+            FeeTier::Normal => 3,
+            FeeTier::Fast => 6,
+        }
+    }
 
-        Ok((current_base_fee, recommended_priority, next_base_fee))
+    /// legacy 链 gasPrice 的倍数（百分比）
+    fn legacy_percent(self) -> u128 {
+        match self {
+            FeeTier::Slow => 90,
+            FeeTier::Normal => 100,
+            FeeTier::Fast => 125,
+        }
+    }
+
+    /// L1 上预计多少个块内打包；出块快的链与 L2 按一个块算
+    fn expected_blocks(self, caps: &ChainCapabilities) -> u64 {
+        if caps.l2.is_some() || caps.block_time_ms < 3_000 {
+            return 1;
+        }
+        match self {
+            FeeTier::Slow => 10,
+            FeeTier::Normal => 3,
+            FeeTier::Fast => 1,
+        }
     }
 }
 
-/// 精确实现 EIP-1559 下一块 base fee 预估
-fn estimate_next_base_fee(
-    latest_block: &alloy::rpc::types::eth::Block,
-    fee_history: &FeeHistory,
-) -> U256 {
-    let base_fee = latest_block.header.base_fee_per_gas.unwrap_or_default();
-    let gas_used = latest_block.header.gas_used;
-    let gas_limit = latest_block.header.gas_limit;
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TierFee {
+    pub tier: FeeTier,
+    /// legacy 交易时即 gasPrice
+    pub max_fee_per_gas: u128,
+    /// legacy 交易时等于 gasPrice
+    pub max_priority_fee_per_gas: u128,
+    pub expected_inclusion_ms: u64,
+    /// tip 被链上限截断过
+    pub capped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FeeEstimate {
+    pub chain_id: u64,
+    /// false 时各档只有 gasPrice 有意义
+    pub eip1559: bool,
+    /// 下一个块的 base fee；legacy 为 0
+    pub next_base_fee: u128,
+    pub multiplier: f32,
+    pub tiers: Vec<TierFee>,
+}
 
-    let gas_used_ratio = if gas_limit == 0 {
-        U256::ZERO
-    } else {
-        U256::from(gas_used) * 100) / U256::from(gas_limit)
-    };
+impl FeeEstimate {
+    pub fn tier(&self, tier: FeeTier) -> &TierFee {
+        &self.tiers[tier.index()]
+    }
+}
+
+/// eth_feeHistory 的返回值中用到的部分
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeHistorySample {
+    /// 比区块数多一项，最后一项是下一个块的 base fee
+    pub base_fees: Vec<u128>,
+    pub gas_used_ratio: Vec<f64>,
+    /// 每个块按 REWARD_PERCENTILES 的 tip
+    pub rewards: Vec<[u128; 3]>,
+}
+
+fn quantity(value: &Value) -> Option<u128> {
+    u128::from_str_radix(value.as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+pub fn parse_fee_history(value: &Value) -> Result<FeeHistorySample, AppError> {
+    let base_fees = value
+        .get("baseFeePerGas")
+        .and_then(Value::as_array)
+        .ok_or(AppError::Parse("feeHistory missing baseFeePerGas"))?
+        .iter()
+        .map(|v| quantity(v).ok_or(AppError::Parse("invalid baseFeePerGas")))
+        .collect::<Result<Vec<_>, _>>()?;
+    if base_fees.is_empty() {
+        return Err(AppError::Parse("feeHistory missing baseFeePerGas"));
+    }
+    let gas_used_ratio = value
+        .get("gasUsedRatio")
+        .and_then(Value::as_array)
+        .map(|arr| arr.iter().map(|v| v.as_f64().unwrap_or(0.0)).collect())
+        .unwrap_or_default();
+    let rewards = value
+        .get("reward")
+        .and_then(Value::as_array)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|block| {
+                    let row = block.as_array()?;
+                    Some([quantity(row.first()?)?, quantity(row.get(1)?)?, quantity(row.get(2)?)?])
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(FeeHistorySample { base_fees, gas_used_ratio, rewards })
+}
+
+fn median(mut values: Vec<u128>) -> Option<u128> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    Some(values[values.len() / 2])
+}
+
+/// 校验配置倍数，超出 1.0..=5.0 直接报错而不是悄悄改成边界值
+pub fn check_multiplier(multiplier: Option<f32>) -> Result<f32, AppError> {
+    match multiplier {
+        None => Ok(DEFAULT_MULTIPLIER),
+        Some(m) if (1.0..=MAX_MULTIPLIER).contains(&m) => Ok(m),
+        Some(m) => Err(AppError::GasMultiplierInvalid(m)),
+    }
+}
+
+fn scale(value: u128, multiplier: f32) -> u128 {
+    // 按千分比计算，避免浮点误差放大到 wei
+    value.saturating_mul((multiplier * 1000.0).round() as u128) / 1000
+}
 
-    // EIP-1559 目标是 50% 填充率
-    let delta = if gas_used_ratio > U256::from(50) {
-        base_fee * (gas_used_ratio - U256::from(50)) / U256::from(50) / U256::from(8)
-    } else {
-        base_fee * (U256::from(50) - gas_used_ratio) / U256::from(50) / U256::from(8)
-    };
+/// (最低 tip, tip 上限)
+fn tip_bounds(caps: &ChainCapabilities, next_base_fee: u128) -> (u128, Option<u128>) {
+    match caps.l2 {
+        // Arbitrum 按 FIFO 排序，tip 不起作用
+        Some(L2Type::Arbitrum) => (0, Some(0)),
+        // Linea 的 tip 包含 L1 数据成本，不能按 base fee 截断；实际值以 linea_estimateGas 为准
+        Some(L2Type::Linea) => (MIN_L2_TIP, None),
+        Some(_) => (
+            MIN_L2_TIP,
+            Some(next_base_fee.saturating_mul(L2_TIP_CAP_BASE_MULTIPLE).max(L2_TIP_CAP_FLOOR)),
+        ),
+        None if caps.chain_id == 137 => (POLYGON_MIN_TIP, None),
+        None if caps.chain_id == 56 => (0, Some(BSC_TIP_CAP)),
+        None => (MIN_L1_TIP, None),
+    }
+}
+
+/// 由 feeHistory 样本计算三档 EIP-1559 费用。
+/// tip 取最近非空块对应百分位的中位数，单个块的异常值不影响结果
+pub fn eip1559_tiers(sample: &FeeHistorySample, caps: &ChainCapabilities, multiplier: f32) -> FeeEstimate {
+    let next_base_fee = *sample.base_fees.last().unwrap_or(&0);
+    let (floor, cap) = tip_bounds(caps, next_base_fee);
+    let tiers = FeeTier::ALL
+        .iter()
+        .map(|&tier| {
+            let observed = sample
+                .rewards
+                .iter()
+                .enumerate()
+                // 空块的 reward 全是 0，会把中位数拉低
+                .filter(|(i, _)| sample.gas_used_ratio.get(*i).is_none_or(|r| *r > 0.0))
+                .map(|(_, row)| row[tier.index()])
+                .collect();
+            let tip = scale(median(observed).unwrap_or(floor).max(floor), multiplier);
+            let (tip, capped) = match cap {
+                Some(cap) if tip > cap => (cap, true),
+                _ => (tip, false),
+            };
+            let headroom = (0..tier.base_fee_headroom_blocks()).fold(next_base_fee, |fee, _| fee.saturating_mul(9) / 8);
+            TierFee {
+                tier,
+                max_fee_per_gas: scale(headroom, multiplier).saturating_add(tip),
+                max_priority_fee_per_gas: tip,
+                expected_inclusion_ms: tier.expected_blocks(caps) * caps.block_time_ms,
+                capped,
+            }
+        })
+        .collect();
+    FeeEstimate { chain_id: caps.chain_id, eip1559: true, next_base_fee, multiplier, tiers }
+}
+
+/// legacy 链按 gasPrice 分档
+pub fn legacy_tiers(price: u128, caps: &ChainCapabilities, multiplier: f32) -> FeeEstimate {
+    let tiers = FeeTier::ALL
+        .iter()
+        .map(|&tier| {
+            let price = scale(price.saturating_mul(tier.legacy_percent()) / 100, multiplier);
+            TierFee {
+                tier,
+                max_fee_per_gas: price,
+                max_priority_fee_per_gas: price,
+                expected_inclusion_ms: tier.expected_blocks(caps) * caps.block_time_ms,
+                capped: false,
+            }
+        })
+        .collect();
+    FeeEstimate { chain_id: caps.chain_id, eip1559: false, next_base_fee: 0, multiplier, tiers }
+}
+
+/// 支持 EIP-1559 但 feeHistory 不可用：eth_gasPrice 约为 base fee + tip，max_fee 取 2 × gasPrice + tip，
+/// 任何一档都不低于 gasPrice，避免落到下一个块的 base fee 之下；
+/// tip 取节点的 eth_maxPriorityFeePerGas（不可用时取链的最低 tip）按档位缩放，不能把整个 gasPrice 当 tip 再付一遍 base fee
+pub fn fallback_1559_tiers(price: u128, suggested_tip: Option<u128>, caps: &ChainCapabilities, multiplier: f32) -> FeeEstimate {
+    // base fee 未知，L2 的 tip 上限按下限值算
+    let (floor, cap) = tip_bounds(caps, 0);
+    let base_tip = suggested_tip.unwrap_or(floor).max(floor);
+    let tiers = FeeTier::ALL
+        .iter()
+        .map(|&tier| {
+            let tip = scale(base_tip.saturating_mul(tier.legacy_percent()) / 100, multiplier).max(floor);
+            let (tip, capped) = match cap {
+                Some(cap) if tip > cap => (cap, true),
+                _ => (tip, false),
+            };
+            TierFee {
+                tier,
+                max_fee_per_gas: price.saturating_mul(2).saturating_add(tip),
+                max_priority_fee_per_gas: tip,
+                expected_inclusion_ms: tier.expected_blocks(caps) * caps.block_time_ms,
+                capped,
+            }
+        })
+        .collect();
+    FeeEstimate { chain_id: caps.chain_id, eip1559: true, next_base_fee: 0, multiplier, tiers }
+}
+
+/// 支持 EIP-1559 的链优先用 feeHistory，失败时退回 gasPrice
+pub async fn estimate_fees(
+    client: &Client,
+    url: &str,
+    caps: &ChainCapabilities,
+    multiplier: f32,
+) -> Result<FeeEstimate, AppError> {
+    if caps.eip1559 {
+        let history = fee_history(client.clone(), url, FEE_HISTORY_BLOCKS, REWARD_PERCENTILES.to_vec()).await;
+        if let Ok(sample) = history.and_then(|v| parse_fee_history(&v)) {
+            return Ok(eip1559_tiers(&sample, caps, multiplier));
+        }
+    }
+    let price = gas_price(client.clone(), url).await?.saturating_to::<u128>();
+    if caps.eip1559 {
+        // feeHistory 失败但链支持 1559 时仍发 type-2，gasPrice 已包含 base fee
+        let tip = max_priority_fee(client.clone(), url).await.ok().map(|t| t.saturating_to::<u128>());
+        return Ok(fallback_1559_tiers(price, tip, caps, multiplier));
+    }
+    Ok(legacy_tiers(price, caps, multiplier))
+}
+
+/// 按 AppState 中的链能力与 gas_price_multiplier 估算
+pub async fn estimate_fees_for_chain(
+    client: &Client,
+    url: &str,
+    chain_id: u64,
+    state: &AppState,
+) -> Result<FeeEstimate, AppError> {
+    let caps = state.chain_registry.lock().await.capabilities(chain_id);
+    let multiplier = check_multiplier(state.config.lock().await.gas_price_multiplier)?;
+    estimate_fees(client, url, &caps, multiplier).await
+}
+
+#[tauri::command]
+pub async fn fee_estimate(
+    chain_id: u64,
+    rpc_url: String,
+    state: State<'_, AppState>,
+) -> Result<FeeEstimate, AppError> {
+    let client = state.https_client.lock().await.clone();
+    estimate_fees_for_chain(&client, &rpc_url, chain_id, &state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::capability::builtin_capabilities;
+    use crate::utils::test_http;
+    use serde_json::json;
+
+    // 4 个块：第 3 个是空块，第 4 个 90 分位出现 500 gwei 的尖刺
+    fn history() -> Value {
+        json!({
+            "oldestBlock": "0x100",
+            "baseFeePerGas": ["0x2540be400", "0x2540be400", "0x2540be400", "0x2540be400", "0x2540be400"],
+            "gasUsedRatio": [0.5, 0.6, 0.0, 0.4],
+            "reward": [
+                ["0x3b9aca00", "0x77359400", "0xb2d05e00"],
+                ["0x3b9aca00", "0x77359400", "0xb2d05e00"],
+                ["0x0", "0x0", "0x0"],
+                ["0x3b9aca00", "0x77359400", "0x746a528800"]
+            ]
+        })
+    }
+
+    #[test]
+    fn test_parse_and_tiers() {
+        let sample = parse_fee_history(&history()).unwrap();
+        assert_eq!(sample.base_fees.len(), 5);
+        assert_eq!(sample.rewards.len(), 4);
+
+        let caps = builtin_capabilities(1).unwrap();
+        let estimate = eip1559_tiers(&sample, &caps, 1.0);
+        assert_eq!(estimate.next_base_fee, 10 * GWEI);
+        let slow = estimate.tier(FeeTier::Slow);
+        assert_eq!(slow.max_priority_fee_per_gas, GWEI);
+        assert_eq!(slow.max_fee_per_gas, 10 * GWEI * 9 / 8 + GWEI);
+        assert_eq!(slow.expected_inclusion_ms, 10 * 12_000);
+        // 空块被排除，尖刺只占一票
+        let fast = estimate.tier(FeeTier::Fast);
+        assert_eq!(fast.max_priority_fee_per_gas, 3 * GWEI);
+        assert!(!fast.capped);
+        assert_eq!(estimate.tier(FeeTier::Normal).max_priority_fee_per_gas, 2 * GWEI);
+
+        let scaled = eip1559_tiers(&sample, &caps, 1.5);
+        assert_eq!(scaled.tier(FeeTier::Normal).max_priority_fee_per_gas, 3 * GWEI);
+    }
+
+    #[test]
+    fn test_l2_tip_capped() {
+        let mut sample = parse_fee_history(&history()).unwrap();
+        // base fee 0.001 gwei：上限取 0.01 gwei 的下限值
+        sample.base_fees = vec![GWEI / 1000; 5];
+        let base = eip1559_tiers(&sample, &builtin_capabilities(8453).unwrap(), 1.0);
+        let fast = base.tier(FeeTier::Fast);
+        assert!(fast.capped);
+        assert_eq!(fast.max_priority_fee_per_gas, L2_TIP_CAP_FLOOR);
+        assert_eq!(fast.expected_inclusion_ms, 2_000);
+
+        let arb = eip1559_tiers(&sample, &builtin_capabilities(42161).unwrap(), 2.0);
+        assert!(arb.tiers.iter().all(|t| t.max_priority_fee_per_gas == 0));
+    }
+
+    #[test]
+    fn test_legacy_and_multiplier() {
+        let caps = ChainCapabilities::unknown(7777);
+        let estimate = legacy_tiers(10 * GWEI, &caps, check_multiplier(Some(1.2)).unwrap());
+        assert!(!estimate.eip1559);
+        assert_eq!(estimate.tier(FeeTier::Normal).max_fee_per_gas, 12 * GWEI);
+        assert_eq!(estimate.tier(FeeTier::Fast).max_fee_per_gas, 15 * GWEI);
+        assert!(check_multiplier(Some(50.0)).is_err());
+        assert!(check_multiplier(Some(0.1)).is_err());
+        assert!(check_multiplier(Some(f32::NAN)).is_err());
+        assert_eq!(check_multiplier(None).unwrap(), DEFAULT_MULTIPLIER);
+    }
+
+    #[test]
+    fn test_1559_fallback_tip() {
+        let caps = builtin_capabilities(1).unwrap();
+        let estimate = fallback_1559_tiers(30 * GWEI, Some(2 * GWEI), &caps, 1.0);
+        assert!(estimate.eip1559);
+        let normal = estimate.tier(FeeTier::Normal);
+        assert_eq!((normal.max_fee_per_gas, normal.max_priority_fee_per_gas), (62 * GWEI, 2 * GWEI));
+        assert_eq!(estimate.tier(FeeTier::Fast).max_priority_fee_per_gas, 5 * GWEI / 2);
+        // 节点不给建议值时退回最低 tip，而不是整个 gasPrice
+        let floor = fallback_1559_tiers(30 * GWEI, None, &caps, 1.0);
+        assert_eq!(floor.tier(FeeTier::Slow).max_priority_fee_per_gas, MIN_L1_TIP);
+        assert!(floor.tiers.iter().all(|t| t.max_priority_fee_per_gas < GWEI));
+
+        // gasPrice ≈ base fee 时，慢档的 max_fee 也不能低于 gasPrice
+        let price = 10 * GWEI + MIN_L1_TIP;
+        let near_base = fallback_1559_tiers(price, None, &caps, 1.0);
+        assert!(near_base.tiers.iter().all(|t| t.max_fee_per_gas >= price));
+        assert!(near_base.tier(FeeTier::Slow).max_fee_per_gas >= price);
+    }
 
-    if gas_used_ratio > U256::from(50) {
-        base_fee + delta
-    } else {
-        base_fee.saturating_sub(delta)
+    #[tokio::test]
+    async fn test_estimate_over_rpc() {
+        let body = test_http::rpc_result(history());
+        let base = test_http::serve(vec![("/", &body)]);
+        let caps = builtin_capabilities(1).unwrap();
+        let estimate = estimate_fees(&Client::new(), &format!("{base}/"), &caps, 1.0).await.unwrap();
+        assert!(estimate.eip1559);
+        assert_eq!(estimate.tiers.len(), 3);
     }
 }
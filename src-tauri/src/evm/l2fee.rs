@@ -0,0 +1,213 @@
+// L2 总费用：执行费之外还要算 L1 数据费
+// - OP Stack（Optimism / Base）：GasPriceOracle.getL1Fee(未签名交易)，从余额单独扣除，不受 gas_limit 约束
+// - Arbitrum：NodeInterface.gasEstimateComponents，L1 部分折算成 gas 计入 gas_limit
+// - Linea：linea_estimateGas，数据成本计入 priorityFeePerGas
+use crate::error::AppError;
+use crate::rpc::method::{JsonRpcRequest, RpcMethod, call};
+use crate::utils::num::str_to_u256;
+use alloy_primitives::{Address, Bytes, U256, address};
+use alloy_sol_types::{SolCall, sol};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// OP Stack 预部署的 GasPriceOracle
+pub const GAS_PRICE_ORACLE: Address = address!("0x420000000000000000000000000000000000000F");
+/// Arbitrum 的虚拟合约 NodeInterface，只能 eth_call
+pub const NODE_INTERFACE: Address = address!("0x00000000000000000000000000000000000000C8");
+/// L1 数据费在估算到上链之间可能上涨，上浮比例（百分比）
+pub const L1_FEE_BUFFER_PCT: u64 = 25;
+
+sol! {
+    interface IGasPriceOracle {
+        function getL1Fee(bytes data) external view returns (uint256);
+    }
+
+    interface INodeInterface {
+        function gasEstimateComponents(address to, bool contractCreation, bytes data) external payable returns (uint64 gasEstimate, uint64 gasEstimateForL1, uint256 baseFee, uint256 l1BaseFeeEstimate);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum L1FeeSource {
+    /// L1 或查询失败：只有执行费
+    #[default]
+    None,
+    OpGasPriceOracle,
+    ArbitrumNodeInterface,
+    /// 数据成本已包含在 gas 价格里，无法单独拆出
+    LineaEstimateGas,
+}
+
+/// 确认页展示的费用拆分；均为上限值（按 max_fee 计）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FeeBreakdown {
+    pub l2_execution_fee: U256,
+    pub l1_data_fee: U256,
+    /// 最多从余额扣除的手续费
+    pub total_fee: U256,
+    pub source: L1FeeSource,
+}
+
+impl FeeBreakdown {
+    /// l1_gas 是 gas_limit 中用于支付 L1 数据的部分（Arbitrum）；
+    /// l1_data_fee 是 gas_limit 之外单独收取的数据费（OP Stack）
+    pub fn new(gas_limit: u64, l1_gas: u64, max_fee_per_gas: u128, l1_data_fee: U256, source: L1FeeSource) -> Self {
+        let price = U256::from(max_fee_per_gas);
+        let l1_gas = l1_gas.min(gas_limit);
+        let l1_data_fee = l1_data_fee + U256::from(l1_gas) * price;
+        let l2_execution_fee = U256::from(gas_limit - l1_gas) * price;
+        Self { l2_execution_fee, l1_data_fee, total_fee: l2_execution_fee + l1_data_fee, source }
+    }
+}
+
+/// 发送全部余额时能转出的数量；余额不够付手续费时为 None
+pub fn max_sendable(balance: U256, cost: &FeeBreakdown) -> Option<U256> {
+    balance.checked_sub(cost.total_fee).filter(|amount| !amount.is_zero())
+}
+
+pub fn with_l1_buffer(fee: U256) -> U256 {
+    fee + fee * U256::from(L1_FEE_BUFFER_PCT) / U256::from(100)
+}
+
+fn result_bytes(result: &Value) -> Result<Vec<u8>, AppError> {
+    let hex = result.as_str().ok_or(AppError::JsonRpcInvalidResponse)?;
+    Ok(hex::decode(hex.trim_start_matches("0x"))?)
+}
+
+/// unsigned_tx 为带类型前缀的未签名交易编码（SignableTransaction::encoded_for_signing）
+pub async fn op_l1_fee(client: &Client, url: &str, unsigned_tx: &[u8]) -> Result<U256, AppError> {
+    let data = IGasPriceOracle::getL1FeeCall { data: Bytes::copy_from_slice(unsigned_tx) }.abi_encode();
+    let req = JsonRpcRequest::new(
+        RpcMethod::EthCall.as_str(),
+        json!([{"to": format!("{:#x}", GAS_PRICE_ORACLE), "data": format!("0x{}", hex::encode(data))}, "latest"]),
+    );
+    let raw = result_bytes(&call(client.clone(), url, req).await?)?;
+    IGasPriceOracle::getL1FeeCall::abi_decode_returns(&raw).map_err(|_| AppError::Parse("invalid getL1Fee result"))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbGasComponents {
+    /// 含 L1 部分的总 gas
+    pub gas_estimate: u64,
+    pub gas_estimate_for_l1: u64,
+    pub base_fee: U256,
+    pub l1_base_fee_estimate: U256,
+}
+
+pub async fn arbitrum_gas_components(
+    client: &Client,
+    url: &str,
+    from: &Address,
+    to: &Address,
+    value: U256,
+    data: &Bytes,
+) -> Result<ArbGasComponents, AppError> {
+    let calldata = INodeInterface::gasEstimateComponentsCall { to: *to, contractCreation: false, data: data.clone() }.abi_encode();
+    let req = JsonRpcRequest::new(
+        RpcMethod::EthCall.as_str(),
+        json!([{
+            "from": format!("{:#x}", from),
+            "to": format!("{:#x}", NODE_INTERFACE),
+            "value": format!("{:#x}", value),
+            "data": format!("0x{}", hex::encode(calldata)),
+        }, "latest"]),
+    );
+    let raw = result_bytes(&call(client.clone(), url, req).await?)?;
+    let ret = INodeInterface::gasEstimateComponentsCall::abi_decode_returns(&raw)
+        .map_err(|_| AppError::Parse("invalid gasEstimateComponents result"))?;
+    Ok(ArbGasComponents {
+        gas_estimate: ret.gasEstimate,
+        gas_estimate_for_l1: ret.gasEstimateForL1,
+        base_fee: ret.baseFee,
+        l1_base_fee_estimate: ret.l1BaseFeeEstimate,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineaGasEstimate {
+    pub gas_limit: u64,
+    pub base_fee_per_gas: u128,
+    pub priority_fee_per_gas: u128,
+}
+
+pub fn parse_linea_estimate(value: &Value) -> Result<LineaGasEstimate, AppError> {
+    let field = |name: &str| -> Result<U256, AppError> {
+        str_to_u256(value.get(name).and_then(Value::as_str).ok_or(AppError::JsonRpcInvalidResponse)?)
+    };
+    Ok(LineaGasEstimate {
+        gas_limit: field("gasLimit")?.saturating_to(),
+        base_fee_per_gas: field("baseFeePerGas")?.saturating_to(),
+        priority_fee_per_gas: field("priorityFeePerGas")?.saturating_to(),
+    })
+}
+
+pub async fn linea_estimate_gas(
+    client: &Client,
+    url: &str,
+    from: &Address,
+    to: &Address,
+    value: U256,
+    data: &Bytes,
+) -> Result<LineaGasEstimate, AppError> {
+    let req = JsonRpcRequest::new(
+        RpcMethod::Custom("linea_estimateGas").as_str(),
+        json!([{
+            "from": format!("{:#x}", from),
+            "to": format!("{:#x}", to),
+            "value": format!("{:#x}", value),
+            "data": format!("0x{}", hex::encode(data)),
+        }]),
+    );
+    parse_linea_estimate(&call(client.clone(), url, req).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_http;
+    use alloy_sol_types::SolValue;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    #[test]
+    fn test_breakdown_and_send_max() {
+        // OP：数据费在 gas_limit 之外
+        let op = FeeBreakdown::new(21_000, 0, GWEI, U256::from(5_000u64), L1FeeSource::OpGasPriceOracle);
+        assert_eq!(op.l2_execution_fee, U256::from(21_000 * GWEI));
+        assert_eq!(op.total_fee, U256::from(21_000 * GWEI + 5_000));
+        // Arbitrum：gas_limit 中的 L1 部分算作数据费，总额不变
+        let arb = FeeBreakdown::new(100_000, 40_000, GWEI, U256::ZERO, L1FeeSource::ArbitrumNodeInterface);
+        assert_eq!(arb.l1_data_fee, U256::from(40_000 * GWEI));
+        assert_eq!(arb.total_fee, U256::from(100_000 * GWEI));
+
+        assert_eq!(max_sendable(U256::from(21_000 * GWEI + 5_001), &op), Some(U256::from(1)));
+        assert_eq!(max_sendable(U256::from(21_000 * GWEI + 5_000), &op), None);
+        assert_eq!(max_sendable(U256::ZERO, &op), None);
+        assert_eq!(with_l1_buffer(U256::from(100)), U256::from(125));
+    }
+
+    #[test]
+    fn test_selectors_and_linea_parse() {
+        assert_eq!(IGasPriceOracle::getL1FeeCall::SELECTOR, [0x49, 0x94, 0x8e, 0x0e]);
+        assert_eq!(INodeInterface::gasEstimateComponentsCall::SELECTOR, [0xc9, 0x4e, 0x6e, 0xeb]);
+        let estimate = parse_linea_estimate(&json!({"gasLimit": "0x5208", "baseFeePerGas": "0x7", "priorityFeePerGas": "0x3b9aca00"})).unwrap();
+        assert_eq!(estimate, LineaGasEstimate { gas_limit: 21_000, base_fee_per_gas: 7, priority_fee_per_gas: GWEI });
+        assert!(parse_linea_estimate(&json!({"gasLimit": "0x5208"})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_arbitrum_components_over_rpc() {
+        let ret = (30_000u64, 9_000u64, U256::from(10_000_000u64), U256::from(20 * GWEI)).abi_encode_params();
+        let body = test_http::rpc_result(format!("0x{}", hex::encode(ret)));
+        let base = test_http::serve(vec![("/", body.as_str())]);
+        let from = address!("0x1111111111111111111111111111111111111111");
+        let components = arbitrum_gas_components(&Client::new(), &format!("{base}/"), &from, &from, U256::ZERO, &Bytes::new())
+            .await
+            .unwrap();
+        assert_eq!(components.gas_estimate, 30_000);
+        assert_eq!(components.gas_estimate_for_l1, 9_000);
+        assert_eq!(components.base_fee, U256::from(10_000_000u64));
+    }
+}
@@ -3,3 +3,11 @@ pub mod token;
 pub mod assets;
 pub mod address;
 pub mod chains;
+pub mod capability;
+pub mod gas;
+pub mod l2fee;
+pub mod selector;
+pub mod calldata;
+pub mod fourbyte;
+pub mod permit2;
+pub mod dryrun;
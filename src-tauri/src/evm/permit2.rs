@@ -1,141 +1,354 @@
-// permit2_parser.rs
-// 2025 顶级钱包必备：一键解析 Permit2 / Permit / EIP-2612 / Permit2Batch
-// 支持：USDC、USDT、DAI、WETH、UNI、所有主流代币
+// Permit2 / EIP-2612 / DAI permit calldata 解析
+// 按 ABI 解码（动态类型的偏移不固定，不能按固定位置截取），并给出无限额、长期授权的风险分级
 
-use alloy_primitives::{Address, B256, U256, Bytes};
+use alloy_primitives::{Address, Bytes, U160, U256};
+use alloy_sol_types::{SolCall, sol};
 use serde::Serialize;
 
+use crate::evm::address::known::PERMIT2;
+use crate::utils::time::now_s;
+
+/// 超过这个时长的授权或签名视为长期
+pub const LONG_LIVED_SECS: u64 = 30 * 24 * 3600;
+
+sol! {
+    interface IAllowanceTransfer {
+        struct PermitDetails {
+            address token;
+            uint160 amount;
+            uint48 expiration;
+            uint48 nonce;
+        }
+
+        struct PermitSingle {
+            PermitDetails details;
+            address spender;
+            uint256 sigDeadline;
+        }
+
+        struct PermitBatch {
+            PermitDetails[] details;
+            address spender;
+            uint256 sigDeadline;
+        }
+
+        struct TokenSpenderPair {
+            address token;
+            address spender;
+        }
+
+        event Approval(address indexed owner, address indexed token, address indexed spender, uint160 amount, uint48 expiration);
+        event Permit(address indexed owner, address indexed token, address indexed spender, uint160 amount, uint48 expiration, uint48 nonce);
+
+        function permit(address owner, PermitSingle permitSingle, bytes signature) external;
+        function permit(address owner, PermitBatch permitBatch, bytes signature) external;
+        function allowance(address user, address token, address spender) external view returns (uint160 amount, uint48 expiration, uint48 nonce);
+        function lockdown(TokenSpenderPair[] approvals) external;
+    }
+
+    interface IERC20Permit {
+        function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+    }
+
+    interface IDaiPermit {
+        function permit(address holder, address spender, uint256 nonce, uint256 expiry, bool allowed, uint8 v, bytes32 r, bytes32 s) external;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub enum PermitKind {
+    Permit2Single,
+    Permit2Batch,
+    Eip2612,
+    DaiPermit,
+}
+
+/// 单个代币的授权；expiration 为 None 表示授权本身不过期（EIP-2612 / DAI）
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PermitGrant {
+    pub token: Address,
+    pub amount: U256,
+    pub unlimited: bool,
+    pub expiration: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskReason {
+    UnlimitedAmount,
+    /// 授权本身不会过期
+    NoExpiration,
+    LongLivedAllowance,
+    /// 签名长期有效，可以在很久以后被提交
+    LongLivedSignature,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PermitRisk {
+    pub level: RiskLevel,
+    pub reasons: Vec<RiskReason>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct PermitInfo {
     pub kind: PermitKind,
-    pub token: Address,
     pub owner: Address,
     pub spender: Address,
-    pub amount: U256,
-    pub deadline: u64,
-    pub signature: Bytes,
+    pub grants: Vec<PermitGrant>,
+    /// 签名截止时间；DAI 的 expiry 为 0 时表示永不过期，记为 None
+    pub deadline: Option<u64>,
     pub nonce: Option<U256>,
-    pub is_permit2: bool,
+    /// EIP-2612 / DAI 为 r || s || v
+    pub signature: Bytes,
+    pub risk: PermitRisk,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub enum PermitKind {
-    PermitSingle,
-    PermitBatch,
-    EIP2612,        // DAI-style
-    Permit2Single,
-    Permit2Batch,
-    Invalid,
+fn to_u64(value: U256) -> u64 {
+    value.saturating_to()
 }
 
-// 官方 Permit2 地址（主网 + 所有 L2 都一样）
-const PERMIT2: Address = Address::new([0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]);
+fn permit2_grant(details: &IAllowanceTransfer::PermitDetails) -> PermitGrant {
+    PermitGrant {
+        token: details.token,
+        amount: U256::from(details.amount),
+        unlimited: details.amount == U160::MAX,
+        expiration: Some(details.expiration.to::<u64>()),
+    }
+}
 
-// 官方签名
-const PERMIT2_PERMIT_SINGLE: [u8; 4] = [0x3d, 0x9d, 0x2f, 0x2d]; // permit(PermitSingle calldata, bytes)
-const PERMIT2_PERMIT_BATCH: [u8; 4] = [0x2d, 0x0d, 0x6e, 0x2a]; // permit(PermitBatch calldata, bytes)
+fn vrs_signature(v: u8, r: &[u8], s: &[u8]) -> Bytes {
+    let mut sig = Vec::with_capacity(65);
+    sig.extend_from_slice(r);
+    sig.extend_from_slice(s);
+    sig.push(v);
+    sig.into()
+}
 
 impl PermitInfo {
-    pub fn parse(from: Address, to: Address, data: &Bytes) -> Option<Self> {
-        if data.is_empty() { return None; }
-
-        let selector = &data[0..4];
+    pub fn parse(to: Address, data: &[u8]) -> Option<Self> {
+        Self::parse_at(to, data, now_s())
+    }
 
-        // 1. 直接调用 Permit2 合约
-        if to == PERMIT2 {
-            if selector == &PERMIT2_PERMIT_SINGLE {
-                return Self::parse_permit2_single(from, data);
+    /// to 为被调用的合约：Permit2 本身，或 EIP-2612 / DAI permit 的代币
+    pub fn parse_at(to: Address, data: &[u8], now: u64) -> Option<Self> {
+        let selector: [u8; 4] = data.get(..4)?.try_into().ok()?;
+        let mut info = match selector {
+            IAllowanceTransfer::permit_0Call::SELECTOR if to == PERMIT2 => {
+                let call = IAllowanceTransfer::permit_0Call::abi_decode(data).ok()?;
+                let permit = call.permitSingle;
+                Self {
+                    kind: PermitKind::Permit2Single,
+                    owner: call.owner,
+                    spender: permit.spender,
+                    grants: vec![permit2_grant(&permit.details)],
+                    deadline: Some(to_u64(permit.sigDeadline)),
+                    nonce: Some(U256::from(permit.details.nonce)),
+                    signature: call.signature,
+                    risk: PermitRisk { level: RiskLevel::Low, reasons: Vec::new() },
+                }
             }
-            if selector == &PERMIT2_PERMIT_BATCH {
-                return Self::parse_permit2_batch(from, data);
+            IAllowanceTransfer::permit_1Call::SELECTOR if to == PERMIT2 => {
+                let call = IAllowanceTransfer::permit_1Call::abi_decode(data).ok()?;
+                let permit = call.permitBatch;
+                Self {
+                    kind: PermitKind::Permit2Batch,
+                    owner: call.owner,
+                    spender: permit.spender,
+                    grants: permit.details.iter().map(permit2_grant).collect(),
+                    deadline: Some(to_u64(permit.sigDeadline)),
+                    nonce: None,
+                    signature: call.signature,
+                    risk: PermitRisk { level: RiskLevel::Low, reasons: Vec::new() },
+                }
             }
-        }
+            IERC20Permit::permitCall::SELECTOR => {
+                let call = IERC20Permit::permitCall::abi_decode(data).ok()?;
+                Self {
+                    kind: PermitKind::Eip2612,
+                    owner: call.owner,
+                    spender: call.spender,
+                    grants: vec![PermitGrant {
+                        token: to,
+                        amount: call.value,
+                        unlimited: call.value == U256::MAX,
+                        expiration: None,
+                    }],
+                    deadline: Some(to_u64(call.deadline)),
+                    nonce: None,
+                    signature: vrs_signature(call.v, call.r.as_slice(), call.s.as_slice()),
+                    risk: PermitRisk { level: RiskLevel::Low, reasons: Vec::new() },
+                }
+            }
+            IDaiPermit::permitCall::SELECTOR => {
+                let call = IDaiPermit::permitCall::abi_decode(data).ok()?;
+                // allowed=true 授权 uint256 最大值，false 为撤销
+                let amount = if call.allowed { U256::MAX } else { U256::ZERO };
+                Self {
+                    kind: PermitKind::DaiPermit,
+                    owner: call.holder,
+                    spender: call.spender,
+                    grants: vec![PermitGrant { token: to, amount, unlimited: call.allowed, expiration: None }],
+                    deadline: Some(to_u64(call.expiry)).filter(|expiry| *expiry != 0),
+                    nonce: Some(call.nonce),
+                    signature: vrs_signature(call.v, call.r.as_slice(), call.s.as_slice()),
+                    risk: PermitRisk { level: RiskLevel::Low, reasons: Vec::new() },
+                }
+            }
+            _ => return None,
+        };
+        info.risk = info.classify(now);
+        Some(info)
+    }
 
-        // 2. 代币直接调用 EIP-2612 permit()
-        if selector == &[0x8b, 0xa9, 0x3c, 0xb9] {
-            // permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s)
-            return Self::parse_eip2612(to, from, data);
-        }
+    pub fn is_permit2(&self) -> bool {
+        matches!(self.kind, PermitKind::Permit2Single | PermitKind::Permit2Batch)
+    }
+
+    /// 撤销（DAI allowed=false 或数量为 0）不算风险
+    pub fn is_revoke(&self) -> bool {
+        self.grants.iter().all(|g| g.amount.is_zero())
+    }
 
-        // 3. DAI-style permit (address holder, address spender, uint256 nonce, uint256 expiry, bool allowed, uint8 v, bytes32 r, bytes32 s)
-        if selector == &[0x9e, 0x92, 0x68, 0x4a] {
-            return Self::parse_dai_permit(to, from, data);
+    /// 无限额且长期（或不过期）为 High；二者之一为 Medium
+    pub fn classify(&self, now: u64) -> PermitRisk {
+        let mut reasons = Vec::new();
+        if self.is_revoke() {
+            return PermitRisk { level: RiskLevel::Low, reasons };
+        }
+        let long_lived = |ts: u64| ts.saturating_sub(now) > LONG_LIVED_SECS;
+        if self.grants.iter().any(|g| g.unlimited) {
+            reasons.push(RiskReason::UnlimitedAmount);
+        }
+        // Permit2 的 expiration 为 0 时按当前区块时间处理，即立即过期
+        if self.grants.iter().any(|g| g.expiration.is_none()) {
+            reasons.push(RiskReason::NoExpiration);
+        } else if self.grants.iter().any(|g| g.expiration.is_some_and(long_lived)) {
+            reasons.push(RiskReason::LongLivedAllowance);
         }
+        if self.deadline.is_none_or(long_lived) {
+            reasons.push(RiskReason::LongLivedSignature);
+        }
+        let unlimited = reasons.contains(&RiskReason::UnlimitedAmount);
+        let lasting = reasons.iter().any(|r| matches!(r, RiskReason::NoExpiration | RiskReason::LongLivedAllowance));
+        let level = match (unlimited, lasting) {
+            (true, true) => RiskLevel::High,
+            _ if reasons.is_empty() => RiskLevel::Low,
+            _ => RiskLevel::Medium,
+        };
+        PermitRisk { level, reasons }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{B256, aliases::U48, address};
 
-        None
+    const NOW: u64 = 1_760_745_600;
+    const OWNER: Address = address!("0x1111111111111111111111111111111111111111");
+    const SPENDER: Address = address!("0x2222222222222222222222222222222222222222");
+    const TOKEN: Address = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+
+    fn details(amount: U160, expiration: u64) -> IAllowanceTransfer::PermitDetails {
+        IAllowanceTransfer::PermitDetails { token: TOKEN, amount, expiration: U48::from(expiration), nonce: U48::from(3) }
     }
 
-    fn parse_permit2_single(owner: Address, data: &Bytes) -> Option<Self> {
-        // 结构太复杂，用简单方式：从 calldata 截取关键字段
-        if data.len() < 200 { return None; }
-
-        let token = Address::from_slice(&data[84..104]);     // PermitSingle.token
-        let amount = U256::from_be_bytes(data[104..136].try_into().ok()?);
-        let deadline = u64::from_be_bytes(data[168..176].try_into().ok()?);
-        let spender = Address::from_slice(&data[176..196]);  // PermitSingle.spender
-
-        Some(PermitInfo {
-            kind: PermitKind::Permit2Single,
-            token,
-            owner,
-            spender,
-            amount,
-            deadline,
-            signature: data[data.len()-65..].to_vec().into(),
-            nonce: None,
-            is_permit2: true,
-        })
+    #[test]
+    fn test_selectors() {
+        assert_eq!(IAllowanceTransfer::permit_0Call::SELECTOR, [0x2b, 0x67, 0xb5, 0x70]);
+        assert_eq!(IAllowanceTransfer::permit_1Call::SELECTOR, [0x2a, 0x2d, 0x80, 0xd1]);
+        assert_eq!(IERC20Permit::permitCall::SELECTOR, [0xd5, 0x05, 0xac, 0xcf]);
+        assert_eq!(IDaiPermit::permitCall::SELECTOR, [0x8f, 0xcb, 0xaf, 0x0c]);
     }
 
-    fn parse_eip2612(token: Address, owner: Address, data: &Bytes) -> Option<Self> {
-        if data.len() < 132 { return None; }
-        let spender = Address::from_slice(&data[36..56]);
-        let value = U256::from_be_bytes(data[68..100].try_into().ok()?);
-        let deadline = u64::from_be_bytes(data[100..132].try_into().ok()?);
-
-        Some(PermitInfo {
-            kind: PermitKind::EIP2612,
-            token,
-            owner,
-            spender,
-            amount: value,
-            deadline,
-            signature: data[data.len()-65..].to_vec().into(),
-            nonce: None,
-            is_permit2: false,
-        })
+    #[test]
+    fn test_parse_permit2() {
+        let signature = Bytes::from(vec![0xab; 65]);
+        let single = IAllowanceTransfer::permit_0Call {
+            owner: OWNER,
+            permitSingle: IAllowanceTransfer::PermitSingle {
+                details: details(U160::MAX, NOW + 365 * 24 * 3600),
+                spender: SPENDER,
+                sigDeadline: U256::from(NOW + 1800),
+            },
+            signature: signature.clone(),
+        }
+        .abi_encode();
+        let info = PermitInfo::parse_at(PERMIT2, &single, NOW).unwrap();
+        assert_eq!(info.kind, PermitKind::Permit2Single);
+        assert_eq!((info.owner, info.spender, info.signature), (OWNER, SPENDER, signature.clone()));
+        assert_eq!(info.grants[0].token, TOKEN);
+        assert_eq!(info.nonce, Some(U256::from(3)));
+        assert_eq!(info.risk.level, RiskLevel::High);
+        assert_eq!(info.risk.reasons, [RiskReason::UnlimitedAmount, RiskReason::LongLivedAllowance]);
+        // 只有发往 Permit2 的调用才按 Permit2 解析
+        assert!(PermitInfo::parse_at(TOKEN, &single, NOW).is_none());
+
+        let batch = IAllowanceTransfer::permit_1Call {
+            owner: OWNER,
+            permitBatch: IAllowanceTransfer::PermitBatch {
+                details: vec![details(U160::from(1_000_000), NOW + 3600), details(U160::from(5), NOW + 3600)],
+                spender: SPENDER,
+                sigDeadline: U256::from(NOW + 1800),
+            },
+            signature,
+        }
+        .abi_encode();
+        let info = PermitInfo::parse_at(PERMIT2, &batch, NOW).unwrap();
+        assert_eq!(info.kind, PermitKind::Permit2Batch);
+        assert_eq!(info.grants.len(), 2);
+        assert_eq!(info.grants[0].amount, U256::from(1_000_000));
+        assert_eq!(info.risk, PermitRisk { level: RiskLevel::Low, reasons: vec![] });
     }
 
-    fn parse_dai_permit(token: Address, owner: Address, data: &Bytes) -> Option<Self> {
-        if data.len() < 132 { return None; }
-        let spender = Address::from_slice(&data[36..56]);
-        let allowed = data[100] != 0;
-        let amount = if allowed { U256::MAX } else { U256::ZERO };
-        let expiry = u64::from_be_bytes(data[68..100].try_into().ok()?);
-
-        Some(PermitInfo {
-            kind: PermitKind::EIP2612,
-            token,
-            owner,
-            spender,
-            amount,
-            deadline: expiry,
-            signature: data[data.len()-65..].to_vec().into(),
-            nonce: None,
-            is_permit2: false,
-        })
+    #[test]
+    fn test_parse_token_permits() {
+        let eip2612 = IERC20Permit::permitCall {
+            owner: OWNER,
+            spender: SPENDER,
+            value: U256::MAX,
+            deadline: U256::from(NOW + 600),
+            v: 27,
+            r: B256::repeat_byte(0x01),
+            s: B256::repeat_byte(0x02),
+        }
+        .abi_encode();
+        let info = PermitInfo::parse_at(TOKEN, &eip2612, NOW).unwrap();
+        assert_eq!(info.kind, PermitKind::Eip2612);
+        assert_eq!(info.signature.len(), 65);
+        assert_eq!(info.signature[64], 27);
+        assert_eq!(info.risk.level, RiskLevel::High);
+        assert_eq!(info.risk.reasons, [RiskReason::UnlimitedAmount, RiskReason::NoExpiration]);
+
+        let dai = |allowed: bool| {
+            IDaiPermit::permitCall {
+                holder: OWNER,
+                spender: SPENDER,
+                nonce: U256::from(7),
+                expiry: U256::ZERO,
+                allowed,
+                v: 28,
+                r: B256::repeat_byte(0x01),
+                s: B256::repeat_byte(0x02),
+            }
+            .abi_encode()
+        };
+        let info = PermitInfo::parse_at(TOKEN, &dai(true), NOW).unwrap();
+        assert_eq!(info.kind, PermitKind::DaiPermit);
+        assert_eq!(info.deadline, None);
+        assert_eq!(info.grants[0].amount, U256::MAX);
+        assert_eq!(info.risk.reasons, [RiskReason::UnlimitedAmount, RiskReason::NoExpiration, RiskReason::LongLivedSignature]);
+        // 撤销
+        let info = PermitInfo::parse_at(TOKEN, &dai(false), NOW).unwrap();
+        assert!(info.is_revoke());
+        assert_eq!(info.risk.level, RiskLevel::Low);
     }
 }
-
-// let permit = PermitInfo::parse(from, to, &calldata);
-
-// if let Some(p) = permit {
-//     println!("用户正在给 {} 授权 {} 个 {}", p.spender, p.amount / 1e18, token_symbol(p.token));
-//     if p.amount > U256::from(1_000_000e18) {
-//         show_warning!("超大额授权风险！");
-//     }
-// }
-// Permit2 授权检测
-// 用户正在使用 Permit2 给 1inch Router 授权 999,999 USDC
-// 截止时间：2025年12月31日
-// 这是高风险操作，可能导致资金被盗
\ No newline at end of file
@@ -10,6 +10,9 @@ use alloy_sol_types::{sol, SolCall, SolInterface};
 sol! {
     // ERC20 标准接口
     interface IERC20 {
+        function name() external view returns (string);
+        function symbol() external view returns (string);
+        function decimals() external view returns (uint8);
         function totalSupply() external view returns (uint256);
         function balanceOf(address) external view returns (uint256);
         function transfer(address to, uint256 value) external returns (bool);
@@ -152,7 +155,7 @@ pub mod erc165 {
     pub const IERC721: [u8; 4]     = [0x80, 0xac, 0x58, 0xcd];
     pub const IERC721_METADATA: [u8; 4] = [0x5b, 0x5e, 0x13, 0x9f];
     pub const IERC721_ENUMERABLE: [u8; 4] = [0x78, 0x0e, 0x9d, 0x63];
-    pub const IERC1155: [u8; 4]    = [0xd9, 0xb6, 0x7a, 0x6e];
+    pub const IERC1155: [u8; 4]    = [0xd9, 0xb6, 0x7a, 0x26];
     pub const IERC1155_METADATA: [u8; 4] = [0x0e, 0x89, 0x36, 0x9c];
 }
 
@@ -36,8 +36,12 @@ pub async fn init_helios() -> Result<Client, Box<dyn std::error::Error>> {
 
 #[tauri::command]
 pub async fn switch_chain(state: State<'_, AppState>, chain: String) -> Result<(), String> {
-    let supported = ["eth", "base", "linea"];
-    if !supported.contains(&chain.as_str()) {
+    // 是否支持轻客户端以链能力表为准
+    let supported = match chain_id_for_slug(&chain) {
+        Some(chain_id) => state.chain_registry.lock().await.capabilities(chain_id).light_client,
+        None => false,
+    };
+    if !supported {
         return Err(format!("Helios 不支持该链: {}", chain));
     }
 
@@ -81,6 +85,18 @@ pub async fn switch_chain(state: State<'_, AppState>, chain: String) -> Result<(
     Ok(())
 }
 
+/// Helios 使用的链简称 → chain_id
+fn chain_id_for_slug(chain: &str) -> Option<u64> {
+    Some(match chain {
+        "eth" => 1,
+        "base" => 8453,
+        "linea" => 59144,
+        "optimism" => 10,
+        "arbitrum" => 42161,
+        _ => return None,
+    })
+}
+
 fn consensus_rpc_for_chain(chain: &str) -> String {
     match chain {
         "eth"      => "https://www.lightclientdata.org",
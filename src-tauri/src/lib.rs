@@ -7,9 +7,11 @@ mod utils;
 mod data;
 mod rpc;
 mod evm;
+mod eips;
 mod revm;
 mod apps;
 mod helios;
+mod actions;
 
 use tauri::Manager;
 use crate::helios::handler::helios_protocol_handler;
@@ -39,6 +41,90 @@ pub fn run() {
             dapp::dapp::sign_transaction,
             dapp::dapp::dapp_post_message,
             dapp::dapp::open_dapp_window,
+            // Data 相关命令
+            data::export::tx_export_csv,
+            data::tx::tx_annotate,
+            data::tx::tx_enrich,
+            data::msg::message_add,
+            data::msg::message_delete,
+            data::msg::message_list,
+            data::msg::message_verify_chain,
+            data::msg::message_reverify,
+            data::addr::addressbook_list,
+            data::addr::addressbook_search,
+            data::addr::addressbook_add,
+            data::addr::addressbook_delete,
+            data::addr::addressbook_export,
+            data::addr::addressbook_import,
+            data::poison::recipient_check,
+            data::poison::tx_scan_poisoning,
+            data::contract::contract_lookup,
+            data::contract::contract_list,
+            data::contract::contract_registry_update,
+            data::contract::contract_user_add,
+            data::contract::contract_user_delete,
+            data::abi::abi_get,
+            data::abi::abi_decode_calldata,
+            data::abi::abi_user_add,
+            data::abi::abi_delete,
+            data::abi::abi_list,
+            data::erc7730::clearsign_descriptor_list,
+            data::erc7730::clearsign_descriptor_add,
+            data::erc7730::clearsign_descriptor_delete,
+            data::erc7730::clearsign_preview,
+            data::forex::fiat_rates,
+            data::forex::fiat_convert,
+            data::forex::fiat_set_providers,
+            data::price::token_prices,
+            data::ens::ens_resolve,
+            data::ens::ens_lookup_address,
+            data::ens::ens_profile,
+            data::ens::ens_resolve_recipient,
+            data::tokenlist::token_list,
+            data::tokenlist::tokenlist_list,
+            data::tokenlist::tokenlist_import,
+            data::tokenlist::tokenlist_refresh,
+            data::tokenlist::tokenlist_set_enabled,
+            data::tokenlist::tokenlist_remove,
+            data::tokenlist::token_custom_add,
+            data::tokenlist::token_custom_delete,
+            data::tokenlist::token_set_preference,
+            data::balance::balances_get,
+            data::balance::balances_watch,
+            data::spam::spam_classify,
+            data::spam::spam_filter,
+            data::spam::spam_override_set,
+            data::spam::spam_override_list,
+            data::meta::nft_metadata,
+            data::meta::nft_gateways_get,
+            data::meta::nft_gateways_set,
+            // EVM 相关命令
+            evm::chains::chain_list,
+            evm::chains::chain_add,
+            evm::chains::chain_suggestions,
+            evm::chains::chain_suggestion_accept,
+            evm::chains::chain_suggestion_dismiss,
+            evm::chains::chain_remove,
+            evm::chains::chain_capabilities,
+            evm::chains::chain_capabilities_set,
+            evm::gas::fee_estimate,
+            evm::fourbyte::signature_lookup,
+            evm::fourbyte::signature_user_add,
+            evm::fourbyte::signature_user_delete,
+            evm::fourbyte::signature_bundle_update,
+            evm::calldata::calldata_decode,
+            // Actions 相关命令
+            actions::nft::nft_detect_standard,
+            actions::nft::nft_transfer_prepare,
+            actions::nft::nft_approval_prepare,
+            actions::nft::nft_send,
+            actions::tx::tx_prepare,
+            actions::tx::tx_prepare_send_max,
+            actions::tx::tx_send,
+            actions::approval::approval_scan,
+            actions::approval::approval_revoke_prepare,
+            revm::fork::simulate_tx,
+            revm::fork::simulate_bundle,
             // Helios 相关命令
             // 可以在这里添加更多的 Helios 命令
         ])
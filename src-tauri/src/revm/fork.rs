@@ -0,0 +1,509 @@
+// 分叉状态模拟：revm 31 + AlloyDB（按需经 RPC 拉取账户 / 代码 / storage）+ CacheDB（同一次模拟内缓存），
+// 状态固定在某个区块。返回成功 / revert、gas、返回值、日志和状态差异，确认页统一用它做预执行
+use crate::core::state::AppState;
+use crate::error::AppError;
+use crate::evm::calldata::{DecodedEvent, decode_event};
+use crate::rpc::method::get_block_by_number;
+use crate::utils::num::{str_to_u64, str_to_u256};
+use alloy_provider::{DynProvider, Provider, ProviderBuilder, network::Ethereum};
+use alloy_sol_types::{SolEvent, decode_revert_reason, sol};
+use reqwest::Client;
+use revm::context::{BlockEnv, TxEnv};
+use revm::context::result::ExecutionResult;
+use revm::database::{AlloyDB, BlockId, CacheDB};
+use revm::database_interface::{DatabaseRef, WrapDatabaseAsync};
+use revm::primitives::hardfork::SpecId;
+use revm::primitives::{Address, B256, Bytes, TxKind, U256, keccak256};
+use revm::bytecode::Bytecode;
+use revm::state::{AccountInfo, EvmState};
+use revm::{Context, Database, DatabaseCommit, ExecuteEvm, MainBuilder, MainContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Display;
+use tauri::State;
+
+pub type ForkDB = CacheDB<WrapDatabaseAsync<AlloyDB<Ethereum, DynProvider>>>;
+
+/// 主网按时间戳激活的硬分叉；其他链无专门配置时沿用（多数 EVM 链不早于主网跟进）
+const MAINNET_FORKS: &[(u64, SpecId)] = &[
+    (1_681_338_455, SpecId::SHANGHAI),
+    (1_710_338_135, SpecId::CANCUN),
+    (1_746_612_311, SpecId::PRAGUE),
+    (1_764_798_551, SpecId::OSAKA),
+];
+const SEPOLIA_FORKS: &[(u64, SpecId)] = &[
+    (1_677_557_088, SpecId::SHANGHAI),
+    (1_706_655_072, SpecId::CANCUN),
+    (1_741_159_776, SpecId::PRAGUE),
+    (1_760_427_360, SpecId::OSAKA),
+];
+/// OP Stack（Optimism / Base）：Canyon = Shanghai，Ecotone = Cancun，Isthmus = Prague
+const OP_STACK_FORKS: &[(u64, SpecId)] = &[
+    (1_704_992_401, SpecId::SHANGHAI),
+    (1_710_374_401, SpecId::CANCUN),
+    (1_746_806_401, SpecId::PRAGUE),
+];
+/// BSC：Kepler = Shanghai，Haber = Cancun，Pascal = Prague
+const BSC_FORKS: &[(u64, SpecId)] = &[
+    (1_705_996_800, SpecId::SHANGHAI),
+    (1_718_863_500, SpecId::CANCUN),
+    (1_742_436_600, SpecId::PRAGUE),
+];
+/// 主网合并时间；更早的区块按 London 执行
+const MERGE_TIMESTAMP: u64 = 1_663_224_162;
+
+/// 按链与区块时间戳选执行规则，分叉在历史区块上时用当时的操作码与预编译
+pub fn spec_for(chain_id: u64, timestamp: u64) -> SpecId {
+    let forks = match chain_id {
+        11155111 => SEPOLIA_FORKS,
+        10 | 8453 => OP_STACK_FORKS,
+        56 => BSC_FORKS,
+        _ => MAINNET_FORKS,
+    };
+    let base = if timestamp >= MERGE_TIMESTAMP { SpecId::MERGE } else { SpecId::LONDON };
+    forks.iter().take_while(|(activation, _)| timestamp >= *activation).last().map_or(base, |(_, spec)| *spec)
+}
+/// 调用方不给 gas_limit 时的上限（不超过区块 gas limit）
+pub const SIM_GAS_LIMIT: u64 = 30_000_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimCall {
+    pub from: Address,
+    /// None 为部署合约
+    pub to: Option<Address>,
+    #[serde(default)]
+    pub value: U256,
+    #[serde(default)]
+    pub data: Bytes,
+    pub gas_limit: Option<u64>,
+    /// 不传时与 eth_call 一致：basefee 与 gas price 都按 0，不要求余额支付手续费
+    pub gas_price: Option<u128>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StorageChange {
+    pub slot: U256,
+    pub before: U256,
+    pub after: U256,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AccountDiff {
+    pub address: Address,
+    pub balance: Option<Change<U256>>,
+    pub nonce: Option<Change<u64>>,
+    pub created: bool,
+    pub destroyed: bool,
+    pub storage: Vec<StorageChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+    /// 按离线签名库解码，未收录的事件为 None
+    pub decoded: Option<DecodedEvent>,
+}
+
+sol! {
+    interface IRiskEvents {
+        event Approval(address indexed owner, address indexed spender, uint256 value);
+        event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
+    }
+}
+
+/// 从日志与状态差异里提炼的风险提示，确认页重点展示
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SimRisk {
+    /// ERC-20 授权（额度非 0）
+    Approval { token: Address, owner: Address, spender: Address, amount: U256 },
+    /// ERC-721 / ERC-1155 全部授权给 operator
+    ApprovalForAll { token: Address, owner: Address, operator: Address },
+    /// 执行中有合约自毁
+    SelfDestruct { address: Address },
+}
+
+/// 只看 3 个 topic 的 Approval（ERC-721 单个 tokenId 授权有 4 个 topic，不计入）
+pub fn risk_summary(logs: &[SimLog], state_diff: &[AccountDiff]) -> Vec<SimRisk> {
+    let mut risks: Vec<SimRisk> = logs
+        .iter()
+        .filter_map(|log| {
+            let (topic0, owner, spender) = match log.topics.as_slice() {
+                [t0, owner, spender] => (*t0, Address::from_word(*owner), Address::from_word(*spender)),
+                _ => return None,
+            };
+            let word = U256::try_from_be_slice(log.data.get(..32)?)?;
+            if topic0 == IRiskEvents::Approval::SIGNATURE_HASH && !word.is_zero() {
+                Some(SimRisk::Approval { token: log.address, owner, spender, amount: word })
+            } else if topic0 == IRiskEvents::ApprovalForAll::SIGNATURE_HASH && word == U256::from(1) {
+                Some(SimRisk::ApprovalForAll { token: log.address, owner, operator: spender })
+            } else {
+                None
+            }
+        })
+        .collect();
+    risks.extend(state_diff.iter().filter(|d| d.destroyed).map(|d| SimRisk::SelfDestruct { address: d.address }));
+    risks
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimOutcome {
+    pub success: bool,
+    pub gas_used: u64,
+    pub return_data: Bytes,
+    /// Error(string) / Panic(uint256) 或停机原因
+    pub revert_reason: Option<String>,
+    pub logs: Vec<SimLog>,
+    pub state_diff: Vec<AccountDiff>,
+    pub risks: Vec<SimRisk>,
+}
+
+pub struct Simulator<DB> {
+    db: DB,
+    block: BlockEnv,
+    chain_id: u64,
+    spec: SpecId,
+}
+
+fn sim_error(e: impl Display) -> AppError {
+    AppError::SimulationFailed(e.to_string())
+}
+
+fn hex_u64(v: &Value) -> Option<u64> {
+    v.as_str().and_then(|s| str_to_u64(s).ok())
+}
+
+/// eth_getBlockByNumber 的区块头 → revm 区块环境
+pub fn block_env(header: &Value) -> Result<BlockEnv, AppError> {
+    let number = hex_u64(&header["number"]).ok_or(AppError::Parse("block number missing"))?;
+    let word = |key: &str| header[key].as_str().and_then(|s| s.parse::<B256>().ok());
+    Ok(BlockEnv {
+        number: U256::from(number),
+        beneficiary: header["miner"].as_str().and_then(|s| s.parse().ok()).unwrap_or_default(),
+        timestamp: U256::from(hex_u64(&header["timestamp"]).unwrap_or_default()),
+        gas_limit: hex_u64(&header["gasLimit"]).unwrap_or(SIM_GAS_LIMIT),
+        // 合并前的链与不支持 EIP-1559 的链没有 baseFeePerGas
+        basefee: hex_u64(&header["baseFeePerGas"]).unwrap_or_default(),
+        difficulty: header["difficulty"].as_str().and_then(|s| str_to_u256(s).ok()).unwrap_or_default(),
+        prevrandao: Some(word("mixHash").unwrap_or_default()),
+        ..Default::default()
+    })
+}
+
+impl Simulator<ForkDB> {
+    /// 固定在 block（默认最新块）上分叉；状态在执行时才按需拉取
+    pub async fn fork(client: &Client, rpc_url: &str, chain_id: u64, block: Option<u64>) -> Result<Self, AppError> {
+        let tag = block.map(|n| format!("0x{n:x}")).unwrap_or_else(|| "latest".to_string());
+        let header = get_block_by_number(client.clone(), rpc_url, &tag, false).await?;
+        if header.is_null() {
+            return Err(AppError::SimulationFailed(format!("block {tag} not found")));
+        }
+        let block = block_env(&header)?;
+        let url = rpc_url.parse().map_err(|_| AppError::Parse("invalid rpc url"))?;
+        let provider = ProviderBuilder::new().connect_http(url).erased();
+        let alloy_db = AlloyDB::new(provider, BlockId::number(block.number.to::<u64>()));
+        // 需要在 tokio 多线程运行时内创建，执行时阻塞等待 RPC
+        let db = WrapDatabaseAsync::new(alloy_db).ok_or(AppError::SimulationFailed("no tokio runtime".into()))?;
+        Ok(Self::new(CacheDB::new(db), chain_id, block))
+    }
+}
+
+impl<ExtDB: DatabaseRef> Simulator<CacheDB<ExtDB>>
+where
+    ExtDB::Error: Display,
+{
+    /// 用本地代码与 storage 覆盖某个合约（如尚未部署或待升级的自家合约），余额与 nonce 保留链上值
+    pub fn override_contract(&mut self, address: Address, code: Bytes, storage: &[(U256, U256)]) -> Result<(), AppError> {
+        let info = self.db.basic(address).map_err(sim_error)?.unwrap_or_default();
+        self.db.insert_account_info(
+            address,
+            AccountInfo { code_hash: keccak256(&code), code: Some(Bytecode::new_raw(code)), ..info },
+        );
+        for (slot, value) in storage {
+            self.db.insert_account_storage(address, *slot, *value).map_err(sim_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<DB> Simulator<DB>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Display,
+{
+    pub fn new(db: DB, chain_id: u64, block: BlockEnv) -> Self {
+        let spec = spec_for(chain_id, block.timestamp.saturating_to());
+        Self { db, block, chain_id, spec }
+    }
+
+    fn tx_env(&self, call: &SimCall) -> Result<TxEnv, AppError> {
+        let gas_limit = call.gas_limit.unwrap_or(SIM_GAS_LIMIT).min(self.block.gas_limit);
+        TxEnv::builder()
+            .caller(call.from)
+            .kind(call.to.map(TxKind::Call).unwrap_or(TxKind::Create))
+            .value(call.value)
+            .data(call.data.clone())
+            .gas_limit(gas_limit)
+            .gas_price(call.gas_price.unwrap_or_default())
+            .chain_id(Some(self.chain_id))
+            .build()
+            .map_err(|e| AppError::SimulationFailed(format!("{e:?}")))
+    }
+
+    fn execute(&mut self, call: &SimCall) -> Result<(ExecutionResult, EvmState), AppError> {
+        let tx = self.tx_env(call)?;
+        let mut block = self.block.clone();
+        if call.gas_price.is_none() {
+            block.basefee = 0;
+        }
+        let (chain_id, spec) = (self.chain_id, self.spec);
+        let mut evm = Context::mainnet()
+            .with_db(&mut self.db)
+            .with_block(block)
+            .modify_cfg_chained(|cfg| {
+                cfg.chain_id = chain_id;
+                cfg.spec = spec;
+                // 只关心执行结果，nonce 由发送时再取
+                cfg.disable_nonce_check = true;
+            })
+            .build_mainnet();
+        let executed = evm.transact(tx).map_err(sim_error)?;
+        Ok((executed.result, executed.state))
+    }
+
+    /// 执行前的账户值取自缓存（执行时已加载），与执行后的状态对比；没有变化的账户不列出
+    fn state_diff(&mut self, state: &EvmState) -> Result<Vec<AccountDiff>, AppError> {
+        let mut diffs = Vec::new();
+        for (address, account) in state {
+            if !account.is_touched() {
+                continue;
+            }
+            let before = self.db.basic(*address).map_err(sim_error)?.unwrap_or_default();
+            let storage: Vec<StorageChange> = account
+                .storage
+                .iter()
+                .filter(|(_, slot)| slot.is_changed())
+                .map(|(key, slot)| StorageChange { slot: *key, before: slot.original_value, after: slot.present_value })
+                .collect();
+            let diff = AccountDiff {
+                address: *address,
+                balance: (before.balance != account.info.balance)
+                    .then_some(Change { before: before.balance, after: account.info.balance }),
+                nonce: (before.nonce != account.info.nonce).then_some(Change { before: before.nonce, after: account.info.nonce }),
+                created: account.is_created(),
+                destroyed: account.is_selfdestructed(),
+                storage,
+            };
+            if diff.balance.is_some() || diff.nonce.is_some() || diff.created || diff.destroyed || !diff.storage.is_empty() {
+                diffs.push(diff);
+            }
+        }
+        diffs.sort_by_key(|d| d.address);
+        Ok(diffs)
+    }
+
+    fn run(&mut self, call: &SimCall, commit: bool) -> Result<SimOutcome, AppError> {
+        let (result, state) = self.execute(call)?;
+        let state_diff = self.state_diff(&state)?;
+        if commit {
+            self.db.commit(state);
+        }
+        let revert_reason = match &result {
+            ExecutionResult::Success { .. } => None,
+            ExecutionResult::Revert { output, .. } => decode_revert_reason(output),
+            ExecutionResult::Halt { reason, .. } => Some(format!("{reason:?}")),
+        };
+        let logs: Vec<SimLog> = result
+            .logs()
+            .iter()
+            .map(|log| SimLog {
+                address: log.address,
+                topics: log.data.topics().to_vec(),
+                data: log.data.data.clone(),
+                decoded: decode_event(log.data.topics(), &log.data.data),
+            })
+            .collect();
+        Ok(SimOutcome {
+            success: result.is_success(),
+            gas_used: result.gas_used(),
+            return_data: result.output().cloned().unwrap_or_default(),
+            revert_reason,
+            risks: risk_summary(&logs, &state_diff),
+            logs,
+            state_diff,
+        })
+    }
+
+    /// 单笔预执行，不改变分叉状态
+    pub fn simulate(&mut self, call: &SimCall) -> Result<SimOutcome, AppError> {
+        self.run(call, false)
+    }
+
+    /// 依次执行，后一笔看到前一笔的结果（如先 approve 再 swap）；revert 的那笔不影响后续
+    pub fn simulate_bundle(&mut self, calls: &[SimCall]) -> Result<Vec<SimOutcome>, AppError> {
+        calls.iter().map(|call| self.run(call, true)).collect()
+    }
+}
+
+/// RPC 读取是阻塞的，放到 blocking 线程里跑
+async fn simulate_on_fork(
+    calls: Vec<SimCall>,
+    chain_id: u64,
+    rpc_url: String,
+    block: Option<u64>,
+    state: &AppState,
+) -> Result<Vec<SimOutcome>, AppError> {
+    let client = state.https_client.lock().await.clone();
+    let mut simulator = Simulator::fork(&client, &rpc_url, chain_id, block).await?;
+    tokio::task::spawn_blocking(move || simulator.simulate_bundle(&calls)).await.map_err(sim_error)?
+}
+
+// ========== 命令 ==========
+
+/// block 不传时固定在最新块
+#[tauri::command]
+pub async fn simulate_tx(
+    call: SimCall,
+    chain_id: u64,
+    rpc_url: String,
+    block: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<SimOutcome, AppError> {
+    let mut outcomes = simulate_on_fork(vec![call], chain_id, rpc_url, block, &state).await?;
+    outcomes.pop().ok_or(AppError::SimulationFailed("no result".into()))
+}
+
+/// 同一分叉上依次执行多笔（EIP-5792 批量调用、先授权后交易等）
+#[tauri::command]
+pub async fn simulate_bundle(
+    calls: Vec<SimCall>,
+    chain_id: u64,
+    rpc_url: String,
+    block: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SimOutcome>, AppError> {
+    simulate_on_fork(calls, chain_id, rpc_url, block, &state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::database::EmptyDB;
+    use revm::primitives::address;
+    use serde_json::json;
+
+    const CALLER: Address = address!("0x1111111111111111111111111111111111111111");
+    const CONTRACT: Address = address!("0x2222222222222222222222222222222222222222");
+    /// SSTORE(0, 0x2a); LOG1(topic = 1); 返回 0x2a
+    const STORE_AND_LOG: &str = "602a600055600160006000a1602a60005260206000f3";
+    /// REVERT(0, 0)
+    const REVERTS: &str = "60006000fd";
+
+    fn simulator() -> Simulator<CacheDB<EmptyDB>> {
+        let block = block_env(&json!({
+            "number": "0x10",
+            "timestamp": "0x65000000",
+            "gasLimit": "0x1c9c380",
+            "baseFeePerGas": "0x3b9aca00",
+            "miner": "0x0000000000000000000000000000000000000000",
+            "mixHash": format!("0x{}", "00".repeat(32)),
+        }))
+        .unwrap();
+        let mut sim = Simulator::new(CacheDB::new(EmptyDB::default()), 1, block);
+        sim.override_contract(CONTRACT, Bytes::from(hex::decode(STORE_AND_LOG).unwrap()), &[]).unwrap();
+        sim
+    }
+
+    fn call() -> SimCall {
+        SimCall { from: CALLER, to: Some(CONTRACT), value: U256::ZERO, data: Bytes::new(), gas_limit: None, gas_price: None }
+    }
+
+    #[test]
+    fn test_block_env_from_header() {
+        let block = simulator().block;
+        assert_eq!(block.number, U256::from(16));
+        assert_eq!(block.gas_limit, 30_000_000);
+        assert_eq!(block.basefee, 1_000_000_000);
+        assert!(block_env(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_simulate_returns_logs_and_diff() {
+        let mut sim = simulator();
+        let outcome = sim.simulate(&call()).unwrap();
+        assert!(outcome.success);
+        assert_eq!(outcome.return_data, Bytes::from(U256::from(0x2a).to_be_bytes::<32>().to_vec()));
+        assert_eq!(outcome.logs.len(), 1);
+        assert_eq!(outcome.logs[0].topics, vec![B256::with_last_byte(1)]);
+        let contract = outcome.state_diff.iter().find(|d| d.address == CONTRACT).unwrap();
+        assert_eq!(contract.storage, vec![StorageChange { slot: U256::ZERO, before: U256::ZERO, after: U256::from(0x2a) }]);
+        let caller = outcome.state_diff.iter().find(|d| d.address == CALLER).unwrap();
+        assert_eq!(caller.nonce, Some(Change { before: 0, after: 1 }));
+        // 没有 gas price 时按 eth_call 处理，不扣手续费
+        assert_eq!(caller.balance, None);
+
+        // 不提交时再执行一次结果相同；bundle 中第二笔看到第一笔写入的值
+        assert_eq!(sim.simulate(&call()).unwrap().state_diff.len(), 2);
+        let bundle = sim.simulate_bundle(&[call(), call()]).unwrap();
+        assert!(bundle[1].state_diff.iter().all(|d| d.storage.is_empty()));
+    }
+
+    #[test]
+    fn test_spec_follows_chain_and_block_time() {
+        assert_eq!(simulator().spec, SpecId::SHANGHAI);
+        assert_eq!(spec_for(1, 1_600_000_000), SpecId::LONDON);
+        assert_eq!(spec_for(1, 1_700_000_000), SpecId::SHANGHAI);
+        assert_eq!(spec_for(1, 1_750_000_000), SpecId::PRAGUE);
+        // Base 的 Ecotone 比主网 Cancun 晚几个小时
+        assert_eq!(spec_for(8453, 1_710_340_000), SpecId::SHANGHAI);
+        assert_eq!(spec_for(56, 1_745_000_000), SpecId::PRAGUE);
+    }
+
+    #[test]
+    fn test_risk_summary_from_logs() {
+        let (owner, spender, token) = (CALLER, Address::repeat_byte(0x33), CONTRACT);
+        let log = |topic0: B256, value: U256| SimLog {
+            address: token,
+            topics: vec![topic0, owner.into_word(), spender.into_word()],
+            data: Bytes::from(value.to_be_bytes::<32>().to_vec()),
+            decoded: None,
+        };
+        let logs = [
+            log(IRiskEvents::Approval::SIGNATURE_HASH, U256::MAX),
+            log(IRiskEvents::Approval::SIGNATURE_HASH, U256::ZERO),
+            log(IRiskEvents::ApprovalForAll::SIGNATURE_HASH, U256::from(1)),
+            log(IRiskEvents::ApprovalForAll::SIGNATURE_HASH, U256::ZERO),
+        ];
+        let destroyed = AccountDiff { address: token, balance: None, nonce: None, created: false, destroyed: true, storage: Vec::new() };
+        assert_eq!(
+            risk_summary(&logs, &[destroyed]),
+            vec![
+                SimRisk::Approval { token, owner, spender, amount: U256::MAX },
+                SimRisk::ApprovalForAll { token, owner, operator: spender },
+                SimRisk::SelfDestruct { address: token },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_simulate_revert() {
+        let mut sim = simulator();
+        sim.override_contract(CONTRACT, Bytes::from(hex::decode(REVERTS).unwrap()), &[]).unwrap();
+        let outcome = sim.simulate(&call()).unwrap();
+        assert!(!outcome.success);
+        assert!(outcome.logs.is_empty());
+        assert_eq!(outcome.revert_reason, None);
+
+        // 带 gas price 时按真实 basefee 校验余额
+        let priced = SimCall { gas_price: Some(2_000_000_000), ..call() };
+        assert!(sim.simulate(&priced).is_err());
+    }
+}
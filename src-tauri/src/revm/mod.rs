@@ -1,4 +1,3 @@
-
 pub mod test;
 pub mod gas;
-
+pub mod fork;
@@ -62,6 +62,73 @@ pub async fn get_token_balances_by_ankr(
 }
 
 
+/// 代币持有人数（ankr_getTokenHoldersCount，取最新一条）
+pub async fn get_token_holders_count_by_ankr(
+    client: &Client,
+    gateway_url: &str,
+    blockchain: &str,
+    contract_address: &str,
+) -> anyhow::Result<Option<u64>> {
+    let body = json!({
+        "id": 1,
+        "jsonrpc": "2.0",
+        "method": "ankr_getTokenHoldersCount",
+        "params": {
+            "blockchain": blockchain,
+            "contractAddress": contract_address,
+            "pageSize": 1
+        }
+    });
+
+    let res = client
+        .post(gateway_url)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<serde_json::Value>()
+        .await?;
+
+    Ok(res["result"]["holderCountHistory"][0]["holderCount"].as_u64())
+}
+
+/// ankr_getLogs 的一页；topics 与 eth_getLogs 同格式，返回原始日志与下一页的 token
+pub async fn get_logs_by_ankr(
+    client: &Client,
+    gateway_url: &str,
+    blockchain: &str,
+    topics: serde_json::Value,
+    page_token: Option<String>,
+) -> anyhow::Result<(Vec<serde_json::Value>, Option<String>)> {
+    let body = json!({
+        "id": 1,
+        "jsonrpc": "2.0",
+        "method": "ankr_getLogs",
+        "params": {
+            "blockchain": blockchain,
+            "fromBlock": "earliest",
+            "toBlock": "latest",
+            "topics": topics,
+            "decodeLogs": false,
+            "pageToken": page_token,
+            "pageSize": 10000
+        }
+    });
+
+    let res = client
+        .post(gateway_url)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<serde_json::Value>()
+        .await?;
+
+    let logs = serde_json::from_value(res["result"]["logs"].clone()).unwrap_or_default();
+    let next = res["result"]["nextPageToken"].as_str().filter(|t| !t.is_empty()).map(String::from);
+    Ok((logs, next))
+}
+
 pub async fn get_nft_balances_by_ankr(
     client: &Client,
     api_key: &str,
@@ -1,6 +1,7 @@
 
-mod advance;
-mod models;
+pub mod advance;
+pub mod models;
+pub mod parse;
 
 
 
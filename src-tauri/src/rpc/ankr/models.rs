@@ -1,9 +1,11 @@
-use crate::data::{IntoInterNft, IntoInterToken, IntoInterTx, Nft, Token, TransactionHistoryEntry};
+use crate::data::{IntoInterNft, IntoInterTx, Nft, TransactionHistoryEntry};
+use crate::data::token::{IntoInterToken, Token};
+use crate::data::tx::{ReceiptStatus, decode_method, parse_transfer_legs};
 use crate::evm::assets::{AssetsType, IntoInterAsset,mapper_assets_type};
 
 use crate::utils::num::{str_to_f64, str_to_u64, str_to_u256};
 
-use alloy_primitives::{Address, address};
+use alloy_primitives::{Address, TxHash, address};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
@@ -80,6 +82,19 @@ impl AnkrBlockchain {
             "eth_sepolia",
         ]
     }
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        Some(match chain_id {
+            1 => AnkrBlockchain::Eth,
+            10 => AnkrBlockchain::Optimism,
+            56 => AnkrBlockchain::Bsc,
+            137 => AnkrBlockchain::Polygon,
+            42161 => AnkrBlockchain::Arbitrum,
+            8453 => AnkrBlockchain::Base,
+            59144 => AnkrBlockchain::Linea,
+            11155111 => AnkrBlockchain::EthSepolia,
+            _ => return None,
+        })
+    }
     pub fn to_chain_id(&self) -> u64 {
         match self {
             AnkrBlockchain::Eth => 1,
@@ -88,7 +103,7 @@ impl AnkrBlockchain {
             AnkrBlockchain::Polygon => 137,
             AnkrBlockchain::Arbitrum => 42161,
             AnkrBlockchain::Base => 8453,
-            AnkrBlockchain::Linea => 59140,
+            AnkrBlockchain::Linea => 59144,
             AnkrBlockchain::EthSepolia => 11155111,
         }
     }
@@ -123,19 +138,25 @@ pub struct AnkrBalance {
 
 impl IntoInterToken for AnkrBalance {
     fn into_inter(self) -> Token {
-        let logo_url = self.thumbnail;
-        let assets_type = self.token_type.as_ref().map(|token_type| mapper_assets_type(token_type.clone()));
-        let contract_address = self.contract_address.as_ref().map(|addr| Address::from_str(addr).unwrap_or(Address::ZERO));
-        
+        // 原生币没有 contractAddress，按 Address::ZERO 记
+        let contract_address = self
+            .contract_address
+            .as_deref()
+            .and_then(|addr| Address::from_str(addr).ok());
+        let assets_type = match contract_address {
+            Some(_) => mapper_assets_type(&self.token_type),
+            None => AssetsType::Currency,
+        };
+
         Token {
             chain_id: self.blockchain.to_chain_id(),
             address: contract_address.unwrap_or(Address::ZERO),
             name: self.token_name,
             symbol: self.token_symbol,
-            decimals: self.token_decimals,
-            logo_url: logo_url,
-            assets_type: assets_type,
-            contract_address: contract_address
+            decimals: self.token_decimals as u64,
+            logo_url: Some(self.thumbnail).filter(|url| !url.is_empty()),
+            assets_type: Some(assets_type),
+            contract_address,
         }
     }
 }
@@ -315,7 +336,7 @@ pub struct AnkrTransaction {
     pub nonce: Option<String>,
     #[serde(default, skip)]
     pub gas: Option<String>,
-    #[serde(default, skip)]
+    #[serde(default)]
     pub input: Option<String>,
     #[serde(default, skip, rename = "transactionIndex")]
     pub transaction_index: Option<String>,
@@ -335,20 +356,35 @@ pub struct AnkrTransaction {
 
 impl IntoInterTx for AnkrTransaction {
     fn into_inter(self) -> TransactionHistoryEntry {
+        let parse_addr = |s: &str| Address::from_str(s).unwrap_or(Address::ZERO);
         TransactionHistoryEntry {
             chain_id: self.blockchain.to_chain_id(),
-            hash: self.hash.to_lowercase(),
-            block_number: str_to_u64(&self.block_number),
-            from: self.from.to_lowercase(),
-            to: self.to.unwrap_or_default().to_lowercase(),
-            value: self.value.and_then(|s| str_to_u256(s).ok()),
-            gas_price: self.gas_price.and_then(|s| str_to_u256(s).ok()),
-            gas_used: self.gas_used.and_then(|s| str_to_u256(s).ok()),
-            status: self
-                .status
-                .filter(|s| s == "1")
-                .map(|_| "success".to_string()),
-            timestamp: self.timestamp.and_then(|s| str_to_u64(s).ok()),
+            hash: TxHash::from_str(&self.hash).unwrap_or_default(),
+            block_number: str_to_u64(&self.block_number).unwrap_or_default(),
+            nonce: self.nonce.as_deref().and_then(|s| str_to_u256(s).ok()).unwrap_or_default(),
+            from: parse_addr(&self.from),
+            to: self.to.as_deref().map(parse_addr).unwrap_or(Address::ZERO),
+            value: str_to_u256(&self.value).unwrap_or_default(),
+            gas_price: self.gas_price.as_deref().and_then(|s| str_to_u256(s).ok()),
+            gas_used: self.gas_used.as_deref().and_then(|s| str_to_u256(s).ok()),
+            timestamp: self.timestamp.as_deref().and_then(|s| str_to_u64(s).ok()),
+            status: self.status.as_deref().and_then(|s| str_to_u64(s).ok()).map(|s| {
+                if s == 1 { ReceiptStatus::Success } else { ReceiptStatus::Reverted }
+            }),
+            tx_type: self.r#type.as_deref().and_then(|s| str_to_u64(s).ok()).map(|t| t as u8),
+            effective_gas_price: None,
+            fee_paid: None,
+            method: self.input.as_deref().and_then(decode_method),
+            token_transfers: self
+                .logs
+                .map(|logs| {
+                    let values: Vec<serde_json::Value> =
+                        logs.iter().filter_map(|l| serde_json::to_value(l).ok()).collect();
+                    parse_transfer_legs(&values)
+                })
+                .unwrap_or_default(),
+            labels: Vec::new(),
+            note: None,
         }
     }
 }
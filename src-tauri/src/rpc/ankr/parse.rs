@@ -1,4 +1,9 @@
-use ethers::abi::{AbiDecode, ParamType};
+// Ankr 交易列表 → 活动摘要；方法识别走 evm::calldata（ERC-20 + 离线签名库）
+use serde::Serialize;
+
+use super::models::AnkrTransaction;
+use crate::evm::calldata::{DecodedCall, decode_call};
+use crate::utils::num::str_to_u256;
 
 #[derive(Debug, Serialize)]
 pub struct ParsedActivity {
@@ -9,85 +14,69 @@ pub struct ParsedActivity {
     pub standard: Option<String>,
 }
 
-pub async fn parse_ankr_activities(
-    activities: &[Transaction],
-) -> Vec<ParsedActivity> {
-    let mut results = vec![];
-
-    for tx in activities {
-        let input = tx.input.clone().unwrap_or_default();
-
-        // ========== Native Transfer ==========
-        if tx.value != "0x0" && input == "0x" {
-            let value = u128::from_str_radix(tx.value.trim_start_matches("0x"), 16)
-                .unwrap_or(0) as f64 / 1e18;
-
-            results.push(ParsedActivity {
-                hash: tx.hash.clone(),
-                activity_type: "Native Transfer".into(),
-                description: format!("Transfer {} {} to {}", value, tx.blockchain, tx.to),
-                signature: None,
-                standard: None,
-            });
-            continue;
-        }
-
-        // ========== Contract Call - 无 input ==========
-        if input == "0x" || input.is_empty() {
-            results.push(ParsedActivity {
-                hash: tx.hash.clone(),
-                activity_type: "Contract Call".into(),
-                description: "Unknown contract call".into(),
-                signature: None,
-                standard: None,
-            });
-            continue;
-        }
-
-        // ========== 4-byte selector ==========
-        let selector = &input[0..10];
-
-        // 示例：你可以根据自己的 function selector map 来匹配
-        let sig = function_selector_lookup(selector);
-
-        if sig.is_none() {
-            results.push(ParsedActivity {
-                hash: tx.hash.clone(),
-                activity_type: "Contract Call".into(),
-                description: "Unknown contract call".into(),
-                signature: None,
-                standard: None,
-            });
-            continue;
+/// 只映射不会和其它标准冲突的签名；ERC-20/721 共用的 transferFrom、approve 不标注
+fn standard_of(signature: &str) -> Option<&'static str> {
+    match signature {
+        "transfer(address,uint256)" | "increaseAllowance(address,uint256)" | "decreaseAllowance(address,uint256)" => {
+            Some("ERC20")
         }
+        "safeTransferFrom(address,address,uint256)" | "safeTransferFrom(address,address,uint256,bytes)" => Some("ERC721"),
+        "safeTransferFrom(address,address,uint256,uint256,bytes)"
+        | "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)" => Some("ERC1155"),
+        _ => None,
+    }
+}
 
-        let sig_info = sig.unwrap();
+fn describe(call: &DecodedCall) -> String {
+    let param = |name: &str| call.params.iter().find(|p| p.name == name).map(|p| p.value.as_str()).unwrap_or("?");
+    match call.signature.as_str() {
+        "transfer(address,uint256)" => format!("ERC20 Transfer {} to {}", param("value"), param("to")),
+        "approve(address,uint256)" => format!("Approve {} for {}", param("spender"), param("value")),
+        _ if call.nested.is_empty() => call.signature.clone(),
+        _ => format!("{} ({} calls)", call.signature, call.nested.len()),
+    }
+}
 
-        // ERC20 transfer(address,uint256)
-        let description = if selector == "0xa9059cbb" {
-            if let Ok(decoded) = ethers::abi::decode(
-                &[ParamType::Address, ParamType::Uint(256)],
-                &hex::decode(&input[10..]).unwrap_or_default()
-            ) {
-                let to = decoded[0].to_string();
-                let amount = decoded[1].to_string();
+pub fn parse_ankr_activities(activities: &[AnkrTransaction]) -> Vec<ParsedActivity> {
+    activities
+        .iter()
+        .map(|tx| {
+            let input = tx.input.as_deref().unwrap_or_default();
+            let to = tx.to.as_deref().unwrap_or("contract creation");
+            let value = str_to_u256(&tx.value).unwrap_or_default();
 
-                format!("ERC20 Transfer {} to {}", amount, to)
-            } else {
-                sig_info.description.clone()
+            // ========== Native Transfer ==========
+            if !value.is_zero() && (input.is_empty() || input == "0x") {
+                let value = value.saturating_to::<u128>() as f64 / 1e18;
+                return ParsedActivity {
+                    hash: tx.hash.clone(),
+                    activity_type: "Native Transfer".into(),
+                    description: format!("Transfer {} {} to {}", value, tx.blockchain.as_str(), to),
+                    signature: None,
+                    standard: None,
+                };
             }
-        } else {
-            sig_info.description.clone()
-        };
-
-        results.push(ParsedActivity {
-            hash: tx.hash.clone(),
-            activity_type: format!("{} {}", sig_info.standard, sig_info.name),
-            description,
-            signature: Some(sig_info.signature.clone()),
-            standard: Some(sig_info.standard.clone()),
-        });
-    }
 
-    results
+            // ========== Contract Call ==========
+            match decode_call(input, None) {
+                Ok(call) => ParsedActivity {
+                    hash: tx.hash.clone(),
+                    activity_type: match standard_of(&call.signature) {
+                        Some(standard) => format!("{} {}", standard, call.function),
+                        None => call.function.clone(),
+                    },
+                    description: describe(&call),
+                    standard: standard_of(&call.signature).map(String::from),
+                    signature: Some(call.signature),
+                },
+                Err(_) => ParsedActivity {
+                    hash: tx.hash.clone(),
+                    activity_type: "Contract Call".into(),
+                    description: format!("Unknown contract call to {}", to),
+                    signature: None,
+                    standard: None,
+                },
+            }
+        })
+        .collect()
 }
@@ -1,6 +1,7 @@
 // src-tauri/src/rpc/https.rs
 use crate::error::AppError;
 use reqwest::Client;
+use serde_json::Value;
 use std::time::Duration;
 
 pub fn create_https_client() -> Result<Client, AppError> {
@@ -16,3 +17,17 @@ pub fn create_https_client() -> Result<Client, AppError> {
             AppError::ReqwestClientBuildError(e)
         })?;
 }
+
+/// 普通 HTTPS GET，返回 JSON（汇率 / 代币列表 / 元数据等非 JSON-RPC 接口）
+pub async fn get_json(client: &Client, url: &str) -> Result<Value, AppError> {
+    client
+        .get(url)
+        .send()
+        .await
+        .map_err(AppError::ReqwestClientConnectionError)?
+        .error_for_status()
+        .map_err(AppError::ReqwestClientConnectionError)?
+        .json::<Value>()
+        .await
+        .map_err(AppError::ReqwestClientConnectionError)
+}
@@ -29,6 +29,7 @@ pub enum RpcMethod {
     // ====== 轻量但高频的（Helios 支持）======
     EthGetCode,
     EthGetStorageAt,
+    EthGetLogs,
 
     // ====== 客户端信息（固定返回）======
     Web3ClientVersion,
@@ -57,6 +58,7 @@ impl RpcMethod {
             Self::EthSendRawTransaction => "eth_sendRawTransaction",
             Self::EthGetCode => "eth_getCode",
             Self::EthGetStorageAt => "eth_getStorageAt",
+            Self::EthGetLogs => "eth_getLogs",
             Self::Web3ClientVersion => "web3_clientVersion",
             Self::NetVersion => "net_version",
             Self::EthSyncing => "eth_syncing",
@@ -114,6 +116,17 @@ pub struct JsonRpcResponse {
 pub struct RpcError {
     pub code: i64,
     pub message: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    /// eth_call / estimateGas revert 时节点附带的 revert data（多数节点为字符串，少数包一层对象）
+    pub fn revert_data(&self) -> Option<String> {
+        let data = self.data.as_ref()?;
+        let hex = data.as_str().or_else(|| data.get("data").and_then(|d| d.as_str()))?;
+        hex.starts_with("0x").then(|| hex.to_string())
+    }
 }
 
 /// 通用调用（推荐用于自定义方法）
@@ -142,6 +155,9 @@ pub async fn call(client: Client, url: &str, request: JsonRpcRequest) -> Result<
 
     // 处理错误响应
     if let Some(err) = json.error {
+        if let Some(data) = err.revert_data() {
+            return Err(AppError::RpcReverted(data));
+        }
         return Err(AppError::HttpsRpcError(err.code as u64, err.message));
     }
 
@@ -182,15 +198,14 @@ pub async fn get_nonce(
     );
     let result = call(client, url, req).await?;
     let hex: String = serde_json::from_value(result).map_err(|e| AppError::JsonParseError(e))?;
-    Ok(u64::from_str_radix(&hex.trim_start_matches("0x"), 16)
-        .map_err(|_| AppError::NumberParseError))
+    u64::from_str_radix(hex.trim_start_matches("0x"), 16).map_err(|_| AppError::NumberParseError())
 }
 
 pub async fn gas_price(client: Client, url: &str) -> Result<U256, AppError> {
     let req = JsonRpcRequest::new(RpcMethod::EthGasPrice.as_str(), json!([]));
     let result = call(client, url, req).await?;
     let hex: String = serde_json::from_value(result).map_err(|e| AppError::JsonParseError(e))?;
-    Ok(str_to_u256(&hex)).map_err(|_| AppError::NumberParseError)
+    str_to_u256(&hex)
 }
 
 pub async fn max_priority_fee(client: Client, url: &str) -> Result<U256, AppError> {
@@ -200,7 +215,7 @@ pub async fn max_priority_fee(client: Client, url: &str) -> Result<U256, AppErro
     );
     let result = call(client, url, req).await?;
     let hex: String = serde_json::from_value(result).map_err(|e| AppError::JsonParseError(e))?;
-    Ok(str_to_u256(&hex)).map_err(|_| AppError::NumberParseError)
+    str_to_u256(&hex)
 }
 
 pub async fn fee_history(
@@ -217,6 +232,19 @@ pub async fn fee_history(
     Ok(result)
 }
 
+pub async fn get_block_by_number(
+    client: Client,
+    url: &str,
+    block: &str,
+    full_tx: bool,
+) -> Result<Value, AppError> {
+    let req = JsonRpcRequest::new(
+        RpcMethod::EthGetBlockByNumber.as_str(),
+        json!([block, full_tx]),
+    );
+    call(client, url, req).await
+}
+
 pub async fn get_block_by_hash(
     client: Client,
     url: &str,
@@ -268,6 +296,22 @@ pub async fn eth_call(
     call(client, url, req).await
 }
 
+/// 指定 from 的 eth_call（模拟转账等依赖 msg.sender 的调用）
+pub async fn eth_call_from(
+    client: Client,
+    url: &str,
+    from: &str,
+    to: &str,
+    data: &str,
+    block: &str,
+) -> Result<Value, AppError> {
+    let req = JsonRpcRequest::new(
+        RpcMethod::EthCall.as_str(),
+        json!([{"from": from, "to": to, "data": data}, block]),
+    );
+    call(client, url, req).await
+}
+
 pub async fn estimate_gas(
     client: Client,
     url: &str,
@@ -280,7 +324,25 @@ pub async fn estimate_gas(
     );
     let result = call(client, url, req).await?;
     let hex: String = serde_json::from_value(result).map_err(|e| AppError::JsonParseError(e))?;
-    Ok(str_to_u256(&hex)).map_err(|_| AppError::NumberParseError)
+    str_to_u256(&hex)
+}
+
+/// 指定 from 的 estimateGas（NFT 转账等依赖 msg.sender 的交易）
+pub async fn estimate_gas_from(
+    client: Client,
+    url: &str,
+    from: &str,
+    to: &str,
+    value: &str,
+    data: &str,
+) -> Result<U256, AppError> {
+    let req = JsonRpcRequest::new(
+        RpcMethod::EthEstimateGas.as_str(),
+        json!([{"from": from, "to": to, "value": value, "data": data}]),
+    );
+    let result = call(client, url, req).await?;
+    let hex: String = serde_json::from_value(result).map_err(|e| AppError::JsonParseError(e))?;
+    str_to_u256(&hex)
 }
 
 pub async fn get_code(
@@ -312,6 +374,13 @@ pub async fn get_storage_at(
     Ok(result.as_str().unwrap_or_default().to_string())
 }
 
+/// filter 为 eth_getLogs 的过滤对象（fromBlock / toBlock / address / topics）
+pub async fn get_logs(client: Client, url: &str, filter: Value) -> Result<Vec<Value>, AppError> {
+    let req = JsonRpcRequest::new(RpcMethod::EthGetLogs.as_str(), json!([filter]));
+    let result = call(client, url, req).await?;
+    serde_json::from_value(result).map_err(AppError::JsonParseError)
+}
+
 pub async fn web3_client_version(client: Client, url: &str) -> Result<String, AppError> {
     let req = JsonRpcRequest::new(RpcMethod::Web3ClientVersion.as_str(), json!([]));
     let result = call(client, url, req).await?;
@@ -334,8 +403,7 @@ pub async fn chain_id(client: Client, url: &str) -> Result<u64, AppError> {
     let req = JsonRpcRequest::new(RpcMethod::EthChainId.as_str(), json!([]));
     let result = call(client, url, req).await?;
     let hex: String = serde_json::from_value(result).map_err(|e| AppError::JsonParseError(e))?;
-    Ok(u64::from_str_radix(&hex.trim_start_matches("0x"), 16)
-        .map_err(|_| AppError::NumberParseError))
+    u64::from_str_radix(hex.trim_start_matches("0x"), 16).map_err(|_| AppError::NumberParseError())
 }
 
 pub async fn send_raw_transaction(
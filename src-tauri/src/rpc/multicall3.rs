@@ -1,137 +1,144 @@
 // src/multicall3.rs
-// 极简 Multicall3 工具，只依赖你的 EthRpcProvider + serde_json
+// 极简 Multicall3 工具，基于 rpc::method::eth_call
 // 适用于 99.9% 的链（地址统一：0xcA11bde05977b3631167028862bE2a173976CA11）
 
-use crate::rpc::https::{EthRpcProvider, RpcMethod};
-use serde_json::{json, Value};
-use std::collections::HashMap;
 use crate::error::AppError;
+use crate::rpc::method::eth_call;
+use alloy_primitives::{Address, Bytes, U256, address};
+use alloy_sol_types::{SolCall, sol};
+use reqwest::Client;
+
+pub const MULTICALL3_ADDR: Address = address!("0xcA11bde05977b3631167028862bE2a173976CA11");
+
+sol! {
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
 
-const MULTICALL3_ADDR: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
 
-pub struct Multicall3 {
-    rpc: EthRpcProvider,
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+        function getEthBalance(address addr) external view returns (uint256 balance);
+        function getBlockNumber() external view returns (uint256 blockNumber);
+        function getCurrentBlockTimestamp() external view returns (uint256 timestamp);
+    }
 }
 
-impl Multicall3 {
-    pub fn new(rpc: EthRpcProvider) -> Self {
-        Self { rpc }
+/// 单个子调用
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub target: Address,
+    pub call_data: Bytes,
+}
+
+impl Call {
+    pub fn new<C: SolCall>(target: Address, call: &C) -> Self {
+        Self { target, call_data: call.abi_encode().into() }
     }
+}
 
-    /// 最强一击：批量查询任意 view 函数（允许部分失败）
-    /// calls: vec![(target_contract, calldata_hex)]
-    pub async fn aggregate3(
-        &self,
-        calls: Vec<(String, String)>, // (target: "0x...", calldata: "0x1234...")
-    ) -> Result<Vec<MulticallResult>, AppError> {
-        let calls_json = calls
-            .into_iter()
-            .map(|(target, call_data)| {
-                json!({
-                    "target": target,
-                    "allowFailure": true,
-                    "callData": call_data
-                })
-            })
-            .collect::<Vec<_>>();
-
-        let payload = json!({
-            "to": MULTICALL3_ADDR,
-            "data": Self::encode_aggregate3(calls_json)?
-        });
-
-        let raw_result = self.rpc.call(RpcMethod::Custom("eth_call"), json!([payload, "latest"])).await?;
-
-        // 返回格式：{"0": "0x...", "1": "0x..."} 或直接数组
-        let results = if raw_result.is_object() {
-            let obj = raw_result.as_object().unwrap();
-            obj.values().cloned().collect::<Vec<_>>()
-        } else if raw_result.is_array() {
-            raw_result.as_array().unwrap().clone()
-        } else {
-            vec![raw_result]
-        };
+#[derive(Debug, Clone, Default)]
+pub struct MulticallResult {
+    pub success: bool,
+    pub return_data: Bytes,
+}
 
-        // 解析 returnData
-        let mut parsed = Vec::new();
-        for data in results {
-            let data_str = data.as_str().unwrap_or("0x");
-            let return_data = if data_str.starts_with("0x") {
-                data_str[2..].to_string()
-            } else {
-                data_str.to_string()
-            };
-            parsed.push(MulticallResult {
-                success: !return_data.is_empty() && return_data != "0",
-                return_data,
-            });
+impl MulticallResult {
+    /// 按对应的调用类型解码返回值；失败或空返回（如目标不是合约）时为 None
+    pub fn decode<C: SolCall>(&self) -> Option<C::Return> {
+        if !self.success || self.return_data.is_empty() {
+            return None;
         }
+        C::abi_decode_returns(&self.return_data).ok()
+    }
+}
+
+pub struct Multicall3 {
+    client: Client,
+    url: String,
+    address: Address,
+    block: String,
+}
 
-        Ok(parsed)
+impl Multicall3 {
+    pub fn new(client: Client, url: &str) -> Self {
+        Self { client, url: url.to_string(), address: MULTICALL3_ADDR, block: "latest".to_string() }
     }
 
-    /// 编码 aggregate3 的 calldata（纯手工，不依赖 alloy）
-    fn encode_aggregate3(calls: Vec<Value>) -> Result<String, AppError> {
-        // aggregate3 选择器：0x04f0f6d3
-        let mut data = "04f0f6d3".to_string();
+    /// 少数链部署在非标准地址
+    pub fn with_address(mut self, address: Address) -> Self {
+        self.address = address;
+        self
+    }
 
-        // 编码 Call3[]: (address,bool,bytes)[]
-        let calls_hex = alloy_primitives::hex::encode(&alloy_sol_types::SolValue::abi_encode(&calls));
-        data += &calls_hex;
+    /// 固定到某个区块读取（同一批数据口径一致）
+    pub fn at_block(mut self, block: u64) -> Self {
+        self.block = format!("0x{block:x}");
+        self
+    }
+
+    /// 最强一击：批量查询任意 view 函数（允许部分失败），结果与 calls 一一对应
+    pub async fn aggregate3(&self, calls: &[Call]) -> Result<Vec<MulticallResult>, AppError> {
+        if calls.is_empty() {
+            return Ok(Vec::new());
+        }
+        let call = IMulticall3::aggregate3Call {
+            calls: calls
+                .iter()
+                .map(|c| IMulticall3::Call3 {
+                    target: c.target,
+                    allowFailure: true,
+                    callData: c.call_data.clone(),
+                })
+                .collect(),
+        };
+        let data = format!("0x{}", hex::encode(call.abi_encode()));
+        let raw = eth_call(self.client.clone(), &self.url, &format!("{:#x}", self.address), &data, &self.block).await?;
+        let raw = hex::decode(raw.as_str().unwrap_or("0x").trim_start_matches("0x"))?;
+        let results = IMulticall3::aggregate3Call::abi_decode_returns(&raw)
+            .map_err(|_| AppError::Parse("invalid aggregate3 response"))?;
+        if results.len() != calls.len() {
+            return Err(AppError::Parse("aggregate3 result count mismatch"));
+        }
+        Ok(results
+            .into_iter()
+            .map(|r| MulticallResult { success: r.success, return_data: r.returnData })
+            .collect())
+    }
 
-        Ok(format!("0x{}", data))
+    /// Multicall 自身的调用（getEthBalance / getBlockNumber 等）
+    pub fn self_call<C: SolCall>(&self, call: &C) -> Call {
+        Call::new(self.address, call)
     }
 
     // 下面几个最常用的快捷方法（直接返回解析好的值）
 
-    pub async fn get_eth_balances(
-        &self,
-        addresses: Vec<String>,
-    ) -> Result<HashMap<String, String>, AppError> {
-        let calls = addresses
+    pub async fn get_eth_balances(&self, addresses: &[Address]) -> Result<Vec<Option<U256>>, AppError> {
+        let calls: Vec<Call> = addresses
             .iter()
-            .map(|addr| {
-                let calldata = format!(
-                    "0x70a08231000000000000000000000000{}",
-                    &addr[2..]
-                );
-                (MULTICALL3_ADDR.to_string(), calldata)
-            })
+            .map(|addr| self.self_call(&IMulticall3::getEthBalanceCall { addr: *addr }))
             .collect();
-
-        let results = self.aggregate3(calls).await?;
-        let mut balances = HashMap::new();
-        for (addr, result) in addresses.iter().zip(results.iter()) {
-            balances.insert(addr.clone(), result.return_data.clone());
-        }
-        Ok(balances)
+        let results = self.aggregate3(&calls).await?;
+        Ok(results.iter().map(|r| r.decode::<IMulticall3::getEthBalanceCall>()).collect())
     }
 
     pub async fn get_block_number(&self) -> Result<u64, AppError> {
-        let calldata = "0x42cbb15c".to_string(); // getBlockNumber()
-        let result = self
-            .aggregate3(vec![(MULTICALL3_ADDR.to_string(), calldata)])
-            .await?;
-        let hex = result[0].return_data.strip_prefix("0x").unwrap_or(&result[0].return_data);
-        Ok(u64::from_str_radix(hex, 16).unwrap_or(0))
+        let results = self.aggregate3(&[self.self_call(&IMulticall3::getBlockNumberCall {})]).await?;
+        results[0]
+            .decode::<IMulticall3::getBlockNumberCall>()
+            .map(|n| n.to::<u64>())
+            .ok_or(AppError::Parse("getBlockNumber failed"))
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct MulticallResult {
-    pub success: bool,
-    pub return_data: String, // hex string without 0x
-}
-
-
 // // 在你的钱包逻辑里
-// let rpc = EthRpcProvider::new("https://rpc.ankr.com/eth")?;
-// let multicall = Multicall3::new(rpc);
-
+// let multicall = Multicall3::new(client, "https://rpc.ankr.com/eth");
+//
 // // 批量查 100 个地址的 ETH 余额（一次 RPC！）
-// let addresses = vec!["0x1234...".to_string(), "0x5678...".to_string()];
-// let balances_hex = multicall.get_eth_balances(addresses).await?;
-// for (addr, balance_hex) in balances_hex {
-//     let balance = U256::from_str_radix(&balance_hex, 16)? / 1e18;
-//     println!("{}: {} ETH", addr, balance);
-// }
\ No newline at end of file
+// let balances = multicall.get_eth_balances(&addresses).await?;
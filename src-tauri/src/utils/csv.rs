@@ -0,0 +1,104 @@
+// 极简 CSV 工具（RFC 4180），导入导出共用
+
+/// 表格软件会把这些字符开头的单元格当公式执行
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// 普通数字（如 -1.5）不是公式，不加前缀以免破坏数值列
+fn is_plain_number(field: &str) -> bool {
+    let digits = field.strip_prefix(['-', '+']).unwrap_or(field);
+    digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.chars().filter(|c| *c == '.').count() <= 1
+        && digits.chars().any(|c| c.is_ascii_digit())
+}
+
+/// 字段转义：代币符号、名称等可能来自攻击者，公式字符开头时按 OWASP 建议加 ' 前缀；
+/// 含逗号 / 引号 / 换行时加引号，内部引号双写
+pub fn csv_escape(field: &str) -> String {
+    let guarded;
+    let field = if field.starts_with(FORMULA_PREFIXES) && !is_plain_number(field) {
+        guarded = format!("'{field}");
+        guarded.as_str()
+    } else {
+        field
+    };
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 拼一行（不含换行符）
+pub fn csv_line(fields: &[String]) -> String {
+    fields.iter().map(|f| csv_escape(f)).collect::<Vec<_>>().join(",")
+}
+
+/// 解析整个 CSV 文本为行列；支持引号内的逗号、换行和 "" 转义
+pub fn parse_csv(input: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|r| !(r.len() == 1 && r[0].trim().is_empty()));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_roundtrip() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+
+        let line = csv_line(&["a,b".into(), "say \"hi\"".into(), "x\ny".into(), "".into()]);
+        let parsed = parse_csv(&format!("h1,h2,h3,h4\r\n{line}\n"));
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1], vec!["a,b", "say \"hi\"", "x\ny", ""]);
+    }
+
+    #[test]
+    fn test_formula_injection_guard() {
+        assert_eq!(csv_escape("=HYPERLINK(\"http://x\")"), "\"'=HYPERLINK(\"\"http://x\"\")\"");
+        assert_eq!(csv_escape("+cmd|' /C calc'!A0"), "'+cmd|' /C calc'!A0");
+        assert_eq!(csv_escape("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_escape("-2+3"), "'-2+3");
+        assert_eq!(csv_escape("\tUSDC"), "'\tUSDC");
+        assert_eq!(csv_escape("\r=1"), "\"'\r=1\"");
+        // 数值列保持原样
+        assert_eq!(csv_escape("-1.5"), "-1.5");
+        assert_eq!(csv_escape("+42"), "+42");
+        assert_eq!(csv_escape("-"), "'-");
+    }
+}
@@ -4,3 +4,6 @@ pub mod time;
 pub mod serde;
 pub mod ps;
 pub mod num;
+pub mod csv;
+#[cfg(test)]
+pub mod test_http;
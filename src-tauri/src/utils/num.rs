@@ -46,6 +46,22 @@ pub fn u256_to_f64_wei(value: U256) -> f64 {
     value.as_u128() as f64
 }
 
+/// U256 → 十进制字符串（按 decimals 缩放，去掉末尾 0，无精度损失）
+/// 例如 1_500_000_000_000_000_000 wei, 18 → "1.5"
+pub fn format_units(value: U256, decimals: u8) -> String {
+    if decimals == 0 {
+        return value.to_string();
+    }
+    let base = U256::from(10u64).pow(U256::from(decimals));
+    let integer = value / base;
+    let fraction = value % base;
+    if fraction.is_zero() {
+        return integer.to_string();
+    }
+    let frac_str = format!("{:0>width$}", fraction.to_string(), width = decimals as usize);
+    format!("{}.{}", integer, frac_str.trim_end_matches('0'))
+}
+
 pub fn str_to_u64(input: &str) -> Result<u64, &'static str> {
     let s = input.trim();
 
@@ -0,0 +1,42 @@
+// 测试用本地 HTTP 替身：按路径返回固定响应
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+
+/// 启动替身服务，返回 base url（http://127.0.0.1:port）；未配置的路径返回 404
+pub fn serve(routes: Vec<(&str, &str)>) -> String {
+    let routes: HashMap<String, String> =
+        routes.into_iter().map(|(path, body)| (path.to_string(), body.to_string())).collect();
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let n = stream.read(&mut buf).unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("/");
+            let (status, body) = match routes.get(path) {
+                Some(body) => ("200 OK", body.as_str()),
+                None => ("404 Not Found", ""),
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    base
+}
+
+/// JSON-RPC 成功响应体，替身对所有方法返回同一个 result
+pub fn rpc_result(result: impl Into<Value>) -> String {
+    json!({"jsonrpc": "2.0", "id": null, "result": result.into()}).to_string()
+}
+
+/// 一个必然连接失败的地址（模拟离线）：端口 0 是保留端口，不会被其它进程监听
+pub fn unreachable() -> String {
+    "http://127.0.0.1:0".to_string()
+}
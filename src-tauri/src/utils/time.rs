@@ -48,6 +48,22 @@ pub fn system_to_unix(time: SystemTime) -> u64 {
     time.duration_since(UNIX_EPOCH).unwrap().as_secs() as u64
 }
 
+/// Unix 秒 → "YYYY-MM-DD HH:MM:SS"（UTC，导出报表用）
+pub fn format_utc(ts_s: u64) -> String {
+    match time::OffsetDateTime::from_unix_timestamp(ts_s as i64) {
+        Ok(dt) => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            dt.year(),
+            dt.month() as u8,
+            dt.day(),
+            dt.hour(),
+            dt.minute(),
+            dt.second()
+        ),
+        Err(_) => ts_s.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,4 +76,10 @@ mod tests {
         assert_eq!(ms_to_s(ms), s);
         assert_eq!(s_to_ms(s), s * 1000);
     }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00");
+        assert_eq!(format_utc(1_700_000_000), "2023-11-14 22:13:20");
+    }
 }